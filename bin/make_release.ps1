@@ -8,7 +8,7 @@ $root_dir = "Nog"
 
 $env:NOG_VERSION=$Version
 
-cargo +nightly build --release -p twm
+cargo +nightly build --release -p twm -p nogctl
 
 if (!$?) {
   echo "Build was not successful. Aborting."
@@ -19,6 +19,10 @@ if (test-path ./$root_dir.zip) {
   remove-item -Path ./$root_dir.zip
 }
 
+if (test-path ./$root_dir.zip.sha256) {
+  remove-item -Path ./$root_dir.zip.sha256
+}
+
 new-item -path . -name $root_dir -itemtype "Directory"
 new-item -path ./$root_dir -name "runtime" -itemtype "Directory"
 new-item -path ./$root_dir -name "config" -itemtype "Directory"
@@ -26,9 +30,11 @@ new-item -path ./$root_dir -name "bin" -itemtype "Directory"
 
 copy-item ./twm/runtime/* ./$root_dir/runtime -recurse
 copy-item ./target/release/twm.exe ./$root_dir/bin/nog.exe
+copy-item ./target/release/nogctl.exe ./$root_dir/bin/nogctl.exe
 
 ./bin/rcedit.exe ./$root_dir/bin/nog.exe --set-icon ./assets/logo.ico
 
 compress-archive ./$root_dir ./$root_dir.zip
+(get-filehash ./$root_dir.zip -algorithm SHA256).hash.tolower() | out-file -encoding ascii ./$root_dir.zip.sha256
 
 remove-item -Path ./$root_dir -recurse
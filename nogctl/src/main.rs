@@ -0,0 +1,157 @@
+//! Sends a single request to the IPC endpoint of a running instance of nog and prints the result.
+//!
+//! ```text
+//! nogctl ws_focus left
+//! nogctl get_ws_info 1
+//! nogctl config_set bar.height 30
+//! ```
+//!
+//! Every parameter that is valid JSON gets sent as is, every other parameter as a string.
+//!
+//! `nogctl subscribe [notifications...]` keeps running and prints every notification on its own
+//! line.
+use serde_json::{json, Value};
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    process,
+};
+
+const USAGE: &str = "Usage: nogctl <method> [params...]";
+/// How long to wait for a free instance of the pipe when every instance is busy
+#[cfg(windows)]
+const PIPE_TIMEOUT_MS: u32 = 5000;
+
+#[cfg(unix)]
+fn connect() -> io::Result<std::os::unix::net::UnixStream> {
+    let mut path = env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+    path.push("nog.sock");
+
+    std::os::unix::net::UnixStream::connect(path)
+}
+
+#[cfg(windows)]
+fn connect() -> io::Result<std::fs::File> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::{shared::winerror::ERROR_PIPE_BUSY, um::namedpipeapi::WaitNamedPipeW};
+
+    let path = PathBuf::from(r"\\.\pipe\nog");
+
+    loop {
+        match std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+        {
+            // nog creates the next instance as soon as it accepted the previous client
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) => {
+                let name: Vec<u16> = path
+                    .as_os_str()
+                    .encode_wide()
+                    .chain(std::iter::once(0))
+                    .collect();
+
+                if unsafe { WaitNamedPipeW(name.as_ptr(), PIPE_TIMEOUT_MS) } == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            result => return result,
+        }
+    }
+}
+
+fn parse_param(param: String) -> Value {
+    serde_json::from_str(&param).unwrap_or(Value::String(param))
+}
+
+fn read_value(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut line = String::new();
+
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Returns the response and the connection, which keeps receiving notifications after a
+/// subscription
+fn send(request: &Value) -> io::Result<(Value, impl BufRead)> {
+    let mut connection = connect()?;
+    writeln!(connection, "{}", request)?;
+    connection.flush()?;
+
+    let mut reader = BufReader::new(connection);
+    let response = read_value(&mut reader)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "nog closed the connection"))?;
+
+    Ok((response, reader))
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+
+    let method = match args.next() {
+        Some(method) if method != "-h" && method != "--help" => method,
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let is_subscription = method == "subscribe";
+    let mut params = args.map(parse_param).collect::<Vec<_>>();
+
+    // `nogctl subscribe focus_changed mode_entered` subscribes to both
+    if is_subscription && !params.is_empty() {
+        params = vec![Value::Array(params)];
+    }
+
+    let request = json!({
+        "method": method,
+        "params": params,
+    });
+
+    let (mut response, mut reader) = match send(&request) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("Failed to talk to nog: {}", e);
+            process::exit(2);
+        }
+    };
+
+    if let Some(error) = response.get("error") {
+        eprintln!("{}", error.as_str().unwrap_or_default());
+        process::exit(1);
+    }
+
+    if is_subscription {
+        loop {
+            match read_value(&mut reader) {
+                Ok(Some(notification)) => println!("{}", notification),
+                Ok(None) => return,
+                Err(e) => {
+                    eprintln!("Failed to talk to nog: {}", e);
+                    process::exit(2);
+                }
+            }
+        }
+    }
+
+    let result = response
+        .get_mut("result")
+        .map(Value::take)
+        .unwrap_or(Value::Null);
+
+    if !result.is_null() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).expect("Failed to format the result")
+        );
+    }
+}
@@ -41,44 +41,27 @@ function nog.split(s, sep)
   return t
 end
 
-local modes = {}
-local previous_kbs = nil
-local current_mode = nil
-
-function nog.mode(name, cb)
-  modes[name] = cb
-end
-
-function nog.toggle_mode(name)
-  local cb = modes[name]
-
-  assert(cb ~= nil, string.format("Mode '%s' has not been defined yet", name))
-
-  if current_mode ~= nil then
-    if current_mode == name then
-      local mode_kbs = nog.get_keybindings()
-
-      nog.__unbind_batch(nog.tbl_filter(mode_kbs, function(kb)
-        return kb.mode == "n"
-      end))
-
-      nog.__bind_batch(nog.tbl_filter(previous_kbs, function(kb)
-        return kb.mode == "n"
-      end))
-
-      current_mode = nil
-    else
+-- the mode whose keybindings are getting bound
+local binding_mode = nil
+
+-- `opts` can contain a `timeout` in milliseconds, `oneshot` and the `exit_key`, which defaults to
+-- "Escape" and can be turned off with `false`
+function nog.mode(name, cb, opts)
+  opts = opts or {}
+  nog.__define_mode(name, opts)
+
+  binding_mode = name
+  local ok, err = pcall(function()
+    if opts.exit_key ~= false then
+      nog.nbind(opts.exit_key or "Escape", function()
+        nog.leave_mode(name)
+      end)
     end
-  else
-    previous_kbs = nog.get_keybindings()
-
-    nog.__unbind_batch(nog.tbl_filter(previous_kbs, function(kb)
-      return kb.mode == "n"
-    end))
-
     cb()
-
-    current_mode = name
+  end)
+  binding_mode = nil
+  if not ok then
+    error(err, 0)
   end
 end
 
@@ -94,14 +77,32 @@ local function create_bind_tbl_fn(mode)
 end
 
 local function create_bind_fn(mode)
-  return function(key, cb)
-    nog.bind(mode, key, cb)
+  return function(key, cb, opts)
+    nog.bind(mode, key, cb, opts)
   end
 end
 
-nog.bind = function(m, k, f)
+-- returns the file that called into the runtime, like the config or a plugin
+local function caller_source()
+  local runtime_source = debug.getinfo(1, "S").source
+  local level = 2
+
+  while true do
+    local info = debug.getinfo(level, "S")
+    if info == nil then
+      return nil
+    end
+    if info.what ~= "C" and info.source ~= runtime_source then
+      return (info.source:gsub("^@", ""))
+    end
+    level = level + 1
+  end
+end
+
+-- `opts` can contain a `when` or `unless` condition on the focused window
+nog.bind = function(m, k, f, opts)
   table.insert(nog.__callbacks, f)
-  nog.__bind(m, k, #nog.__callbacks)
+  nog.__bind(m, k, #nog.__callbacks, caller_source(), binding_mode, opts)
 end
 
 nog.nbind = create_bind_fn("n")
@@ -113,6 +114,8 @@ nog.gbind_tbl = create_bind_tbl_fn("g")
 nog.wbind = create_bind_fn("w")
 nog.wbind_tbl = create_bind_tbl_fn("w")
 
+nog.commands = {}
+
 nog.components = {}
 nog.components.workspaces = function()
   return {
@@ -169,13 +172,16 @@ nog.components.padding = function(amount)
   }
 end
 
-nog.components.active_mode = function()
+nog.components.active_mode = function(show_pending_chord)
   return {
     name = "ActiveMode",
     render = function()
       local mode
-      if current_mode ~= nil then
-        mode = current_mode .. " is active"
+      local chord = show_pending_chord and nog.get_pending_chord()
+      if chord then
+        mode = chord .. " ..."
+      elseif nog.get_current_mode() ~= nil then
+        mode = nog.get_current_mode() .. " is active"
       end
       return {{
         text = mode or "",
@@ -204,6 +210,52 @@ nog.components.current_window = function(max_width)
   }
 end
 
+nog.components.tabs = function(max_width)
+  max_width = max_width or 0
+
+  return {
+    name = "Tabs",
+    render = function(display_id)
+      local ws_id = nog.get_focused_ws_of_display(display_id)
+
+      if not ws_id then
+        return {{ text = "" }}
+      end
+
+      local c = nog.config
+      local focused_win_id = nog.get_focused_win_of_display(display_id)
+      local result = {}
+
+      for _, win_id in ipairs(nog.get_ws_info(ws_id).tabs) do
+        local title = nog.get_win_title(win_id) or ""
+
+        if max_width ~= 0 then
+          title = title:sub(1, max_width)
+        end
+
+        local factor
+        if c.light_theme then
+          factor = win_id == focused_win_id and 0.75 or 0.9
+        else
+          factor = win_id == focused_win_id and 2.0 or 1.5
+        end
+
+        table.insert(result, {
+          text = " " .. title .. " ",
+          value = win_id,
+          bg = nog.scale_color(c.bar.color, factor)
+        })
+      end
+
+      if #result == 0 then
+        return {{ text = "" }}
+      end
+
+      return result
+    end
+  }
+end
+
 nog.components.split_direction = function(values)
   return {
     name = "SplitDirection",
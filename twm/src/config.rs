@@ -1,5 +1,10 @@
 use crate::get_plugins_path;
-use crate::keybindings::keybinding::Keybinding;
+use crate::plugins::{permission::Permission, spec::PluginSpec};
+use crate::keybindings::{
+    conflict::{self, Conflict},
+    keybinding::Keybinding,
+    mode::Mode,
+};
 use bar_config::BarConfig;
 use log::error;
 use rule::Rule;
@@ -18,6 +23,11 @@ pub mod workspace_setting;
 pub struct Config {
     pub path: PathBuf,
     pub plugins_path: PathBuf,
+    /// The plugins that got declared with `nog.plugins`
+    pub plugins: Vec<PluginSpec>,
+    /// What the plugins can use in addition to the safe parts of the standard library by their
+    /// name
+    pub plugin_permissions: HashMap<String, Vec<Permission>>,
     pub use_border: bool,
     pub enable_hot_reloading: bool,
     pub min_width: i32,
@@ -35,15 +45,20 @@ pub struct Config {
     pub bar: BarConfig,
     pub workspaces: Vec<WorkspaceSetting>,
     pub keybindings: Vec<Keybinding>,
+    /// The conflicts that were found while adding keybindings
+    pub keybinding_conflicts: Vec<Conflict>,
     pub rules: Vec<Rule>,
     pub update_channels: Vec<UpdateChannel>,
     pub default_update_channel: Option<String>,
     pub update_interval: Duration,
-    pub mode_handlers: HashMap<String, usize>,
-    /// contains the metadata for each mode (like an icon)
-    /// HashMap<mode, (Option<char>)>
-    pub mode_meta: HashMap<String, Option<char>>,
+    /// The modes that got defined with `nog.mode`
+    pub modes: HashMap<String, Mode>,
     pub allow_right_alt: bool,
+    /// How many milliseconds a chord waits for its next key combo
+    pub chord_timeout: i32,
+    /// How many milliseconds a lua callback can run before it gets aborted, where 0 lets it run
+    /// forever
+    pub callback_timeout: i32,
 }
 
 impl Default for Config {
@@ -51,6 +66,8 @@ impl Default for Config {
         Self {
             path: "".into(),
             plugins_path: get_plugins_path().unwrap(),
+            plugins: Vec::new(),
+            plugin_permissions: HashMap::new(),
             launch_on_startup: false,
             enable_hot_reloading: true,
             min_height: 200,
@@ -66,15 +83,17 @@ impl Default for Config {
             display_app_bar: true,
             ignore_fullscreen_actions: false,
             bar: BarConfig::default(),
-            mode_handlers: HashMap::new(),
-            mode_meta: HashMap::new(),
+            modes: HashMap::new(),
             workspaces: Vec::new(),
             keybindings: vec![],
+            keybinding_conflicts: Vec::new(),
             rules: Vec::new(),
             update_channels: Vec::new(),
             default_update_channel: None,
             update_interval: Duration::from_secs(60 * 60),
             allow_right_alt: false,
+            chord_timeout: 1000,
+            callback_timeout: 0,
         }
     }
 }
@@ -109,6 +128,8 @@ impl Config {
             "min_width" => self.min_width = value.parse().unwrap(),
             "min_height" => self.min_height = value.parse().unwrap(),
             "allow_right_alt" => self.allow_right_alt = value.parse().unwrap(),
+            "chord_timeout" => self.chord_timeout = value.parse().unwrap(),
+            "callback_timeout" => self.callback_timeout = value.parse().unwrap(),
             _ => todo!("{}", field),
         }
     }
@@ -141,20 +162,38 @@ impl Config {
         }
     }
 
-    pub fn add_keybinding(&mut self, keybinding: Keybinding) {
-        if let Some(kb) = self.keybindings.iter_mut().find(|kb| {
-            kb.key == keybinding.key
-                && kb.modifier == keybinding.modifier
-                && kb.mode == keybinding.mode
-        }) {
-            kb.kind = kb.kind;
-            kb.callback_id = kb.callback_id;
-            kb.mode = keybinding.mode;
+    /// Adds the keybinding or replaces the one that uses the same keys and condition. Returns the
+    /// conflict if the replaced keybinding was bound by another kind, mode or file.
+    pub fn add_keybinding(&mut self, keybinding: Keybinding) -> Option<Conflict> {
+        if let Some(kb) = self
+            .keybindings
+            .iter_mut()
+            .find(|kb| kb.has_same_trigger(&keybinding))
+        {
+            let conflict = conflict::find_replace_conflict(kb, &keybinding);
+            *kb = keybinding;
+
+            if let Some(conflict) = &conflict {
+                if !self.keybinding_conflicts.contains(conflict) {
+                    self.keybinding_conflicts.push(conflict.clone());
+                }
+            }
+
+            conflict
         } else {
             self.keybindings.push(keybinding);
+            None
         }
     }
 
+    /// Returns every conflict between the keybindings of the config, which doesn't include
+    /// hotkeys that are used by other applications
+    pub fn find_keybinding_conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = self.keybinding_conflicts.clone();
+        conflicts.extend(conflict::find_conflicts(&self.keybindings));
+        conflicts
+    }
+
     pub fn set_bool_field(&self, field: &str, value: bool) -> Config {
         let mut config = self.clone();
         match field {
@@ -1,3 +1,6 @@
+//! Rules change how nog handles the windows they match. The first rule that matches a window
+//! applies to it.
+use crate::{system::NativeWindow, window::gwl_ex_style::GwlExStyle};
 use regex::Regex;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -32,25 +35,224 @@ impl std::str::FromStr for Action {
     }
 }
 
+/// The class name windows uses for dialog boxes
+const DIALOG_CLASS_NAME: &str = "#32770";
+
+/// What a rule knows about a window when deciding whether it matches
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowProperties {
+    pub title: String,
+    pub process_name: String,
+    pub exe_path: String,
+    pub class_name: String,
+    pub is_dialog: bool,
+    /// The window that owns this window. Its own parent doesn't get captured.
+    pub parent: Option<Box<WindowProperties>>,
+}
+
+impl WindowProperties {
+    pub fn capture(window: &NativeWindow) -> Self {
+        let mut properties = Self::capture_without_parent(window);
+        properties.parent = window
+            .get_parent_window()
+            .ok()
+            .map(|id| Box::new(Self::capture_without_parent(&id.into())));
+
+        properties
+    }
+
+    fn capture_without_parent(window: &NativeWindow) -> Self {
+        let class_name = window.get_class_name().unwrap_or_default();
+        let is_dialog = class_name == DIALOG_CLASS_NAME
+            || window
+                .get_ex_style()
+                .is_ok_and(|style| style.contains(GwlExStyle::DLGMODALFRAME));
+
+        Self {
+            title: window.get_title().unwrap_or_default(),
+            process_name: window.get_process_name(),
+            exe_path: window.get_process_path(),
+            class_name,
+            is_dialog,
+            parent: None,
+        }
+    }
+}
+
+/// Decides whether a rule applies to a window
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    /// Matches the title or the process name. Patterns that contain a backslash match the
+    /// executable path instead of the process name.
+    Pattern(Regex),
+    Process(Regex),
+    Class(Regex),
+    Title(Regex),
+    ExePath(Regex),
+    IsDialog(bool),
+    /// Whether the window has a parent window
+    HasParent(bool),
+    /// Matches windows whose parent window matches
+    Parent(Box<Matcher>),
+    /// Matches if every matcher matches, which is true for an empty list
+    All(Vec<Matcher>),
+    /// Matches if at least one of the matchers matches
+    Any(Vec<Matcher>),
+    /// Matches if none of the matchers match
+    NoneOf(Vec<Matcher>),
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Self::All(Vec::new())
+    }
+}
+
+impl Matcher {
+    pub fn matches(&self, window: &WindowProperties) -> bool {
+        match self {
+            Self::Pattern(regex) => {
+                let process_name = if regex.as_str().contains('\\') {
+                    &window.exe_path
+                } else {
+                    &window.process_name
+                };
+
+                regex.is_match(process_name) || regex.is_match(&window.title)
+            }
+            Self::Process(regex) => regex.is_match(&window.process_name),
+            Self::Class(regex) => regex.is_match(&window.class_name),
+            Self::Title(regex) => regex.is_match(&window.title),
+            Self::ExePath(regex) => regex.is_match(&window.exe_path),
+            Self::IsDialog(is_dialog) => window.is_dialog == *is_dialog,
+            Self::HasParent(has_parent) => window.parent.is_some() == *has_parent,
+            Self::Parent(matcher) => window
+                .parent
+                .as_ref()
+                .is_some_and(|parent| matcher.matches(parent)),
+            Self::All(matchers) => matchers.iter().all(|m| m.matches(window)),
+            Self::Any(matchers) => matchers.iter().any(|m| m.matches(window)),
+            Self::NoneOf(matchers) => !matchers.iter().any(|m| m.matches(window)),
+        }
+    }
+
+    /// The pattern of a rule that got defined the old way, where the pattern is the key of the
+    /// rule
+    pub fn pattern(&self) -> Option<&Regex> {
+        match self {
+            Self::Pattern(regex) => Some(regex),
+            Self::All(matchers) => matchers.iter().find_map(|m| match m {
+                Self::Pattern(regex) => Some(regex),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The process name pattern of a matcher that only checks the process name
+    pub fn process(&self) -> Option<&Regex> {
+        match self {
+            Self::Process(regex) => Some(regex),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Matcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |f: &mut std::fmt::Formatter<'_>, matchers: &[Matcher], separator: &str| {
+            let parts = matchers.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+            write!(f, "({})", parts.join(separator))
+        };
+
+        match self {
+            Self::Pattern(regex) => write!(f, "pattern \"{}\"", regex),
+            Self::Process(regex) => write!(f, "process \"{}\"", regex),
+            Self::Class(regex) => write!(f, "class \"{}\"", regex),
+            Self::Title(regex) => write!(f, "title \"{}\"", regex),
+            Self::ExePath(regex) => write!(f, "exe_path \"{}\"", regex),
+            Self::IsDialog(is_dialog) => write!(f, "is_dialog = {}", is_dialog),
+            Self::HasParent(has_parent) => write!(f, "parent = {}", has_parent),
+            Self::Parent(matcher) => write!(f, "parent ({})", matcher),
+            Self::All(matchers) if matchers.is_empty() => write!(f, "every window"),
+            Self::All(matchers) => join(f, matchers, " and "),
+            Self::Any(matchers) => join(f, matchers, " or "),
+            Self::NoneOf(matchers) => {
+                write!(f, "none of ")?;
+                join(f, matchers, ", ")
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Rule {
-    pub pattern: Regex,
+    pub matcher: Matcher,
     pub has_custom_titlebar: bool,
     pub action: Action,
     pub chromium: bool,
     pub firefox: bool,
     pub workspace_id: i32,
+    /// Matching windows get moved to the scratchpad with this name when they appear
+    pub scratchpad: Option<String>,
+    /// Matching windows float instead of getting tiled
+    pub float: bool,
+    /// Matching windows get fullscreened once they are managed
+    pub fullscreen: bool,
+    /// The display whose focused workspace matching windows get moved to, counting from 1 like
+    /// the `monitor` of a workspace. Gets ignored if the rule has a workspace.
+    pub display: Option<i32>,
+    /// The initial size of floating windows
+    pub size: Option<(i32, i32)>,
+    /// The initial position of floating windows relative to the tiling area. Floating windows
+    /// get centered without it.
+    pub position: Option<(i32, i32)>,
+    /// Between 0 (invisible) and 1 (opaque)
+    pub opacity: Option<f32>,
+    /// Keeps the border away from matching windows, even if `use_border` is enabled
+    pub no_border: bool,
+    /// `Some(true)` focuses matching windows when they appear and `Some(false)` opens them in
+    /// the background, without changing the focus or the workspace
+    pub focus: Option<bool>,
+}
+
+impl Rule {
+    /// A rule that ignores every window of the process
+    pub fn ignore_process(pattern: Regex) -> Self {
+        Self {
+            matcher: Matcher::Process(pattern),
+            action: Action::Ignore,
+            ..Self::default()
+        }
+    }
 }
 
 impl Default for Rule {
     fn default() -> Self {
         Self {
-            pattern: Regex::new("").unwrap(),
+            matcher: Matcher::default(),
             has_custom_titlebar: false,
             action: Action::Validate,
             chromium: false,
             firefox: false,
             workspace_id: -1,
+            scratchpad: None,
+            float: false,
+            fullscreen: false,
+            display: None,
+            size: None,
+            position: None,
+            opacity: None,
+            no_border: false,
+            focus: None,
         }
     }
 }
+
+/// Returns the first rule that matches the window
+pub fn find_match<'a>(rules: Vec<&'a Rule>, window: &WindowProperties) -> Option<&'a Rule> {
+    rules.into_iter().find(|rule| rule.matcher.matches(window))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,109 @@
+use super::{find_match, Matcher, Rule, WindowProperties};
+use crate::system::{fake, fake::FakeWindow, NativeWindow};
+use crate::window::gwl_ex_style::GwlExStyle;
+use regex::Regex;
+
+fn regex(pattern: &str) -> Regex {
+    Regex::new(pattern).unwrap()
+}
+
+fn save_dialog() -> WindowProperties {
+    WindowProperties {
+        title: "Save As".into(),
+        process_name: "notepad.exe".into(),
+        exe_path: "C:\\Windows\\notepad.exe".into(),
+        class_name: "#32770".into(),
+        is_dialog: true,
+        parent: Some(Box::new(WindowProperties {
+            title: "notes.txt - Notepad".into(),
+            process_name: "notepad.exe".into(),
+            exe_path: "C:\\Windows\\notepad.exe".into(),
+            class_name: "Notepad".into(),
+            ..WindowProperties::default()
+        })),
+    }
+}
+
+#[test]
+fn pattern_matches_the_title_or_the_process_name() {
+    assert!(Matcher::Pattern(regex("^notepad.exe$")).matches(&save_dialog()));
+    assert!(Matcher::Pattern(regex("Save")).matches(&save_dialog()));
+    assert!(Matcher::Pattern(regex("Windows\\\\notepad")).matches(&save_dialog()));
+    assert!(!Matcher::Pattern(regex("Notepad")).matches(&save_dialog()));
+}
+
+#[test]
+fn matchers_combine() {
+    let dialog_of_notepad = Matcher::All(vec![
+        Matcher::IsDialog(true),
+        Matcher::Parent(Box::new(Matcher::Class(regex("^Notepad$")))),
+    ]);
+    assert!(dialog_of_notepad.matches(&save_dialog()));
+
+    let not_notepad = Matcher::NoneOf(vec![
+        Matcher::Process(regex("^notepad\\.exe$")),
+        Matcher::ExePath(regex("^C:\\\\Program Files")),
+    ]);
+    assert!(!not_notepad.matches(&save_dialog()));
+
+    let either = Matcher::Any(vec![
+        Matcher::Title(regex("^Open$")),
+        Matcher::HasParent(true),
+    ]);
+    assert!(either.matches(&save_dialog()));
+    assert!(!either.matches(&WindowProperties::default()));
+
+    assert!(Matcher::default().matches(&WindowProperties::default()));
+}
+
+#[test]
+fn first_matching_rule_wins() {
+    let dialogs = Rule {
+        matcher: Matcher::IsDialog(true),
+        float: true,
+        ..Rule::default()
+    };
+    let notepad = Rule {
+        matcher: Matcher::Process(regex("^notepad\\.exe$")),
+        workspace_id: 2,
+        ..Rule::default()
+    };
+
+    let rule = find_match(vec![&dialogs, &notepad], &save_dialog()).unwrap();
+    assert!(rule.float);
+
+    let rule = find_match(
+        vec![&dialogs, &notepad],
+        save_dialog().parent.as_ref().unwrap(),
+    );
+    assert_eq!(rule.unwrap().workspace_id, 2);
+}
+
+#[test]
+fn captures_dialogs_and_their_parent() {
+    fake::reset();
+    let parent = fake::add_window(FakeWindow::new("notes.txt - Notepad", "notepad.exe"));
+    let dialog = fake::add_window(
+        FakeWindow::new("Save As", "notepad.exe")
+            .with_ex_style(GwlExStyle::DLGMODALFRAME)
+            .with_parent(parent),
+    );
+
+    let properties = WindowProperties::capture(&NativeWindow::from(dialog));
+    assert!(properties.is_dialog);
+    assert_eq!(properties.parent.unwrap().title, "notes.txt - Notepad");
+
+    let properties = WindowProperties::capture(&NativeWindow::from(parent));
+    assert!(!properties.is_dialog);
+    assert_eq!(properties.parent, None);
+}
+
+#[test]
+fn captures_dialogs_by_their_class_name() {
+    fake::reset();
+    let dialog = fake::add_window(FakeWindow::new("Open", "notepad.exe").with_class_name("#32770"));
+
+    let properties = WindowProperties::capture(&NativeWindow::from(dialog));
+    assert!(properties.is_dialog);
+    assert_eq!(properties.class_name, "#32770");
+}
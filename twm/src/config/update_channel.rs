@@ -1,14 +1,20 @@
 use repository::Repository;
-use semantic_version::SemanticVersion;
+use std::path::PathBuf;
 
 pub mod repository;
 pub mod semantic_version;
+
+/// Where nog looks for new releases
 #[derive(Clone, Debug)]
 pub struct UpdateChannel {
     pub name: String,
     pub repo: Repository,
+    /// Only releases that were built from this branch get installed
     pub branch: String,
-    pub version: SemanticVersion,
+    /// A directory that contains the releases, which gets used instead of the GitHub repository
+    pub path: Option<PathBuf>,
+    /// Whether pre-releases like `1.2.0-beta.1` get installed
+    pub pre_releases: bool,
 }
 
 impl Default for UpdateChannel {
@@ -17,7 +23,8 @@ impl Default for UpdateChannel {
             name: "".into(),
             branch: "master".into(),
             repo: Repository::default(),
-            version: SemanticVersion::default(),
+            path: None,
+            pre_releases: false,
         }
     }
 }
@@ -1,14 +1,14 @@
 #[derive(Clone, Debug)]
 pub struct Repository {
-    origin: String,
-    name: String,
+    pub origin: String,
+    pub name: String,
 }
 
 impl Default for Repository {
     fn default() -> Self {
         Self {
             origin: "TimUntersberger".into(),
-            name: "wwm".into(),
+            name: "nog".into(),
         }
     }
 }
@@ -22,3 +22,9 @@ impl From<String> for Repository {
         Self { origin, name }
     }
 }
+
+impl std::fmt::Display for Repository {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.origin, self.name)
+    }
+}
@@ -1,22 +1,112 @@
-use std::str::FromStr;
-#[derive(Clone, Debug, Default)]
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+/// A version like `1.2.3` or `1.2.3-beta.1`. Versions get ordered like semver describes it, so a
+/// pre-release comes before the release with the same number.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct SemanticVersion {
     pub major: i32,
     pub minor: i32,
     pub patch: i32,
+    /// The identifiers after the `-`, like `["beta", "1"]`
+    pub pre: Vec<String>,
+}
+
+impl SemanticVersion {
+    pub fn is_pre_release(&self) -> bool {
+        !self.pre.is_empty()
+    }
+}
+
+/// Compares two pre-release identifiers. Numeric identifiers are lower than alphanumeric ones.
+fn cmp_identifier(a: &str, b: &str) -> Ordering {
+    match (u64::from_str(a), u64::from_str(b)) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+impl Ord for SemanticVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self
+                    .pre
+                    .iter()
+                    .zip(&other.pre)
+                    .map(|(a, b)| cmp_identifier(a, b))
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or_else(|| self.pre.len().cmp(&other.pre.len())),
+            })
+    }
+}
+
+impl PartialOrd for SemanticVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for SemanticVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if self.is_pre_release() {
+            write!(f, "-{}", self.pre.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SemanticVersion {
+    type Err = String;
+
+    /// Accepts a leading `v` like in `v1.2.3` and ignores build metadata like in `1.2.3+abc`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{} isn't a semantic version", s);
+        let version = s.trim().trim_start_matches('v');
+        let version = version.split('+').next().unwrap_or_default();
+        let (numbers, pre) = match version.find('-') {
+            Some(idx) => (&version[..idx], Some(&version[idx + 1..])),
+            None => (version, None),
+        };
+
+        let numbers = numbers
+            .split('.')
+            .map(|n| i32::from_str(n).map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let pre = match pre {
+            Some(pre) => pre.split('.').map(String::from).collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+
+        if pre.iter().any(|id| id.is_empty()) {
+            return Err(invalid());
+        }
+
+        match numbers.as_slice() {
+            [major, minor, patch] => Ok(Self {
+                major: *major,
+                minor: *minor,
+                patch: *patch,
+                pre,
+            }),
+            _ => Err(invalid()),
+        }
+    }
 }
 
 impl From<String> for SemanticVersion {
+    /// Falls back to `0.0.0` if the string isn't a semantic version
     fn from(s: String) -> Self {
-        let mut tokens = s.split('.');
-        let major = i32::from_str(tokens.next().unwrap_or_default()).unwrap_or_default();
-        let minor = i32::from_str(tokens.next().unwrap_or_default()).unwrap_or_default();
-        let patch = i32::from_str(tokens.next().unwrap_or_default()).unwrap_or_default();
-
-        Self {
-            major,
-            minor,
-            patch,
-        }
+        Self::from_str(&s).unwrap_or_default()
     }
 }
+
+#[cfg(test)]
+mod tests;
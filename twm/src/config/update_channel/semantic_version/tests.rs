@@ -0,0 +1,62 @@
+use super::SemanticVersion;
+use std::str::FromStr;
+
+fn version(s: &str) -> SemanticVersion {
+    SemanticVersion::from_str(s).unwrap()
+}
+
+#[test]
+fn parses_versions() {
+    let v = version("v0.13.1+abc");
+    assert_eq!((v.major, v.minor, v.patch), (0, 13, 1));
+    assert!(!v.is_pre_release());
+    assert_eq!(version("1.0.0-beta.1").pre, vec!["beta", "1"]);
+    assert_eq!(version("v1.2.3"), version("1.2.3"));
+}
+
+#[test]
+fn rejects_invalid_versions() {
+    assert!(SemanticVersion::from_str("1.2").is_err());
+    assert!(SemanticVersion::from_str("master 8f3c2a1").is_err());
+    assert!(SemanticVersion::from_str("1.2.3-").is_err());
+    assert!(SemanticVersion::from_str("1.2.3-beta..1").is_err());
+    assert_eq!(
+        SemanticVersion::from(String::from("development")),
+        SemanticVersion::default()
+    );
+}
+
+#[test]
+fn displays_versions() {
+    assert_eq!(version("v1.2.3").to_string(), "1.2.3");
+    assert_eq!(version("1.2.3-rc.2+abc").to_string(), "1.2.3-rc.2");
+}
+
+#[test]
+fn orders_versions() {
+    let ordered = [
+        "0.9.9",
+        "1.0.0-alpha",
+        "1.0.0-alpha.1",
+        "1.0.0-alpha.beta",
+        "1.0.0-beta",
+        "1.0.0-beta.2",
+        "1.0.0-beta.11",
+        "1.0.0-rc.1",
+        "1.0.0",
+        "1.0.1",
+        "1.1.0",
+        "2.0.0",
+    ];
+
+    for pair in ordered.windows(2) {
+        assert!(
+            version(pair[0]) < version(pair[1]),
+            "{} < {}",
+            pair[0],
+            pair[1]
+        );
+    }
+
+    assert_eq!(version("1.0.0+a"), version("1.0.0+b"));
+}
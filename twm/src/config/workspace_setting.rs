@@ -1,8 +1,11 @@
+use crate::tile_grid::layout::Layout;
+
 #[derive(Debug, Clone)]
 pub struct WorkspaceSetting {
     pub id: i32,
     pub monitor: i32,
     pub text: String,
+    pub layout: Layout,
 }
 
 impl Default for WorkspaceSetting {
@@ -11,6 +14,7 @@ impl Default for WorkspaceSetting {
             id: -1,
             monitor: -1,
             text: "".into(),
+            layout: Layout::default(),
         }
     }
 }
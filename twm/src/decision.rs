@@ -0,0 +1,224 @@
+//! Records why nog did or didn't manage a window, because a window that doesn't get tiled gives
+//! no hint about what stopped it.
+//!
+//! A window that isn't forced by the user or a rule has to pass every check to get managed.
+use crate::{
+    config::rule::{Action, WindowProperties},
+    system::NativeWindow,
+    system::WindowId,
+    window::gwl_ex_style::GwlExStyle,
+    window::gwl_style::GwlStyle,
+    AppState,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    /// Why the check passed or failed
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, passed: bool, detail: String) -> Self {
+        Self {
+            name,
+            passed,
+            detail,
+        }
+    }
+}
+
+/// What nog did with the window
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Tiled(i32),
+    Floating(i32),
+    Scratchpad(String),
+    NotManaged,
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Tiled(_) => "tiled",
+            Self::Floating(_) => "floating",
+            Self::Scratchpad(_) => "scratchpad",
+            Self::NotManaged => "not_managed",
+        }
+    }
+
+    pub fn workspace(&self) -> Option<i32> {
+        match self {
+            Self::Tiled(id) | Self::Floating(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Decision {
+    pub window: WindowId,
+    pub properties: WindowProperties,
+    pub style: GwlStyle,
+    pub ex_style: GwlExStyle,
+    /// Describes the matcher of the rule that applies to the window
+    pub rule: Option<String>,
+    pub action: Action,
+    /// The user asked nog to manage the window, like when toggling a window that floats
+    pub forced: bool,
+    pub checks: Vec<Check>,
+    pub outcome: Outcome,
+}
+
+impl Decision {
+    /// Runs the checks against the window, whose matching rule has to be set already. The outcome
+    /// stays `NotManaged` until nog manages the window.
+    pub fn evaluate(state: &AppState, window: &NativeWindow, forced: bool) -> Self {
+        let config = &state.config;
+        let rule = window.rule.clone().unwrap_or_default();
+
+        let size = match window.get_rect() {
+            Ok(rect) => {
+                let (width, height) = (rect.width(), rect.height());
+                let (min_width, min_height) = (config.min_width, config.min_height);
+                let passed = width >= min_width && height >= min_height;
+                let comparison = if passed {
+                    "is at least"
+                } else {
+                    "is smaller than"
+                };
+                Check::new(
+                    "size",
+                    passed,
+                    format!(
+                        "{}x{} {} the minimum of {}x{}",
+                        width, height, comparison, min_width, min_height
+                    ),
+                )
+            }
+            Err(_) => Check::new("size", false, "the size is unknown".into()),
+        };
+
+        let parent = match window.get_parent_window() {
+            Ok(id) => Check::new("parent", false, format!("has the parent window {}", id)),
+            Err(_) => Check::new("parent", true, "has no parent window".into()),
+        };
+
+        let style = if window.should_manage() {
+            Check::new(
+                "style",
+                true,
+                "has a title bar and isn't a modal dialog".into(),
+            )
+        } else {
+            Check::new(
+                "style",
+                false,
+                "has no title bar or is a modal dialog".into(),
+            )
+        };
+
+        let workspace = match state.get_current_display().get_focused_grid() {
+            Some(grid) if config.ignore_fullscreen_actions && grid.is_fullscreened() => Check::new(
+                "workspace",
+                false,
+                format!("workspace {} is fullscreened", grid.id),
+            ),
+            Some(grid) => Check::new(
+                "workspace",
+                true,
+                format!("workspace {} accepts new windows", grid.id),
+            ),
+            None => Check::new("workspace", false, "no workspace is focused".into()),
+        };
+
+        Self {
+            window: window.id,
+            properties: WindowProperties::capture(window),
+            style: window.get_style().unwrap_or_default(),
+            ex_style: window.get_ex_style().unwrap_or_default(),
+            rule: window.rule.as_ref().map(|rule| rule.matcher.to_string()),
+            action: rule.action,
+            forced,
+            checks: vec![size, parent, style, workspace],
+            outcome: Outcome::NotManaged,
+        }
+    }
+
+    /// Whether nog manages the window according to its rule and the checks
+    pub fn should_manage(&self) -> bool {
+        self.forced
+            || self.action == Action::Manage
+            || (self.action == Action::Validate && self.checks.iter().all(|c| c.passed))
+    }
+
+    pub fn failed_checks(&self) -> Vec<&Check> {
+        self.checks.iter().filter(|c| !c.passed).collect()
+    }
+
+    /// Explains the outcome in one sentence
+    pub fn summary(&self) -> String {
+        let reason = if self.forced {
+            "the user asked for it".to_string()
+        } else if self.action != Action::Validate {
+            format!("its rule says {}", self.action)
+        } else if self.should_manage() {
+            "it passed every check".to_string()
+        } else {
+            let failed = self
+                .failed_checks()
+                .iter()
+                .map(|c| c.name)
+                .collect::<Vec<_>>();
+            format!("these checks failed: {}", failed.join(", "))
+        };
+
+        match &self.outcome {
+            Outcome::Tiled(id) => format!("Tiled on workspace {}, because {}", id, reason),
+            Outcome::Floating(id) => format!("Floating on workspace {}, because {}", id, reason),
+            Outcome::Scratchpad(name) => format!("Moved to the scratchpad {} by its rule", name),
+            Outcome::NotManaged if self.should_manage() => {
+                "Not managed, because there was no workspace for it".into()
+            }
+            Outcome::NotManaged => format!("Not managed, because {}", reason),
+        }
+    }
+
+    /// The lines of the window inspector
+    pub fn lines(&self) -> Vec<String> {
+        let properties = &self.properties;
+        let mut lines = vec![
+            format!("Window {}", self.window),
+            format!("Title: {}", properties.title),
+            format!(
+                "Process: {} ({})",
+                properties.process_name, properties.exe_path
+            ),
+            format!("Class: {}", properties.class_name),
+            format!("Style: {:?}", self.style),
+            format!("Extended style: {:?}", self.ex_style),
+            format!(
+                "Rule: {}",
+                self.rule
+                    .as_ref()
+                    .map(|rule| format!("{} ({})", rule, self.action))
+                    .unwrap_or_else(|| "none".into())
+            ),
+            String::new(),
+        ];
+
+        for check in &self.checks {
+            let result = if check.passed { "passed" } else { "failed" };
+            lines.push(format!("{} {}: {}", check.name, result, check.detail));
+        }
+
+        lines.push(String::new());
+        lines.push(self.summary());
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests;
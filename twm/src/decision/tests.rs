@@ -0,0 +1,119 @@
+use super::{Decision, Outcome};
+use crate::{
+    config::rule::{Matcher, Rule},
+    system::{
+        fake,
+        fake::testing::{create_state, send_event, show_window},
+        fake::FakeWindow,
+        Rectangle,
+    },
+    win_event_handler::win_event_type::WinEventType,
+    window::gwl_style::GwlStyle,
+};
+use regex::Regex;
+
+fn failed_checks(decision: &Decision) -> Vec<&'static str> {
+    decision.failed_checks().iter().map(|c| c.name).collect()
+}
+
+#[test]
+fn managed_window_passes_every_check() {
+    let mut state = create_state();
+    let id = show_window(&mut state, FakeWindow::new("Terminal", "term.exe"));
+    let decision = &state.decisions[&id];
+
+    assert_eq!(decision.outcome, Outcome::Tiled(1));
+    assert!(failed_checks(decision).is_empty());
+    assert_eq!(
+        decision.summary(),
+        "Tiled on workspace 1, because it passed every check"
+    );
+}
+
+#[test]
+fn small_window_fails_the_size_check() {
+    let mut state = create_state();
+    let tooltip = FakeWindow::new("Tooltip", "term.exe").with_rect(Rectangle {
+        left: 0,
+        right: 100,
+        top: 0,
+        bottom: 20,
+    });
+    let id = show_window(&mut state, tooltip);
+    let decision = &state.decisions[&id];
+
+    assert_eq!(decision.outcome, Outcome::NotManaged);
+    assert_eq!(failed_checks(decision), vec!["size"]);
+    assert_eq!(
+        decision.summary(),
+        "Not managed, because these checks failed: size"
+    );
+}
+
+#[test]
+fn child_window_fails_the_parent_check() {
+    let mut state = create_state();
+    let parent = show_window(&mut state, FakeWindow::new("Editor", "editor.exe"));
+    let id = show_window(
+        &mut state,
+        FakeWindow::new("Find", "editor.exe").with_parent(parent),
+    );
+
+    assert_eq!(failed_checks(&state.decisions[&id]), vec!["parent"]);
+}
+
+#[test]
+fn rule_overrides_the_checks() {
+    let mut state = create_state();
+    state
+        .additonal_rules
+        .push(Rule::ignore_process(Regex::new("^term.exe$").unwrap()));
+    let id = show_window(&mut state, FakeWindow::new("Terminal", "term.exe"));
+    let decision = &state.decisions[&id];
+
+    assert_eq!(decision.outcome, Outcome::NotManaged);
+    assert_eq!(decision.rule.as_deref(), Some("process \"^term.exe$\""));
+    assert_eq!(
+        decision.summary(),
+        "Not managed, because its rule says ignore"
+    );
+}
+
+#[test]
+fn rule_with_unknown_workspace_leaves_the_window_alone() {
+    let mut state = create_state();
+    state.additonal_rules.push(Rule {
+        matcher: Matcher::Process(Regex::new("^term.exe$").unwrap()),
+        workspace_id: 42,
+        ..Default::default()
+    });
+    let id = show_window(&mut state, FakeWindow::new("Terminal", "term.exe"));
+    let window = fake::get_window(id).unwrap();
+
+    assert_eq!(state.decisions[&id].outcome, Outcome::NotManaged);
+    assert!(window.visible);
+    assert!(window.style.contains(GwlStyle::CAPTION));
+    assert_eq!(state.workspace_id, 1);
+}
+
+#[test]
+fn unseen_window_gets_explained_on_demand() {
+    let state = create_state();
+    let id = fake::add_window(FakeWindow::new("Terminal", "term.exe"));
+    let decision = state.explain_window(Some(id)).unwrap();
+
+    assert_eq!(decision.window, id);
+    assert_eq!(decision.properties.process_name, "term.exe");
+    assert_eq!(decision.outcome, Outcome::NotManaged);
+    assert!(decision.should_manage());
+}
+
+#[test]
+fn decisions_are_forgotten_when_the_window_gets_destroyed() {
+    let mut state = create_state();
+    let id = show_window(&mut state, FakeWindow::new("Terminal", "term.exe"));
+    fake::remove_window(id);
+    send_event(&mut state, WinEventType::Destroy, id);
+
+    assert!(!state.decisions.contains_key(&id));
+}
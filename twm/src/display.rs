@@ -6,7 +6,6 @@ use crate::{
     system::SystemResult,
     system::{api, Rectangle},
     task_bar,
-    tile_grid::store::Store,
     tile_grid::TileGrid,
 };
 use std::cmp::Ordering;
@@ -23,6 +22,12 @@ pub struct Display {
     pub appbar: Option<Bar>,
 }
 
+impl PartialEq<i32> for Display {
+    fn eq(&self, other: &i32) -> bool {
+        self.id == *other
+    }
+}
+
 impl Display {
     pub fn height(&self) -> i32 {
         self.rect.height()
@@ -121,6 +126,24 @@ impl Display {
 
         self.rect.left + offset
     }
+    /// Returns the space that tiles can use, which is the working area without the outer gap
+    pub fn tiling_area(&self, config: &Config) -> Rectangle {
+        let margin = if config.outer_gap > 0 {
+            config.outer_gap
+        } else {
+            0
+        };
+
+        let left = self.working_area_left() + (margin / 2);
+        let top = self.working_area_top(config) + (margin / 2);
+
+        Rectangle {
+            left,
+            top,
+            right: left + self.working_area_width(config) - margin,
+            bottom: top + self.working_area_height(config) - margin,
+        }
+    }
     pub fn get_grid_by_id(&self, id: i32) -> Option<&TileGrid> {
         self.grids.iter().find(|g| g.id == id)
     }
@@ -144,8 +167,6 @@ impl Display {
     pub fn refresh_grid(&self, config: &Config) -> SystemResult {
         if let Some(g) = self.get_focused_grid() {
             g.draw_grid(self, config)?;
-
-            Store::save(g.id, g.to_string());
         }
 
         Ok(())
@@ -228,14 +249,14 @@ pub fn init(config: &Config) -> Vec<Display> {
     });
 
     for i in 1..11 {
-        let monitor = config
-            .workspaces
-            .iter()
-            .find(|s| s.id == i)
-            .map(|s| s.monitor)
-            .unwrap_or(-1);
+        let setting = config.workspaces.iter().find(|s| s.id == i);
+        let monitor = setting.map(|s| s.monitor).unwrap_or(-1);
+
+        let mut grid = TileGrid::new(i, renderer::NativeRenderer);
 
-        let grid = TileGrid::new(i, renderer::NativeRenderer);
+        if let Some(setting) = setting {
+            grid.layout = setting.layout.clone();
+        }
 
         if let Some(d) = displays.get_mut((monitor - 1) as usize) {
             d.grids.push(grid);
@@ -1,11 +1,12 @@
 use crate::{
-    bar::item_section::ItemSection, keybindings::keybinding::Keybinding, popup::Popup,
-    system::DisplayId, win_event_handler::win_event::WinEvent,
+    bar::item_section::ItemSection, ipc::Request, keybindings::keybinding::Keybinding,
+    popup::Popup, system::DisplayId, win_event_handler::win_event::WinEvent,
 };
 use crossbeam_channel::unbounded;
 use crossbeam_channel::Receiver;
 use crossbeam_channel::Sender;
 use mlua::Error as LuaError;
+use serde_json::Value;
 
 #[derive(Debug, Clone)]
 pub enum Event {
@@ -15,16 +16,16 @@ pub enum Event {
     LuaRuntimeError(LuaError),
     CallCallback {
         idx: usize,
-        /// This is required, because the callbacks run in a seperate thread and mode callbacks
-        /// have to notify the keybindings manager that they finished executing so it can register
-        /// all of the mode specific bindings
-        is_mode_callback: bool,
+        /// Gets passed to the callback as a table, which is how hooks describe what happened
+        arg: Option<Value>,
     },
     ToggleAppbar(DisplayId),
     UpdateBarSections(DisplayId, ItemSection, ItemSection, ItemSection),
     ChangeWorkspace(i32, bool),
     RedrawAppBar,
     ReloadConfig,
+    /// A request of an IPC client, which gets answered once it got handled
+    IpcRequest(Request),
     Exit,
 }
 
@@ -1,4 +1,5 @@
 use crate::{
+    keybindings::condition::FocusedWindow,
     system::SystemResult,
     win_event_handler::{win_event::WinEvent, win_event_type::WinEventType},
     AppState,
@@ -10,6 +11,34 @@ mod focus_change;
 mod show;
 
 pub fn handle(state: &mut AppState, ev: WinEvent) -> SystemResult {
+    if ev.typ == WinEventType::Destroy {
+        for grid in state.get_grids_mut() {
+            grid.remove_floating(ev.window.id);
+        }
+        state.scratchpad.remove_window(ev.window.id);
+        state.decisions.remove(&ev.window.id);
+    }
+
+    // keybindings can depend on the focused window, even if nog doesn't manage it
+    if ev.typ == WinEventType::FocusChange {
+        if let Some(kbm) = &state.keybindings_manager {
+            kbm.focus_changed(FocusedWindow::capture(&ev.window));
+        }
+    }
+
+    // nog hides and shows floating and scratchpad windows on its own, which must not cause them to
+    // get managed
+    if let WinEventType::Show(_) = ev.typ {
+        let is_floating = state
+            .get_grids()
+            .iter()
+            .any(|g| g.get_floating_window(ev.window.id).is_some());
+
+        if is_floating || state.scratchpad.contains(ev.window.id) {
+            return Ok(());
+        }
+    }
+
     let grids = state.get_grids_mut();
     let mut title: Option<String> = None;
     let mut grid_id: Option<i32> = None;
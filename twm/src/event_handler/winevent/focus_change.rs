@@ -1,9 +1,25 @@
-use crate::{system::NativeWindow, system::SystemResult, AppState};
+use crate::{hooks::Hook, system::NativeWindow, system::SystemResult, AppState};
 
 pub fn handle(state: &mut AppState, window: NativeWindow) -> SystemResult {
     if let Some(g) = state.find_grid_containing_window(window.id) {
         g.focus_tile_by_window_id(window.id);
-        state.workspace_id = g.id;
+        let grid_id = g.id;
+        let previous = state.workspace_id;
+        state.workspace_id = grid_id;
+
+        if previous != grid_id {
+            state.emit_hook(
+                Hook::WorkspaceChange,
+                vec![("workspace", grid_id.into()), ("previous", previous.into())],
+            );
+        }
+        state.emit_hook(
+            Hook::FocusChange,
+            vec![
+                ("window", window.id.0.into()),
+                ("workspace", grid_id.into()),
+            ],
+        );
     }
 
     Ok(())
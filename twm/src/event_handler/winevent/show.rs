@@ -1,27 +1,16 @@
 use crate::{system::NativeWindow, system::SystemResult, AppState};
-use crate::config::rule::Action as RuleAction;
+use crate::config::{rule::Rule, Config};
+use crate::decision::{Decision, Outcome};
+use crate::hooks::Hook;
+use crate::system::{Rectangle, SystemError};
 use log::{debug, error};
 
 pub fn handle(state: &mut AppState, mut window: NativeWindow, force: bool) -> SystemResult {
-    let min_width = state.config.min_width;
-    let min_height = state.config.min_height;
-
     let config = state.config.clone();
-    let rect = fail!(window
+    fail!(window
         .get_rect()
         .map_err(|_| "Failed to get rectangle of new window"));
 
-    let too_small = rect.right - rect.left < min_width || rect.bottom - rect.top < min_height;
-
-    let grid_allows_managing = {
-        let display = state.get_current_display();
-        if let Some(grid) = display.get_focused_grid() {
-            !config.ignore_fullscreen_actions || !grid.is_fullscreened()
-        } else {
-            false
-        }
-    };
-
     let rules = config
         .rules
         .iter()
@@ -30,24 +19,161 @@ pub fn handle(state: &mut AppState, mut window: NativeWindow, force: bool) -> Sy
 
     window.set_matching_rule(rules);
 
-    let parent = window.get_parent_window();
     let rule = window.rule.clone().unwrap_or_default();
-    let should_manage = force || rule.action == RuleAction::Manage || (rule.action == RuleAction::Validate && !too_small && parent.is_err() && window.should_manage() && grid_allows_managing);
+    let mut decision = Decision::evaluate(state, &window, force);
 
-    if should_manage {
-        debug!("Managing window");
-        if rule.workspace_id != -1 {
-            state.change_workspace(rule.workspace_id, false)?;
+    if let Some(name) = rule.scratchpad.clone() {
+        match state.scratchpad.ensure_free(&name, window.id) {
+            Ok(()) => {
+                debug!("Moving window to the scratchpad {}", name);
+                decision.outcome = Outcome::Scratchpad(name.clone());
+                state.decisions.insert(window.id, decision);
+                state.scratchpad.insert(name, window);
+                return Ok(());
+            }
+            Err(e) => debug!("{}", e),
         }
+    }
+
+    if decision.should_manage() {
+        decision.outcome = manage(state, window, &rule, &config)?;
+    }
+
+    debug!("{}", decision.summary());
+    state.decisions.insert(decision.window, decision);
+
+    Ok(())
+}
+
+/// Moves the window into the workspace it belongs to
+fn manage(
+    state: &mut AppState,
+    mut window: NativeWindow,
+    rule: &Rule,
+    config: &Config,
+) -> SystemResult<Outcome> {
+    debug!("Managing window");
+    let in_background = rule.focus == Some(false);
+    let focused_window = state
+        .get_current_grid()
+        .and_then(|g| g.get_focused_window())
+        .cloned();
+
+    // the workspace of a rule might not exist, in which case the window stays untouched
+    let grid_id = match target_workspace(state, rule) {
+        Some(id) if state.find_grid_display(id).is_some() => id,
+        _ => return Ok(Outcome::NotManaged),
+    };
+
+    if rule.workspace_id != -1 && !in_background {
+        state.change_workspace(rule.workspace_id, false)?;
+    }
 
+    for grid in state.get_grids_mut() {
+        grid.remove_floating(window.id);
+    }
+
+    let is_visible = state
+        .find_grid_display(grid_id)
+        .is_some_and(|d| d.focused_grid_id == Some(grid_id));
+
+    // floating windows keep their title bar, like after toggling them
+    if !rule.float {
         window.init(config.remove_title_bar, config.use_border)?;
+    }
+
+    if let Some(opacity) = rule.opacity {
+        window
+            .set_opacity(opacity)
+            .map_err(SystemError::SetOpacity)?;
+    }
+
+    if !is_visible {
+        window.hide();
+    }
+
+    // the window gets moved into the grid
+    let mut fields = vec![
+        ("window", window.id.0.into()),
+        ("title", window.get_title().unwrap_or_default().into()),
+        ("process_name", window.get_process_name().into()),
+    ];
+    let display = match state.find_grid_display_mut(grid_id) {
+        Some(display) => display,
+        None => return Ok(Outcome::NotManaged),
+    };
+    let area = display.tiling_area(config);
+    let grid = display.get_grid_by_id_mut(grid_id).unwrap();
+    let previous = grid.get_focused_window().map(|w| w.id);
+    let focus_target = match rule.focus {
+        Some(true) if is_visible => Some(window.clone()),
+        // keeps the focus where it was, even if the window appeared on another display
+        Some(false) => focused_window,
+        _ => None,
+    };
 
-        let display = state.get_current_display_mut();
-        if let Some(grid) = display.get_focused_grid_mut() {
-            grid.push(window);
+    if rule.float {
+        let rect = floating_rect(&window, rule, &area)?;
+        window
+            .set_window_pos(rect, None, None)
+            .map_err(SystemError::MoveWindow)?;
+        grid.floating_windows.push(window.into());
+    } else {
+        grid.push(window);
+
+        if rule.fullscreen && !grid.is_fullscreened() {
+            grid.toggle_fullscreen();
+        }
+
+        if let (true, Some(previous)) = (in_background, previous) {
+            grid.focus_tile_by_window_id(previous);
         }
-        display.refresh_grid(&config)?;
     }
+    display.refresh_grid(config)?;
 
-    Ok(())
+    if let Some(window) = focus_target {
+        window.focus()?;
+    }
+
+    fields.push(("workspace", grid_id.into()));
+    state.emit_hook(Hook::WindowManaged, fields);
+
+    Ok(if rule.float {
+        Outcome::Floating(grid_id)
+    } else {
+        Outcome::Tiled(grid_id)
+    })
+}
+
+/// The workspace a new window gets moved to. Without a workspace or a display in the rule it is
+/// the focused workspace.
+fn target_workspace(state: &AppState, rule: &Rule) -> Option<i32> {
+    if rule.workspace_id != -1 {
+        return Some(rule.workspace_id);
+    }
+
+    rule.display
+        .and_then(|display| state.displays.get((display - 1) as usize))
+        .unwrap_or_else(|| state.get_current_display())
+        .focused_grid_id
+}
+
+/// Floating windows get centered in the tiling area, unless the rule has a position
+fn floating_rect(window: &NativeWindow, rule: &Rule, area: &Rectangle) -> SystemResult<Rectangle> {
+    let mut rect = window.get_rect().map_err(SystemError::MoveWindow)?;
+
+    if let Some((width, height)) = rule.size {
+        rect.right = rect.left + width;
+        rect.bottom = rect.top + height;
+    }
+
+    Ok(match rule.position {
+        Some((x, y)) => Rectangle {
+            left: area.left + x,
+            top: area.top + y,
+            right: area.left + x + rect.width(),
+            bottom: area.top + y + rect.height(),
+        },
+        None => rect.centered_in(area),
+    })
 }
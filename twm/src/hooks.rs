@@ -0,0 +1,68 @@
+//! Lua callbacks that get registered with `nog.on` and run whenever nog does something.
+//!
+//! Hooks run like keybinding callbacks: nog sends a `CallCallback` event with a table describing
+//! what happened, so they never run while the state is locked.
+use crate::event::{Event, EventSender};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use strum_macros::{Display, EnumString};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum Hook {
+    /// `{ window, workspace, title, process_name }`
+    WindowManaged,
+    /// `{ window, workspace }`
+    WindowUnmanaged,
+    /// `{ window, workspace }`
+    FocusChange,
+    /// `{ workspace, previous }`
+    WorkspaceChange,
+    /// `{ mode }`
+    ModeEnter,
+    /// `{ mode }`
+    ModeLeave,
+    WorkModeEnter,
+    WorkModeLeave,
+    ConfigReload,
+}
+
+/// The ids of the callbacks of every hook in the order they got registered
+#[derive(Clone, Debug, Default)]
+pub struct Hooks(HashMap<Hook, Vec<usize>>);
+
+impl Hooks {
+    pub fn add(&mut self, hook: Hook, callback_id: usize) {
+        self.0.entry(hook).or_default().push(callback_id);
+    }
+
+    /// The config registers every hook again when it gets reloaded
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Sends an event for every callback of the hook. `fields` describe what happened.
+    pub fn emit(&self, sender: &EventSender, hook: Hook, fields: Vec<(&str, Value)>) {
+        let callbacks = match self.0.get(&hook) {
+            Some(callbacks) => callbacks,
+            None => return,
+        };
+
+        let mut arg = Map::new();
+        arg.insert("hook".into(), hook.to_string().into());
+        for (key, value) in fields {
+            arg.insert(key.into(), value);
+        }
+        let arg = Value::Object(arg);
+
+        for idx in callbacks {
+            let _ = sender.send(Event::CallCallback {
+                idx: *idx,
+                arg: Some(arg.clone()),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,51 @@
+use super::{Hook, Hooks};
+use crate::event::{Event, EventChannel};
+use serde_json::json;
+use std::str::FromStr;
+
+#[test]
+fn parses_hook_names() {
+    assert_eq!(Hook::from_str("window_managed"), Ok(Hook::WindowManaged));
+    assert_eq!(Hook::from_str("work_mode_enter"), Ok(Hook::WorkModeEnter));
+    assert!(Hook::from_str("WindowManaged").is_err());
+    assert_eq!(Hook::ConfigReload.to_string(), "config_reload");
+}
+
+#[test]
+fn emits_every_callback_of_the_hook() {
+    let channel = EventChannel::default();
+    let mut hooks = Hooks::default();
+    hooks.add(Hook::FocusChange, 3);
+    hooks.add(Hook::WorkspaceChange, 4);
+    hooks.add(Hook::FocusChange, 5);
+
+    hooks.emit(
+        &channel.sender,
+        Hook::FocusChange,
+        vec![("window", json!(1))],
+    );
+
+    let calls = channel
+        .receiver
+        .try_iter()
+        .map(|event| match event {
+            Event::CallCallback { idx, arg, .. } => (idx, arg.unwrap()),
+            event => panic!("unexpected event {:?}", event),
+        })
+        .collect::<Vec<_>>();
+    let arg = json!({ "hook": "focus_change", "window": 1 });
+
+    assert_eq!(calls, vec![(3, arg.clone()), (5, arg)]);
+}
+
+#[test]
+fn clear_removes_every_callback() {
+    let channel = EventChannel::default();
+    let mut hooks = Hooks::default();
+    hooks.add(Hook::ConfigReload, 1);
+    hooks.clear();
+
+    hooks.emit(&channel.sender, Hook::ConfigReload, Vec::new());
+
+    assert!(channel.receiver.try_recv().is_err());
+}
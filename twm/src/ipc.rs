@@ -0,0 +1,363 @@
+//! Lets other programs control a running instance of nog through a local socket (a named pipe on
+//! windows).
+//!
+//! A client sends requests as single lines of JSON and gets a single line of JSON back for every
+//! request, in the order they were sent.
+//!
+//! ```text
+//! > {"id": 1, "method": "ws_focus", "params": ["left"]}
+//! < {"id":1,"result":null}
+//! > {"id": 2, "method": "ws_foo"}
+//! < {"error":"unknown method `ws_foo`","id":2}
+//! ```
+//!
+//! `id` is optional and gets sent back unchanged. Every `ws_*`, `win_*`, `get_*` and `is_*`
+//! function of the `nog` global can be called, in addition to `config_get` and `config_set`, which
+//! read and write `nog.config` using a dotted path like `bar.height`. `plugin_permissions` and
+//! `update_channels` can't be changed over IPC.
+//!
+//! Requests get turned into events, so they are handled one after another together with every
+//! other event of the main loop.
+//!
+//! A client that sends a `subscribe` request receives notifications about changes of the state
+//! afterwards instead of being able to send more requests (see [`subscription`](subscription)).
+use crate::{
+    event::{Event, EventSender},
+    lua::{get_err_msg, json_to_lua, lua_to_json, LuaRuntime},
+    AppState,
+};
+use crossbeam_channel::{unbounded, Sender};
+use log::{debug, error, info};
+use mlua::{Lua, MultiValue, Table, Value as LuaValue};
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    sync::Arc,
+    thread,
+};
+use thiserror::Error;
+
+mod subscription;
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod win;
+
+pub use subscription::{notify_changes, Status, Subscriber};
+#[cfg(unix)]
+pub use unix::{endpoint, Listener};
+#[cfg(windows)]
+pub use win::{endpoint, Listener};
+
+/// The prefixes of the functions of the `nog` global that can be called
+const EXPOSED_PREFIXES: [&str; 4] = ["ws_", "win_", "get_", "is_"];
+/// The settings that `config_set` can't change, because they decide what plugins and updates are
+/// allowed to do. Only the config itself can change them.
+const PROTECTED_SETTINGS: [&str; 2] = ["plugin_permissions", "update_channels"];
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum IpcError {
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("unknown method `{0}`")]
+    UnknownMethod(String),
+    #[error("`{0}` can only be changed by the config")]
+    ProtectedSetting(String),
+    #[error("{0}")]
+    Lua(String),
+}
+
+impl From<mlua::Error> for IpcError {
+    fn from(e: mlua::Error) -> Self {
+        IpcError::Lua(get_err_msg(&e))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub id: Value,
+    pub method: String,
+    pub params: Vec<Value>,
+    reply: Sender<Value>,
+}
+
+impl Request {
+    /// Parses a line that was sent by a client. The response gets sent to `reply`.
+    ///
+    /// An invalid request gets turned into the error response the client should receive.
+    pub fn parse(line: &str, reply: Sender<Value>) -> Result<Self, Value> {
+        let mut object = match serde_json::from_str(line) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => {
+                return Err(response(
+                    Value::Null,
+                    Err(IpcError::InvalidRequest("expected an object".into())),
+                ))
+            }
+            Err(e) => {
+                return Err(response(
+                    Value::Null,
+                    Err(IpcError::InvalidRequest(e.to_string())),
+                ))
+            }
+        };
+
+        let id = object.remove("id").unwrap_or(Value::Null);
+
+        let method = match object.remove("method") {
+            Some(Value::String(method)) => method,
+            _ => {
+                return Err(response(
+                    id,
+                    Err(IpcError::InvalidRequest(
+                        "`method` has to be a string".into(),
+                    )),
+                ))
+            }
+        };
+
+        let params = match object.remove("params") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(params)) => params,
+            Some(_) => {
+                return Err(response(
+                    id,
+                    Err(IpcError::InvalidRequest(
+                        "`params` has to be an array".into(),
+                    )),
+                ))
+            }
+        };
+
+        Ok(Self {
+            id,
+            method,
+            params,
+            reply,
+        })
+    }
+
+    /// A subscription keeps receiving notifications after the response
+    pub fn is_subscription(&self) -> bool {
+        self.method == "subscribe"
+    }
+
+    pub fn respond(self, result: Result<Value, IpcError>) {
+        // the client might have disconnected in the meantime
+        let _ = self.reply.send(response(self.id, result));
+    }
+}
+
+fn response(id: Value, result: Result<Value, IpcError>) -> Value {
+    let mut object = Map::new();
+    object.insert("id".into(), id);
+
+    match result {
+        Ok(value) => object.insert("result".into(), value),
+        Err(e) => object.insert("error".into(), Value::String(e.to_string())),
+    };
+
+    Value::Object(object)
+}
+
+/// Starts listening for clients in a separate thread. Every client gets its own thread.
+pub fn start(state_arc: Arc<Mutex<AppState>>) {
+    let sender = state_arc.lock().event_channel.sender.clone();
+
+    thread::spawn(move || {
+        let path = endpoint();
+        let listener = match Listener::bind(&path) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Failed to listen for IPC clients on {:?}: {}", path, e);
+                return;
+            }
+        };
+
+        info!("Listening for IPC clients on {:?}", path);
+
+        loop {
+            match listener.accept() {
+                Ok(connection) => {
+                    let sender = sender.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = handle_connection(connection, sender) {
+                            debug!("IPC client disconnected: {}", e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    error!("Failed to accept IPC client: {}", e);
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Answers the requests of a single client until it disconnects
+pub fn handle_connection(connection: impl Read + Write, sender: EventSender) -> io::Result<()> {
+    let mut reader = BufReader::new(connection);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // a subscription can't be allowed to block the main loop while notifying the client
+        let (reply_sender, reply_receiver) = unbounded();
+        let (response, is_subscription) = match Request::parse(&line, reply_sender) {
+            Ok(request) => {
+                let is_subscription = request.is_subscription();
+                // both of these only fail if nog is shutting down
+                if sender.send(Event::IpcRequest(request)).is_err() {
+                    return Ok(());
+                }
+                match reply_receiver.recv() {
+                    Ok(response) => (response, is_subscription),
+                    Err(_) => return Ok(()),
+                }
+            }
+            Err(response) => (response, false),
+        };
+
+        let subscribed = is_subscription && response.get("error").is_none();
+        let connection = reader.get_mut();
+        writeln!(connection, "{}", response)?;
+        connection.flush()?;
+
+        if subscribed {
+            // the client gets removed from the subscribers once the receiver is dropped
+            for notification in reply_receiver {
+                writeln!(connection, "{}", notification)?;
+                connection.flush()?;
+            }
+            return Ok(());
+        }
+    }
+}
+
+/// Handles a request that was received by the main loop
+pub fn handle_request(state_arc: Arc<Mutex<AppState>>, request: Request) {
+    if request.is_subscription() {
+        let result = Subscriber::new(&request.params, request.reply.clone())
+            .map(|subscriber| state_arc.lock().subscribers.push(subscriber))
+            .map(|_| Value::Null);
+        request.respond(result);
+        return;
+    }
+
+    let rt = state_arc.lock().lua_rt.clone();
+    let result = call(&rt, &request.method, &request.params);
+
+    request.respond(result);
+}
+
+fn call(rt: &LuaRuntime, method: &str, params: &[Value]) -> Result<Value, IpcError> {
+    match method {
+        "config_get" => {
+            let path = path_param(params)?;
+            Ok(rt.with_lua(|lua| {
+                let value = get_path(lua, &path)?;
+                Ok(lua_to_json(value))
+            })?)
+        }
+        "config_set" => {
+            let path = path_param(params)?;
+            let (key, parent_path) = match path.split_last() {
+                Some(x) => x,
+                None => {
+                    return Err(IpcError::InvalidRequest(
+                        "config_set needs the path of a setting".into(),
+                    ))
+                }
+            };
+            if let Some(setting) = PROTECTED_SETTINGS.iter().find(|s| **s == path[0]) {
+                return Err(IpcError::ProtectedSetting(setting.to_string()));
+            }
+            let value = params.get(1).cloned().unwrap_or(Value::Null);
+
+            let parent = rt.with_lua(|lua| match get_path(lua, parent_path)? {
+                LuaValue::Table(parent) => {
+                    // this goes through the proxy of the config, which applies the change
+                    parent.set(key.as_str(), json_to_lua(lua, &value)?)?;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            })?;
+
+            if parent {
+                Ok(Value::Null)
+            } else {
+                Err(IpcError::InvalidRequest(format!(
+                    "{} is not a table",
+                    parent_path.join(".")
+                )))
+            }
+        }
+        method if EXPOSED_PREFIXES.iter().any(|p| method.starts_with(p)) => {
+            let result = rt.with_lua(|lua| {
+                let nog_tbl = lua.globals().get::<_, Table>("nog")?;
+
+                match nog_tbl.get::<_, LuaValue>(method)? {
+                    LuaValue::Function(f) => {
+                        let args = params
+                            .iter()
+                            .map(|p| json_to_lua(lua, p))
+                            .collect::<mlua::Result<Vec<_>>>()?;
+                        let values = f.call::<_, MultiValue>(MultiValue::from_vec(args))?;
+                        let value = values.into_iter().next().unwrap_or(LuaValue::Nil);
+
+                        Ok(Some(lua_to_json(value)))
+                    }
+                    _ => Ok(None),
+                }
+            })?;
+
+            result.ok_or_else(|| IpcError::UnknownMethod(method.into()))
+        }
+        method => Err(IpcError::UnknownMethod(method.into())),
+    }
+}
+
+/// Returns the parts of the dotted path, which is the first parameter. A missing path refers to
+/// the whole config.
+fn path_param(params: &[Value]) -> Result<Vec<String>, IpcError> {
+    match params.first() {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::String(path)) if path.is_empty() => Ok(Vec::new()),
+        Some(Value::String(path)) => Ok(path.split('.').map(String::from).collect()),
+        Some(_) => Err(IpcError::InvalidRequest(
+            "the path has to be a string".into(),
+        )),
+    }
+}
+
+/// Returns the value inside of `nog.config` at the path or `nil` if it doesn't exist
+fn get_path<'lua>(lua: &'lua Lua, path: &[String]) -> mlua::Result<LuaValue<'lua>> {
+    let mut value = lua
+        .globals()
+        .get::<_, Table>("nog")?
+        .get::<_, LuaValue>("config")?;
+
+    for part in path {
+        value = match value {
+            // `get` also reads through the proxies of the config
+            LuaValue::Table(tbl) => tbl.get(part.as_str())?,
+            _ => return Ok(LuaValue::Nil),
+        };
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,228 @@
+//! Pushes notifications about changes of the state to clients that subscribed to them.
+//!
+//! The state gets captured before and after every event of the main loop. Every difference
+//! between the two turns into a notification like this one:
+//!
+//! ```text
+//! {"event":"workspace_changed","previous":1,"workspace":2}
+//! ```
+use super::IpcError;
+use crate::AppState;
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// The names of every notification a client can subscribe to
+pub const EVENTS: [&str; 7] = [
+    "workspace_changed",
+    "window_managed",
+    "window_unmanaged",
+    "focus_changed",
+    "mode_entered",
+    "mode_left",
+    "config_reloaded",
+];
+
+#[derive(Debug, Clone)]
+pub struct Subscriber {
+    /// The notifications the client wants to receive. Empty means every notification.
+    events: Vec<String>,
+    sender: Sender<Value>,
+}
+
+impl Subscriber {
+    /// `params` is an optional list of the notifications the client wants to receive
+    pub fn new(params: &[Value], sender: Sender<Value>) -> Result<Self, IpcError> {
+        let events = match params.first() {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(events)) => events
+                .iter()
+                .map(|event| match event.as_str() {
+                    Some(name) if EVENTS.contains(&name) => Ok(name.to_string()),
+                    _ => Err(IpcError::InvalidRequest(format!(
+                        "{} is not a notification",
+                        event
+                    ))),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => {
+                return Err(IpcError::InvalidRequest(
+                    "the notifications have to be a list".into(),
+                ))
+            }
+        };
+
+        Ok(Self { events, sender })
+    }
+
+    fn wants(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event)
+    }
+}
+
+/// The parts of the state that clients get notified about
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Status {
+    pub workspace_id: i32,
+    pub focused_window: Option<i32>,
+    /// Every tiled or floating window and its workspace, sorted by the id of the window
+    pub windows: Vec<(i32, i32)>,
+    pub mode: Option<String>,
+}
+
+impl Status {
+    /// Returns `None` if nobody would get notified anyway
+    pub fn capture(state_arc: &Arc<Mutex<AppState>>) -> Option<Self> {
+        let state = state_arc.lock();
+        if state.subscribers.is_empty() {
+            return None;
+        }
+
+        let mut windows = state
+            .get_grids()
+            .into_iter()
+            .flat_map(|grid| {
+                grid.get_windows()
+                    .into_iter()
+                    .chain(grid.floating_windows.iter().map(|f| &f.window))
+                    .map(move |window| (window.id.0, grid.id))
+            })
+            .collect::<Vec<_>>();
+        windows.sort();
+
+        Some(Self {
+            workspace_id: state.workspace_id,
+            focused_window: state
+                .get_current_grid()
+                .and_then(|grid| grid.get_focused_window())
+                .map(|window| window.id.0),
+            windows,
+            mode: state.modes.current().map(|mode| mode.name.clone()),
+        })
+    }
+
+    /// Returns a notification for every difference to the previous status
+    pub fn diff(&self, previous: &Status) -> Vec<Value> {
+        let mut notifications = Vec::new();
+
+        if self.workspace_id != previous.workspace_id {
+            notifications.push(notification(
+                "workspace_changed",
+                vec![
+                    ("workspace", self.workspace_id.into()),
+                    ("previous", previous.workspace_id.into()),
+                ],
+            ));
+        }
+
+        let window_ids =
+            |status: &Status| status.windows.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        let (ids, previous_ids) = (window_ids(self), window_ids(previous));
+
+        for (id, workspace_id) in &previous.windows {
+            if !ids.contains(id) {
+                notifications.push(notification(
+                    "window_unmanaged",
+                    vec![
+                        ("window", (*id).into()),
+                        ("workspace", (*workspace_id).into()),
+                    ],
+                ));
+            }
+        }
+
+        for (id, workspace_id) in &self.windows {
+            if !previous_ids.contains(id) {
+                notifications.push(notification(
+                    "window_managed",
+                    vec![
+                        ("window", (*id).into()),
+                        ("workspace", (*workspace_id).into()),
+                    ],
+                ));
+            }
+        }
+
+        if self.focused_window != previous.focused_window {
+            notifications.push(notification(
+                "focus_changed",
+                vec![(
+                    "window",
+                    self.focused_window.map(Value::from).unwrap_or(Value::Null),
+                )],
+            ));
+        }
+
+        if self.mode != previous.mode {
+            if let Some(mode) = &previous.mode {
+                notifications.push(notification(
+                    "mode_left",
+                    vec![("mode", mode.as_str().into())],
+                ));
+            }
+            if let Some(mode) = &self.mode {
+                notifications.push(notification(
+                    "mode_entered",
+                    vec![("mode", mode.as_str().into())],
+                ));
+            }
+        }
+
+        notifications
+    }
+}
+
+fn notification(event: &str, fields: Vec<(&str, Value)>) -> Value {
+    let mut object = Map::new();
+    object.insert("event".into(), event.into());
+
+    for (key, value) in fields {
+        object.insert(key.into(), value);
+    }
+
+    Value::Object(object)
+}
+
+/// Notifies the subscribers about every change since the status got captured.
+/// `config_reloaded` has to be set if the config got reloaded in the meantime.
+pub fn notify_changes(
+    state_arc: &Arc<Mutex<AppState>>,
+    previous: Option<Status>,
+    config_reloaded: bool,
+) {
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return,
+    };
+
+    let mut notifications = Vec::new();
+
+    if config_reloaded {
+        notifications.push(notification("config_reloaded", Vec::new()));
+    }
+
+    if let Some(status) = Status::capture(state_arc) {
+        notifications.extend(status.diff(&previous));
+    }
+
+    notify(&mut state_arc.lock(), notifications);
+}
+
+/// Sends the notifications to every subscriber that wants them. Subscribers that disconnected
+/// get removed.
+fn notify(state: &mut AppState, notifications: Vec<Value>) {
+    for notification in notifications {
+        let event = notification["event"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        state.subscribers.retain(|subscriber| {
+            !subscriber.wants(&event) || subscriber.sender.send(notification.clone()).is_ok()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests;
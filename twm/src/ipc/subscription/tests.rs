@@ -0,0 +1,80 @@
+use super::{Status, Subscriber};
+use crossbeam_channel::unbounded;
+use serde_json::{json, Value};
+
+fn status(workspace_id: i32, focused_window: Option<i32>, windows: Vec<(i32, i32)>) -> Status {
+    Status {
+        workspace_id,
+        focused_window,
+        windows,
+        mode: None,
+    }
+}
+
+#[test]
+fn same_status_has_no_notifications() {
+    let status = status(1, Some(1), vec![(1, 1)]);
+
+    assert!(status.diff(&status.clone()).is_empty());
+}
+
+#[test]
+fn reports_managed_and_unmanaged_windows() {
+    let previous = status(1, Some(1), vec![(1, 1), (2, 2)]);
+    let current = status(1, Some(1), vec![(1, 1), (3, 1)]);
+
+    assert_eq!(
+        current.diff(&previous),
+        vec![
+            json!({ "event": "window_unmanaged", "window": 2, "workspace": 2 }),
+            json!({ "event": "window_managed", "window": 3, "workspace": 1 }),
+        ]
+    );
+}
+
+#[test]
+fn switching_modes_leaves_the_previous_one_first() {
+    let mut previous = status(1, None, Vec::new());
+    previous.mode = Some("resize".into());
+    let mut current = previous.clone();
+    current.mode = Some("move".into());
+
+    assert_eq!(
+        current.diff(&previous),
+        vec![
+            json!({ "event": "mode_left", "mode": "resize" }),
+            json!({ "event": "mode_entered", "mode": "move" }),
+        ]
+    );
+}
+
+#[test]
+fn losing_focus_reports_null() {
+    let previous = status(1, Some(1), Vec::new());
+    let current = status(2, None, Vec::new());
+
+    assert_eq!(
+        current.diff(&previous),
+        vec![
+            json!({ "event": "workspace_changed", "workspace": 2, "previous": 1 }),
+            json!({ "event": "focus_changed", "window": Value::Null }),
+        ]
+    );
+}
+
+#[test]
+fn filters_notifications() {
+    let (sender, _) = unbounded();
+    let subscriber = Subscriber::new(&[json!(["mode_entered"])], sender).unwrap();
+
+    assert!(subscriber.wants("mode_entered"));
+    assert!(!subscriber.wants("mode_left"));
+}
+
+#[test]
+fn rejects_unknown_notifications() {
+    let (sender, _) = unbounded();
+
+    assert!(Subscriber::new(&[json!(["mode"])], sender.clone()).is_err());
+    assert!(Subscriber::new(&[json!("mode_entered")], sender).is_err());
+}
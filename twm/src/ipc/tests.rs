@@ -0,0 +1,129 @@
+use super::{call, handle_connection, IpcError, Request};
+use crate::{event::Event, lua::LuaRuntime};
+use crossbeam_channel::{bounded, unbounded};
+use serde_json::{json, Value};
+
+fn parse(line: &str) -> Result<Request, Value> {
+    let (sender, _) = bounded(1);
+    Request::parse(line, sender)
+}
+
+#[test]
+fn parses_request() {
+    let request = parse(r#"{"id": 7, "method": "ws_focus", "params": ["left"]}"#).unwrap();
+
+    assert_eq!(request.id, json!(7));
+    assert_eq!(request.method, "ws_focus");
+    assert_eq!(request.params, vec![json!("left")]);
+}
+
+#[test]
+fn id_and_params_are_optional() {
+    let request = parse(r#"{"method": "get_current_ws"}"#).unwrap();
+
+    assert_eq!(request.id, Value::Null);
+    assert!(request.params.is_empty());
+}
+
+#[test]
+fn invalid_request_keeps_id() {
+    let response = parse(r#"{"id": "a", "params": []}"#).unwrap_err();
+
+    assert_eq!(
+        response,
+        json!({ "id": "a", "error": "invalid request: `method` has to be a string" })
+    );
+}
+
+#[test]
+fn invalid_json_is_rejected() {
+    let response = parse("{").unwrap_err();
+
+    assert_eq!(response["id"], Value::Null);
+    assert!(response["error"].is_string());
+}
+
+#[test]
+fn config_set_rejects_protected_settings() {
+    let rt = LuaRuntime::new();
+
+    assert_eq!(
+        call(
+            &rt,
+            "config_set",
+            &[json!("plugin_permissions.x"), json!([])]
+        ),
+        Err(IpcError::ProtectedSetting("plugin_permissions".into()))
+    );
+    assert_eq!(
+        call(&rt, "config_set", &[json!("update_channels"), json!({})]),
+        Err(IpcError::ProtectedSetting("update_channels".into()))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn answers_every_line_of_a_connection() {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        os::unix::net::UnixStream,
+        thread,
+    };
+
+    let (sender, receiver) = unbounded();
+    let (server, mut client) = UnixStream::pair().unwrap();
+
+    thread::spawn(move || handle_connection(server, sender));
+    thread::spawn(move || {
+        for event in receiver {
+            if let Event::IpcRequest(request) = event {
+                let method = request.method.clone();
+                request.respond(Ok(Value::String(method)));
+            }
+        }
+    });
+
+    writeln!(client, r#"{{"id": 1, "method": "get_a"}}"#).unwrap();
+    writeln!(client).unwrap();
+    writeln!(client, "nope").unwrap();
+    writeln!(client, r#"{{"id": 2, "method": "get_b"}}"#).unwrap();
+
+    let mut lines = BufReader::new(client).lines();
+    let mut next = || serde_json::from_str::<Value>(&lines.next().unwrap().unwrap()).unwrap();
+
+    assert_eq!(next(), json!({ "id": 1, "result": "get_a" }));
+    assert!(next()["error"].is_string());
+    assert_eq!(next(), json!({ "id": 2, "result": "get_b" }));
+}
+
+#[cfg(unix)]
+#[test]
+fn subscription_streams_notifications() {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        os::unix::net::UnixStream,
+        thread,
+    };
+
+    let (sender, receiver) = unbounded();
+    let (server, mut client) = UnixStream::pair().unwrap();
+
+    thread::spawn(move || handle_connection(server, sender));
+    thread::spawn(move || {
+        for event in receiver {
+            if let Event::IpcRequest(request) = event {
+                let reply = request.reply.clone();
+                request.respond(Ok(Value::Null));
+                reply.send(json!({ "event": "config_reloaded" })).unwrap();
+            }
+        }
+    });
+
+    writeln!(client, r#"{{"id": 1, "method": "subscribe"}}"#).unwrap();
+
+    let mut lines = BufReader::new(client).lines();
+    let mut next = || serde_json::from_str::<Value>(&lines.next().unwrap().unwrap()).unwrap();
+
+    assert_eq!(next(), json!({ "id": 1, "result": null }));
+    assert_eq!(next(), json!({ "event": "config_reloaded" }));
+}
@@ -0,0 +1,52 @@
+use std::{
+    env, fs, io,
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+};
+
+/// Returns the path of the socket, which lives in the runtime directory of the user
+pub fn endpoint() -> PathBuf {
+    let mut path = env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+    path.push("nog.sock");
+    path
+}
+
+/// Removes the socket once it gets dropped
+pub struct Listener {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl Listener {
+    /// Fails if another instance is already listening on the path. A socket that got left behind
+    /// by an instance that didn't shut down cleanly gets replaced.
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "another instance of nog is already running",
+                ));
+            }
+            fs::remove_file(path)?;
+        }
+
+        Ok(Self {
+            listener: UnixListener::bind(path)?,
+            path: path.into(),
+        })
+    }
+
+    /// Blocks until the next client connects
+    pub fn accept(&self) -> io::Result<UnixStream> {
+        self.listener.accept().map(|(stream, _)| stream)
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
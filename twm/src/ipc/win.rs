@@ -0,0 +1,172 @@
+use std::{
+    cell::Cell,
+    io,
+    os::windows::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    ptr,
+};
+use winapi::{
+    shared::{
+        minwindef::{DWORD, LPCVOID, LPVOID},
+        winerror::{ERROR_ACCESS_DENIED, ERROR_BROKEN_PIPE, ERROR_PIPE_CONNECTED},
+    },
+    um::{
+        errhandlingapi::GetLastError,
+        fileapi::{FlushFileBuffers, ReadFile, WriteFile},
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        namedpipeapi::{ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe},
+        winbase::{
+            FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX, PIPE_READMODE_BYTE,
+            PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+        },
+        winnt::HANDLE,
+    },
+};
+
+const BUFFER_SIZE: DWORD = 4096;
+
+/// Returns the name of the named pipe
+pub fn endpoint() -> PathBuf {
+    PathBuf::from(r"\\.\pipe\nog")
+}
+
+pub struct Listener {
+    name: Vec<u16>,
+    /// The instance that got created by `bind` and isn't connected yet
+    first: Cell<Option<Connection>>,
+}
+
+impl Listener {
+    /// Fails if the pipe already exists, so no other program can create it before nog and receive
+    /// the requests of the clients.
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        let name: Vec<u16> = path
+            .as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let first = create_instance(&name, FILE_FLAG_FIRST_PIPE_INSTANCE).map_err(|e| {
+            if e.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) {
+                io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "the pipe already exists, another instance of nog might be running",
+                )
+            } else {
+                e
+            }
+        })?;
+
+        Ok(Self {
+            name,
+            first: Cell::new(Some(first)),
+        })
+    }
+
+    /// Creates a new instance of the pipe and blocks until a client connects to it
+    pub fn accept(&self) -> io::Result<Connection> {
+        let connection = match self.first.take() {
+            Some(connection) => connection,
+            None => create_instance(&self.name, 0)?,
+        };
+
+        // a client that connected between creating the pipe and waiting for it is fine as well
+        if unsafe { ConnectNamedPipe(connection.0, ptr::null_mut()) } == 0
+            && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED
+        {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(connection)
+    }
+}
+
+fn create_instance(name: &[u16], flags: DWORD) -> io::Result<Connection> {
+    let handle = unsafe {
+        CreateNamedPipeW(
+            name.as_ptr(),
+            PIPE_ACCESS_DUPLEX | flags,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            BUFFER_SIZE,
+            BUFFER_SIZE,
+            0,
+            ptr::null_mut(),
+        )
+    };
+
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(Connection(handle))
+}
+
+/// A connected instance of the pipe
+pub struct Connection(HANDLE);
+
+// The handle is only ever used by the thread that owns the connection
+unsafe impl Send for Connection {}
+
+impl io::Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read: DWORD = 0;
+        let ok = unsafe {
+            ReadFile(
+                self.0,
+                buf.as_mut_ptr() as LPVOID,
+                buf.len() as DWORD,
+                &mut read,
+                ptr::null_mut(),
+            )
+        };
+
+        if ok == 0 {
+            // the client closed its end of the pipe
+            if unsafe { GetLastError() } == ERROR_BROKEN_PIPE {
+                return Ok(0);
+            }
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(read as usize)
+    }
+}
+
+impl io::Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written: DWORD = 0;
+        let ok = unsafe {
+            WriteFile(
+                self.0,
+                buf.as_ptr() as LPCVOID,
+                buf.len() as DWORD,
+                &mut written,
+                ptr::null_mut(),
+            )
+        };
+
+        if ok == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(written as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if unsafe { FlushFileBuffers(self.0) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        unsafe {
+            DisconnectNamedPipe(self.0);
+            CloseHandle(self.0);
+        }
+    }
+}
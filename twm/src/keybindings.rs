@@ -1,11 +1,14 @@
 use crate::{config::Config, event::Event, popup::Popup, system, system::api, AppState};
+use chord::{ChordAction, ChordState};
+use condition::FocusedWindow;
+use conflict::Conflict;
 use key::Key;
-use keybinding::Keybinding;
+use keybinding::{KeyCombo, Keybinding};
 use log::{debug, error, info};
 use modifier::Modifier;
 use num_traits::FromPrimitive;
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::{
     fmt::Debug,
     sync::atomic::{AtomicBool, Ordering},
@@ -14,15 +17,17 @@ use std::{
     sync::mpsc::Sender,
     sync::Arc,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+pub mod chord;
+pub mod condition;
+pub mod conflict;
 pub mod key;
 pub mod keybinding;
+pub mod mode;
 pub mod modifier;
 
-pub type Mode = Option<String>;
-
 #[derive(Debug, Clone)]
 pub enum ChanMessage {
     Stop,
@@ -34,14 +39,34 @@ pub enum ChanMessage {
     UnregisterKeybinding(Keybinding),
     UnregisterKeybindingBatch(Vec<Keybinding>),
     UnregisterKeybindings,
-    ModeCbExecuted,
+    ReportConflicts,
+    FocusChanged(FocusedWindow),
+    /// The current mode changed. Contains the new mode and when the first active mode times out.
+    ModeChanged(Option<String>, Option<Instant>),
 }
 
 struct KbManagerInner {
     running: AtomicBool,
     stopped: AtomicBool,
     allow_right_alt: bool,
-    state: Arc<Mutex<AppState>>
+    state: Arc<Mutex<AppState>>,
+    /// How many keybindings share each registered hotkey
+    registered: HashMap<i32, usize>,
+    /// The key combos that got registered to continue the pending chord
+    chord_keys: Vec<Keybinding>,
+    /// The keybindings whose hotkey the OS refused to register
+    rejected: Vec<Keybinding>,
+    /// The window the conditions of the keybindings got checked against
+    focused: FocusedWindow,
+    /// The callback ids of the keybindings whose condition doesn't match the focused window
+    inactive: HashSet<usize>,
+    /// Registered hotkeys that got unregistered, because none of their keybindings is active
+    /// while the focused window has focus
+    passed_through: HashMap<i32, Keybinding>,
+    /// The mode whose keybindings are registered
+    mode: Option<String>,
+    /// When the first active mode times out
+    mode_expires_at: Option<Instant>,
 }
 
 impl KbManagerInner {
@@ -51,35 +76,94 @@ impl KbManagerInner {
             stopped: AtomicBool::new(false),
             state,
             allow_right_alt,
+            registered: HashMap::new(),
+            chord_keys: Vec::new(),
+            rejected: Vec::new(),
+            focused: FocusedWindow::default(),
+            inactive: HashSet::new(),
+            passed_through: HashMap::new(),
+            mode: None,
+            mode_expires_at: None,
         }
     }
 
-    pub fn unregister_kb(&self, kb: &Keybinding) {
-        info!("Unregistering {:?}", kb);
-        api::unregister_keybinding(kb).map_err(|err| {
-            error!("WINAPI {:?}", err);
-        });
-    }
+    /// The keybindings whose hotkeys are registered in the current mode
+    fn active_keybindings(&self, work_mode: bool) -> Vec<Keybinding> {
+        let mode = self.mode.as_deref();
 
-    pub fn unregister_all(&self) {
         self.state
             .lock()
             .config
             .keybindings
             .iter()
-            .for_each(|kb| self.unregister_kb(kb));
+            .filter(|kb| kb.is_active(work_mode, mode))
+            .cloned()
+            .collect()
+    }
+
+    pub fn unregister_kb(&mut self, kb: &Keybinding) {
+        self.rejected.retain(|x| !x.has_same_keys(kb));
+        self.unregister_hotkey(kb);
+    }
+
+    fn unregister_hotkey(&mut self, kb: &Keybinding) {
+        let count = match self.registered.get_mut(&kb.get_id()) {
+            Some(count) => count,
+            None => return,
+        };
+
+        *count -= 1;
+
+        if *count == 0 {
+            self.registered.remove(&kb.get_id());
+
+            // the hotkey isn't registered while its keys get passed through
+            if self.passed_through.remove(&kb.get_id()).is_some() {
+                return;
+            }
+
+            info!("Unregistering {:?}", kb);
+            if let Err(err) = api::unregister_keybinding(kb) {
+                error!("WINAPI {:?}", err);
+            }
+        }
     }
 
-    pub fn register_kb(&self, kb: &Keybinding) -> Result<(), String> {
-        info!("Registering {:?}", kb);
-        api::register_keybinding(kb).map_err(|err| {
-            let msg = KbManager::make_keybinding_error(&kb);
-            error!("{}", &msg);
-            msg
-        })
+    pub fn unregister_all(&mut self, work_mode: bool) {
+        let kbs = self.active_keybindings(work_mode);
+        kbs.iter().for_each(|kb| self.unregister_kb(kb));
     }
 
-    pub fn register_all(&self, kbs: &Vec<&Keybinding>, state_arc: Arc<Mutex<AppState>>) {
+    pub fn register_kb(&mut self, kb: &Keybinding) -> Result<(), String> {
+        let result = self.register_hotkey(kb);
+
+        if result.is_ok() {
+            self.rejected.retain(|x| x.get_id() != kb.get_id());
+        } else if !self.rejected.iter().any(|x| x.has_same_keys(kb)) {
+            self.rejected.push(kb.clone());
+        }
+
+        result
+    }
+
+    fn register_hotkey(&mut self, kb: &Keybinding) -> Result<(), String> {
+        let count = self.registered.entry(kb.get_id()).or_insert(0);
+
+        // chords that start with the same key combo share their hotkey
+        if *count == 0 {
+            info!("Registering {:?}", kb);
+            api::register_keybinding(kb).map_err(|err| {
+                error!("WINAPI {:?}", err);
+                KbManager::make_keybinding_error(kb)
+            })?;
+        }
+
+        *count += 1;
+
+        Ok(())
+    }
+
+    pub fn register_all(&mut self, kbs: &Vec<&Keybinding>, state_arc: Arc<Mutex<AppState>>) {
         let mut errors = Vec::new();
 
         for kb in kbs {
@@ -93,20 +177,119 @@ impl KbManagerInner {
         }
     }
 
-    pub fn get_keybinding(&self, key: Key, modifier: Modifier) -> Option<Keybinding> {
-                self.state
-                .lock()
-                .config
-                .keybindings
-                .iter()
-                .find(|kb| kb.key == key && kb.modifier == modifier)
-                .map(|kb| kb.clone())
+    /// Registers the key combos that can continue the pending chord until the chord is over. Key
+    /// combos that are already registered get reused.
+    pub fn register_chord_keys(&mut self, combos: Vec<KeyCombo>) {
+        self.unregister_chord_keys();
+
+        for combo in combos {
+            let kb = Keybinding::from(combo);
+            if self.register_hotkey(&kb).is_ok() {
+                self.chord_keys.push(kb);
+            }
+        }
+    }
+
+    pub fn unregister_chord_keys(&mut self) {
+        for kb in std::mem::take(&mut self.chord_keys) {
+            self.unregister_hotkey(&kb);
+        }
+    }
+
+    /// Swaps the hotkeys of the previous mode with the ones of the new mode. Hotkeys that both
+    /// modes use stay registered.
+    pub fn change_mode(&mut self, mode: Option<String>, work_mode: bool) {
+        let previous = self.active_keybindings(work_mode);
+        self.mode = mode;
+        let next = self.active_keybindings(work_mode);
+
+        let is_in = |kbs: &[Keybinding], kb: &Keybinding| {
+            kbs.iter().any(|x| x.callback_id == kb.callback_id)
+        };
+
+        // registering first keeps shared hotkeys registered
+        for kb in next.iter().filter(|kb| !is_in(&previous, kb)) {
+            // failures get reported together with every other conflict
+            let _ = self.register_kb(kb);
+        }
+
+        for kb in previous.iter().filter(|kb| !is_in(&next, kb)) {
+            self.unregister_kb(kb);
+        }
+    }
+
+    /// Unregisters the hotkeys whose keybindings are all inactive, so that the focused window
+    /// receives the keys instead, and registers the ones that have an active keybinding again.
+    /// `keybindings` are the keybindings of the current mode.
+    pub fn pass_through(&mut self, keybindings: &[Keybinding], inactive: HashSet<usize>) {
+        let mut needed = HashSet::new();
+        let mut unneeded = HashMap::new();
+
+        for kb in keybindings {
+            if inactive.contains(&kb.callback_id) {
+                unneeded.entry(kb.get_id()).or_insert(kb);
+            } else {
+                needed.insert(kb.get_id());
+            }
+        }
+
+        // the key combos that continue a pending chord are always needed
+        needed.extend(self.chord_keys.iter().map(|kb| kb.get_id()));
+
+        for (id, kb) in self.passed_through.clone() {
+            if needed.contains(&id) || !unneeded.contains_key(&id) {
+                self.passed_through.remove(&id);
+                info!("Registering {:?} again", kb);
+                if let Err(err) = api::register_keybinding(&kb) {
+                    error!("WINAPI {:?}", err);
+                }
+            }
+        }
+
+        for (id, kb) in unneeded {
+            if needed.contains(&id)
+                || !self.registered.contains_key(&id)
+                || self.passed_through.contains_key(&id)
+            {
+                continue;
+            }
+
+            info!("Passing {:?} through", kb);
+            match api::unregister_keybinding(kb) {
+                Ok(()) => {
+                    self.passed_through.insert(id, kb.clone());
+                }
+                Err(err) => error!("WINAPI {:?}", err),
+            }
+        }
+
+        self.inactive = inactive;
+    }
+}
+
+/// Checks the conditions of the keybindings against the focused window and passes the keys of
+/// inactive keybindings through
+fn update_conditions(inner: &Mutex<KbManagerInner>, state_arc: &Arc<Mutex<AppState>>) {
+    let work_mode = state_arc.lock().work_mode;
+    let kbs = inner.lock().active_keybindings(work_mode);
+    let rt = state_arc.lock().lua_rt.clone();
+
+    if kbs.iter().all(|kb| kb.when.is_none()) && inner.lock().passed_through.is_empty() {
+        inner.lock().inactive.clear();
+        return;
     }
+
+    let focused = inner.lock().focused.clone();
+    // predicates are lua callbacks, which may lock the state themselves
+    let inactive = condition::find_inactive(&kbs, &focused, &rt);
+
+    inner.lock().pass_through(&kbs, inactive);
 }
 
 #[derive(Clone)]
 pub struct KbManager {
     inner: Arc<Mutex<KbManagerInner>>,
+    chord: Arc<Mutex<ChordState>>,
     pub sender: Sender<ChanMessage>,
     receiver: Arc<Mutex<Receiver<ChanMessage>>>,
 }
@@ -122,6 +305,7 @@ impl KbManager {
         let (sender, receiver) = channel();
         Self {
             inner: Arc::new(Mutex::new(KbManagerInner::new(state, allow_right_alt))),
+            chord: Arc::new(Mutex::new(ChordState::default())),
             sender,
             receiver: Arc::new(Mutex::new(receiver)),
         }
@@ -172,6 +356,39 @@ impl KbManager {
             .send(ChanMessage::RegisterKeybindingBatch(kbs))
             .expect("Failed to send RegisterKeybindingBatch");
     }
+    /// Registers the keybindings of the mode in place of the ones of the previous mode
+    pub fn mode_changed(&self, mode: Option<String>, expires_at: Option<Instant>) {
+        self.sender
+            .send(ChanMessage::ModeChanged(mode, expires_at))
+            .expect("Failed to send ModeChanged");
+    }
+    /// Checks the conditions of the keybindings against the window that got focused
+    pub fn focus_changed(&self, window: FocusedWindow) {
+        self.sender
+            .send(ChanMessage::FocusChanged(window))
+            .expect("Failed to send FocusChanged");
+    }
+    /// The key combos of the chord that is waiting for its next key combo, like `ALT+W`
+    pub fn pending_chord(&self) -> Option<String> {
+        self.chord.lock().pending_keys()
+    }
+    /// The keybindings whose hotkey is most likely used by another application
+    pub fn unavailable_keybindings(&self) -> Vec<Conflict> {
+        self.inner
+            .lock()
+            .rejected
+            .iter()
+            .cloned()
+            .map(Conflict::Unavailable)
+            .collect()
+    }
+    /// Shows every conflict in a popup once the keybindings that are waiting to be registered
+    /// got registered
+    pub fn report_conflicts(&self) {
+        self.sender
+            .send(ChanMessage::ReportConflicts)
+            .expect("Failed to send ReportConflicts");
+    }
     pub fn is_running(&self) -> bool {
         self.inner.lock().running.load(Ordering::SeqCst)
     }
@@ -182,132 +399,196 @@ impl KbManager {
     }
     pub fn start(&self, state_arc: Arc<Mutex<AppState>>) {
         let inner = self.inner.clone();
+        let chord = self.chord.clone();
         let receiver = self.receiver.clone();
         let state = state_arc.clone();
 
         thread::spawn(move || {
             let receiver = receiver.lock();
             {
-                let inner = inner.lock();
-                inner.register_all(
-                    &state
-                        .lock()
-                        .config
-                        .keybindings
-                        .iter()
-                        .filter(|kb| kb.is_global())
-                        .collect(),
-                    state.clone(),
-                );
+                let mut inner = inner.lock();
+                for kb in inner.active_keybindings(false) {
+                    // failures get reported together with every other conflict
+                    let _ = inner.register_kb(&kb);
+                }
             }
 
             loop {
                 if let Ok(msg) = receiver.try_recv() {
                     debug!("KbManager received {:?}", msg);
+                    // the keybindings that are active can change
+                    let check_conditions = matches!(
+                        msg,
+                        ChanMessage::FocusChanged(_)
+                            | ChanMessage::EnterWorkMode
+                            | ChanMessage::LeaveWorkMode
+                            | ChanMessage::RegisterKeybinding(_)
+                            | ChanMessage::RegisterKeybindingBatch(_)
+                            | ChanMessage::RegisterKeybindings
+                            | ChanMessage::ModeChanged(..)
+                    );
+                    let work_mode = state.lock().work_mode;
+
                     match msg {
-                        ChanMessage::ModeCbExecuted => unreachable!(),
                         ChanMessage::Stop => {
                             debug!("Stopping KbManager");
-                            let inner = inner.lock();
-                            inner.unregister_all();
+                            let mut inner = inner.lock();
+                            inner.unregister_chord_keys();
+                            inner.unregister_all(work_mode);
                             inner.running.store(false, Ordering::SeqCst);
                             break;
                         }
                         ChanMessage::LeaveWorkMode => {
-                            let inner = inner.lock();
-                            for kb in state
-                                .lock()
-                                .config
-                                .keybindings
-                                .iter()
-                                .filter(|kb| !kb.is_global())
-                            {
+                            let mut inner = inner.lock();
+                            let kbs = inner.active_keybindings(true);
+                            for kb in kbs.iter().filter(|kb| !kb.is_global()) {
                                 inner.unregister_kb(kb);
                             }
                         }
                         ChanMessage::EnterWorkMode => {
-                            let inner = inner.lock();
-                            for kb in state
-                                .lock()
-                                .config
-                                .keybindings
-                                .iter()
-                                .filter(|kb| !kb.is_global())
-                            {
+                            let mut inner = inner.lock();
+                            let kbs = inner.active_keybindings(true);
+                            for kb in kbs.iter().filter(|kb| !kb.is_global()) {
                                 inner.register_kb(kb);
                             }
                         }
                         ChanMessage::UnregisterKeybinding(kb) => {
-                            let inner = inner.lock();
-                            inner.unregister_kb(&kb);
+                            let mut inner = inner.lock();
+                            if kb.is_active(work_mode, inner.mode.as_deref()) {
+                                inner.unregister_kb(&kb);
+                            }
                         }
                         ChanMessage::UnregisterKeybindingBatch(kbs) => {
-                            let inner = inner.lock();
+                            let mut inner = inner.lock();
                             for kb in kbs {
-                                inner.unregister_kb(&kb);
+                                if kb.is_active(work_mode, inner.mode.as_deref()) {
+                                    inner.unregister_kb(&kb);
+                                }
                             }
                         }
                         ChanMessage::RegisterKeybinding(kb) => {
-                            let inner = inner.lock();
-                            inner.register_kb(&kb);
+                            let mut inner = inner.lock();
+                            if kb.is_active(work_mode, inner.mode.as_deref()) {
+                                // failures get logged and show up in the unavailable keybindings
+                                let _ = inner.register_kb(&kb);
+                            }
                         }
                         ChanMessage::RegisterKeybindingBatch(kbs) => {
-                            let inner = inner.lock();
+                            let mut inner = inner.lock();
                             for kb in kbs {
-                                inner.register_kb(&kb);
+                                if kb.is_active(work_mode, inner.mode.as_deref()) {
+                                    // failures get logged and show up in the unavailable keybindings
+                                    let _ = inner.register_kb(&kb);
+                                }
                             }
                         }
                         ChanMessage::UnregisterKeybindings => {
+                            inner.lock().unregister_all(work_mode);
+                        }
+                        ChanMessage::ModeChanged(mode, expires_at) => {
+                            let mut inner = inner.lock();
+                            inner.unregister_chord_keys();
+                            chord.lock().cancel();
+                            inner.change_mode(mode, work_mode);
+                            inner.mode_expires_at = expires_at;
+                        }
+                        ChanMessage::ReportConflicts => {
                             let inner = inner.lock();
-                            if state.lock().work_mode {
-                                inner.unregister_all();
-                            } else {
-                                for kb in state
-                                    .lock()
-                                    .config
-                                    .keybindings
-                                    .iter()
-                                    .filter(|kb| kb.is_global())
-                                {
-                                    inner.unregister_kb(kb);
-                                }
-                            }
+                            let mut conflicts = state.lock().config.find_keybinding_conflicts();
+                            conflicts
+                                .extend(inner.rejected.iter().cloned().map(Conflict::Unavailable));
                             drop(inner);
+
+                            if !conflicts.is_empty() {
+                                Popup::error(
+                                    conflicts.iter().map(|c| c.to_string()).collect(),
+                                    state.clone(),
+                                );
+                            }
+                        }
+                        ChanMessage::FocusChanged(window) => {
+                            inner.lock().focused = window;
                         }
                         ChanMessage::RegisterKeybindings => {
-                            let inner = inner.lock();
-                            let work_mode = state.lock().work_mode;
-                            inner.register_all(
-                                &state
-                                    .lock()
-                                    .config
-                                    .keybindings
-                                    .iter()
-                                    .filter(|kb| kb.is_global() || work_mode)
-                                    .collect(),
-                                state.clone(),
-                            );
+                            let mut inner = inner.lock();
+                            let kbs = inner.active_keybindings(work_mode);
+                            inner.register_all(&kbs.iter().collect(), state.clone());
                         }
                     };
+
+                    if check_conditions {
+                        update_conditions(&inner, &state);
+                    }
                 }
 
                 let inner_lock = inner.lock();
-                let kb = do_loop(&inner_lock);
+                let combo = do_loop(&inner_lock);
                 drop(inner_lock);
-                if let Some(kb) = kb {
+                if let Some(combo) = combo {
+                    let inactive = inner.lock().inactive.clone();
                     // if we fail to grab state here, the key event will just need to be ignored
                     // to avoid blocking other threads that might be trying to change state.
                     if let Some(state) = state.try_lock_for(Duration::from_millis(100)) {
                         let work_mode = state.work_mode;
-                        if work_mode || kb.is_global() {
-                            let sender = state.event_channel.sender.clone();
-                            sender
-                                .send(Event::Keybinding(kb))
-                                .expect("Failed to send key event");
+                        let mode = state.modes.current().map(|mode| mode.name.as_str());
+                        let timeout =
+                            Duration::from_millis(state.config.chord_timeout.max(0) as u64);
+                        let kbs = state
+                            .config
+                            .keybindings
+                            .iter()
+                            .filter(|kb| kb.is_active(work_mode, mode))
+                            .filter(|kb| !inactive.contains(&kb.callback_id))
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        let sender = state.event_channel.sender.clone();
+                        drop(state);
+
+                        let mut chord = chord.lock();
+                        let was_pending = chord.is_pending();
+                        let action = chord.press(combo, &kbs, Instant::now(), timeout);
+                        let is_pending = chord.is_pending();
+                        drop(chord);
+
+                        match action {
+                            ChordAction::Trigger(kb) => {
+                                inner.lock().unregister_chord_keys();
+                                sender
+                                    .send(Event::Keybinding(kb))
+                                    .expect("Failed to send key event");
+                            }
+                            ChordAction::Wait(combos) => inner.lock().register_chord_keys(combos),
+                            ChordAction::Ignore => inner.lock().unregister_chord_keys(),
+                        }
+
+                        if was_pending || is_pending {
+                            let _ = sender.send(Event::RedrawAppBar);
                         }
                     }
                 }
 
+                if chord.lock().expire(Instant::now()) {
+                    debug!("Pending chord timed out");
+                    inner.lock().unregister_chord_keys();
+                    let _ = state.lock().event_channel.sender.send(Event::RedrawAppBar);
+                }
+
+                let now = Instant::now();
+                let mode_expired = {
+                    let mut inner = inner.lock();
+                    let expired = inner.mode_expires_at.is_some_and(|at| at <= now);
+                    if expired {
+                        inner.mode_expires_at = None;
+                    }
+                    expired
+                };
+
+                if mode_expired {
+                    debug!("Mode timed out");
+                    // sends the mode that is left over, once the expired modes are left
+                    state.lock().expire_modes(now);
+                }
+
                 thread::sleep(Duration::from_millis(10));
             }
         });
@@ -323,13 +604,20 @@ impl KbManager {
     }
 }
 
-#[cfg(not(any(target_os = "windows", target_os = "linux")))]
-fn do_loop(inner: &Arc<KbManagerInner>) -> Option<Keybinding> {
+#[cfg(not(any(target_os = "windows", target_os = "linux", feature = "headless")))]
+fn do_loop(inner: &Arc<KbManagerInner>) -> Option<KeyCombo> {
     todo!();
 }
 
-#[cfg(target_os = "windows")]
-fn do_loop(inner: &KbManagerInner) -> Option<Keybinding> {
+/// The fake system has no keyboard, so keybindings only get triggered by sending
+/// `Event::Keybinding` directly.
+#[cfg(feature = "headless")]
+fn do_loop(_inner: &KbManagerInner) -> Option<KeyCombo> {
+    None
+}
+
+#[cfg(all(target_os = "windows", not(feature = "headless")))]
+fn do_loop(inner: &KbManagerInner) -> Option<KeyCombo> {
     use winapi::um::winuser::GetKeyState;
     use winapi::um::winuser::VK_RMENU;
     use winapi::um::winuser::WM_HOTKEY;
@@ -350,9 +638,12 @@ fn do_loop(inner: &KbManagerInner) -> Option<Keybinding> {
         let modifier = Modifier::from_bits((msg.lParam & 0xffff) as u32).unwrap();
 
         if let Some(key) = Key::from_isize(msg.lParam >> 16) {
-            return inner.get_keybinding(key, modifier);
+            return Some(KeyCombo { key, modifier });
         }
     }
 
     None
 }
+
+#[cfg(test)]
+mod tests;
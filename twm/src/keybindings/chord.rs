@@ -0,0 +1,141 @@
+//! Keeps track of chords like `Alt+W 1`, which consist of key combos that have to be pressed one
+//! after another.
+//!
+//! Only the first key combo of a chord gets registered as a hotkey. Once it is pressed the chord
+//! is pending and the key combos that can follow have to be registered until the chord either
+//! completes or times out.
+use super::keybinding::{KeyCombo, Keybinding};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChordAction {
+    /// The pressed key combos trigger the keybinding
+    Trigger(Keybinding),
+    /// The pressed key combos are the start of at least one chord, which continues with one of
+    /// these key combos
+    Wait(Vec<KeyCombo>),
+    /// Nothing is bound to the pressed key combos
+    Ignore,
+}
+
+#[derive(Debug, Clone)]
+struct PendingChord {
+    pressed: Vec<KeyCombo>,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChordState {
+    pending: Option<PendingChord>,
+}
+
+impl ChordState {
+    /// Handles a key combo that got pressed. `keybindings` are the keybindings that are currently
+    /// active.
+    ///
+    /// A key combo that doesn't continue the pending chord cancels it and gets handled like it
+    /// was pressed on its own.
+    pub fn press(
+        &mut self,
+        combo: KeyCombo,
+        keybindings: &[Keybinding],
+        now: Instant,
+        timeout: Duration,
+    ) -> ChordAction {
+        self.expire(now);
+
+        let mut pressed = self
+            .pending
+            .take()
+            .map(|pending| pending.pressed)
+            .unwrap_or_default();
+        let continues_chord = !pressed.is_empty();
+        pressed.push(combo);
+
+        match resolve(&pressed, keybindings) {
+            ChordAction::Ignore if continues_chord => self.press(combo, keybindings, now, timeout),
+            ChordAction::Wait(next) => {
+                self.pending = Some(PendingChord {
+                    pressed,
+                    expires_at: now + timeout,
+                });
+                ChordAction::Wait(next)
+            }
+            action => action,
+        }
+    }
+
+    /// Cancels the pending chord if it timed out. Returns whether it did.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        match &self.pending {
+            Some(pending) if pending.expires_at <= now => {
+                self.pending = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Cancels the pending chord, like when the keybindings change
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// The key combos of the pending chord that were already pressed, like `ALT+W`
+    pub fn pending_keys(&self) -> Option<String> {
+        self.pending.as_ref().map(|pending| {
+            pending
+                .pressed
+                .iter()
+                .map(|combo| combo.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    }
+}
+
+/// Keybindings of the current mode win over global keybindings, which are active in every mode,
+/// and keybindings with a condition win over the ones without
+fn specificity(kb: &Keybinding) -> u8 {
+    (kb.mode.is_some() as u8) * 2 + kb.when.is_some() as u8
+}
+
+/// A keybinding that gets triggered by the pressed key combos wins over chords that start with
+/// them. If more than one keybinding gets triggered, the most specific one wins.
+fn resolve(pressed: &[KeyCombo], keybindings: &[Keybinding]) -> ChordAction {
+    let mut next = Vec::new();
+    let mut triggered: Option<&Keybinding> = None;
+
+    for kb in keybindings {
+        let combos = kb.key_combos();
+
+        if !combos.starts_with(pressed) {
+            continue;
+        }
+
+        match combos.get(pressed.len()) {
+            None => {
+                if triggered.is_none_or(|t| specificity(t) < specificity(kb)) {
+                    triggered = Some(kb);
+                }
+            }
+            Some(combo) if !next.contains(combo) => next.push(*combo),
+            Some(_) => {}
+        }
+    }
+
+    if let Some(kb) = triggered {
+        ChordAction::Trigger(kb.clone())
+    } else if next.is_empty() {
+        ChordAction::Ignore
+    } else {
+        ChordAction::Wait(next)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,136 @@
+use super::{ChordAction, ChordState};
+use crate::keybindings::{
+    condition::Condition,
+    keybinding::{KeyCombo, Keybinding},
+};
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+const TIMEOUT: Duration = Duration::from_millis(1000);
+
+fn kb(key: &str, callback_id: usize) -> Keybinding {
+    let mut kb = Keybinding::from_str(key).unwrap();
+    kb.callback_id = callback_id;
+    kb
+}
+
+fn combo(key: &str) -> KeyCombo {
+    KeyCombo::from_str(key).unwrap()
+}
+
+fn triggered(action: ChordAction) -> Option<usize> {
+    match action {
+        ChordAction::Trigger(kb) => Some(kb.callback_id),
+        _ => None,
+    }
+}
+
+#[test]
+fn single_key_combo_triggers_immediately() {
+    let kbs = vec![kb("Alt+H", 1), kb("Alt+W 1", 2)];
+    let mut state = ChordState::default();
+
+    let action = state.press(combo("Alt+H"), &kbs, Instant::now(), TIMEOUT);
+
+    assert_eq!(triggered(action), Some(1));
+    assert!(!state.is_pending());
+}
+
+#[test]
+fn chord_waits_for_its_next_key_combo() {
+    let kbs = vec![kb("Alt+W 1", 1), kb("Alt+W 2", 2), kb("Alt+W Shift+2", 3)];
+    let mut state = ChordState::default();
+    let now = Instant::now();
+
+    assert_eq!(
+        state.press(combo("Alt+W"), &kbs, now, TIMEOUT),
+        ChordAction::Wait(vec![combo("1"), combo("2"), combo("Shift+2")])
+    );
+    assert_eq!(state.pending_keys(), Some("ALT+W".into()));
+
+    let action = state.press(combo("2"), &kbs, now, TIMEOUT);
+
+    assert_eq!(triggered(action), Some(2));
+    assert_eq!(state.pending_keys(), None);
+}
+
+#[test]
+fn longer_chords_keep_waiting() {
+    let kbs = vec![kb("Alt+W G 1", 1)];
+    let mut state = ChordState::default();
+    let now = Instant::now();
+
+    state.press(combo("Alt+W"), &kbs, now, TIMEOUT);
+    state.press(combo("G"), &kbs, now, TIMEOUT);
+
+    assert_eq!(state.pending_keys(), Some("ALT+W G".into()));
+    assert_eq!(
+        triggered(state.press(combo("1"), &kbs, now, TIMEOUT)),
+        Some(1)
+    );
+}
+
+#[test]
+fn unrelated_key_combo_cancels_the_chord() {
+    let kbs = vec![kb("Alt+W 1", 1), kb("Alt+H", 2)];
+    let mut state = ChordState::default();
+    let now = Instant::now();
+
+    state.press(combo("Alt+W"), &kbs, now, TIMEOUT);
+    let action = state.press(combo("Alt+H"), &kbs, now, TIMEOUT);
+
+    assert_eq!(triggered(action), Some(2));
+    assert!(!state.is_pending());
+
+    state.press(combo("Alt+W"), &kbs, now, TIMEOUT);
+
+    assert_eq!(
+        state.press(combo("2"), &kbs, now, TIMEOUT),
+        ChordAction::Ignore
+    );
+    assert!(!state.is_pending());
+}
+
+#[test]
+fn chord_times_out() {
+    let kbs = vec![kb("Alt+W 1", 1)];
+    let mut state = ChordState::default();
+    let now = Instant::now();
+
+    state.press(combo("Alt+W"), &kbs, now, TIMEOUT);
+
+    assert!(!state.expire(now + TIMEOUT / 2));
+    assert!(state.expire(now + TIMEOUT));
+    assert!(!state.is_pending());
+
+    state.press(combo("Alt+W"), &kbs, now, TIMEOUT);
+
+    assert_eq!(
+        state.press(combo("1"), &kbs, now + TIMEOUT * 2, TIMEOUT),
+        ChordAction::Ignore
+    );
+}
+
+#[test]
+fn keybinding_wins_over_chords_that_start_with_it() {
+    let kbs = vec![kb("Alt+W 1", 1), kb("Alt+W", 2)];
+    let mut state = ChordState::default();
+
+    let action = state.press(combo("Alt+W"), &kbs, Instant::now(), TIMEOUT);
+
+    assert_eq!(triggered(action), Some(2));
+}
+
+#[test]
+fn keybinding_with_a_condition_wins() {
+    let mut in_editor = kb("Alt+H", 2);
+    in_editor.when = Some(Condition::default());
+    let kbs = vec![kb("Alt+H", 1), in_editor];
+    let mut state = ChordState::default();
+
+    let action = state.press(combo("Alt+H"), &kbs, Instant::now(), TIMEOUT);
+
+    assert_eq!(triggered(action), Some(2));
+}
@@ -0,0 +1,114 @@
+//! Conditions that only keep a keybinding active while certain windows have focus, so the same
+//! key combo can do different things depending on the focused window.
+//!
+//! The keybinding manager checks the conditions whenever the focus changes and unregisters
+//! hotkeys whose keybindings are all inactive, which passes the keys through to the focused
+//! window.
+use super::keybinding::Keybinding;
+use crate::{lua::LuaRuntime, system::NativeWindow, system::WindowId};
+use log::error;
+use mlua::ToLua;
+use regex::Regex;
+use std::collections::HashSet;
+
+/// The window that has focus, which the conditions get checked against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FocusedWindow {
+    pub id: WindowId,
+    pub title: String,
+    pub process_name: String,
+    pub class_name: String,
+}
+
+impl FocusedWindow {
+    pub fn capture(window: &NativeWindow) -> Self {
+        Self {
+            id: window.id,
+            title: window.get_title().unwrap_or_default(),
+            process_name: window.get_process_name(),
+            class_name: window.get_class_name().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Condition {
+    pub process_name: Option<Regex>,
+    pub title: Option<Regex>,
+    pub class_name: Option<Regex>,
+    /// The id of a lua callback, which receives the focused window and returns whether it matches
+    pub predicate: Option<usize>,
+    /// The keybinding is active unless the window matches
+    pub negated: bool,
+}
+
+impl PartialEq for Condition {
+    fn eq(&self, other: &Self) -> bool {
+        fn pattern(regex: &Option<Regex>) -> Option<&str> {
+            regex.as_ref().map(|r| r.as_str())
+        }
+
+        pattern(&self.process_name) == pattern(&other.process_name)
+            && pattern(&self.title) == pattern(&other.title)
+            && pattern(&self.class_name) == pattern(&other.class_name)
+            && self.predicate == other.predicate
+            && self.negated == other.negated
+    }
+}
+
+impl Condition {
+    /// Whether every pattern matches the window. The predicate isn't part of this.
+    pub fn matches_patterns(&self, window: &FocusedWindow) -> bool {
+        let is_match = |regex: &Option<Regex>, text: &str| {
+            regex.as_ref().is_none_or(|regex| regex.is_match(text))
+        };
+
+        is_match(&self.process_name, &window.process_name)
+            && is_match(&self.title, &window.title)
+            && is_match(&self.class_name, &window.class_name)
+    }
+
+    /// Whether the keybinding is active while the window has focus. The predicate only gets
+    /// called if every pattern matches.
+    pub fn is_active(&self, window: &FocusedWindow, rt: &LuaRuntime) -> bool {
+        let matches = self.matches_patterns(window)
+            && self
+                .predicate
+                .is_none_or(|id| call_predicate(id, window, rt));
+
+        matches != self.negated
+    }
+}
+
+/// A predicate that fails doesn't match
+fn call_predicate(id: usize, window: &FocusedWindow, rt: &LuaRuntime) -> bool {
+    rt.watch("keybinding condition", |lua| {
+        let window = window.clone().to_lua(lua)?;
+        LuaRuntime::get_callback(lua, id)?.call::<_, bool>(window)
+    })
+    .unwrap_or_else(|e| {
+        error!("Keybinding condition failed: {}", e);
+        false
+    })
+}
+
+/// Returns the callback ids of the keybindings whose condition doesn't match the focused window.
+///
+/// This calls lua predicates, so the state must not be locked.
+pub fn find_inactive(
+    keybindings: &[Keybinding],
+    window: &FocusedWindow,
+    rt: &LuaRuntime,
+) -> HashSet<usize> {
+    keybindings
+        .iter()
+        .filter(|kb| match &kb.when {
+            Some(condition) => !condition.is_active(window, rt),
+            None => false,
+        })
+        .map(|kb| kb.callback_id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
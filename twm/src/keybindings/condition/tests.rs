@@ -0,0 +1,77 @@
+use super::{find_inactive, Condition, FocusedWindow};
+use crate::{keybindings::keybinding::Keybinding, lua::LuaRuntime};
+use regex::Regex;
+use std::str::FromStr;
+
+fn editor() -> FocusedWindow {
+    FocusedWindow {
+        id: 1.into(),
+        title: "main.rs - nog - Visual Studio Code".into(),
+        process_name: "Code.exe".into(),
+        class_name: "Chrome_WidgetWin_1".into(),
+    }
+}
+
+fn condition(process_name: &str) -> Condition {
+    Condition {
+        process_name: Some(Regex::new(process_name).unwrap()),
+        ..Condition::default()
+    }
+}
+
+#[test]
+fn every_pattern_has_to_match() {
+    let mut cond = condition("^Code\\.exe$");
+    assert!(cond.matches_patterns(&editor()));
+
+    cond.title = Some(Regex::new("\\.lua").unwrap());
+    assert!(!cond.matches_patterns(&editor()));
+
+    cond.title = Some(Regex::new("\\.rs").unwrap());
+    cond.class_name = Some(Regex::new("^Chrome").unwrap());
+    assert!(cond.matches_patterns(&editor()));
+}
+
+#[test]
+fn negated_condition_is_active_unless_the_window_matches() {
+    let rt = LuaRuntime::new();
+    let mut cond = condition("^Code\\.exe$");
+    cond.negated = true;
+
+    assert!(!cond.is_active(&editor(), &rt));
+    assert!(cond.is_active(&FocusedWindow::default(), &rt));
+}
+
+#[test]
+fn finds_keybindings_whose_condition_does_not_match() {
+    let rt = LuaRuntime::new();
+    let mut in_editor = Keybinding::from_str("Alt+H").unwrap();
+    in_editor.callback_id = 1;
+    in_editor.when = Some(condition("^Code\\.exe$"));
+    let mut elsewhere = Keybinding::from_str("Alt+H").unwrap();
+    elsewhere.callback_id = 2;
+    let mut in_browser = Keybinding::from_str("Alt+J").unwrap();
+    in_browser.callback_id = 3;
+    in_browser.when = Some(condition("firefox"));
+
+    let kbs = vec![in_editor, elsewhere, in_browser];
+
+    assert_eq!(
+        find_inactive(&kbs, &editor(), &rt),
+        vec![3].into_iter().collect()
+    );
+    assert_eq!(
+        find_inactive(&kbs, &FocusedWindow::default(), &rt),
+        vec![1, 3].into_iter().collect()
+    );
+}
+
+#[test]
+fn conditions_with_the_same_patterns_are_equal() {
+    assert_eq!(condition("Code"), condition("Code"));
+    assert_ne!(condition("Code"), condition("code"));
+
+    let mut negated = condition("Code");
+    negated.negated = true;
+    assert_ne!(condition("Code"), negated);
+}
@@ -0,0 +1,140 @@
+//! Finds keybindings that can't get triggered the way the config expects.
+use super::keybinding::{Keybinding, KeybindingKind};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conflict {
+    /// `keybinding` can't get triggered, because `by` uses the same keys or is the start of its
+    /// chord
+    Shadowed {
+        keybinding: Keybinding,
+        by: Keybinding,
+    },
+    /// Two files bind the same keys. Only `duplicate`, which came last, can get triggered.
+    Duplicate {
+        keybinding: Keybinding,
+        duplicate: Keybinding,
+    },
+    /// The OS refused to register the hotkey, most likely because another application already
+    /// uses it
+    Unavailable(Keybinding),
+}
+
+impl Conflict {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Shadowed { .. } => "shadowed",
+            Self::Duplicate { .. } => "duplicate",
+            Self::Unavailable(_) => "unavailable",
+        }
+    }
+
+    /// The keybinding that can't get triggered
+    pub fn keybinding(&self) -> &Keybinding {
+        match self {
+            Self::Shadowed { keybinding, .. } => keybinding,
+            Self::Duplicate { keybinding, .. } => keybinding,
+            Self::Unavailable(keybinding) => keybinding,
+        }
+    }
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Shadowed { keybinding, by } => write!(
+                f,
+                "{} ({}) is shadowed by {} ({})",
+                keybinding.as_key_combo(),
+                describe(keybinding),
+                by.as_key_combo(),
+                describe(by)
+            ),
+            Self::Duplicate {
+                keybinding,
+                duplicate,
+            } => write!(
+                f,
+                "{} is bound in {} and {}",
+                keybinding.as_key_combo(),
+                keybinding.source.as_deref().unwrap_or("an unknown file"),
+                duplicate.source.as_deref().unwrap_or("an unknown file")
+            ),
+            Self::Unavailable(keybinding) => write!(
+                f,
+                "{} is already used by another application",
+                keybinding.as_key_combo()
+            ),
+        }
+    }
+}
+
+fn describe(kb: &Keybinding) -> String {
+    let kind = match kb.kind {
+        KeybindingKind::Global => "global",
+        KeybindingKind::Work => "work",
+        KeybindingKind::Normal => "normal",
+    };
+
+    match &kb.mode {
+        Some(mode) => format!("{} in mode {}", kind, mode),
+        None => kind.into(),
+    }
+}
+
+/// Returns the conflict that arises if `new` replaces `old`, which uses the same keys in the
+/// same mode
+pub fn find_replace_conflict(old: &Keybinding, new: &Keybinding) -> Option<Conflict> {
+    if old.kind != new.kind {
+        Some(Conflict::Shadowed {
+            keybinding: old.clone(),
+            by: new.clone(),
+        })
+    } else if old.source.is_some() && new.source.is_some() && old.source != new.source {
+        Some(Conflict::Duplicate {
+            keybinding: old.clone(),
+            duplicate: new.clone(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Finds every keybinding that is shadowed by another one in the list. The first keybinding
+/// with the same keys wins and a keybinding wins over chords that start with its keys.
+///
+/// A keybinding with a condition only shadows keybindings with the same condition, because they
+/// are active while different windows have focus. Keybindings of a mode win over the other
+/// keybindings with the same keys and can only get shadowed by a keybinding that is the start of
+/// their chord.
+pub fn find_conflicts(keybindings: &[Keybinding]) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+
+    for (i, kb) in keybindings.iter().enumerate() {
+        let combos = kb.key_combos();
+
+        let shadowed_by = keybindings.iter().enumerate().find(|(j, other)| {
+            let other_combos = other.key_combos();
+
+            if other.when.is_some() && other.when != kb.when {
+                false
+            } else if other_combos.len() == combos.len() {
+                *j < i && other_combos == combos && other.when == kb.when && other.mode == kb.mode
+            } else {
+                (other.mode.is_none() || other.mode == kb.mode) && combos.starts_with(&other_combos)
+            }
+        });
+
+        if let Some((_, by)) = shadowed_by {
+            conflicts.push(Conflict::Shadowed {
+                keybinding: kb.clone(),
+                by: by.clone(),
+            });
+        }
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,158 @@
+use super::{find_conflicts, Conflict};
+use crate::{
+    config::Config,
+    keybindings::condition::Condition,
+    keybindings::keybinding::{Keybinding, KeybindingKind},
+};
+use regex::Regex;
+use std::str::FromStr;
+
+fn kb(key: &str, kind: KeybindingKind, source: &str) -> Keybinding {
+    let mut kb = Keybinding::from_str(key).unwrap();
+    kb.kind = kind;
+    kb.source = Some(source.into());
+    kb
+}
+
+#[test]
+fn keybinding_shadows_chords_that_start_with_it() {
+    let leader = kb("Alt+W", KeybindingKind::Global, "init.lua");
+    let chord = kb("Alt+W 1", KeybindingKind::Normal, "init.lua");
+    let other = kb("Alt+Q 1", KeybindingKind::Normal, "init.lua");
+
+    let conflicts = find_conflicts(&[chord.clone(), other, leader.clone()]);
+
+    assert_eq!(
+        conflicts,
+        vec![Conflict::Shadowed {
+            keybinding: chord,
+            by: leader
+        }]
+    );
+    assert_eq!(
+        conflicts[0].to_string(),
+        "ALT+W 1 (normal) is shadowed by ALT+W (global)"
+    );
+}
+
+#[test]
+fn first_keybinding_with_the_same_keys_wins() {
+    let first = kb("Alt+H", KeybindingKind::Normal, "init.lua");
+    let second = kb("alt+h", KeybindingKind::Work, "init.lua");
+
+    assert_eq!(
+        find_conflicts(&[first.clone(), second.clone()]),
+        vec![Conflict::Shadowed {
+            keybinding: second,
+            by: first
+        }]
+    );
+}
+
+#[test]
+fn replacing_a_keybinding_of_another_file_is_a_duplicate() {
+    let mut config = Config::default();
+    let first = kb("Alt+H", KeybindingKind::Normal, "plugins/a.lua");
+    let mut second = kb("Alt+H", KeybindingKind::Normal, "plugins/b.lua");
+    second.callback_id = 7;
+
+    assert_eq!(config.add_keybinding(first.clone()), None);
+
+    let conflict = config.add_keybinding(second.clone()).unwrap();
+
+    assert_eq!(
+        conflict.to_string(),
+        "ALT+H is bound in plugins/a.lua and plugins/b.lua"
+    );
+    assert_eq!(config.keybindings, vec![second.clone()]);
+
+    // binding the keys again doesn't report the same conflict twice
+    config.add_keybinding(first);
+    config.add_keybinding(second);
+
+    assert_eq!(config.find_keybinding_conflicts().len(), 2);
+}
+
+#[test]
+fn replacing_a_keybinding_of_another_kind_shadows_it() {
+    let mut config = Config::default();
+    let mut global = kb("Alt+R", KeybindingKind::Global, "init.lua");
+    global.mode = Some("resize".into());
+    let mut normal = kb("Alt+R", KeybindingKind::Normal, "init.lua");
+    normal.mode = Some("resize".into());
+
+    config.add_keybinding(global);
+
+    assert_eq!(
+        config.add_keybinding(normal).unwrap().to_string(),
+        "ALT+R (global in mode resize) is shadowed by ALT+R (normal in mode resize)"
+    );
+    assert_eq!(config.keybindings.len(), 1);
+    assert_eq!(config.keybindings[0].kind, KeybindingKind::Normal);
+}
+
+#[test]
+fn keybindings_of_a_mode_win_over_other_keybindings() {
+    let mut config = Config::default();
+    let global = kb("Alt+R", KeybindingKind::Global, "init.lua");
+    let leader = kb("Alt+W", KeybindingKind::Global, "init.lua");
+    let mut in_mode = kb("Alt+R", KeybindingKind::Normal, "init.lua");
+    in_mode.mode = Some("resize".into());
+    let mut chord = kb("Alt+W 1", KeybindingKind::Normal, "init.lua");
+    chord.mode = Some("resize".into());
+
+    config.add_keybinding(global);
+    config.add_keybinding(leader.clone());
+
+    assert_eq!(config.add_keybinding(in_mode), None);
+    assert_eq!(config.add_keybinding(chord.clone()), None);
+    assert_eq!(config.keybindings.len(), 4);
+    assert_eq!(
+        config.find_keybinding_conflicts(),
+        vec![Conflict::Shadowed {
+            keybinding: chord,
+            by: leader
+        }]
+    );
+}
+
+#[test]
+fn rebinding_in_the_same_file_is_fine() {
+    let mut config = Config::default();
+    let first = kb("Alt+H", KeybindingKind::Normal, "init.lua");
+    let mut second = first.clone();
+    second.callback_id = 2;
+
+    config.add_keybinding(first);
+
+    assert_eq!(config.add_keybinding(second), None);
+    assert_eq!(config.keybindings[0].callback_id, 2);
+    assert!(config.find_keybinding_conflicts().is_empty());
+}
+
+#[test]
+fn keybindings_with_different_conditions_do_not_conflict() {
+    let mut config = Config::default();
+    let mut in_editor = kb("Alt+H", KeybindingKind::Normal, "init.lua");
+    in_editor.when = Some(Condition {
+        process_name: Some(Regex::new("^Code\\.exe$").unwrap()),
+        ..Condition::default()
+    });
+    let mut elsewhere = kb("Alt+H", KeybindingKind::Normal, "plugins/a.lua");
+    elsewhere.callback_id = 2;
+    let mut chord = kb("Alt+H 1", KeybindingKind::Normal, "init.lua");
+    chord.callback_id = 3;
+
+    assert_eq!(config.add_keybinding(in_editor.clone()), None);
+    assert_eq!(config.add_keybinding(elsewhere.clone()), None);
+    assert_eq!(config.add_keybinding(chord.clone()), None);
+
+    assert_eq!(config.keybindings.len(), 3);
+    assert_eq!(
+        config.find_keybinding_conflicts(),
+        vec![Conflict::Shadowed {
+            keybinding: chord,
+            by: elsewhere
+        }]
+    );
+}
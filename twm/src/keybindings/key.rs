@@ -1,7 +1,29 @@
-use strum_macros::{EnumString, Display, AsRefStr};
-use winapi::um::winuser::*;
+use num_traits::{FromPrimitive, ToPrimitive};
+use strum::IntoEnumIterator;
+use strum_macros::{EnumString, Display, AsRefStr, EnumIter};
 
-#[derive(Clone, Copy, FromPrimitive, ToPrimitive, PartialEq, EnumString, AsRefStr, Display, Debug)]
+/// Other names of keys that can be used in key combos. The names are case insensitive.
+const ALIASES: [(&str, Key); 17] = [
+    ("return", Key::Enter),
+    ("esc", Key::Escape),
+    ("del", Key::Delete),
+    ("ins", Key::Insert),
+    ("pgup", Key::PageUp),
+    ("pgdn", Key::PageDown),
+    ("menu", Key::Apps),
+    ("=", Key::Plus),
+    ("-", Key::Minus),
+    (";", Key::OEM_1),
+    ("/", Key::OEM_2),
+    ("`", Key::OEM_3),
+    ("[", Key::OEM_4),
+    ("\\", Key::OEM_5),
+    ("]", Key::OEM_6),
+    ("'", Key::OEM_7),
+    ("<", Key::OEM_102),
+];
+
+#[derive(Clone, Copy, PartialEq, EnumString, AsRefStr, Display, Debug, EnumIter)]
 #[allow(dead_code)]
 pub enum Key {
     Enter = 0x0D,
@@ -33,36 +55,63 @@ pub enum Key {
     X = 0x58,
     Y = 0x59,
     Z = 0x5A,
-    Tab = VK_TAB as isize,
-    Space = VK_SPACE as isize,
-    F1 = VK_F1 as isize,
-    F2 = VK_F2 as isize,
-    F3 = VK_F3 as isize,
-    F4 = VK_F4 as isize,
-    F5 = VK_F5 as isize,
-    F6 = VK_F6 as isize,
-    F7 = VK_F7 as isize,
-    F8 = VK_F8 as isize,
-    F9 = VK_F9 as isize,
-    F10 = VK_F10 as isize,
-    F11 = VK_F11 as isize,
-    F12 = VK_F12 as isize,
+    Tab = 0x09,
+    Space = 0x20,
+    F1 = 0x70,
+    F2 = 0x71,
+    F3 = 0x72,
+    F4 = 0x73,
+    F5 = 0x74,
+    F6 = 0x75,
+    F7 = 0x76,
+    F8 = 0x77,
+    F9 = 0x78,
+    F10 = 0x79,
+    F11 = 0x7A,
+    F12 = 0x7B,
+    F13 = 0x7C,
+    F14 = 0x7D,
+    F15 = 0x7E,
+    F16 = 0x7F,
+    F17 = 0x80,
+    F18 = 0x81,
+    F19 = 0x82,
+    F20 = 0x83,
+    F21 = 0x84,
+    F22 = 0x85,
+    F23 = 0x86,
+    F24 = 0x87,
     #[strum(serialize = ",")]
-    Comma = VK_OEM_COMMA as isize,
+    Comma = 0xBC,
     #[strum(serialize = ".")]
-    Period = VK_OEM_PERIOD as isize,
+    Period = 0xBE,
     #[strum(serialize = "Shift")]
-    LShift = VK_LSHIFT as isize,
+    LShift = 0xA0,
     #[strum(serialize = "Control")]
-    LControl = VK_LCONTROL as isize,
+    LControl = 0xA2,
     #[strum(serialize = "Alt")]
-    LAlt = VK_LMENU as isize,
-    Escape = VK_ESCAPE as isize,
-    Backspace = VK_BACK as isize,
+    LAlt = 0xA4,
+    Escape = 0x1B,
+    Backspace = 0x08,
     Left = 0x25,
     Up = 0x26,
     Right = 0x27,
     Down = 0x28,
+    PageUp = 0x21,
+    PageDown = 0x22,
+    End = 0x23,
+    Home = 0x24,
+    Insert = 0x2D,
+    Delete = 0x2E,
+    Clear = 0x0C,
+    Pause = 0x13,
+    CapsLock = 0x14,
+    NumLock = 0x90,
+    ScrollLock = 0x91,
+    PrintScreen = 0x2C,
+    /// The context menu key
+    Apps = 0x5D,
+    Sleep = 0x5F,
     #[strum(serialize = "0")]
     Zero = 0x30,
     #[strum(serialize = "1")]
@@ -83,13 +132,79 @@ pub enum Key {
     Eight = 0x38,
     #[strum(serialize = "9")]
     Nine = 0x39,
-    OEM_1 = VK_OEM_1 as isize,
-    OEM_2 = VK_OEM_2 as isize,
-    OEM_3 = VK_OEM_3 as isize,
-    OEM_4 = VK_OEM_4 as isize,
-    OEM_5 = VK_OEM_5 as isize,
-    OEM_6 = VK_OEM_6 as isize,
-    OEM_7 = VK_OEM_7 as isize,
-    OEM_8 = VK_OEM_8 as isize,
-    OEM_102 = VK_OEM_102 as isize,
+    OEM_1 = 0xBA,
+    OEM_2 = 0xBF,
+    OEM_3 = 0xC0,
+    OEM_4 = 0xDB,
+    OEM_5 = 0xDC,
+    OEM_6 = 0xDD,
+    OEM_7 = 0xDE,
+    OEM_8 = 0xDF,
+    OEM_102 = 0xE2,
+    Numpad0 = 0x60,
+    Numpad1 = 0x61,
+    Numpad2 = 0x62,
+    Numpad3 = 0x63,
+    Numpad4 = 0x64,
+    Numpad5 = 0x65,
+    Numpad6 = 0x66,
+    Numpad7 = 0x67,
+    Numpad8 = 0x68,
+    Numpad9 = 0x69,
+    NumpadMultiply = 0x6A,
+    NumpadAdd = 0x6B,
+    NumpadSeparator = 0x6C,
+    NumpadSubtract = 0x6D,
+    NumpadDecimal = 0x6E,
+    NumpadDivide = 0x6F,
+    BrowserBack = 0xA6,
+    BrowserForward = 0xA7,
+    BrowserRefresh = 0xA8,
+    BrowserStop = 0xA9,
+    BrowserSearch = 0xAA,
+    BrowserFavorites = 0xAB,
+    BrowserHome = 0xAC,
+    VolumeMute = 0xAD,
+    VolumeDown = 0xAE,
+    VolumeUp = 0xAF,
+    MediaNext = 0xB0,
+    MediaPrevious = 0xB1,
+    MediaStop = 0xB2,
+    MediaPlayPause = 0xB3,
+    LaunchMail = 0xB4,
+    LaunchMediaSelect = 0xB5,
+    LaunchApp1 = 0xB6,
+    LaunchApp2 = 0xB7,
+}
+
+impl Key {
+    /// Finds the key with the given name or alias, ignoring the case
+    pub fn from_name(name: &str) -> Option<Key> {
+        ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+            .map(|(_, key)| *key)
+            .or_else(|| Key::iter().find(|key| key.as_ref().eq_ignore_ascii_case(name)))
+    }
+}
+
+// The derives of num_derive would put these impls inside of a constant instead of next to the enum
+impl FromPrimitive for Key {
+    fn from_i64(n: i64) -> Option<Self> {
+        Key::iter().find(|key| *key as i64 == n)
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        Key::iter().find(|key| *key as u64 == n)
+    }
+}
+
+impl ToPrimitive for Key {
+    fn to_i64(&self) -> Option<i64> {
+        Some(*self as i64)
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        Some(*self as u64)
+    }
 }
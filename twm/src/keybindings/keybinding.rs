@@ -1,5 +1,19 @@
-use super::{key::Key, modifier::Modifier};
-use std::{fmt::Debug, str::FromStr};
+use super::{condition::Condition, key::Key, modifier::Modifier};
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum KeybindingError {
+    #[error("missing key in `{0}`")]
+    MissingKey(String),
+    #[error("unknown modifier `{0}` in `{1}`")]
+    UnknownModifier(String, String),
+    #[error("unknown key `{0}` in `{1}`")]
+    UnknownKey(String, String),
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum KeybindingKind {
@@ -27,19 +41,79 @@ impl Default for KeybindingKind {
     }
 }
 
+/// A key together with the modifiers that have to be held down while pressing it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct KeyCombo {
+    pub key: Key,
+    pub modifier: Modifier,
+}
+
+impl KeyCombo {
+    pub fn get_id(&self) -> i32 {
+        (self.key as u32 + self.modifier.bits() * 1000) as i32
+    }
+}
+
+impl Display for KeyCombo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifier.is_empty() {
+            write!(f, "{}", self.key)
+        } else {
+            let modifier_str = format!("{:?}", self.modifier).replace(" | ", "+");
+            write!(f, "{}+{}", modifier_str, self.key)
+        }
+    }
+}
+
+impl FromStr for KeyCombo {
+    type Err = KeybindingError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut key_combo_parts = s.split('+').collect::<Vec<&str>>();
+        let raw_key = key_combo_parts.pop().unwrap_or_default();
+
+        if raw_key.is_empty() {
+            return Err(KeybindingError::MissingKey(s.into()));
+        }
+
+        let mut modifier = Modifier::default();
+        for raw_modifier in key_combo_parts {
+            modifier.insert(
+                Modifier::from_name(raw_modifier).ok_or_else(|| {
+                    KeybindingError::UnknownModifier(raw_modifier.into(), s.into())
+                })?,
+            );
+        }
+
+        let key = Key::from_name(raw_key)
+            .ok_or_else(|| KeybindingError::UnknownKey(raw_key.into(), s.into()))?;
+
+        Ok(Self { key, modifier })
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct Keybinding {
     pub kind: KeybindingKind,
     /// This is the id of the callback in the global callbacks store
     pub callback_id: usize,
+    /// The mode that bound the keybinding
     pub mode: Option<String>,
+    /// The file that bound the keybinding, like the config or a plugin
+    pub source: Option<String>,
     pub key: Key,
     pub modifier: Modifier,
+    /// The key combos that have to be pressed one after another after `key` and `modifier` to
+    /// trigger a chord like `Alt+W 1`. Empty for every other keybinding.
+    pub sequence: Vec<KeyCombo>,
+    /// The keybinding is only active while the focused window matches the condition
+    pub when: Option<Condition>,
 }
 
 impl Keybinding {
+    /// The id of the hotkey that gets registered. It only depends on the first key combo, so
+    /// chords that start with the same key combo share their hotkey.
     pub fn get_id(&self) -> i32 {
-        (self.key as u32 + self.modifier.bits() * 1000) as i32
+        self.first_key_combo().get_id()
     }
 
     pub fn is_global(&self) -> bool {
@@ -54,80 +128,113 @@ impl Keybinding {
         self.kind == KeybindingKind::Normal
     }
 
-    pub fn as_key_combo(&self) -> String {
-        if self.modifier.is_empty() {
-            format!("{}", self.key)
-        } else {
-            let modifier_str = format!("{:?}", self.modifier).replace(" | ", "+");
-            format!("{}+{}", modifier_str, self.key)
+    pub fn is_chord(&self) -> bool {
+        !self.sequence.is_empty()
+    }
+
+    pub fn first_key_combo(&self) -> KeyCombo {
+        KeyCombo {
+            key: self.key,
+            modifier: self.modifier,
         }
     }
-}
 
-impl FromStr for Keybinding {
-    type Err = Box<dyn std::error::Error>;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let key_combo_parts = s.split('+').collect::<Vec<&str>>();
-        let modifier_count = key_combo_parts.len() - 1;
+    /// Every key combo that has to be pressed to trigger the keybinding in order
+    pub fn key_combos(&self) -> Vec<KeyCombo> {
+        let mut combos = vec![self.first_key_combo()];
+        combos.extend(self.sequence.iter().copied());
+        combos
+    }
+
+    /// Whether both keybindings get triggered by the same keys
+    pub fn has_same_keys(&self, other: &Keybinding) -> bool {
+        self.key == other.key && self.modifier == other.modifier && self.sequence == other.sequence
+    }
+
+    /// Whether both keybindings get triggered by the same keys in the same mode while the same
+    /// windows have focus
+    pub fn has_same_trigger(&self, other: &Keybinding) -> bool {
+        self.has_same_keys(other) && self.mode == other.mode && self.when == other.when
+    }
+
+    /// Whether the keybinding can get triggered. Keybindings of a mode are only active while it
+    /// is the current mode and normal keybindings are inactive while any mode is.
+    pub fn is_active(&self, work_mode: bool, mode: Option<&str>) -> bool {
+        if !work_mode && !self.is_global() {
+            return false;
+        }
+
+        match &self.mode {
+            Some(kb_mode) => Some(kb_mode.as_str()) == mode,
+            None => !self.is_normal() || mode.is_none(),
+        }
+    }
 
-        let modifier = key_combo_parts
+    pub fn as_key_combo(&self) -> String {
+        self.key_combos()
             .iter()
-            .take(modifier_count)
-            .map(|x| match x.to_lowercase().as_str() {
-                "alt" => Modifier::ALT,
-                "control" => Modifier::CONTROL,
-                "shift" => Modifier::SHIFT,
-                _ => Modifier::default(),
-            })
-            .fold(Modifier::default(), |mut sum, crr| {
-                sum.insert(crr);
-
-                sum
-            });
-
-        let mut raw_key = key_combo_parts.iter().last().unwrap().to_string();
-        let mut raw_key_chars = raw_key.chars();
-        raw_key = format!(
-            "{}{}",
-            raw_key_chars.next().unwrap().to_uppercase(),
-            raw_key_chars.collect::<String>()
-        );
-        let key = Key::from_str(&raw_key)
-            .ok()
-            .ok_or(format!("Invalid key {}", raw_key))?;
-
-        Ok(Self {
+            .map(|combo| combo.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl From<KeyCombo> for Keybinding {
+    fn from(combo: KeyCombo) -> Self {
+        Self {
             kind: KeybindingKind::default(),
             callback_id: 0,
             mode: None,
-            modifier,
-            key,
-        })
+            source: None,
+            key: combo.key,
+            modifier: combo.modifier,
+            sequence: Vec::new(),
+            when: None,
+        }
+    }
+}
+
+/// Parses a single key combo like `Alt+Enter` or a chord like `Alt+W 1`, which consists of key
+/// combos separated by whitespace.
+impl FromStr for Keybinding {
+    type Err = KeybindingError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut combos = s
+            .split_whitespace()
+            .map(KeyCombo::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            // the errors of a key combo only contain the combo instead of the whole keybinding
+            .map_err(|e| match e {
+                KeybindingError::MissingKey(_) => KeybindingError::MissingKey(s.into()),
+                KeybindingError::UnknownModifier(modifier, _) => {
+                    KeybindingError::UnknownModifier(modifier, s.into())
+                }
+                KeybindingError::UnknownKey(key, _) => KeybindingError::UnknownKey(key, s.into()),
+            })?
+            .into_iter();
+
+        let first = combos
+            .next()
+            .ok_or_else(|| KeybindingError::MissingKey(s.into()))?;
+        let mut kb = Self::from(first);
+        kb.sequence = combos.collect();
+
+        Ok(kb)
     }
 }
 
 impl Debug for Keybinding {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let modifier_str = format!("{:?}", self.modifier).replace(" | ", "+");
-        if modifier_str == "(empty)" {
-            f.write_str(&format!(
-                "Keybinding({:?}, {}, {:?}, {}, {:?})",
-                self.key,
-                self.callback_id,
-                self.kind,
-                self.get_id(),
-                self.mode
-            ))
-        } else {
-            f.write_str(&format!(
-                "Keybinding({}+{:?}, {}, {:?}, {}, {:?})",
-                modifier_str,
-                self.key,
-                self.callback_id,
-                self.kind,
-                self.get_id(),
-                self.mode
-            ))
-        }
+        f.write_str(&format!(
+            "Keybinding({}, {}, {:?}, {}, {:?})",
+            self.as_key_combo(),
+            self.callback_id,
+            self.kind,
+            self.get_id(),
+            self.mode
+        ))
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,150 @@
+use super::{KeyCombo, Keybinding, KeybindingError, KeybindingKind};
+use crate::keybindings::{key::Key, modifier::Modifier};
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+
+#[test]
+fn parses_key_combo() {
+    let kb = Keybinding::from_str("Alt+Control+t").unwrap();
+
+    assert_eq!(kb.key, Key::T);
+    assert_eq!(kb.modifier, Modifier::ALT | Modifier::CONTROL);
+    assert!(!kb.is_chord());
+}
+
+#[test]
+fn parses_chord() {
+    let kb = Keybinding::from_str("Alt+W  Shift+1 2").unwrap();
+
+    assert_eq!(
+        kb.key_combos(),
+        vec![
+            KeyCombo {
+                key: Key::W,
+                modifier: Modifier::ALT
+            },
+            KeyCombo {
+                key: Key::One,
+                modifier: Modifier::SHIFT
+            },
+            KeyCombo {
+                key: Key::Two,
+                modifier: Modifier::empty()
+            },
+        ]
+    );
+    assert_eq!(kb.as_key_combo(), "ALT+W SHIFT+1 2");
+}
+
+#[test]
+fn chords_share_the_id_of_their_first_key_combo() {
+    let leader = Keybinding::from_str("Alt+W").unwrap();
+    let a = Keybinding::from_str("Alt+W 1").unwrap();
+    let b = Keybinding::from_str("Alt+W 2").unwrap();
+
+    assert_eq!(a.get_id(), leader.get_id());
+    assert_eq!(b.get_id(), leader.get_id());
+    assert!(!a.has_same_keys(&b));
+    assert!(a.has_same_keys(&Keybinding::from_str("alt+w 1").unwrap()));
+}
+
+#[test]
+fn every_key_combo_survives_a_round_trip() {
+    let modifiers = [
+        Modifier::ALT,
+        Modifier::CONTROL,
+        Modifier::SHIFT,
+        Modifier::WIN,
+    ];
+
+    for key in Key::iter() {
+        for bits in 0..(1 << modifiers.len()) {
+            let modifier = modifiers
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| bits & (1 << i) != 0)
+                .fold(Modifier::empty(), |sum, (_, m)| sum | *m);
+            let combo = KeyCombo { key, modifier };
+            let kb = Keybinding::from(combo);
+
+            assert_eq!(
+                Keybinding::from_str(&kb.as_key_combo()).map(|kb| kb.first_key_combo()),
+                Ok(combo),
+                "{}",
+                kb.as_key_combo()
+            );
+        }
+    }
+}
+
+#[test]
+fn every_key_has_a_unique_name() {
+    for key in Key::iter() {
+        assert_eq!(Key::from_name(&key.to_string().to_lowercase()), Some(key));
+    }
+}
+
+#[test]
+fn parses_aliases() {
+    let kb = Keybinding::from_str("ctrl+cmd+return").unwrap();
+    assert_eq!(kb.key, Key::Enter);
+    assert_eq!(kb.modifier, Modifier::CONTROL | Modifier::WIN);
+
+    let kb = Keybinding::from_str("Super+Shift+;").unwrap();
+    assert_eq!(kb.key, Key::OEM_1);
+    assert_eq!(kb.modifier, Modifier::WIN | Modifier::SHIFT);
+
+    assert_eq!(Key::from_name("ESC"), Some(Key::Escape));
+    assert_eq!(Key::from_name("f24"), Some(Key::F24));
+    assert_eq!(Key::from_name("numpad7"), Some(Key::Numpad7));
+}
+
+#[test]
+fn reports_unknown_tokens() {
+    assert_eq!(
+        Keybinding::from_str("lalt+w"),
+        Err(KeybindingError::UnknownModifier(
+            "lalt".into(),
+            "lalt+w".into()
+        ))
+    );
+    assert_eq!(
+        Keybinding::from_str("Alt+W Foo"),
+        Err(KeybindingError::UnknownKey(
+            "Foo".into(),
+            "Alt+W Foo".into()
+        ))
+    );
+    assert_eq!(
+        Keybinding::from_str("Alt+"),
+        Err(KeybindingError::MissingKey("Alt+".into()))
+    );
+    assert_eq!(
+        Keybinding::from_str("Alt+W Alt+"),
+        Err(KeybindingError::MissingKey("Alt+W Alt+".into()))
+    );
+    assert_eq!(
+        Keybinding::from_str(" "),
+        Err(KeybindingError::MissingKey(" ".into()))
+    );
+}
+
+#[test]
+fn keybindings_of_a_mode_replace_normal_keybindings() {
+    let mut in_mode = Keybinding::from_str("H").unwrap();
+    in_mode.mode = Some("resize".into());
+    let normal = Keybinding::from_str("Alt+H").unwrap();
+    let mut work = Keybinding::from_str("Alt+W").unwrap();
+    work.kind = KeybindingKind::Work;
+
+    assert!(normal.is_active(true, None));
+    assert!(!in_mode.is_active(true, None));
+
+    assert!(!normal.is_active(true, Some("resize")));
+    assert!(in_mode.is_active(true, Some("resize")));
+    assert!(!in_mode.is_active(true, Some("move")));
+    assert!(work.is_active(true, Some("resize")));
+
+    assert!(!in_mode.is_active(false, Some("resize")));
+    assert!(!work.is_active(false, None));
+}
@@ -0,0 +1,122 @@
+//! Modes change what the keys do. While a mode is active only the keybindings that got bound
+//! while defining it are active, in place of the normal keybindings.
+//!
+//! Modes stack, so leaving a mode that got entered from another mode returns to the other mode.
+use std::time::{Duration, Instant};
+
+/// A mode that got defined with `nog.mode`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mode {
+    pub name: String,
+    /// Leaves the mode once it was active for this long
+    pub timeout: Option<Duration>,
+    /// Leaves the mode once one of its keybindings got triggered
+    pub oneshot: bool,
+}
+
+impl Mode {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            timeout: None,
+            oneshot: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ActiveMode {
+    mode: Mode,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModeStack {
+    active: Vec<ActiveMode>,
+}
+
+impl ModeStack {
+    /// The mode whose keybindings are active
+    pub fn current(&self) -> Option<&Mode> {
+        self.active.last().map(|active| &active.mode)
+    }
+
+    /// The names of every active mode in the order they got entered
+    pub fn names(&self) -> Vec<String> {
+        self.active
+            .iter()
+            .map(|active| active.mode.name.clone())
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// Enters the mode on top of the current one. Returns false if the mode is already active.
+    pub fn enter(&mut self, mode: &Mode, now: Instant) -> bool {
+        if self.contains(&mode.name) {
+            return false;
+        }
+
+        self.active.push(ActiveMode {
+            mode: mode.clone(),
+            expires_at: mode.timeout.map(|timeout| now + timeout),
+        });
+
+        true
+    }
+
+    /// Leaves the mode and every mode that got entered after it. Returns the names of the modes
+    /// that got left, the last one first.
+    pub fn leave(&mut self, name: &str) -> Vec<String> {
+        match self.position(name) {
+            Some(i) => self.leave_from(i),
+            None => Vec::new(),
+        }
+    }
+
+    /// Leaves every mode. Returns the names of the modes that got left, the last one first.
+    pub fn clear(&mut self) -> Vec<String> {
+        self.leave_from(0)
+    }
+
+    /// Leaves every mode that timed out together with the modes that got entered after it
+    pub fn expire(&mut self, now: Instant) -> Vec<String> {
+        let expired = self.active.iter().position(|active| {
+            active
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= now)
+        });
+
+        match expired {
+            Some(i) => self.leave_from(i),
+            None => Vec::new(),
+        }
+    }
+
+    /// When the first active mode times out
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.active
+            .iter()
+            .filter_map(|active| active.expires_at)
+            .min()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.active
+            .iter()
+            .position(|active| active.mode.name == name)
+    }
+
+    fn leave_from(&mut self, i: usize) -> Vec<String> {
+        self.active
+            .drain(i..)
+            .rev()
+            .map(|active| active.mode.name)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
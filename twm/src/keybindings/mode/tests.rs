@@ -0,0 +1,58 @@
+use super::{Mode, ModeStack};
+use std::time::{Duration, Instant};
+
+fn mode(name: &str, timeout: Option<u64>) -> Mode {
+    Mode {
+        timeout: timeout.map(Duration::from_millis),
+        ..Mode::new(name)
+    }
+}
+
+#[test]
+fn leaving_a_mode_returns_to_the_previous_one() {
+    let mut stack = ModeStack::default();
+    let now = Instant::now();
+
+    assert!(stack.enter(&mode("resize", None), now));
+    assert!(stack.enter(&mode("move", None), now));
+    assert!(!stack.enter(&mode("resize", None), now));
+    assert_eq!(stack.names(), vec!["resize", "move"]);
+
+    assert_eq!(stack.leave("move"), vec!["move"]);
+    assert_eq!(stack.current().map(|m| m.name.as_str()), Some("resize"));
+    assert!(stack.leave("move").is_empty());
+}
+
+#[test]
+fn leaving_a_mode_leaves_every_mode_entered_after_it() {
+    let mut stack = ModeStack::default();
+    let now = Instant::now();
+
+    stack.enter(&mode("a", None), now);
+    stack.enter(&mode("b", None), now);
+    stack.enter(&mode("c", None), now);
+
+    assert_eq!(stack.leave("b"), vec!["c", "b"]);
+    assert_eq!(stack.names(), vec!["a"]);
+    assert_eq!(stack.clear(), vec!["a"]);
+    assert!(stack.current().is_none());
+}
+
+#[test]
+fn modes_time_out() {
+    let mut stack = ModeStack::default();
+    let now = Instant::now();
+
+    stack.enter(&mode("a", None), now);
+    stack.enter(&mode("b", Some(1000)), now);
+    stack.enter(&mode("c", Some(2000)), now);
+
+    assert_eq!(stack.next_expiry(), Some(now + Duration::from_millis(1000)));
+    assert!(stack.expire(now + Duration::from_millis(500)).is_empty());
+    assert_eq!(
+        stack.expire(now + Duration::from_millis(1000)),
+        vec!["c", "b"]
+    );
+    assert_eq!(stack.names(), vec!["a"]);
+    assert_eq!(stack.next_expiry(), None);
+}
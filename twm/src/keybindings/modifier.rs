@@ -7,5 +7,19 @@ bitflags! {
         const ALT = 0x0001;
         const CONTROL = 0x0002;
         const SHIFT = 0x0004;
+        const WIN = 0x0008;
+    }
+}
+
+impl Modifier {
+    /// Finds the modifier with the given name or alias, ignoring the case
+    pub fn from_name(name: &str) -> Option<Modifier> {
+        match name.to_lowercase().as_str() {
+            "alt" => Some(Modifier::ALT),
+            "control" | "ctrl" => Some(Modifier::CONTROL),
+            "shift" => Some(Modifier::SHIFT),
+            "win" | "super" | "cmd" => Some(Modifier::WIN),
+            _ => None,
+        }
     }
 }
@@ -0,0 +1,52 @@
+use super::KbManagerInner;
+use crate::{keybindings::keybinding::Keybinding, system::fake, AppState};
+use parking_lot::Mutex;
+use std::{str::FromStr, sync::Arc};
+
+fn kb(key: &str, callback_id: usize) -> Keybinding {
+    let mut kb = Keybinding::from_str(key).unwrap();
+    kb.callback_id = callback_id;
+    kb
+}
+
+#[test]
+fn passes_keys_of_inactive_keybindings_through() {
+    let mut inner = KbManagerInner::new(Arc::new(Mutex::new(AppState::default())), false);
+    let in_editor = kb("Alt+H", 1);
+    let elsewhere = kb("Alt+J", 2);
+    let kbs = vec![in_editor.clone(), elsewhere.clone()];
+
+    for kb in &kbs {
+        inner.register_kb(kb).unwrap();
+    }
+
+    inner.pass_through(&kbs, vec![1].into_iter().collect());
+    assert_eq!(fake::get_registered_keybindings(), vec![elsewhere.get_id()]);
+
+    inner.pass_through(&kbs, Default::default());
+    assert_eq!(
+        fake::get_registered_keybindings(),
+        vec![elsewhere.get_id(), in_editor.get_id()]
+    );
+
+    // unbinding a keybinding whose keys get passed through doesn't unregister anything
+    inner.pass_through(&kbs, vec![1].into_iter().collect());
+    inner.unregister_kb(&in_editor);
+    inner.unregister_kb(&elsewhere);
+    assert!(fake::get_registered_keybindings().is_empty());
+    assert!(inner.passed_through.is_empty());
+}
+
+#[test]
+fn keeps_hotkeys_with_an_active_keybinding() {
+    let mut inner = KbManagerInner::new(Arc::new(Mutex::new(AppState::default())), false);
+    let kbs = vec![kb("Alt+H", 1), kb("Alt+H", 2), kb("Alt+H 1", 3)];
+
+    inner.register_kb(&kbs[0]).unwrap();
+
+    inner.pass_through(&kbs, vec![1, 2].into_iter().collect());
+    assert_eq!(fake::get_registered_keybindings(), vec![kbs[0].get_id()]);
+
+    inner.pass_through(&kbs, vec![1, 2, 3].into_iter().collect());
+    assert!(fake::get_registered_keybindings().is_empty());
+}
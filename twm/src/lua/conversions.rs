@@ -1,13 +1,23 @@
-use mlua::{Error as LuaError, FromLua, Table, ToLua, Value};
+use mlua::{Error as LuaError, FromLua, Lua, Table, ToLua, Value};
+use serde_json::{Map, Number, Value as JsonValue};
 
 use crate::{
-    bar::component::Component, config::rule::Action as RuleAction,
-    keybindings::keybinding::Keybinding, split_direction::SplitDirection,
-keybindings::keybinding::KeybindingKind};
+    bar::component::Component, config::rule::Action as RuleAction, config::rule::Matcher,
+    decision::{Decision, Outcome},
+    keybindings::condition::{Condition, FocusedWindow},
+    keybindings::conflict::Conflict, keybindings::keybinding::Keybinding,
+    plugins::{loader::{Plugin, Status}, spec::{PluginSpec, Revision}},
+    split_direction::SplitDirection, update::UpdateError,
+keybindings::keybinding::KeybindingKind, tile_grid::layout::Layout, tile_grid::layout::LayoutError};
 use crate::{bar::component::ComponentText, direction::Direction, system::SystemError};
+use regex::Regex;
 use std::str::FromStr;
 
-use super::LuaRuntime;
+use super::{watchdog::Stats, LuaRuntime};
+
+/// Deeper tables turn into `null` when converting them into JSON, which also stops tables that
+/// reference themselves
+const MAX_DEPTH: usize = 32;
 
 impl From<SystemError> for LuaError {
     fn from(e: SystemError) -> Self {
@@ -15,6 +25,12 @@ impl From<SystemError> for LuaError {
     }
 }
 
+impl From<UpdateError> for LuaError {
+    fn from(e: UpdateError) -> Self {
+        LuaError::RuntimeError(e.to_string())
+    }
+}
+
 impl FromLua<'_> for Direction {
     fn from_lua(lua_value: mlua::Value<'_>, lua: &'_ mlua::Lua) -> mlua::Result<Self> {
         let mut raw_direction = String::from_lua(lua_value, lua)?.to_lowercase();
@@ -45,6 +61,68 @@ impl FromLua<'_> for SplitDirection {
     }
 }
 
+impl ToLua<'_> for Layout {
+    fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
+        match self {
+            Layout::MasterStack { count, ratio } => {
+                let tbl = lua.create_table()?;
+
+                tbl.set("name", self.name())?;
+                tbl.set("count", count)?;
+                tbl.set("ratio", ratio)?;
+
+                tbl.to_lua(lua)
+            }
+            layout => layout.name().to_lua(lua),
+        }
+    }
+}
+
+/// Whether a layout with this name got registered with `nog.register_layout`
+fn is_custom_layout(lua: &mlua::Lua, name: &str) -> mlua::Result<bool> {
+    lua.globals()
+        .get::<_, Table>("nog")?
+        .get::<_, Table>("__layouts")?
+        .contains_key(name)
+}
+
+/// A layout is either the name of the layout or a table containing the name and the options of the layout.
+///
+/// ```lua
+/// "grid"
+/// { name = "master_stack", count = 2, ratio = 0.5 }
+/// ```
+impl FromLua<'_> for Layout {
+    fn from_lua(lua_value: Value<'_>, lua: &'_ mlua::Lua) -> mlua::Result<Self> {
+        let to_lua_err = |e: LayoutError| LuaError::RuntimeError(e.to_string());
+
+        match lua_value {
+            Value::Table(tbl) => {
+                let layout = Layout::from_str(&tbl.get::<_, String>("name")?).map_err(to_lua_err)?;
+
+                match layout {
+                    Layout::MasterStack { count, ratio } => Layout::master_stack(
+                        tbl.get::<_, Option<u32>>("count")?.unwrap_or(count),
+                        tbl.get::<_, Option<f32>>("ratio")?.unwrap_or(ratio),
+                    )
+                    .map_err(to_lua_err),
+                    layout => Ok(layout),
+                }
+            }
+            value => {
+                let name = String::from_lua(value, lua)?;
+
+                match Layout::from_str(&name) {
+                    Err(LayoutError::Unknown(_)) if is_custom_layout(lua, &name)? => {
+                        Ok(Layout::Custom(name))
+                    }
+                    res => res.map_err(to_lua_err),
+                }
+            }
+        }
+    }
+}
+
 impl ToLua<'_> for Keybinding {
     fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
         let tbl = lua.create_table()?;
@@ -52,6 +130,9 @@ impl ToLua<'_> for Keybinding {
         tbl.set("mode", self.kind.to_short_string())?;
         tbl.set("key", self.as_key_combo())?;
         tbl.set("callback_id", self.callback_id)?;
+        tbl.set("source", self.source)?;
+        tbl.set("mode_name", self.mode)?;
+        tbl.set("when", self.when)?;
 
         tbl.to_lua(lua)
     }
@@ -67,15 +148,321 @@ impl FromLua<'_> for Keybinding {
         let raw_mode = tbl.get::<_, String>("mode")?;
 
         if raw_mode == "g" {
-            kb.kind == KeybindingKind::Global;
+            kb.kind = KeybindingKind::Global;
         } else if raw_mode == "w" {
-            kb.kind == KeybindingKind::Work;
+            kb.kind = KeybindingKind::Work;
         }
 
+        kb.source = tbl.get("source")?;
+        kb.mode = tbl.get("mode_name")?;
+        kb.when = tbl.get("when")?;
+
         Ok(kb)
     }
 }
 
+impl ToLua<'_> for FocusedWindow {
+    fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
+        let tbl = lua.create_table()?;
+
+        tbl.set("window", self.id.0)?;
+        tbl.set("title", self.title)?;
+        tbl.set("process_name", self.process_name)?;
+        tbl.set("class_name", self.class_name)?;
+
+        tbl.to_lua(lua)
+    }
+}
+
+impl ToLua<'_> for Condition {
+    fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
+        let tbl = lua.create_table()?;
+        let pattern = |regex: Option<Regex>| regex.map(|r| r.as_str().to_string());
+
+        tbl.set("process_name", pattern(self.process_name))?;
+        tbl.set("title", pattern(self.title))?;
+        tbl.set("class_name", pattern(self.class_name))?;
+        tbl.set("predicate", self.predicate)?;
+        tbl.set("negated", self.negated)?;
+
+        tbl.to_lua(lua)
+    }
+}
+
+fn pattern_field(tbl: &Table, key: &str) -> mlua::Result<Option<Regex>> {
+    tbl.get::<_, Option<String>>(key)?
+        .map(|pattern| {
+            Regex::new(&pattern).map_err(|e| {
+                LuaError::RuntimeError(format!("Invalid {} pattern: {}", key, e))
+            })
+        })
+        .transpose()
+}
+
+/// A condition is either a predicate, which receives the focused window, or a table of patterns
+/// that the focused window has to match. The predicate is stored as a callback.
+///
+/// ```lua
+/// function(win) return win.title:find("vim") ~= nil end
+/// { process_name = "^Code\\.exe$", title = "NVIM", predicate = function(win) ... end }
+/// ```
+impl FromLua<'_> for Condition {
+    fn from_lua(lua_value: Value<'_>, lua: &'_ mlua::Lua) -> mlua::Result<Self> {
+        let predicate = |value: Value<'_>| match value {
+            Value::Nil => Ok(None),
+            Value::Function(f) => LuaRuntime::add_callback(lua, f).map(Some),
+            // the id of a predicate that already got stored
+            value => usize::from_lua(value, lua).map(Some),
+        };
+
+        match lua_value {
+            Value::Function(_) => Ok(Condition {
+                predicate: predicate(lua_value)?,
+                ..Condition::default()
+            }),
+            value => {
+                let tbl = Table::from_lua(value, lua)?;
+
+                Ok(Condition {
+                    process_name: pattern_field(&tbl, "process_name")?,
+                    title: pattern_field(&tbl, "title")?,
+                    class_name: pattern_field(&tbl, "class_name")?,
+                    predicate: predicate(tbl.get("predicate")?)?,
+                    negated: tbl.get::<_, Option<bool>>("negated")?.unwrap_or(false),
+                })
+            }
+        }
+    }
+}
+
+/// Adds the properties of the matcher to the table. The matchers of a list at the top level get
+/// added directly, which is how the table of a rule contains them.
+fn set_matcher(tbl: &Table, matcher: Matcher, top_level: bool) -> mlua::Result<()> {
+    match matcher {
+        Matcher::Pattern(regex) => tbl.set("pattern", regex.as_str()),
+        Matcher::Process(regex) => tbl.set("process", regex.as_str()),
+        Matcher::Class(regex) => tbl.set("class", regex.as_str()),
+        Matcher::Title(regex) => tbl.set("title", regex.as_str()),
+        Matcher::ExePath(regex) => tbl.set("exe_path", regex.as_str()),
+        Matcher::IsDialog(is_dialog) => tbl.set("is_dialog", is_dialog),
+        Matcher::HasParent(has_parent) => tbl.set("parent", has_parent),
+        Matcher::Parent(matcher) => tbl.set("parent", *matcher),
+        Matcher::All(matchers) if top_level => {
+            for matcher in matchers {
+                set_matcher(tbl, matcher, false)?;
+            }
+            Ok(())
+        }
+        Matcher::All(matchers) => tbl.set("all", matchers),
+        Matcher::Any(matchers) => tbl.set("any", matchers),
+        Matcher::NoneOf(matchers) => tbl.set("none", matchers),
+    }
+}
+
+impl ToLua<'_> for Matcher {
+    fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
+        let tbl = lua.create_table()?;
+        set_matcher(&tbl, self, true)?;
+        tbl.to_lua(lua)
+    }
+}
+
+/// A matcher is a table of properties, which all have to match the window. Other fields of the
+/// table get ignored, so the table of a rule can contain its matcher.
+///
+/// ```lua
+/// { process = "^Code\\.exe$", is_dialog = true }
+/// { any = { { class = "#32770" }, { parent = { process = "^explorer\\.exe$" } } } }
+/// ```
+impl FromLua<'_> for Matcher {
+    fn from_lua(lua_value: Value<'_>, lua: &'_ mlua::Lua) -> mlua::Result<Self> {
+        let tbl = Table::from_lua(lua_value, lua)?;
+        let mut matchers = Vec::new();
+
+        let patterns = [
+            ("pattern", Matcher::Pattern as fn(Regex) -> Matcher),
+            ("process", Matcher::Process),
+            ("class", Matcher::Class),
+            ("title", Matcher::Title),
+            ("exe_path", Matcher::ExePath),
+        ];
+        for (key, matcher) in patterns.iter() {
+            if let Some(regex) = pattern_field(&tbl, key)? {
+                matchers.push(matcher(regex));
+            }
+        }
+
+        if let Some(is_dialog) = tbl.get::<_, Option<bool>>("is_dialog")? {
+            matchers.push(Matcher::IsDialog(is_dialog));
+        }
+
+        match tbl.get::<_, Value>("parent")? {
+            Value::Nil => {}
+            Value::Boolean(has_parent) => matchers.push(Matcher::HasParent(has_parent)),
+            value => matchers.push(Matcher::Parent(Box::new(Matcher::from_lua(value, lua)?))),
+        }
+
+        let lists = [
+            ("all", Matcher::All as fn(Vec<Matcher>) -> Matcher),
+            ("any", Matcher::Any),
+            ("none", Matcher::NoneOf),
+        ];
+        for (key, matcher) in lists.iter() {
+            if let Some(list) = tbl.get::<_, Option<Vec<Matcher>>>(*key)? {
+                matchers.push(matcher(list));
+            }
+        }
+
+        Ok(match matchers.len() {
+            1 => matchers.remove(0),
+            _ => Matcher::All(matchers),
+        })
+    }
+}
+
+impl FromLua<'_> for PluginSpec {
+    /// Accepts `"owner/repo"`, `"owner/repo@tag"` or a table like `{ "owner/repo", branch = "dev" }`
+    fn from_lua(value: Value<'_>, _: &'_ Lua) -> mlua::Result<Self> {
+        let invalid = |reason: String| LuaError::from(SystemError::InvalidPlugin(reason));
+
+        let tbl = match value {
+            Value::String(s) => return PluginSpec::from_str(s.to_str()?).map_err(invalid),
+            Value::Table(tbl) => tbl,
+            _ => return Err(invalid("a plugin has to be a string or a table".into())),
+        };
+
+        let name = tbl
+            .get::<_, Option<String>>(1)?
+            .ok_or_else(|| invalid("the first value of the table has to be the name".into()))?;
+        let mut spec = PluginSpec::from_str(&name).map_err(invalid)?;
+
+        for key in &["branch", "tag", "commit"] {
+            if let Some(value) = tbl.get::<_, Option<String>>(*key)? {
+                if spec.revision != Revision::Default {
+                    return Err(invalid(format!(
+                        "{} can only have one of @tag, branch, tag and commit",
+                        name
+                    )));
+                }
+                spec.revision = match *key {
+                    "branch" => Revision::Branch(value),
+                    "tag" => Revision::Tag(value),
+                    _ => Revision::Commit(value),
+                };
+            }
+        }
+
+        if let Some(url) = tbl.get::<_, Option<String>>("url")? {
+            spec.source = url;
+        }
+
+        if let Some(dir) = tbl.get::<_, Option<String>>("as")? {
+            spec = spec.with_dir(&dir).map_err(invalid)?;
+        }
+
+        Ok(spec)
+    }
+}
+
+/// The durations are in milliseconds
+impl ToLua<'_> for Stats {
+    fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
+        let ms = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+        let tbl = lua.create_table()?;
+
+        tbl.set("calls", self.calls)?;
+        tbl.set("aborted", self.aborted)?;
+        tbl.set("total", ms(self.total))?;
+        tbl.set("average", ms(self.total) / self.calls.max(1) as f64)?;
+        tbl.set("max", ms(self.max))?;
+        tbl.set("last", ms(self.last))?;
+
+        tbl.to_lua(lua)
+    }
+}
+
+impl ToLua<'_> for Plugin {
+    fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
+        let tbl = lua.create_table()?;
+
+        tbl.set("name", self.name)?;
+        tbl.set("path", self.path.to_string_lossy().to_string())?;
+        tbl.set("status", self.status.name())?;
+        tbl.set("errors", self.errors)?;
+        tbl.set("last_error", self.last_error)?;
+
+        if let Status::Failed(reason) = self.status {
+            tbl.set("error", reason)?;
+        }
+
+        if let Some(manifest) = self.manifest {
+            let dependencies = lua.create_table()?;
+            for dep in manifest.dependencies {
+                let version = dep.version.map_or("*".to_string(), |v| v.to_string());
+                dependencies.set(dep.name, version)?;
+            }
+
+            tbl.set("version", manifest.version.to_string())?;
+            tbl.set("nog", manifest.nog.map(|v| v.to_string()))?;
+            tbl.set("dependencies", dependencies)?;
+            tbl.set("main", manifest.main)?;
+            tbl.set("components", manifest.components)?;
+            tbl.set("commands", manifest.commands)?;
+        }
+
+        Ok(Value::Table(tbl))
+    }
+}
+
+impl ToLua<'_> for Decision {
+    fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
+        let tbl = lua.create_table()?;
+        let checks = lua.create_table()?;
+
+        for (i, check) in self.checks.iter().enumerate() {
+            let check_tbl = lua.create_table()?;
+            check_tbl.set("name", check.name)?;
+            check_tbl.set("passed", check.passed)?;
+            check_tbl.set("detail", check.detail.clone())?;
+            checks.set(i + 1, check_tbl)?;
+        }
+
+        tbl.set("window", self.window.0)?;
+        tbl.set("title", self.properties.title.clone())?;
+        tbl.set("process_name", self.properties.process_name.clone())?;
+        tbl.set("exe_path", self.properties.exe_path.clone())?;
+        tbl.set("class_name", self.properties.class_name.clone())?;
+        tbl.set("is_dialog", self.properties.is_dialog)?;
+        tbl.set("style", format!("{:?}", self.style))?;
+        tbl.set("ex_style", format!("{:?}", self.ex_style))?;
+        tbl.set("rule", self.rule.clone())?;
+        tbl.set("action", self.action.to_string())?;
+        tbl.set("forced", self.forced)?;
+        tbl.set("checks", checks)?;
+        tbl.set("outcome", self.outcome.name())?;
+        tbl.set("workspace", self.outcome.workspace())?;
+        tbl.set("summary", self.summary())?;
+
+        if let Outcome::Scratchpad(name) = self.outcome {
+            tbl.set("scratchpad", name)?;
+        }
+
+        tbl.to_lua(lua)
+    }
+}
+
+impl ToLua<'_> for Conflict {
+    fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
+        let tbl = lua.create_table()?;
+
+        tbl.set("kind", self.name())?;
+        tbl.set("key", self.keybinding().as_key_combo())?;
+        tbl.set("message", self.to_string())?;
+
+        tbl.to_lua(lua)
+    }
+}
+
 impl ToLua<'_> for Component {
     fn to_lua(self, lua: &'_ mlua::Lua) -> mlua::Result<Value<'_>> {
         let tbl = lua.create_table()?;
@@ -134,3 +521,98 @@ impl FromLua<'_> for ComponentText {
         Ok(text)
     }
 }
+
+/// Converts a lua value into JSON. Tables with a sequence turn into arrays, every other table
+/// into an object. Values that can't be represented, like functions, turn into `null`.
+pub fn lua_to_json(value: Value) -> JsonValue {
+    to_json(value, 0)
+}
+
+fn to_json(value: Value, depth: usize) -> JsonValue {
+    match value {
+        Value::Nil => JsonValue::Null,
+        Value::Boolean(x) => JsonValue::Bool(x),
+        Value::Integer(x) => JsonValue::Number(x.into()),
+        Value::Number(x) if x.fract() == 0.0 && x.abs() < i64::MAX as f64 => {
+            JsonValue::Number((x as i64).into())
+        }
+        Value::Number(x) => Number::from_f64(x)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        Value::String(x) => JsonValue::String(String::from_utf8_lossy(x.as_bytes()).into_owned()),
+        Value::Table(tbl) if depth < MAX_DEPTH => {
+            let tbl = unproxy(tbl);
+            let len = tbl.raw_len();
+
+            if len > 0 {
+                JsonValue::Array(
+                    (1..=len)
+                        .map(|i| to_json(tbl.raw_get(i).unwrap_or(Value::Nil), depth + 1))
+                        .collect(),
+                )
+            } else {
+                let mut object = Map::new();
+
+                for (key, value) in tbl.pairs::<Value, Value>().flatten() {
+                    let key = match key {
+                        Value::String(key) => {
+                            String::from_utf8_lossy(key.as_bytes()).into_owned()
+                        }
+                        Value::Integer(key) => key.to_string(),
+                        Value::Number(key) => key.to_string(),
+                        _ => continue,
+                    };
+                    object.insert(key, to_json(value, depth + 1));
+                }
+
+                JsonValue::Object(object)
+            }
+        }
+        _ => JsonValue::Null,
+    }
+}
+
+/// The tables of the config are empty proxies, which forward every read to the actual table
+fn unproxy(tbl: Table) -> Table {
+    match tbl
+        .get_metatable()
+        .map(|m| m.raw_get::<_, Value>("__index"))
+    {
+        Some(Ok(Value::Table(inner)))
+            if tbl.clone().pairs::<Value, Value>().next().is_none() =>
+        {
+            unproxy(inner)
+        }
+        _ => tbl,
+    }
+}
+
+/// Converts JSON into a lua value. `null` turns into `nil`.
+pub fn json_to_lua<'lua>(lua: &'lua Lua, value: &JsonValue) -> mlua::Result<Value<'lua>> {
+    Ok(match value {
+        JsonValue::Null => Value::Nil,
+        JsonValue::Bool(x) => Value::Boolean(*x),
+        JsonValue::Number(x) => match x.as_i64() {
+            Some(x) => Value::Integer(x),
+            None => Value::Number(x.as_f64().unwrap_or_default()),
+        },
+        JsonValue::String(x) => Value::String(lua.create_string(x)?),
+        JsonValue::Array(values) => {
+            let tbl = lua.create_table()?;
+            for (i, value) in values.iter().enumerate() {
+                tbl.raw_set(i + 1, json_to_lua(lua, value)?)?;
+            }
+            Value::Table(tbl)
+        }
+        JsonValue::Object(object) => {
+            let tbl = lua.create_table()?;
+            for (key, value) in object {
+                tbl.raw_set(key.as_str(), json_to_lua(lua, value)?)?;
+            }
+            Value::Table(tbl)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,48 @@
+use super::{json_to_lua, lua_to_json};
+use mlua::{Lua, Value};
+use serde_json::json;
+
+#[test]
+fn converts_lua_values() {
+    let lua = Lua::new();
+    let value = json!({ "list": [1, 2.5, "x", true], "nested": { "a": null } });
+    let converted = json_to_lua(&lua, &value).unwrap();
+
+    // `null` turns into `nil`, which removes the key
+    assert_eq!(
+        lua_to_json(converted),
+        json!({ "list": [1, 2.5, "x", true], "nested": {} })
+    );
+}
+
+#[test]
+fn integral_numbers_stay_integers() {
+    assert_eq!(lua_to_json(Value::Number(3.0)), json!(3));
+}
+
+#[test]
+fn functions_turn_into_null() {
+    let lua = Lua::new();
+    let value = lua.load("{ f = function() end, x = 1 }").eval().unwrap();
+
+    assert_eq!(lua_to_json(value), json!({ "f": null, "x": 1 }));
+}
+
+#[test]
+fn reads_through_proxies() {
+    let lua = Lua::new();
+    let value = lua
+        .load("setmetatable({}, { __index = { height = 20 } })")
+        .eval()
+        .unwrap();
+
+    assert_eq!(lua_to_json(value), json!({ "height": 20 }));
+}
+
+#[test]
+fn self_referencing_tables_terminate() {
+    let lua = Lua::new();
+    let value = lua.load("local t = {} t.t = t return t").eval().unwrap();
+
+    assert!(lua_to_json(value).is_object());
+}
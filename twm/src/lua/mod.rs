@@ -1,24 +1,33 @@
-use std::{str::FromStr, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, str::FromStr, sync::Arc, thread, time::Duration};
 
 use chrono::Local;
 use mlua::{Error as LuaError, FromLua, Function, Lua, Table, Value, Result as RuntimeResult};
 use parking_lot::Mutex;
 use regex::Regex;
 
-use log::{info, warn};
+use log::{error, info, warn};
 
 use crate::{
-    bar::component::Component, config::bar_config::BarComponentsConfig, config::rule::Rule,
+    bar::component::Component, config::bar_config::BarComponentsConfig, config::rule::{Matcher, Rule},
     config::workspace_setting::WorkspaceSetting, config::Config, direction::Direction,
-    event::Event, get_config_path, keybindings::keybinding::Keybinding,
+    event::Event, get_config_path, hooks::Hook, keybindings::condition::Condition,
+    keybindings::keybinding::Keybinding, keybindings::mode::Mode,
     keybindings::keybinding::KeybindingKind, split_direction::SplitDirection, system,
-    system::DisplayId, system::WindowId, AppState,
-get_runtime_path, popup::Popup};
+    system::DisplayId, system::Rectangle, system::SystemResult, system::WindowId,
+    tile_grid::layout::Area, tile_grid::layout::CustomArrangement, tile_grid::layout::Layout,
+    tile_grid::layout::LayoutTile, tile_grid::snapshot::SlotRule, AppState,
+get_runtime_path, popup::Popup, update,
+config::update_channel::{repository::Repository, UpdateChannel},
+plugins::{loader::{self, Plugin, Status}, permission::Permission, spec::PluginSpec, Change, PluginManager}};
 
 mod conversions;
 mod runtime;
+mod sandbox;
+mod watchdog;
 
+pub use conversions::{json_to_lua, lua_to_json};
 pub use runtime::{get_err_msg, LuaRuntime};
+pub use sandbox::{attribute as attribute_error, PluginError};
 
 /// This is macro is necessary, because if you use the default way of type checking input
 /// (specifying the type in the function declaration) the error is different than how it would look
@@ -85,16 +94,17 @@ fn comp_from_tbl(
 
     let id = LuaRuntime::add_callback(lua, render)?;
     let state = state_arc.clone();
+    let cb_name = format!("component {}", name);
     let mut comp = Component::new(&name, move |disp_id| {
         let rt = state.lock().lua_rt.clone();
-        let res = rt.with_lua(|lua| {
+        let res = rt.watch(&cb_name, |lua| {
             let cb = LuaRuntime::get_callback(&lua, id)?;
-            cb.call(disp_id.0)
+            cb.call(disp_id.0).map_err(|e| sandbox::attribute(lua, &cb, e))
         });
 
         Ok(match res {
             Err(e) => {
-                state.lock().emit_lua_rt_error(&crate::lua::get_err_msg(&e));
+                state.lock().event_channel.sender.send(Event::LuaRuntimeError(e)).unwrap();
                 vec![]
             }
             Ok(x) => x,
@@ -104,15 +114,17 @@ fn comp_from_tbl(
     if let Some(on_click_fn) = on_click {
         let id = LuaRuntime::add_callback(lua, on_click_fn)?;
         let state = state_arc.clone();
+        let cb_name = format!("component {} on_click", name);
         comp.with_on_click(move |display_id, value, idx| {
             let rt = state.lock().lua_rt.clone();
-            let res = rt.with_lua(|lua| {
+            let res = rt.watch(&cb_name, |lua| {
                 let cb = LuaRuntime::get_callback(&lua, id)?;
                 cb.call::<_, ()>((display_id.0, value, idx))
+                    .map_err(|e| sandbox::attribute(lua, &cb, e))
             });
 
             if let Err(e) = res {
-                state.lock().emit_lua_rt_error(&crate::lua::get_err_msg(&e));
+                state.lock().event_channel.sender.send(Event::LuaRuntimeError(e)).unwrap();
             }
 
             Ok(())
@@ -169,6 +181,8 @@ fn config_to_lua<'a>(lua: &'a Lua, config: &Config) -> mlua::Result<Table<'a>> {
     let tbl = lua.create_table()?;
     let rules_tbl = lua.create_table()?;
     let workspaces_tbl = lua.create_table()?;
+    let update_channels_tbl = lua.create_table()?;
+    let plugin_permissions_tbl = lua.create_table()?;
     let bar_tbl = lua.create_table()?;
     let bar_components_tbl = lua.create_table()?;
 
@@ -196,6 +210,10 @@ fn config_to_lua<'a>(lua: &'a Lua, config: &Config) -> mlua::Result<Table<'a>> {
     map_prop!(tbl, config, display_app_bar);
     map_prop!(tbl, config, ignore_fullscreen_actions);
     map_prop!(tbl, config, allow_right_alt);
+    map_prop!(tbl, config, chord_timeout);
+    map_prop!(tbl, config, callback_timeout);
+    map_prop!(tbl, config, default_update_channel, true);
+    tbl.set("update_interval", config.update_interval.as_secs() / 60)?;
 
     map_prop!(bar_tbl, config.bar, color);
     map_prop!(bar_tbl, config.bar, height);
@@ -211,24 +229,68 @@ fn config_to_lua<'a>(lua: &'a Lua, config: &Config) -> mlua::Result<Table<'a>> {
 
         tbl.set("monitor", ws.monitor)?;
         tbl.set("text", ws.text.clone())?;
+        tbl.set("layout", ws.layout.clone())?;
 
         workspaces_tbl.set(ws.id, tbl)?;
     }
 
     for rule in &config.rules {
-        let tbl = lua.create_table()?;
+        let tbl = Table::from_lua(lua.pack(rule.matcher.clone())?, lua)?;
 
         tbl.set("chromium", rule.chromium)?;
         tbl.set("firefox", rule.firefox)?;
         tbl.set("has_custom_titlebar", rule.has_custom_titlebar)?;
         tbl.set("action", rule.action.to_string())?;
         tbl.set("workspace_id", rule.workspace_id)?;
+        tbl.set("scratchpad", rule.scratchpad.clone())?;
+        tbl.set("float", rule.float)?;
+        tbl.set("fullscreen", rule.fullscreen)?;
+        tbl.set("display", rule.display)?;
+        tbl.set("opacity", rule.opacity)?;
+        tbl.set("no_border", rule.no_border)?;
+        tbl.set("focus", rule.focus)?;
+
+        if let Some((width, height)) = rule.size {
+            let size = lua.create_table()?;
+            size.set("width", width)?;
+            size.set("height", height)?;
+            tbl.set("size", size)?;
+        }
+
+        if let Some((x, y)) = rule.position {
+            let position = lua.create_table()?;
+            position.set("x", x)?;
+            position.set("y", y)?;
+            tbl.set("position", position)?;
+        }
+
+        // rules that got defined with a pattern as their key keep it
+        match rule.matcher.pattern() {
+            Some(pattern) => rules_tbl.set(pattern.as_str(), tbl)?,
+            None => rules_tbl.set(rules_tbl.raw_len() + 1, tbl)?,
+        }
+    }
+
+    for channel in &config.update_channels {
+        let channel_tbl = lua.create_table()?;
 
-        rules_tbl.set(rule.pattern.to_string(), tbl)?;
+        channel_tbl.set("repo", channel.repo.to_string())?;
+        channel_tbl.set("branch", channel.branch.clone())?;
+        channel_tbl.set("path", channel.path.as_ref().and_then(|p| p.to_str()))?;
+        channel_tbl.set("pre_releases", channel.pre_releases)?;
+
+        update_channels_tbl.set(channel.name.clone(), channel_tbl)?;
+    }
+
+    for (name, permissions) in &config.plugin_permissions {
+        let names = permissions.iter().map(|p| p.name()).collect::<Vec<_>>();
+        plugin_permissions_tbl.set(name.clone(), names)?;
     }
 
     bar_tbl.set("components", bar_components_tbl)?;
     tbl.set("bar", bar_tbl)?;
+    tbl.set("update_channels", update_channels_tbl)?;
+    tbl.set("plugin_permissions", plugin_permissions_tbl)?;
     tbl.set("workspaces", workspaces_tbl)?;
     tbl.set("rules", rules_tbl)?;
 
@@ -244,6 +306,7 @@ fn ws_from_tbl(lua: &Lua, id: i32, tbl: Table) -> mlua::Result<WorkspaceSetting>
             match key.as_str() {
                 "text" => ws.text = FromLua::from_lua(val, lua)?,
                 "monitor" => ws.monitor = FromLua::from_lua(val, lua)?,
+                "layout" => ws.layout = FromLua::from_lua(val, lua)?,
                 _ => {}
             }
         }
@@ -252,24 +315,77 @@ fn ws_from_tbl(lua: &Lua, id: i32, tbl: Table) -> mlua::Result<WorkspaceSetting>
     Ok(ws)
 }
 
-fn rule_from_tbl(lua: &Lua, raw_pat: String, tbl: Table) -> mlua::Result<Rule> {
-    let mut rule = Rule::default();
-    rule.pattern = Regex::new(&raw_pat).map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+fn update_channel_from_tbl(name: String, tbl: Table) -> mlua::Result<UpdateChannel> {
+    let default = UpdateChannel::default();
 
-    for pair in tbl.pairs::<String, Value>() {
-        if let Ok((key, val)) = pair {
-            match key.as_str() {
-                "action" => rule.action = FromLua::from_lua(val, lua)?,
-                "chromium" => rule.chromium = FromLua::from_lua(val, lua)?,
-                "firefox" => rule.firefox = FromLua::from_lua(val, lua)?,
-                "has_custom_titlebar" => rule.has_custom_titlebar = FromLua::from_lua(val, lua)?,
-                "workspace_id" => rule.workspace_id = FromLua::from_lua(val, lua)?,
-                _ => {}
+    Ok(UpdateChannel {
+        name,
+        repo: tbl.get::<_, Option<String>>("repo")?.map_or(default.repo, Repository::from),
+        branch: tbl.get::<_, Option<String>>("branch")?.unwrap_or(default.branch),
+        path: tbl.get::<_, Option<String>>("path")?.map(PathBuf::from),
+        pre_releases: tbl.get::<_, Option<bool>>("pre_releases")?.unwrap_or(default.pre_releases),
+    })
+}
+
+/// Rules that got defined with a pattern as their key match the pattern in addition to the
+/// matchers of their table
+fn rule_from_tbl(lua: &Lua, raw_pat: Option<String>, tbl: Table) -> mlua::Result<Rule> {
+    let default = Rule::default();
+    let mut matcher = Matcher::from_lua(Value::Table(tbl.clone()), lua)?;
+
+    // a misspelled matcher would otherwise turn the rule into one that matches every window
+    if raw_pat.is_none() && matches!(&matcher, Matcher::All(matchers) if matchers.is_empty()) {
+        return Err(LuaError::RuntimeError(
+            "A rule needs at least one of pattern, process, class, title, exe_path, is_dialog, \
+             parent, all, any or none"
+                .into(),
+        ));
+    }
+
+    if let (Some(raw_pat), None) = (raw_pat, matcher.pattern()) {
+        let pattern = Regex::new(&raw_pat).map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+        matcher = match matcher {
+            Matcher::All(mut matchers) => {
+                matchers.insert(0, Matcher::Pattern(pattern));
+                Matcher::All(matchers)
             }
-        }
+            matcher => Matcher::All(vec![Matcher::Pattern(pattern), matcher]),
+        };
     }
 
-    Ok(rule)
+    let size = match tbl.get::<_, Option<Table>>("size")? {
+        Some(size) => Some((size.get("width")?, size.get("height")?)),
+        None => None,
+    };
+    let position = match tbl.get::<_, Option<Table>>("position")? {
+        Some(position) => Some((position.get("x")?, position.get("y")?)),
+        None => None,
+    };
+    let opacity = tbl.get::<_, Option<f32>>("opacity")?;
+    if let Some(opacity) = opacity.filter(|opacity| !(0.0..=1.0).contains(opacity)) {
+        return Err(LuaError::RuntimeError(format!(
+            "The opacity of a rule has to be between 0 and 1, not {}",
+            opacity
+        )));
+    }
+
+    Ok(Rule {
+        matcher,
+        has_custom_titlebar: tbl.get::<_, Option<bool>>("has_custom_titlebar")?.unwrap_or(default.has_custom_titlebar),
+        action: tbl.get::<_, Option<_>>("action")?.unwrap_or(default.action),
+        chromium: tbl.get::<_, Option<bool>>("chromium")?.unwrap_or(default.chromium),
+        firefox: tbl.get::<_, Option<bool>>("firefox")?.unwrap_or(default.firefox),
+        workspace_id: tbl.get::<_, Option<i32>>("workspace_id")?.unwrap_or(default.workspace_id),
+        scratchpad: tbl.get("scratchpad")?,
+        float: tbl.get::<_, Option<bool>>("float")?.unwrap_or(default.float),
+        fullscreen: tbl.get::<_, Option<bool>>("fullscreen")?.unwrap_or(default.fullscreen),
+        display: tbl.get("display")?,
+        size,
+        position,
+        opacity,
+        no_border: tbl.get::<_, Option<bool>>("no_border")?.unwrap_or(default.no_border),
+        focus: tbl.get("focus")?,
+    })
 }
 
 fn components_from_tbl(
@@ -320,6 +436,7 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
         // This never gets cleaned up which could causes some performance problems after a long
         // time
         nog_tbl.set("__callbacks", cb_tbl)?;
+        nog_tbl.set("__layouts", lua.create_table()?)?;
         nog_tbl.set("__is_setup", true)?;
         nog_tbl.set("version", option_env!("NOG_VERSION").unwrap_or("DEV"))?;
         nog_tbl.set("runtime_path", get_runtime_path().to_str())?;
@@ -507,6 +624,45 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
                     }),
                     "ignore_fullscreen_actions" => set_prop!(ignore_fullscreen_actions, bool),
                     "allow_right_alt" => set_prop!(allow_right_alt, bool),
+                    "chord_timeout" => set_prop!(chord_timeout, i32),
+                    "callback_timeout" => {
+                        let ms = validate!(lua, value: i32)?;
+                        watchdog::set_timeout(lua, &state.lua_rt.1, ms)?;
+                        state.config.callback_timeout = ms;
+                        Ok(())
+                    }
+                    "default_update_channel" => set_prop!(default_update_channel, Option<String>),
+                    "update_interval" => {
+                        let minutes = validate!(lua, value: u64)?;
+                        state.config.update_interval = Duration::from_secs(minutes.max(1) * 60);
+                        Ok(())
+                    }
+                    "plugin_permissions" => {
+                        let tbl = validate!(lua, value: Table)?;
+                        let mut permissions = HashMap::new();
+                        for res in tbl.pairs::<String, Vec<String>>() {
+                            let (name, names) = res?;
+                            let allowed = names
+                                .iter()
+                                .map(|name| Permission::from_str(name))
+                                .collect::<Result<Vec<_>, _>>()
+                                .map_err(LuaError::RuntimeError)?;
+                            permissions.insert(name, allowed);
+                        }
+                        sandbox::set_permissions(lua, &permissions)?;
+                        state.config.plugin_permissions = permissions;
+                        Ok(())
+                    }
+                    "update_channels" => {
+                        let tbl = validate!(lua, value: Table)?;
+                        let mut channels = Vec::new();
+                        for res in tbl.pairs::<String, Table>() {
+                            let (name, tbl) = res?;
+                            channels.push(update_channel_from_tbl(name, tbl)?);
+                        }
+                        state.config.update_channels = channels;
+                        Ok(())
+                    }
                     "workspaces" => {
                         let tbl = validate!(lua, value: Table)?;
                         let mut workspaces = Vec::new();
@@ -515,15 +671,25 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
                                 workspaces.push(ws_from_tbl(lua, id, tbl)?);
                             }
                         }
+                        for ws in &workspaces {
+                            if let Some(grid) = state.get_grid_by_id_mut(ws.id) {
+                                grid.layout = ws.layout.clone();
+                            }
+                        }
                         state.config.workspaces = workspaces;
                         Ok(())
                     }
                     "rules" => {
                         let tbl = validate!(lua, value: Table)?;
                         let mut rules = Vec::new();
-                        for res in tbl.pairs::<String, Table>() {
-                            if let Ok((pat, tbl)) = res {
-                                rules.push(rule_from_tbl(lua, pat, tbl)?);
+                        // the rules of the list keep their order and come before the rules that
+                        // have a pattern as their key, whose order is undefined
+                        for tbl in tbl.clone().sequence_values::<Table>() {
+                            rules.push(rule_from_tbl(lua, None, tbl?)?);
+                        }
+                        for res in tbl.pairs::<Value, Table>() {
+                            if let Ok((Value::String(pat), tbl)) = res {
+                                rules.push(rule_from_tbl(lua, Some(pat.to_str()?.into()), tbl)?);
                             }
                         }
                         state.config.rules = rules;
@@ -571,11 +737,101 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
             Ok(())
         });
 
+        let watchdog = rt.1.clone();
+        def_fn!(lua, nog_tbl, "debug_stats", move |lua, (): ()| {
+            watchdog::debug_stats(lua, &watchdog)
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "on", move |lua, (name, cb): (Value, Value)| {
+            validate!(lua, { name: String, cb: Function });
+
+            let hook = Hook::from_str(&name)
+                .map_err(|_| LuaError::RuntimeError(format!("{} is not a hook", name)))?;
+            let id = LuaRuntime::add_callback(lua, cb)?;
+
+            state.lock().hooks.add(hook, id);
+
+            Ok(())
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "__define_mode", move |lua, (name, opts): (Value, Value)| {
+            validate!(lua, { name: String, opts: Table });
+
+            let mut mode = Mode::new(&name);
+            mode.timeout = opts
+                .get::<_, Option<u64>>("timeout")?
+                .map(Duration::from_millis);
+            mode.oneshot = opts.get::<_, Option<bool>>("oneshot")?.unwrap_or(false);
+
+            state.lock().config.modes.insert(name, mode);
+
+            Ok(())
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "enter_mode", move |lua, name: Value| {
+            validate!(lua, { name: String });
+            state.lock().enter_mode(&name).map_err(LuaError::RuntimeError)
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "leave_mode", move |lua, name: Value| {
+            validate!(lua, { name: Option<String> });
+            let mut state = state.lock();
+            let name = name.or_else(|| state.modes.current().map(|mode| mode.name.clone()));
+
+            if let Some(name) = name {
+                state.leave_mode(&name);
+            }
+
+            Ok(())
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "toggle_mode", move |lua, name: Value| {
+            validate!(lua, { name: String });
+            state.lock().toggle_mode(&name).map_err(LuaError::RuntimeError)
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "get_current_mode", move |_, (): ()| {
+            Ok(state.lock().modes.current().map(|mode| mode.name.clone()))
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "get_modes", move |_, (): ()| {
+            Ok(state.lock().modes.names())
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "update_check", move |lua, cb: Value| {
+            let cb = validate!(lua, cb: Option<Function>)?;
+            let cb_id = cb.map(|cb| LuaRuntime::add_callback(lua, cb)).transpose()?;
+            update::run_in_background(state.clone(), false, cb_id);
+            Ok(())
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "update_apply", move |lua, cb: Value| {
+            let cb = validate!(lua, cb: Option<Function>)?;
+            let cb_id = cb.map(|cb| LuaRuntime::add_callback(lua, cb)).transpose()?;
+            update::run_in_background(state.clone(), true, cb_id);
+            Ok(())
+        });
+
         let state = state_arc.clone();
         def_fn!(lua, nog_tbl, "get_keybindings", move |lua, (): ()| {
             Ok(state.lock().config.keybindings.clone())
         });
 
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "get_pending_chord", move |_, (): ()| {
+            let kbm = state.lock().keybindings_manager.clone();
+            Ok(kbm.and_then(|kbm| kbm.pending_chord()))
+        });
+
         def_fn!(lua, nog_tbl, "popup_close", move |_, (): ()| {
             crate::popup::close();
 
@@ -645,6 +901,8 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
             }
         );
 
+        def_ffi_fn!(state_arc, lua, nog_tbl, "scratchpad_toggle", toggle_scratchpad, name: String);
+
         let state = state_arc.clone();
         def_fn!(lua, nog_tbl, "toggle_work_mode", move |_, (): ()| {
             AppState::toggle_work_mode(state.clone())
@@ -671,16 +929,39 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
         let state = state_arc.clone();
         def_fn!(lua, nog_tbl, "get_ws_info", move |lua, ws_id: Value| {
             validate!(lua, { ws_id: i32 });
-            Ok(state.lock().get_grid_by_id(ws_id).map(|ws| {
-                let tbl = lua.create_table().unwrap();
-                tbl.set("id", ws.id);
-                tbl.set("is_fullscreen", ws.is_fullscreened());
-                tbl.set("is_empty", ws.is_empty());
-                tbl.set("split_direction", ws.next_axis.to_string());
-                let windows = ws.get_windows().iter().map(|w| w.id.0).collect::<Vec<_>>();
-                tbl.set("windows", windows);
-                tbl
-            }))
+            let state = state.lock();
+            let ws = match state.get_grid_by_id(ws_id) {
+                Some(ws) => ws,
+                None => return Ok(None),
+            };
+
+            let tbl = lua.create_table()?;
+            tbl.set("id", ws.id)?;
+            tbl.set("is_fullscreen", ws.is_fullscreened())?;
+            tbl.set("is_empty", ws.is_empty())?;
+            tbl.set("split_direction", ws.next_axis.to_string())?;
+            tbl.set("layout", ws.layout.clone())?;
+            let windows = ws.get_windows().iter().map(|w| w.id.0).collect::<Vec<_>>();
+            tbl.set("windows", windows)?;
+            let tabs = ws.get_tabs().iter().map(|w| w.id.0).collect::<Vec<_>>();
+            tbl.set("tabs", tabs)?;
+            Ok(Some(tbl))
+        });
+
+        def_fn!(lua, nog_tbl, "register_layout", move |lua, (name, f): (Value, Value)| {
+            validate!(lua, { name: String, f: Function });
+
+            if Layout::NAMES.contains(&name.as_str()) {
+                return Err(LuaError::RuntimeError(format!(
+                    "{} is the name of a built-in layout",
+                    name
+                )));
+            }
+
+            lua.globals()
+                .get::<_, Table>("nog")?
+                .get::<_, Table>("__layouts")?
+                .set(name, f)
         });
 
         let state = state_arc.clone();
@@ -743,7 +1024,7 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
                     .keybindings
                     .iter()
                     .enumerate()
-                    .find(|(_, kb)| kb.key == s_kb.key && kb.modifier == s_kb.modifier)
+                    .find(|(_, kb)| kb.has_same_trigger(s_kb))
                     .map(|(i, _)| i)
                 {
                     state.config.keybindings.remove(i);
@@ -760,17 +1041,16 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
             validate!(lua, { key: String });
             let mut state_g = state.lock();
             // The dummy keybinding that is being searched for
-            let s_kb = Keybinding::from_str(&key).unwrap();
-            if let Some((i, kb)) = state_g
+            let s_kb = Keybinding::from_str(&key).map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            // keybindings with a condition can share their keys
+            let (removed, kept) = state_g
                 .config
                 .keybindings
-                .iter()
-                .enumerate()
-                .find(|(_, kb)| kb.key == s_kb.key && kb.modifier == s_kb.modifier)
-                .map(|(i, kb)| (i, kb.clone()))
-            {
-                state_g.config.keybindings.remove(i);
-                if let Some(kbm) = state_g.keybindings_manager.as_ref() {
+                .drain(..)
+                .partition::<Vec<_>, _>(|kb| kb.has_same_keys(&s_kb));
+            state_g.config.keybindings = kept;
+            if let Some(kbm) = state_g.keybindings_manager.as_ref() {
+                for kb in removed {
                     kbm.unregister_keybinding(kb);
                 }
             }
@@ -802,7 +1082,10 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
 
         let state = state_arc.clone();
         def_fn!(lua, nog_tbl, "__bind", move |lua,
-                                            (mode, key, id): (
+                                            (mode, key, id, source, mode_name, opts): (
+            Value,
+            Value,
+            Value,
             Value,
             Value,
             Value
@@ -810,9 +1093,32 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
             validate!(lua, {
                 mode: String,
                 key: String,
-                id: usize
+                id: usize,
+                source: Option<String>,
+                mode_name: Option<String>,
+                opts: Option<Table>
             });
-            let mut kb = Keybinding::from_str(&key).unwrap();
+            let when = match opts {
+                Some(opts) => {
+                    let when = opts.get::<_, Option<Condition>>("when")?;
+                    let unless = opts.get::<_, Option<Condition>>("unless")?;
+
+                    match (when, unless) {
+                        (Some(_), Some(_)) => {
+                            return Err(LuaError::RuntimeError(
+                                "A keybinding can't have both `when` and `unless`".into(),
+                            ))
+                        }
+                        (None, Some(mut unless)) => {
+                            unless.negated = true;
+                            Some(unless)
+                        }
+                        (when, None) => when,
+                    }
+                }
+                None => None,
+            };
+            let mut kb = Keybinding::from_str(&key).map_err(|e| LuaError::RuntimeError(e.to_string()))?;
             kb.kind = match mode.as_str() {
                 "g" => KeybindingKind::Global,
                 "w" => KeybindingKind::Work,
@@ -820,21 +1126,42 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
             };
 
             kb.callback_id = id;
+            kb.source = source;
+            kb.mode = mode_name;
+            kb.when = when;
             let mut state = state.lock();
-            match state.config.keybindings.iter_mut().find(|x| x.get_id() == kb.get_id()) {
-                Some(kb) => {
-                    kb.callback_id = id;
-                },
-                None => {
-                    state.config.keybindings.push(kb.clone());
-                    if let Some(kbm) = state.keybindings_manager.as_ref() {
-                        kbm.register_keybinding(kb);
-                    }
+            let is_new = !state.config.keybindings.iter().any(|x| x.has_same_trigger(&kb));
+
+            if let Some(conflict) = state.config.add_keybinding(kb.clone()) {
+                warn!("{}", conflict);
+            }
+
+            if is_new {
+                if let Some(kbm) = state.keybindings_manager.as_ref() {
+                    kbm.register_keybinding(kb);
                 }
             }
             Ok(())
         });
 
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "check_keybindings", move |_, (): ()| {
+            let (mut conflicts, kbm) = {
+                let state = state.lock();
+                (
+                    state.config.find_keybinding_conflicts(),
+                    state.keybindings_manager.clone(),
+                )
+            };
+
+            // the keybinding manager locks the state while registering keybindings
+            if let Some(kbm) = kbm {
+                conflicts.extend(kbm.unavailable_keybindings());
+            }
+
+            Ok(conflicts)
+        });
+
         let globals = lua.globals();
         globals.set("nog", nog_tbl)?;
 
@@ -843,6 +1170,129 @@ fn setup_nog_global(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) {
     .unwrap();
 }
 
+/// Calls the custom layout of every focused grid whose tiles or available space changed since they
+/// got arranged the last time and redraws the grid afterwards.
+///
+/// The state can't be locked while calling the layout, so this can't happen while rendering.
+pub fn arrange_custom_layouts(state_arc: Arc<Mutex<AppState>>) -> SystemResult {
+    let state = state_arc.lock();
+    let rt = state.lua_rt.clone();
+    let sender = state.event_channel.sender.clone();
+    let outdated = state
+        .displays
+        .iter()
+        .filter_map(|d| {
+            let grid = d.get_focused_grid()?;
+            let name = match &grid.layout {
+                Layout::Custom(name) => name.clone(),
+                _ => return None,
+            };
+            let tiles = grid.get_layout_tiles();
+            let area = d.tiling_area(&state.config);
+            let is_up_to_date = grid.custom_arrangement.as_ref().is_some_and(|a| {
+                a.is_arrangement_of(&name, &tiles, area.width() as u32, area.height() as u32)
+            });
+
+            if is_up_to_date {
+                None
+            } else {
+                Some((d.id, grid.id, name, tiles, area))
+            }
+        })
+        .collect::<Vec<_>>();
+    drop(state);
+
+    for (display_id, grid_id, name, tiles, area) in outdated {
+        let cb_name = format!("layout {}", name);
+        let res = rt.watch(&cb_name, |lua| call_custom_layout(lua, &name, &tiles, area));
+        let areas = res.unwrap_or_else(|e| {
+            sender.send(Event::LuaRuntimeError(e)).unwrap();
+            // tiles that don't have an area stay where they are, which is the best we can do
+            vec![None; tiles.len()]
+        });
+
+        let mut state = state_arc.lock();
+        let config = state.config.clone();
+
+        if let Some(display) = state.get_display_by_id_mut(display_id) {
+            if let Some(grid) = display.get_grid_by_id_mut(grid_id) {
+                grid.custom_arrangement = Some(CustomArrangement {
+                    layout: name,
+                    tiles,
+                    width: area.width() as u32,
+                    height: area.height() as u32,
+                    areas,
+                });
+            }
+
+            if display.focused_grid_id == Some(grid_id) {
+                display.refresh_grid(&config)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Calls the layout function with the tiles and the available space and converts the returned
+/// rectangles to areas relative to the available space.
+fn call_custom_layout(
+    lua: &Lua,
+    name: &str,
+    tiles: &[LayoutTile],
+    area: Rectangle,
+) -> mlua::Result<Vec<Option<Area>>> {
+    let f = lua
+        .globals()
+        .get::<_, Table>("nog")?
+        .get::<_, Table>("__layouts")?
+        .get::<_, Option<Function>>(name)?
+        .ok_or_else(|| LuaError::RuntimeError(format!("Unknown layout {}", name)))?;
+
+    let tiles_tbl = lua.create_table()?;
+    for (i, tile) in tiles.iter().enumerate() {
+        let tbl = lua.create_table()?;
+        tbl.set("id", tile.window_id.0)?;
+        tbl.set("title", tile.title.clone())?;
+        tbl.set("focused", tile.focused)?;
+        tiles_tbl.set(i + 1, tbl)?;
+    }
+
+    let area_tbl = lua.create_table()?;
+    area_tbl.set("x", area.left)?;
+    area_tbl.set("y", area.top)?;
+    area_tbl.set("width", area.width())?;
+    area_tbl.set("height", area.height())?;
+
+    let rects = f.call::<_, Table>((tiles_tbl, area_tbl))?;
+
+    (1..=tiles.len())
+        .map(|i| {
+            let rect = match rects.get::<_, Option<Table>>(i)? {
+                Some(rect) => rect,
+                None => return Ok(None),
+            };
+            let x = rect.get::<_, i32>("x")?;
+            let y = rect.get::<_, i32>("y")?;
+            let width = rect.get::<_, i32>("width")?;
+            let height = rect.get::<_, i32>("height")?;
+
+            // everything outside of the available space gets cut off
+            let left = x.max(area.left).min(area.right);
+            let top = y.max(area.top).min(area.bottom);
+            let right = (x + width).max(left).min(area.right);
+            let bottom = (y + height).max(top).min(area.bottom);
+
+            Ok(Some(Area::new(
+                (left - area.left) as u32,
+                (top - area.top) as u32,
+                (right - left) as u32,
+                (bottom - top) as u32,
+            )))
+        })
+        .collect()
+}
+
 fn load_window_functions(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) -> mlua::Result<()> {
     rt.with_lua(|lua| {
         let nog_tbl = lua.globals().get::<_, Table>("nog")?;
@@ -858,9 +1308,26 @@ fn load_window_functions(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) -> ml
 
         l_def_ffi_fn!("minimize", minimize_window);
         l_def_ffi_fn!("toggle_floating", toggle_floating);
+        l_def_ffi_fn!("move", move_floating, direction: Direction, amount: i32);
+        l_def_ffi_fn!("resize_float", resize_floating, direction: Direction, amount: i32);
         l_def_ffi_fn!("ignore", ignore_window);
         l_def_ffi_fn!("close", close_window);
         l_def_ffi_fn!("move_to_ws", move_window_to_workspace, ws_id: i32);
+        l_def_ffi_fn!("to_scratchpad", move_to_scratchpad, name: String);
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "win_explain", move |lua, win_id: Value| {
+            validate!(lua, { win_id: Option<i32> });
+            Ok(state.lock().explain_window(win_id.map(WindowId))?)
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "win_inspect", move |lua, win_id: Value| {
+            validate!(lua, { win_id: Option<i32> });
+            let lines = state.lock().explain_window(win_id.map(WindowId))?.lines();
+            Popup::show(lines, state.clone());
+            Ok(())
+        });
 
         Ok(())
     })
@@ -879,15 +1346,310 @@ fn load_plugin_functions(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) -> ml
             };
         }
 
-        l_def_ffi_fn!("install", install_plugin, name: String);
-        l_def_ffi_fn!("update", update_plugins);
-        l_def_ffi_fn!("uninstall", uninstall_plugin, name: String);
         l_def_ffi_fn!("list", get_plugins);
 
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "plugins", move |lua, decls: Value| {
+            validate!(lua, { decls: Table });
+
+            let mut specs = Vec::new();
+            for decl in decls.sequence_values::<Value>() {
+                specs.push(PluginSpec::from_lua(decl?, lua)?);
+            }
+            PluginManager::validate(&specs)?;
+
+            state.lock().config.plugins = specs;
+
+            let is_setup = lua
+                .globals()
+                .get::<_, Table>("nog")?
+                .get::<_, bool>("__is_setup")?;
+
+            if is_setup {
+                // the config can require the plugins that are already installed right after
+                // declaring them, while the missing ones get loaded once the sync installed them
+                load_plugins(lua, &state)?;
+            }
+            sync_plugins(state.clone(), false);
+
+            Ok(())
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "plug_info", move |_, name: String| {
+            let state = state.lock();
+            // the declared name of a plugin like `owner/repo` also works
+            let path = state
+                .config
+                .plugins
+                .iter()
+                .find(|spec| spec.name == name)
+                .map(|spec| state.config.plugins_path.join(&spec.dir));
+
+            Ok(state
+                .plugins
+                .iter()
+                .find(|plugin| plugin.name == name || Some(&plugin.path) == path.as_ref())
+                .cloned())
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "plug_disable", move |lua, name: String| {
+            set_plugin_enabled(lua, &state, &name, false)
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "plug_enable", move |lua, name: String| {
+            set_plugin_enabled(lua, &state, &name, true)
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "plug_install", move |_, (): ()| {
+            sync_plugins(state.clone(), false);
+            Ok(())
+        });
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "plug_update", move |_, (): ()| {
+            sync_plugins(state.clone(), true);
+            Ok(())
+        });
+
         Ok(())
     })
 }
 
+/// Disables or enables a loaded plugin
+fn set_plugin_enabled(lua: &Lua, state_arc: &Arc<Mutex<AppState>>, name: &str, enabled: bool) -> mlua::Result<()> {
+    let status = state_arc
+        .lock()
+        .plugins
+        .iter()
+        .find(|plugin| plugin.name == name)
+        .map(|plugin| plugin.status.clone());
+
+    match (status, enabled) {
+        (Some(Status::Loaded), false) => sandbox::disable(lua, name)?,
+        (Some(Status::Disabled), true) => sandbox::enable(lua, name)?,
+        (Some(Status::Loaded), true) | (Some(Status::Disabled), false) => return Ok(()),
+        (Some(_), _) => return Err(LuaError::RuntimeError(format!("The plugin {} isn't loaded", name))),
+        (None, _) => return Err(LuaError::RuntimeError(format!("The plugin {} isn't installed", name))),
+    }
+
+    if let Some(plugin) = state_arc.lock().plugins.iter_mut().find(|plugin| plugin.name == name) {
+        plugin.status = if enabled { Status::Loaded } else { Status::Disabled };
+    }
+
+    Ok(())
+}
+
+/// Requires the entry module of a plugin in its own environment and adds the components and
+/// commands it provides
+fn load_plugin(lua: &Lua, plugin: &Plugin, permissions: &[Permission]) -> mlua::Result<()> {
+    sandbox::add_plugin(lua, &plugin.name, plugin.lua_path(), permissions)?;
+
+    let (manifest, main) = match plugin.manifest.as_ref().and_then(|m| Some((m, m.main.as_ref()?))) {
+        Some(entry) => entry,
+        None => return Ok(()),
+    };
+
+    let module = sandbox::require(lua, &plugin.name, main)?;
+    let nog_tbl = lua.globals().get::<_, Table>("nog")?;
+
+    for (key, names) in &[("components", &manifest.components), ("commands", &manifest.commands)] {
+        let provided = match &module {
+            Value::Table(tbl) => tbl.get::<_, Option<Table>>(*key)?,
+            _ => None,
+        };
+        let target = nog_tbl.get::<_, Table>(*key)?;
+        let mut functions = Vec::new();
+
+        for name in names.iter() {
+            let function = match &provided {
+                Some(tbl) => tbl.get::<_, Option<Function>>(name.as_str())?,
+                None => None,
+            }
+            .ok_or_else(|| LuaError::RuntimeError(format!("{} doesn't return the function {}.{}", main, key, name)))?;
+
+            if target.contains_key(name.as_str())? {
+                return Err(LuaError::RuntimeError(format!("nog.{}.{} already exists", key, name)));
+            }
+
+            functions.push((name.as_str(), function));
+        }
+
+        for (name, function) in functions {
+            target.set(name, function)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes the modules of the installed plugins available to `require` without running their entry
+/// modules
+fn add_sandboxes(lua: &Lua, state_arc: &Arc<Mutex<AppState>>) -> mlua::Result<()> {
+    let (plugins_path, permissions) = {
+        let state = state_arc.lock();
+        (state.config.plugins_path.clone(), state.config.plugin_permissions.clone())
+    };
+
+    for plugin in loader::discover(&plugins_path, &update::current_version()) {
+        if !plugin.is_failed() {
+            let allowed = permissions.get(&plugin.name).map(Vec::as_slice).unwrap_or_default();
+            sandbox::add_plugin(lua, &plugin.name, plugin.lua_path(), allowed)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the plugins of the plugins folder that aren't loaded yet. Every plugin gets loaded after
+/// its dependencies.
+fn load_plugins(lua: &Lua, state_arc: &Arc<Mutex<AppState>>) -> mlua::Result<()> {
+    let (plugins_path, permissions, previous) = {
+        let state = state_arc.lock();
+        let previous = state
+            .plugins
+            .iter()
+            .map(|plugin| (plugin.path.clone(), plugin.clone()))
+            .collect::<HashMap<_, _>>();
+        (state.config.plugins_path.clone(), state.config.plugin_permissions.clone(), previous)
+    };
+
+    let mut plugins = loader::discover(&plugins_path, &update::current_version());
+
+    for i in 0..plugins.len() {
+        if let Some(plugin) = previous.get(&plugins[i].path) {
+            if plugin.status == Status::Loaded || plugin.status == Status::Disabled {
+                plugins[i] = plugin.clone();
+                continue;
+            }
+        }
+
+        if plugins[i].status == Status::Pending {
+            let allowed = permissions.get(&plugins[i].name).map(Vec::as_slice).unwrap_or_default();
+            plugins[i].status = match plugins[i].failed_dependency(&plugins) {
+                Some(dep) => Status::Failed(format!("depends on {}, which failed to load", dep)),
+                None => match load_plugin(lua, &plugins[i], allowed) {
+                    Ok(()) => Status::Loaded,
+                    Err(e) => {
+                        sandbox::remove_plugin(lua, &plugins[i].name)?;
+                        Status::Failed(get_err_msg(&e))
+                    }
+                },
+            };
+        }
+
+        if let Status::Failed(reason) = &plugins[i].status {
+            if previous.get(&plugins[i].path).map(|p| &p.status) != Some(&plugins[i].status) {
+                error!("Failed to load the plugin {}: {}", plugins[i].name, reason);
+            }
+        }
+    }
+
+    state_arc.lock().plugins = plugins;
+
+    Ok(())
+}
+
+/// `None` while no sync of the plugins is running. Otherwise the sync that got requested while
+/// it runs, which starts once it is done. `Some(true)` if that sync updates the plugins.
+///
+/// Two syncs at the same time would clone into the same folders and write `plugins.lock` at the
+/// same time, so every request that comes in while one is running gets merged into a single one.
+static PLUGIN_SYNC: Mutex<Option<Option<bool>>> = Mutex::new(None);
+
+/// Syncs the declared plugins in the background and loads them once they are installed. A popup
+/// shows the progress as soon as a plugin got installed, updated, removed or failed.
+fn sync_plugins(state_arc: Arc<Mutex<AppState>>, update: bool) {
+    {
+        let mut sync = PLUGIN_SYNC.lock();
+        if let Some(requested) = sync.as_mut() {
+            *requested = Some(requested.unwrap_or(false) || update);
+            return;
+        }
+        *sync = Some(None);
+    }
+
+    thread::spawn(move || {
+        let mut update = update;
+        loop {
+            run_plugin_sync(&state_arc, update);
+
+            let mut sync = PLUGIN_SYNC.lock();
+            match sync.take().flatten() {
+                Some(requested) => {
+                    *sync = Some(None);
+                    update = requested;
+                }
+                None => break,
+            }
+        }
+    });
+}
+
+fn run_plugin_sync(state_arc: &Arc<Mutex<AppState>>, update: bool) {
+    let (manager, specs, rt) = {
+        let state = state_arc.lock();
+        (
+            PluginManager::with_plugins_path(state.config.plugins_path.clone()),
+            state.config.plugins.clone(),
+            state.lua_rt.clone(),
+        )
+    };
+
+    let action = if update { "Updating" } else { "Installing" };
+    let mut lines = Vec::new();
+    // syncing plugins that are already installed at the right commit doesn't need a popup
+    let mut visible = false;
+    let report = manager.sync(&specs, update, |name, result| {
+        let line = match result {
+            Ok(change) => format!("{}: {}", name, change),
+            Err(e) => e.to_string(),
+        };
+        let header = format!("{} plugins ({}/{})", action, lines.len() + 1, specs.len());
+        match result {
+            Ok(_) => info!("{} {}", header, line),
+            Err(_) => error!("{} {}", header, line),
+        }
+        lines.push(line);
+
+        visible |= !matches!(result, Ok(Change::Unchanged(_)));
+        if visible {
+            let text = vec![header, String::new()].into_iter().chain(lines.clone()).collect();
+            if let Err(e) = Popup::new_error(text).create(state_arc.clone()) {
+                error!("Failed to show the progress of the plugins. {}", e);
+            }
+        }
+    });
+
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            Popup::error(vec![e.to_string()], state_arc.clone());
+            return;
+        }
+    };
+
+    if let Err(e) = rt.with_lua(|lua| load_plugins(lua, state_arc)) {
+        error!("Failed to load the plugins. {}", e);
+    }
+
+    if !visible && report.removed.is_empty() {
+        return;
+    }
+
+    let header = match report.errors().len() {
+        0 => "The plugins are up to date".to_string(),
+        n => format!("{} plugins failed", n),
+    };
+    let text = vec![header, String::new()].into_iter().chain(report.lines()).collect();
+    if let Err(e) = Popup::new_error(text).create(state_arc.clone()) {
+        error!("Failed to show the result of syncing the plugins. {}", e);
+    }
+}
+
 fn load_workspace_functions(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) -> mlua::Result<()> {
     rt.with_lua(|lua| {
         let nog_tbl = lua.globals().get::<_, Table>("nog")?;
@@ -913,6 +1675,9 @@ fn load_workspace_functions(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) ->
         l_def_ffi_fn!("change", emit_change_workspace, ws_id: i32);
         l_def_ffi_fn!("move_in", move_in, direction: Direction);
         l_def_ffi_fn!("move_out", move_out, direction: Direction);
+        l_def_ffi_fn!("stack", stack, direction: Direction);
+        l_def_ffi_fn!("next_tab", next_tab);
+        l_def_ffi_fn!("prev_tab", prev_tab);
         l_def_ffi_fn!("swap_columns_and_rows", swap_columns_and_rows);
         l_def_ffi_fn!("focus", focus, direction: Direction);
         l_def_ffi_fn!("resize", resize, direction: Direction, amount: i32);
@@ -922,6 +1687,37 @@ fn load_workspace_functions(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) ->
             set_split_direction,
             direction: SplitDirection
         );
+        l_def_ffi_fn!("set_layout", set_layout, layout: Layout);
+        l_def_ffi_fn!("save_layout", save_layout, name: String);
+
+        let state = state_arc.clone();
+        def_fn!(lua, nog_tbl, "ws_load_layout", move |lua, (name, slots): (Value, Value)| {
+            validate!(lua, { name: String, slots: Option<Table> });
+
+            let mut rules = Vec::new();
+
+            if let Some(slots) = slots {
+                for pair in slots.pairs::<usize, String>() {
+                    let (slot, pattern) = pair.map_err(|_| {
+                        LuaError::RuntimeError(
+                            "Expected `slots` to map slot numbers to patterns".into(),
+                        )
+                    })?;
+                    let pattern = Regex::new(&pattern)
+                        .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+
+                    rules.push(SlotRule { slot, pattern });
+                }
+            }
+
+            // pairs doesn't have a defined order
+            rules.sort_by_key(|r| r.slot);
+
+            state
+                .lock()
+                .load_layout(name, rules)
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))
+        });
 
         Ok(())
     })
@@ -929,19 +1725,15 @@ fn load_workspace_functions(state_arc: Arc<Mutex<AppState>>, rt: &LuaRuntime) ->
 
 pub fn setup_lua_rt(state_arc: Arc<Mutex<AppState>>) {
     let rt = state_arc.lock().lua_rt.clone();
+    let callback_timeout = state_arc.lock().config.callback_timeout;
 
     setup_nog_global(state_arc.clone(), &rt);
 
     rt.with_lua(|lua| {
-        let state = state_arc.lock();
         let package_tbl = lua.globals().get::<_, Table>("package")?;
         let mut path = package_tbl.get::<_, String>("path")?;
         let mut cpath = package_tbl.get::<_, String>("cpath")?;
 
-        for plug_path in state.get_plugins().unwrap() {
-            path = format!("{}\\lua\\?.lua;{}", plug_path, path);
-        }
-
         path = format!("{}\\config\\?.lua;{}", get_config_path().to_str().unwrap(), path);
 
         #[cfg(debug_assertions)]
@@ -969,7 +1761,8 @@ pub fn setup_lua_rt(state_arc: Arc<Mutex<AppState>>) {
         package_tbl.set("path", path)?;
         package_tbl.set("cpath", cpath)?;
 
-        Ok(())
+        sandbox::install(lua)?;
+        watchdog::set_timeout(lua, &rt.1, callback_timeout)
     }).unwrap();
 
     let mut path = get_runtime_path();
@@ -981,4 +1774,20 @@ pub fn setup_lua_rt(state_arc: Arc<Mutex<AppState>>) {
     load_window_functions(state_arc.clone(), &rt).unwrap();
     load_workspace_functions(state_arc.clone(), &rt).unwrap();
     load_plugin_functions(state_arc.clone(), &rt).unwrap();
+
+    if let Err(e) = rt.with_lua(|lua| add_sandboxes(lua, &state_arc)) {
+        error!("Failed to prepare the plugins. {}", e);
+    }
 }
+
+/// Loads the plugins that the config didn't load with `nog.plugins`, which has to happen after it
+/// ran, so the plugins start with the permissions it gave them
+pub fn load_remaining_plugins(state_arc: &Arc<Mutex<AppState>>) {
+    let rt = state_arc.lock().lua_rt.clone();
+    if let Err(e) = rt.with_lua(|lua| load_plugins(lua, state_arc)) {
+        error!("Failed to load the plugins. {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests;
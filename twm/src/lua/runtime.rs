@@ -4,12 +4,14 @@ use log::{debug, error};
 use mlua::{Function, Lua, Table};
 use parking_lot::Mutex;
 
+use super::watchdog::Watchdog;
+
 pub const CALLBACK_TBL_NAME: &'static str = "__callbacks";
 
 //TODO: Fix unwraps
 
 #[derive(Clone)]
-pub struct LuaRuntime(pub Arc<Mutex<Lua>>);
+pub struct LuaRuntime(pub Arc<Mutex<Lua>>, pub Arc<Mutex<Watchdog>>);
 
 pub fn get_err_msg(e: &mlua::Error) -> String {
     match e {
@@ -24,13 +26,28 @@ impl LuaRuntime {
     pub fn new() -> Self {
         // We have to use the `unsafe_new` function instead of `new`, because we need the dll
         // lookup.
-        Self(Arc::new(Mutex::new(unsafe { Lua::unsafe_new() })))
+        Self(
+            Arc::new(Mutex::new(unsafe { Lua::unsafe_new() })),
+            Arc::default(),
+        )
     }
 
     pub fn with_lua<R>(&self, f: impl Fn(&mut Lua) -> mlua::Result<R>) -> mlua::Result<R> {
         f(&mut self.0.lock())
     }
 
+    /// Works like `with_lua` for running callbacks, but the watchdog aborts them once they run
+    /// longer than `callback_timeout`. `name` describes the callback in the error and in
+    /// `nog.debug_stats`.
+    pub fn watch<R>(&self, name: &str, f: impl Fn(&mut Lua) -> mlua::Result<R>) -> mlua::Result<R> {
+        self.with_lua(|lua| {
+            self.1.lock().start(name);
+            let res = f(lua);
+            self.1.lock().finish();
+            res
+        })
+    }
+
     pub fn enable_setup(&self) -> mlua::Result<()> {
         self.with_lua(|lua| {
             lua.globals()
@@ -0,0 +1,448 @@
+//! Every plugin runs in its own environment table, which only contains the safe parts of the
+//! standard library and the `nog` table. The config itself keeps the unrestricted global
+//! environment.
+//!
+//! The modules of the plugins don't get found through `package.path`. A searcher in
+//! `package.loaders` finds them in the `lua` folders of the plugins and loads them into the
+//! environment of the plugin they belong to, so `require` works like before for the config. Inside
+//! a plugin `require` only finds the modules of the plugins and the libraries it is allowed to use,
+//! everything else in `package.loaded` (like `_G` or the modules of the config) stays hidden.
+use super::get_err_msg;
+use crate::plugins::permission::Permission;
+use mlua::{ChunkMode, Error as LuaError, Function, Lua, MultiValue, Table, Value};
+use std::{collections::HashMap, fs, path::PathBuf};
+use thiserror::Error;
+
+/// Maps the environment of every plugin to its name
+const ENVS: &str = "nog_plugin_envs";
+/// The plugins in the order they got added, where each one is a table with `name`, `env`,
+/// `lua_path` and `permissions`
+const SANDBOXES: &str = "nog_plugin_sandboxes";
+/// What got replaced when a plugin got disabled by the name of the plugin
+const DISABLED: &str = "nog_disabled_plugins";
+/// The modules of the plugins that already got loaded by the path of their file, because
+/// different plugins can have modules with the same name
+const MODULES: &str = "nog_plugin_modules";
+
+/// `getmetatable` isn't part of it, because the metatable of strings would give access to the
+/// `string` library of the config
+const SAFE_GLOBALS: [&str; 18] = [
+    "_VERSION",
+    "assert",
+    "error",
+    "ipairs",
+    "next",
+    "pairs",
+    "pcall",
+    "print",
+    "rawequal",
+    "rawget",
+    "rawset",
+    "select",
+    "setmetatable",
+    "tonumber",
+    "tostring",
+    "type",
+    "unpack",
+    "xpcall",
+];
+const SAFE_LIBS: [&str; 5] = ["bit", "coroutine", "math", "string", "table"];
+const SAFE_OS_FUNCTIONS: [&str; 4] = ["clock", "date", "difftime", "time"];
+
+/// An error of a callback that got created by a plugin
+#[derive(Debug, Error)]
+#[error("The plugin {plugin} failed: {message}")]
+pub struct PluginError {
+    pub plugin: String,
+    pub message: String,
+}
+
+impl PluginError {
+    pub fn find(error: &LuaError) -> Option<&Self> {
+        match error {
+            LuaError::ExternalError(e) => e.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Adds the searcher that finds the modules of the plugins
+pub fn install(lua: &Lua) -> mlua::Result<()> {
+    lua.set_named_registry_value(ENVS, lua.create_table()?)?;
+    lua.set_named_registry_value(SANDBOXES, lua.create_table()?)?;
+    lua.set_named_registry_value(DISABLED, lua.create_table()?)?;
+    lua.set_named_registry_value(MODULES, lua.create_table()?)?;
+
+    let searcher = lua.create_function(|lua, module: String| {
+        // errors can't be raised here, because the searcher gets called by lua
+        let found = find(lua, None, &module).map(|found| found.is_some());
+
+        Ok(match found {
+            Ok(true) => Value::Function(
+                lua.create_function(|lua, module: String| load_module(lua, None, &module))?,
+            ),
+            Ok(false) => Value::String(
+                lua.create_string(&format!("\n\tno module '{}' in the plugins", module))?,
+            ),
+            Err(e) => Value::String(lua.create_string(&format!("\n\t{}", get_err_msg(&e)))?),
+        })
+    })?;
+
+    // the modules of the config come first
+    let globals = lua.globals();
+    let loaders = globals
+        .get::<_, Table>("package")?
+        .get::<_, Table>("loaders")?;
+    globals
+        .get::<_, Table>("table")?
+        .get::<_, Function>("insert")?
+        .call::<_, ()>((loaders, 3, searcher))
+}
+
+fn sandboxes<'lua>(lua: &'lua Lua) -> mlua::Result<Vec<Table<'lua>>> {
+    lua.named_registry_value::<_, Table>(SANDBOXES)?
+        .sequence_values::<Table>()
+        .collect()
+}
+
+fn sandbox<'lua>(lua: &'lua Lua, plugin: &str) -> mlua::Result<Option<Table<'lua>>> {
+    for sandbox in sandboxes(lua)? {
+        if sandbox.get::<_, String>("name")? == plugin {
+            return Ok(Some(sandbox));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Creates the environment of the plugin, whose modules live in `lua_path`. Does nothing if the
+/// plugin already has one.
+pub fn add_plugin(
+    lua: &Lua,
+    plugin: &str,
+    lua_path: PathBuf,
+    permissions: &[Permission],
+) -> mlua::Result<()> {
+    if sandbox(lua, plugin)?.is_some() {
+        return Ok(());
+    }
+
+    let globals = lua.globals();
+    let env = lua.create_table()?;
+
+    for name in &SAFE_GLOBALS {
+        env.set(*name, globals.get::<_, Value>(*name)?)?;
+    }
+
+    env.set(
+        "getmetatable",
+        lua.create_function(|lua, value: Value| match value {
+            Value::String(_) => Ok(Value::Nil),
+            value => lua
+                .globals()
+                .get::<_, Function>("getmetatable")?
+                .call(value),
+        })?,
+    )?;
+
+    // copies, so a plugin can't change the libraries of the others
+    for name in &SAFE_LIBS {
+        if let Some(lib) = globals.get::<_, Option<Table>>(*name)? {
+            env.set(*name, copy(lua, &lib)?)?;
+        }
+    }
+
+    let name = plugin.to_string();
+    env.set(
+        "require",
+        lua.create_function(move |lua, module: String| require(lua, &name, &module))?,
+    )?;
+    env.set("nog", globals.get::<_, Value>("nog")?)?;
+    env.set("_G", env.clone())?;
+
+    let sandbox = lua.create_table()?;
+    sandbox.set("name", plugin)?;
+    sandbox.set("env", env.clone())?;
+    sandbox.set("lua_path", lua_path.to_string_lossy().to_string())?;
+    set_sandbox_permissions(lua, &sandbox, permissions)?;
+
+    let sandboxes = lua.named_registry_value::<_, Table>(SANDBOXES)?;
+    sandboxes.set(sandboxes.raw_len() + 1, sandbox)?;
+    lua.named_registry_value::<_, Table>(ENVS)?
+        .set(env, plugin)?;
+
+    Ok(())
+}
+
+/// Stops the modules of the plugin from being found, which happens when it failed to load
+pub fn remove_plugin(lua: &Lua, plugin: &str) -> mlua::Result<()> {
+    let remaining = lua.create_table()?;
+    for sandbox in sandboxes(lua)? {
+        if sandbox.get::<_, String>("name")? != plugin {
+            remaining.set(remaining.raw_len() + 1, sandbox)?;
+        }
+    }
+
+    lua.set_named_registry_value(SANDBOXES, remaining)
+}
+
+fn copy<'lua>(lua: &'lua Lua, tbl: &Table<'lua>) -> mlua::Result<Table<'lua>> {
+    let copy = lua.create_table()?;
+    for pair in tbl.clone().pairs::<Value, Value>() {
+        let (key, value) = pair?;
+        copy.set(key, value)?;
+    }
+    Ok(copy)
+}
+
+fn set_sandbox_permissions(
+    lua: &Lua,
+    sandbox: &Table,
+    permissions: &[Permission],
+) -> mlua::Result<()> {
+    let globals = lua.globals();
+    let env = sandbox.get::<_, Table>("env")?;
+    let permissions_tbl = lua.create_table()?;
+
+    for permission in permissions {
+        permissions_tbl.set(permission.name(), true)?;
+    }
+
+    let os = globals.get::<_, Table>("os")?;
+    if permissions.contains(&Permission::Os) {
+        env.set("os", os)?;
+    } else {
+        let safe_os = lua.create_table()?;
+        for name in &SAFE_OS_FUNCTIONS {
+            safe_os.set(*name, os.get::<_, Value>(*name)?)?;
+        }
+        env.set("os", safe_os)?;
+    }
+
+    if permissions.contains(&Permission::Io) {
+        env.set("io", globals.get::<_, Value>("io")?)?;
+    } else {
+        env.set("io", Value::Nil)?;
+    }
+
+    sandbox.set("permissions", permissions_tbl)
+}
+
+/// Changes what the plugins are allowed to use. Plugins that aren't part of `permissions` only get
+/// the safe parts of the standard library.
+pub fn set_permissions(
+    lua: &Lua,
+    permissions: &HashMap<String, Vec<Permission>>,
+) -> mlua::Result<()> {
+    for sandbox in sandboxes(lua)? {
+        let name = sandbox.get::<_, String>("name")?;
+        let allowed = permissions
+            .get(&name)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        set_sandbox_permissions(lua, &sandbox, allowed)?;
+    }
+
+    Ok(())
+}
+
+fn is_allowed(lua: &Lua, plugin: &str, permission: Permission) -> mlua::Result<bool> {
+    Ok(match sandbox(lua, plugin)? {
+        Some(sandbox) => sandbox
+            .get::<_, Table>("permissions")?
+            .get::<_, Option<bool>>(permission.name())?
+            .unwrap_or_default(),
+        None => false,
+    })
+}
+
+/// Finds the file of a module in the plugins, where the modules of `preferred` come first. Returns
+/// the file together with the environment of the plugin it belongs to.
+fn find<'lua>(
+    lua: &'lua Lua,
+    preferred: Option<&str>,
+    module: &str,
+) -> mlua::Result<Option<(PathBuf, Table<'lua>)>> {
+    let mut sandboxes = sandboxes(lua)?;
+    if let Some(preferred) = preferred {
+        sandboxes.sort_by_key(|sandbox| {
+            sandbox.get::<_, String>("name").ok().as_deref() != Some(preferred)
+        });
+    }
+
+    let relative = module.split('.').collect::<PathBuf>();
+
+    for sandbox in sandboxes {
+        let lua_path = PathBuf::from(sandbox.get::<_, String>("lua_path")?);
+        let candidates = [
+            lua_path.join(&relative).with_extension("lua"),
+            lua_path.join(&relative).join("init.lua"),
+        ];
+
+        if let Some(path) = candidates.iter().find(|path| path.is_file()) {
+            return Ok(Some((path.clone(), sandbox.get::<_, Table>("env")?)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Loads every module of the plugins once into the environment of the plugin it belongs to, so
+/// the config and every plugin get the same one
+fn load_module<'lua>(
+    lua: &'lua Lua,
+    preferred: Option<&str>,
+    module: &str,
+) -> mlua::Result<Value<'lua>> {
+    let (path, env) = find(lua, preferred, module)?.ok_or_else(|| {
+        LuaError::RuntimeError(format!("module '{}' not found in the plugins", module))
+    })?;
+    let key = path.to_string_lossy().to_string();
+
+    let modules = lua.named_registry_value::<_, Table>(MODULES)?;
+    match modules.get::<_, Value>(key.as_str())? {
+        Value::Nil => {}
+        value => return Ok(value),
+    }
+
+    let content = fs::read_to_string(&path).map_err(LuaError::external)?;
+    let chunk = lua
+        .load(&content)
+        .set_name(&format!("@{}", path.display()))?
+        .set_mode(ChunkMode::Text)
+        .set_environment(env)?
+        .into_function()?;
+
+    let value = match chunk.call::<_, Value>(module)? {
+        Value::Nil => Value::Boolean(true),
+        value => value,
+    };
+    modules.set(key, value.clone())?;
+
+    Ok(value)
+}
+
+/// The `require` of a plugin, which only finds the modules of the plugins, the safe libraries and
+/// the libraries the plugin is allowed to use
+pub fn require<'lua>(lua: &'lua Lua, plugin: &str, module: &str) -> mlua::Result<Value<'lua>> {
+    let env = sandbox(lua, plugin)?
+        .ok_or_else(|| LuaError::RuntimeError(format!("{} isn't a plugin", plugin)))?
+        .get::<_, Table>("env")?;
+
+    if SAFE_LIBS.contains(&module) {
+        return env.get(module);
+    }
+
+    if let Ok(permission) = module.parse::<Permission>() {
+        if !is_allowed(lua, plugin, permission)? {
+            return Err(LuaError::RuntimeError(format!(
+                "the plugin {} isn't allowed to require {}",
+                plugin, module
+            )));
+        }
+
+        return match permission {
+            Permission::Os | Permission::Io => env.get(module),
+            Permission::Ffi => lua.globals().get::<_, Function>("require")?.call(module),
+        };
+    }
+
+    load_module(lua, Some(plugin), module)
+}
+
+/// The name of the plugin that created the function
+pub fn owner(lua: &Lua, function: &Function) -> Option<String> {
+    let env = lua
+        .globals()
+        .get::<_, Function>("getfenv")
+        .and_then(|getfenv| getfenv.call::<_, Value>(function.clone()))
+        .ok()?;
+
+    lua.named_registry_value::<_, Table>(ENVS)
+        .and_then(|envs| envs.get::<_, Option<String>>(env))
+        .ok()
+        .flatten()
+}
+
+/// Names the plugin that created the callback in its error
+pub fn attribute(lua: &Lua, callback: &Function, error: LuaError) -> LuaError {
+    match owner(lua, callback) {
+        Some(plugin) => LuaError::external(PluginError {
+            plugin,
+            message: get_err_msg(&error),
+        }),
+        None => error,
+    }
+}
+
+/// The functions of the table that got created by the plugin
+fn owned_by<'lua>(
+    lua: &'lua Lua,
+    tbl: &Table<'lua>,
+    plugin: &str,
+) -> mlua::Result<Vec<(Value<'lua>, Function<'lua>)>> {
+    let mut owned = Vec::new();
+    for pair in tbl.clone().pairs::<Value, Value>() {
+        if let (key, Value::Function(f)) = pair? {
+            if owner(lua, &f).as_deref() == Some(plugin) {
+                owned.push((key, f));
+            }
+        }
+    }
+    Ok(owned)
+}
+
+/// Replaces the callbacks of the plugin with ones that do nothing and removes the components and
+/// commands it added
+pub fn disable(lua: &Lua, plugin: &str) -> mlua::Result<()> {
+    let disabled = lua.named_registry_value::<_, Table>(DISABLED)?;
+    if disabled.contains_key(plugin)? {
+        return Ok(());
+    }
+
+    let nog_tbl = lua.globals().get::<_, Table>("nog")?;
+    let replaced = lua.create_table()?;
+    let noop = lua.create_function(|lua, _: MultiValue| lua.create_table())?;
+
+    for (key, stub) in &[
+        (super::runtime::CALLBACK_TBL_NAME, Some(&noop)),
+        ("components", None),
+        ("commands", None),
+    ] {
+        let tbl = nog_tbl.get::<_, Table>(*key)?;
+        let stash = lua.create_table()?;
+
+        for (name, f) in owned_by(lua, &tbl, plugin)? {
+            stash.set(name.clone(), f)?;
+            tbl.set(name, stub.cloned())?;
+        }
+
+        replaced.set(*key, stash)?;
+    }
+
+    disabled.set(plugin, replaced)
+}
+
+/// Restores what got replaced when the plugin got disabled
+pub fn enable(lua: &Lua, plugin: &str) -> mlua::Result<()> {
+    let disabled = lua.named_registry_value::<_, Table>(DISABLED)?;
+    let replaced = match disabled.get::<_, Option<Table>>(plugin)? {
+        Some(replaced) => replaced,
+        None => return Ok(()),
+    };
+
+    let nog_tbl = lua.globals().get::<_, Table>("nog")?;
+    for pair in replaced.pairs::<String, Table>() {
+        let (key, stash) = pair?;
+        let tbl = nog_tbl.get::<_, Table>(key)?;
+        for pair in stash.pairs::<Value, Function>() {
+            let (name, f) = pair?;
+            tbl.set(name, f)?;
+        }
+    }
+
+    disabled.set(plugin, Value::Nil)
+}
+
+#[cfg(test)]
+mod tests;
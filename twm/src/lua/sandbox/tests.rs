@@ -0,0 +1,234 @@
+use super::{
+    add_plugin, attribute, disable, enable, install, owner, require, set_permissions, PluginError,
+};
+use crate::{plugins::permission::Permission, util::testing::TempDir};
+use mlua::{Function, Lua, Table, Value};
+use std::{collections::HashMap, fs};
+
+/// A lua state like the one of nog with the plugins `a` and `b`
+fn setup(name: &str) -> (Lua, TempDir) {
+    let root = TempDir::new(&format!("sandbox-{}", name));
+
+    let write = |path: &str, content: &str| {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    };
+    write(
+        "a/lua/a_mod/init.lua",
+        r#"
+            leaked = true
+            string.upper = nil
+            return {
+                io = function() return io end,
+                exit = function() return os.exit end,
+                time = os.time,
+                loadstring = loadstring,
+                getmetatable = getmetatable,
+                fail = function() error("boom") end,
+            }
+        "#,
+    );
+    write(
+        "b/lua/b_mod.lua",
+        r#"
+            local a = require("a_mod")
+            return { a = a, leaked = leaked }
+        "#,
+    );
+
+    for plugin in &["a", "b"] {
+        write(
+            &format!("{}/lua/utils.lua", plugin),
+            &format!("return {{ plugin = '{}', f = function() end }}", plugin),
+        );
+    }
+
+    let lua = unsafe { Lua::unsafe_new() };
+    lua.load("nog = { __callbacks = {}, components = {}, commands = {} }")
+        .exec()
+        .unwrap();
+    install(&lua).unwrap();
+    add_plugin(&lua, "a", root.join("a/lua"), &[]).unwrap();
+    add_plugin(&lua, "b", root.join("b/lua"), &[]).unwrap();
+
+    (lua, root)
+}
+
+fn get<'lua>(lua: &'lua Lua, code: &str) -> Value<'lua> {
+    lua.load(code).eval().unwrap()
+}
+
+fn get_table<'lua>(lua: &'lua Lua, code: &str) -> Table<'lua> {
+    lua.load(code).eval().unwrap()
+}
+
+#[test]
+fn modules_of_plugins_run_in_their_own_environment() {
+    let (lua, _root) = setup("env");
+
+    assert!(matches!(get(&lua, "require('a_mod')"), Value::Table(_)));
+    assert!(matches!(get(&lua, "leaked"), Value::Nil));
+    assert!(matches!(get(&lua, "string.upper"), Value::Function(_)));
+
+    // `b` shares the module with the config, but not the globals of `a`
+    let b = require(&lua, "b", "b_mod").unwrap();
+    let b = match b {
+        Value::Table(b) => b,
+        _ => panic!("b_mod didn't return a table"),
+    };
+    assert_eq!(
+        b.get::<_, Table>("a").unwrap(),
+        get_table(&lua, "require('a_mod')")
+    );
+    assert!(b.get::<_, Option<bool>>("leaked").unwrap().is_none());
+
+    let time = get_table(&lua, "require('a_mod')")
+        .get::<_, Function>("time")
+        .unwrap();
+    assert_eq!(owner(&lua, &time), None);
+    let fail = get_table(&lua, "require('a_mod')")
+        .get::<_, Function>("fail")
+        .unwrap();
+    assert_eq!(owner(&lua, &fail).as_deref(), Some("a"));
+}
+
+#[test]
+fn plugins_get_their_own_module_of_the_same_name() {
+    let (lua, _root) = setup("same-name");
+
+    for plugin in &["a", "b", "a"] {
+        let utils = match require(&lua, plugin, "utils").unwrap() {
+            Value::Table(utils) => utils,
+            _ => panic!("utils didn't return a table"),
+        };
+        let f = utils.get::<_, Function>("f").unwrap();
+
+        assert_eq!(utils.get::<_, String>("plugin").unwrap(), *plugin);
+        assert_eq!(owner(&lua, &f).as_deref(), Some(*plugin));
+    }
+}
+
+#[test]
+fn plugins_only_get_the_libraries_they_are_allowed_to_use() {
+    let (lua, _root) = setup("permissions");
+    let a = get_table(&lua, "require('a_mod')");
+    let call = |name: &str| {
+        a.get::<_, Function>(name)
+            .unwrap()
+            .call::<_, Value>(())
+            .unwrap()
+    };
+
+    assert!(matches!(call("io"), Value::Nil));
+    assert!(matches!(call("exit"), Value::Nil));
+    assert!(matches!(
+        a.get::<_, Value>("time").unwrap(),
+        Value::Function(_)
+    ));
+    assert!(matches!(
+        a.get::<_, Value>("loadstring").unwrap(),
+        Value::Nil
+    ));
+    assert!(require(&lua, "a", "ffi").is_err());
+    assert!(require(&lua, "a", "debug").is_err());
+
+    let mut permissions = HashMap::new();
+    permissions.insert("a".to_string(), vec![Permission::Io, Permission::Os]);
+    set_permissions(&lua, &permissions).unwrap();
+
+    assert!(matches!(call("io"), Value::Table(_)));
+    assert!(matches!(call("exit"), Value::Function(_)));
+    assert!(require(&lua, "a", "ffi").is_err());
+    assert!(require(&lua, "b", "io").is_err());
+}
+
+#[test]
+fn plugins_cant_reach_the_global_environment() {
+    let (lua, _root) = setup("escape");
+    lua.load("package.loaded.config_module = { secret = true }")
+        .exec()
+        .unwrap();
+
+    assert!(require(&lua, "a", "_G").is_err());
+    assert!(require(&lua, "a", "config_module").is_err());
+    assert!(require(&lua, "a", "package").is_err());
+
+    // the libraries a plugin requires are its own copies, which `a_mod` changed
+    let a = get_table(&lua, "require('a_mod')");
+    let string = match require(&lua, "a", "string").unwrap() {
+        Value::Table(string) => string,
+        _ => panic!("string isn't a table"),
+    };
+    assert!(string != get_table(&lua, "string"));
+    assert!(string
+        .get::<_, Option<Function>>("upper")
+        .unwrap()
+        .is_none());
+
+    let getmetatable = a.get::<_, Function>("getmetatable").unwrap();
+    assert!(matches!(
+        getmetatable.call::<_, Value>("text").unwrap(),
+        Value::Nil
+    ));
+}
+
+#[test]
+fn errors_of_callbacks_name_the_plugin() {
+    let (lua, _root) = setup("errors");
+    let fail = get_table(&lua, "require('a_mod')")
+        .get::<_, Function>("fail")
+        .unwrap();
+
+    let error = attribute(&lua, &fail, fail.call::<_, ()>(()).unwrap_err());
+    let error = PluginError::find(&error).unwrap();
+
+    assert_eq!(error.plugin, "a");
+    assert!(error.message.contains("boom"));
+
+    let config_fn = get(&lua, "function() error('config') end");
+    let config_fn = match config_fn {
+        Value::Function(f) => f,
+        _ => unreachable!(),
+    };
+    let error = attribute(&lua, &config_fn, config_fn.call::<_, ()>(()).unwrap_err());
+    assert!(PluginError::find(&error).is_none());
+}
+
+#[test]
+fn disabled_plugins_dont_run() {
+    let (lua, _root) = setup("disable");
+    lua.load(
+        r#"
+            local a = require("a_mod")
+            nog.__callbacks[1] = a.fail
+            nog.__callbacks[2] = function() end
+            nog.components.clock = a.io
+        "#,
+    )
+    .exec()
+    .unwrap();
+    let callback = |id: usize| get(&lua, &format!("nog.__callbacks[{}]", id));
+    let (original, config_callback) = (callback(1), callback(2));
+
+    disable(&lua, "a").unwrap();
+
+    assert!(callback(1) != original);
+    assert!(matches!(
+        get_table(&lua, "nog.__callbacks")
+            .get::<_, Function>(1)
+            .unwrap()
+            .call::<_, Value>(()),
+        Ok(Value::Table(_))
+    ));
+    assert!(callback(2) == config_callback);
+    assert!(matches!(get(&lua, "nog.components.clock"), Value::Nil));
+
+    enable(&lua, "a").unwrap();
+
+    assert!(callback(1) == original);
+    assert!(matches!(
+        get(&lua, "nog.components.clock"),
+        Value::Function(_)
+    ));
+}
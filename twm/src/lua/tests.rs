@@ -0,0 +1,26 @@
+use super::rule_from_tbl;
+use crate::config::rule::Action;
+use mlua::{Lua, Table};
+
+fn rule_tbl<'lua>(lua: &'lua Lua, source: &str) -> Table<'lua> {
+    lua.load(source).eval().unwrap()
+}
+
+#[test]
+fn rules_without_a_matcher_are_rejected() {
+    let lua = Lua::new();
+    let tbl = rule_tbl(&lua, r#"{ proces = "x", action = "ignore" }"#);
+
+    let error = rule_from_tbl(&lua, None, tbl).unwrap_err();
+    assert!(error.to_string().contains("A rule needs at least one of"));
+}
+
+#[test]
+fn pattern_keys_are_the_matcher_of_their_rule() {
+    let lua = Lua::new();
+    let tbl = rule_tbl(&lua, r#"{ action = "ignore" }"#);
+
+    let rule = rule_from_tbl(&lua, Some("^term.exe$".into()), tbl).unwrap();
+    assert_eq!(rule.matcher.pattern().unwrap().as_str(), "^term.exe$");
+    assert_eq!(rule.action, Action::Ignore);
+}
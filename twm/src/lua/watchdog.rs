@@ -0,0 +1,191 @@
+//! Aborts lua callbacks that run longer than `callback_timeout` and measures how long every
+//! callback takes.
+//!
+//! A hook checks the running callback every thousand instructions. The hook is a lua function that
+//! raises the error itself, because errors of rust functions can't unwind through the hooks of
+//! LuaJIT everywhere. LuaJIT doesn't call hooks inside compiled code either, which is why the JIT is
+//! off while the watchdog is enabled.
+use mlua::{Function, Lua, Table};
+use parking_lot::Mutex;
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// How many instructions lua runs between two checks of the watchdog
+const INSTRUCTIONS: u32 = 1000;
+/// The original `debug.sethook`, which the config can't replace
+const SETHOOK: &str = "nog_watchdog_sethook";
+
+/// The error of a callback that ran longer than `callback_timeout`
+#[derive(Debug, Error)]
+#[error("{callback} ran longer than {}ms and got aborted", .timeout.as_millis())]
+pub struct Timeout {
+    pub callback: String,
+    pub timeout: Duration,
+}
+
+/// How long the runs of a callback took
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub calls: u32,
+    /// How many runs got aborted by the watchdog
+    pub aborted: u32,
+    pub total: Duration,
+    pub max: Duration,
+    pub last: Duration,
+}
+
+#[derive(Debug)]
+struct Run {
+    callback: String,
+    started: Instant,
+    aborted: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    /// Callbacks can run as long as they want without a timeout
+    timeout: Option<Duration>,
+    run: Option<Run>,
+    /// The stats of every callback by its name
+    stats: BTreeMap<String, Stats>,
+}
+
+impl Watchdog {
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn stats(&self) -> &BTreeMap<String, Stats> {
+        &self.stats
+    }
+
+    /// `callback` describes the callback in the error and the stats, like `keybinding Alt+H`
+    pub fn start(&mut self, callback: &str) {
+        self.run = Some(Run {
+            callback: callback.into(),
+            started: Instant::now(),
+            aborted: false,
+        });
+    }
+
+    pub fn finish(&mut self) {
+        let run = match self.run.take() {
+            Some(run) => run,
+            None => return,
+        };
+
+        let took = run.started.elapsed();
+        let stats = self.stats.entry(run.callback).or_default();
+        stats.calls += 1;
+        stats.total += took;
+        stats.max = stats.max.max(took);
+        stats.last = took;
+        if run.aborted {
+            stats.aborted += 1;
+        }
+    }
+
+    /// Fails once the running callback took longer than the timeout. A callback that catches the
+    /// error with `pcall` gets the error again on the next check.
+    pub fn check(&mut self) -> Result<(), Timeout> {
+        let (run, timeout) = match (self.run.as_mut(), self.timeout) {
+            (Some(run), Some(timeout)) => (run, timeout),
+            _ => return Ok(()),
+        };
+
+        if run.started.elapsed() <= timeout {
+            return Ok(());
+        }
+
+        run.aborted = true;
+        Err(Timeout {
+            callback: run.callback.clone(),
+            timeout,
+        })
+    }
+}
+
+fn sethook(lua: &Lua) -> mlua::Result<Function<'_>> {
+    if let Ok(sethook) = lua.named_registry_value::<_, Function>(SETHOOK) {
+        return Ok(sethook);
+    }
+
+    let sethook = lua
+        .globals()
+        .get::<_, Table>("debug")?
+        .get::<_, Function>("sethook")?;
+    lua.set_named_registry_value(SETHOOK, sethook.clone())?;
+
+    Ok(sethook)
+}
+
+/// Sets the timeout of the watchdog in milliseconds, where 0 or less disables it. This can happen
+/// at any time, like when the config or an IPC client changes `callback_timeout`, and applies to
+/// the callback that is running right now as well.
+pub fn set_timeout(lua: &Lua, watchdog: &Arc<Mutex<Watchdog>>, ms: i32) -> mlua::Result<()> {
+    let timeout = if ms > 0 {
+        Some(Duration::from_millis(ms as u64))
+    } else {
+        None
+    };
+    watchdog.lock().timeout = timeout;
+
+    let sethook = sethook(lua)?;
+    let jit = lua.globals().get::<_, Table>("jit")?;
+    let jit_fn = |name: &str| jit.get::<_, Function>(name)?.call::<_, ()>(());
+
+    if timeout.is_none() {
+        sethook.call::<_, ()>(())?;
+        return jit_fn("on");
+    }
+
+    let watchdog = watchdog.clone();
+    let check =
+        lua.create_function(move |_, ()| Ok(watchdog.lock().check().err().map(|e| e.to_string())))?;
+    let hook = lua
+        .load(
+            r#"
+                local check = ...
+                return function()
+                    local err = check()
+                    if err then
+                        error(err, 2)
+                    end
+                end
+            "#,
+        )
+        .set_name("watchdog")?
+        .call::<_, Function>(check)?;
+    sethook.call::<_, ()>((hook, "", INSTRUCTIONS))?;
+
+    // the code that already got compiled would keep running without the hook
+    jit_fn("off")?;
+    jit_fn("flush")
+}
+
+/// The table that `nog.debug_stats` returns
+pub fn debug_stats<'lua>(
+    lua: &'lua Lua,
+    watchdog: &Arc<Mutex<Watchdog>>,
+) -> mlua::Result<Table<'lua>> {
+    let (timeout, callbacks) = {
+        let watchdog = watchdog.lock();
+        (watchdog.timeout(), watchdog.stats().clone())
+    };
+
+    let tbl = lua.create_table()?;
+    tbl.set(
+        "callback_timeout",
+        timeout.map_or(0, |t| t.as_millis() as u64),
+    )?;
+    tbl.set("callbacks", lua.create_table_from(callbacks)?)?;
+
+    Ok(tbl)
+}
+
+#[cfg(test)]
+mod tests;
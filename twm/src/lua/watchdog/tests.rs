@@ -0,0 +1,120 @@
+use super::{debug_stats, set_timeout, Watchdog};
+use crate::lua::{get_err_msg, LuaRuntime};
+use mlua::{Function, Table};
+use std::{thread, time::Duration};
+
+#[test]
+fn measures_every_callback() {
+    let mut watchdog = Watchdog::default();
+
+    for _ in 0..2 {
+        watchdog.start("keybinding Alt+H");
+        watchdog.finish();
+    }
+    watchdog.start("component clock");
+    thread::sleep(Duration::from_millis(5));
+    assert!(watchdog.check().is_ok());
+    watchdog.finish();
+
+    let stats = watchdog.stats();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats["keybinding Alt+H"].calls, 2);
+    assert_eq!(stats["component clock"].calls, 1);
+    assert_eq!(stats["component clock"].aborted, 0);
+    assert!(stats["component clock"].max >= Duration::from_millis(5));
+    assert_eq!(
+        stats["component clock"].total,
+        stats["component clock"].last
+    );
+}
+
+#[test]
+fn aborts_callbacks_that_run_too_long() {
+    let mut watchdog = Watchdog {
+        timeout: Some(Duration::from_millis(1)),
+        ..Default::default()
+    };
+
+    // nothing is running while the config gets executed
+    thread::sleep(Duration::from_millis(5));
+    assert!(watchdog.check().is_ok());
+
+    watchdog.start("keybinding Alt+H");
+    thread::sleep(Duration::from_millis(5));
+    let error = watchdog.check().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "keybinding Alt+H ran longer than 1ms and got aborted"
+    );
+    assert!(watchdog.check().is_err());
+    watchdog.finish();
+
+    assert!(watchdog.check().is_ok());
+    assert_eq!(watchdog.stats()["keybinding Alt+H"].aborted, 1);
+}
+
+#[test]
+fn the_jit_is_off_while_the_watchdog_is_enabled() {
+    let rt = LuaRuntime::new();
+    let jit_enabled = || {
+        rt.with_lua(|lua| {
+            lua.globals()
+                .get::<_, Table>("jit")?
+                .get::<_, Function>("status")?
+                .call::<_, bool>(())
+        })
+        .unwrap()
+    };
+
+    assert!(jit_enabled());
+
+    rt.with_lua(|lua| set_timeout(lua, &rt.1, 1000)).unwrap();
+    assert!(!jit_enabled());
+
+    rt.with_lua(|lua| set_timeout(lua, &rt.1, 0)).unwrap();
+    assert!(jit_enabled());
+}
+
+#[test]
+fn callbacks_that_loop_forever_get_aborted() {
+    let rt = LuaRuntime::new();
+    rt.with_lua(|lua| set_timeout(lua, &rt.1, 50)).unwrap();
+
+    let error = rt
+        .watch("keybinding Alt+H", |lua| {
+            lua.load("local x = 0 while true do x = x + 1 end").exec()
+        })
+        .unwrap_err();
+    assert!(
+        get_err_msg(&error).contains("keybinding Alt+H ran longer than 50ms and got aborted"),
+        "{}",
+        error
+    );
+
+    // code that runs after the callback isn't affected
+    rt.with_lua(|lua| {
+        lua.load("local x = 0 for i = 1, 100000 do x = x + i end")
+            .exec()
+    })
+    .unwrap();
+    rt.watch("component clock", |lua| lua.load("return 1").exec())
+        .unwrap();
+
+    rt.with_lua(|lua| {
+        let stats = debug_stats(lua, &rt.1)?;
+        let callback = |name: &str| -> Table {
+            stats
+                .get::<_, Table>("callbacks")
+                .and_then(|callbacks| callbacks.get(name))
+                .unwrap()
+        };
+
+        assert_eq!(stats.get::<_, u64>("callback_timeout")?, 50);
+        assert_eq!(callback("keybinding Alt+H").get::<_, u32>("aborted")?, 1);
+        assert!(callback("keybinding Alt+H").get::<_, f64>("last")? >= 50.0);
+        assert_eq!(callback("component clock").get::<_, u32>("calls")?, 1);
+        assert_eq!(callback("component clock").get::<_, u32>("aborted")?, 0);
+        Ok(())
+    })
+    .unwrap();
+}
@@ -6,32 +6,40 @@ extern crate num_derive;
 extern crate strum_macros;
 
 use bar::component::{self, Component, ComponentText};
-use config::{bar_config::BarConfig, rule::{Rule, Action as RuleAction}, workspace_setting::WorkspaceSetting, Config};
+use config::{rule::Rule, workspace_setting::WorkspaceSetting, Config};
 use crossbeam_channel::select;
+use decision::{Decision, Outcome};
 use direction::Direction;
 use display::Display;
 use event::Event;
 use event::EventChannel;
+use hooks::{Hook, Hooks};
 use hot_reload::update_config;
-use itertools::Itertools;
-use keybindings::{keybinding::Keybinding, keybinding::KeybindingKind, KbManager};
+use keybindings::{keybinding::Keybinding, mode::ModeStack, KbManager};
 use log::debug;
 use log::{error, info};
 use lua::{setup_lua_rt, LuaRuntime};
 use parking_lot::{deadlock, Mutex};
+use plugins::loader::Plugin;
 use popup::Popup;
 use regex::Regex;
 use split_direction::SplitDirection;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
-use std::process::Command;
 use std::str::FromStr;
 use std::{fmt::Debug, fs::ReadDir, path::Path};
-use std::{mem, thread, time::Duration};
+use std::{mem, thread, time::{Duration, Instant}};
 use std::{process, sync::atomic::AtomicBool, sync::Arc};
 use system::NativeWindow;
-use system::{DisplayId, SystemResult, SystemError, WinEventListener, WindowId};
+use system::{DisplayId, Rectangle, SystemResult, SystemError, WinEventListener, WindowId};
 use task_bar::Taskbar;
-use tile_grid::{store::Store, TileGrid};
+use scratchpad::{Scratchpad, ScratchpadError};
+use session::{Session, SessionStore};
+use tile_grid::{
+    layout::Layout,
+    snapshot::{LayoutSnapshot, SlotRule, SnapshotError},
+    TileGrid,
+};
 use win_event_handler::{win_event::WinEvent, win_event_type::WinEventType};
 use window::Window;
 
@@ -116,23 +124,33 @@ mod macros {
 
 mod bar;
 mod config;
+mod decision;
 mod direction;
 mod display;
 mod event;
 mod event_handler;
+mod hooks;
 mod hot_reload;
+mod ipc;
 mod keybindings;
 mod logging;
 mod lua;
+#[cfg(not(feature = "headless"))]
 mod message_loop;
+mod plugins;
 mod popup;
 mod renderer;
+mod scratchpad;
+mod session;
+#[cfg(all(test, feature = "headless"))]
+mod simulation;
 mod split_direction;
 mod startup;
 mod system;
 mod task_bar;
 mod tile;
 mod tile_grid;
+#[cfg(not(feature = "headless"))]
 mod tray;
 mod update;
 mod util;
@@ -150,6 +168,20 @@ pub struct AppState {
     pub additonal_rules: Vec<Rule>,
     pub window_event_listener: WinEventListener,
     pub workspace_id: i32,
+    pub session_store: SessionStore,
+    /// Layout snapshots that got saved with `nog.ws_save_layout`
+    pub saved_layouts: BTreeMap<String, LayoutSnapshot>,
+    pub scratchpad: Scratchpad,
+    /// IPC clients that get notified about changes of the state
+    pub subscribers: Vec<ipc::Subscriber>,
+    /// Lua callbacks that got registered with `nog.on`
+    pub hooks: Hooks,
+    /// The modes that are active, the current one last
+    pub modes: ModeStack,
+    /// Why nog did or didn't manage the windows that appeared
+    pub decisions: HashMap<WindowId, Decision>,
+    /// The plugins of the plugins folder in the order they got loaded in
+    pub plugins: Vec<Plugin>,
 }
 
 impl Default for AppState {
@@ -164,6 +196,14 @@ impl Default for AppState {
             additonal_rules: Vec::new(),
             window_event_listener: WinEventListener::default(),
             workspace_id: 1,
+            session_store: SessionStore::new(Some(SessionStore::default_path())),
+            saved_layouts: BTreeMap::new(),
+            scratchpad: Scratchpad::default(),
+            subscribers: Vec::new(),
+            hooks: Hooks::default(),
+            modes: ModeStack::default(),
+            decisions: HashMap::new(),
+            plugins: Vec::new(),
             config,
         }
     }
@@ -182,103 +222,13 @@ impl AppState {
             d.cleanup(self.config.remove_task_bar)?;
         }
 
-        Ok(())
-    }
-
-    pub fn install_plugin(&mut self, name: String) -> SystemResult {
-        let url = format!("https://www.github.com/{}", &name);
-        let mut path = self.config.plugins_path.clone();
-        path.push(name.split("/").join("_"));
-
-        if path.exists() {
-            debug!("{} is already installed", name);
-        } else {
-            debug!("Installing {} from {}", name, url);
-            Command::new("git")
-                .arg("clone")
-                .arg(&url)
-                .arg(&path)
-                .spawn()
-                .unwrap()
-                .wait()
-                .unwrap();
-
-            path.push("plugin");
+        for window in self.scratchpad.release_all() {
+            window.show();
         }
 
         Ok(())
     }
 
-    pub fn update_plugins(&mut self) -> SystemResult {
-        if let Ok(dirs) = get_plugins_path_iter() {
-            for dir in dirs {
-                if let Ok(dir) = dir {
-                    let name = dir.file_name().to_str().unwrap().to_string();
-
-                    let mut path = self.config.plugins_path.clone();
-                    path.push(&name);
-
-                    let name = name.split("_").join("/");
-                    let url = format!("https://www.github.com/{}", name);
-
-                    let output = Command::new("git")
-                        .arg("rev-parse")
-                        .arg("--is-inside-work-tree")
-                        .current_dir(&path)
-                        .output()
-                        .unwrap();
-
-                    let is_git_repo = output.stdout.iter().map(|&x| x as char).count() != 0;
-
-                    if !is_git_repo {
-                        debug!("{} is not a git repo", name);
-                        continue;
-                    }
-
-                    let output = Command::new("git")
-                        .arg("rev-list")
-                        .arg("HEAD...origin/master")
-                        .arg("--count")
-                        .current_dir(&path)
-                        .output()
-                        .unwrap();
-
-                    let has_updates =
-                        output.stdout.iter().map(|&x| x as char).collect::<String>() != "0\n";
-
-                    if has_updates {
-                        debug!("Updating {}", name);
-                        Command::new("git")
-                            .arg("pull")
-                            .arg(&url)
-                            .spawn()
-                            .unwrap()
-                            .wait()
-                            .unwrap();
-                    } else {
-                        debug!("{} is up to date", &name);
-                    }
-                }
-            }
-        }
-        Ok(())
-    }
-
-    pub fn uninstall_plugin(&mut self, name: String) -> SystemResult {
-        let mut path = self.config.plugins_path.clone();
-        path.push(name.split("/").join("_"));
-
-        if path.exists() {
-            debug!("Uninstalling {}", name);
-            if let Err(e) = std::fs::remove_dir_all(path) {
-                error!("Failed to remove plugin: {}", e.to_string());
-            }
-        } else {
-            debug!("{} is not installed", name);
-        }
-        Ok(())
-    }
-
     pub fn get_plugins(&self) -> SystemResult<Vec<String>> {
         let mut list: Vec<String> = Vec::new();
 
@@ -318,12 +268,107 @@ impl AppState {
         Ok(())
     }
 
-    pub fn emit_lua_rt_error(&mut self, msg: &str) {
-        self.event_channel
-            .sender
-            .send(Event::LuaRuntimeError(mlua::Error::RuntimeError(
-                msg.to_string(),
-            )));
+    /// Runs the callbacks of the hook once the current event got handled
+    pub fn emit_hook(&self, hook: Hook, fields: Vec<(&str, serde_json::Value)>) {
+        self.hooks.emit(&self.event_channel.sender, hook, fields);
+    }
+
+    /// Describes a callback in errors and `nog.debug_stats`, like `keybinding Alt+H` or
+    /// `hook focus_change`
+    fn callback_name(&self, idx: usize, arg: Option<&serde_json::Value>) -> String {
+        if let Some(hook) = arg.and_then(|arg| arg.get("hook")).and_then(|hook| hook.as_str()) {
+            return format!("hook {}", hook);
+        }
+
+        self.config
+            .keybindings
+            .iter()
+            .find(|kb| kb.callback_id == idx)
+            .map(|kb| format!("keybinding {}", kb.as_key_combo()))
+            .unwrap_or_else(|| format!("callback {}", idx))
+    }
+
+    /// Enters the mode on top of the current one
+    pub fn enter_mode(&mut self, name: &str) -> Result<(), String> {
+        let mode = self
+            .config
+            .modes
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Mode '{}' has not been defined yet", name))?;
+
+        if self.modes.enter(&mode, Instant::now()) {
+            self.modes_changed(Vec::new(), Some(name));
+        }
+
+        Ok(())
+    }
+
+    /// Leaves the mode and every mode that got entered after it
+    pub fn leave_mode(&mut self, name: &str) {
+        let left = self.modes.leave(name);
+        self.modes_changed(left, None);
+    }
+
+    pub fn toggle_mode(&mut self, name: &str) -> Result<(), String> {
+        if self.modes.contains(name) {
+            self.leave_mode(name);
+            Ok(())
+        } else {
+            self.enter_mode(name)
+        }
+    }
+
+    pub fn leave_all_modes(&mut self) {
+        let left = self.modes.clear();
+        self.modes_changed(left, None);
+    }
+
+    /// Leaves every mode that timed out
+    pub fn expire_modes(&mut self, now: Instant) {
+        let left = self.modes.expire(now);
+        self.modes_changed(left, None);
+    }
+
+    /// Oneshot modes get left once one of their keybindings got triggered
+    fn keybinding_triggered(&mut self, kb: &Keybinding) {
+        let oneshot = match self.modes.current() {
+            Some(mode) if mode.oneshot && kb.mode.as_ref() == Some(&mode.name) => mode.name.clone(),
+            _ => return,
+        };
+
+        self.leave_mode(&oneshot);
+    }
+
+    fn modes_changed(&mut self, left: Vec<String>, entered: Option<&str>) {
+        if left.is_empty() && entered.is_none() {
+            return;
+        }
+
+        for mode in left {
+            self.emit_hook(Hook::ModeLeave, vec![("mode", mode.into())]);
+        }
+
+        if let Some(mode) = entered {
+            self.emit_hook(Hook::ModeEnter, vec![("mode", mode.into())]);
+        }
+
+        if let Some(kbm) = &self.keybindings_manager {
+            kbm.mode_changed(
+                self.modes.current().map(|mode| mode.name.clone()),
+                self.modes.next_expiry(),
+            );
+        }
+
+        self.redraw_app_bars();
+    }
+
+    /// Remembers the error of a callback that belongs to the plugin
+    pub fn plugin_failed(&mut self, name: &str, message: &str) {
+        if let Some(plugin) = self.plugins.iter_mut().find(|plugin| plugin.name == name) {
+            plugin.errors += 1;
+            plugin.last_error = Some(message.into());
+        }
     }
 
     pub fn move_workspace_to_monitor(&mut self, monitor: i32) -> SystemResult {
@@ -428,17 +473,14 @@ impl AppState {
 
     pub fn ignore_window(&mut self) -> SystemResult {
         if let Some(window) = self.get_current_grid().unwrap().get_focused_window() {
-            let mut rule = Rule::default();
-
             let process_name = window.get_process_name();
             let pattern = format!("^{}$", process_name);
 
             debug!("Adding rule with pattern {}", pattern);
 
-            rule.pattern = regex::Regex::new(&pattern).expect("Failed to build regex");
-            rule.action = RuleAction::Ignore;
-
-            self.additonal_rules.push(rule);
+            self.additonal_rules.push(Rule::ignore_process(
+                regex::Regex::new(&pattern).expect("Failed to build regex"),
+            ));
 
             self.toggle_floating()?;
         }
@@ -454,7 +496,6 @@ impl AppState {
             if let Some(target_grid) = self.get_grid_by_id_mut(id) {
                 window.hide();
                 target_grid.push(window);
-                Store::save(id, target_grid.to_string());
             }
         });
 
@@ -495,27 +536,55 @@ impl AppState {
             this = state_arc.lock();
         }
 
+        this.restore_session()?;
+
+        info!("Registering windows event handler");
+        this.window_event_listener.start(&this.event_channel);
+
+        let kb = this.keybindings_manager.as_ref().unwrap().clone();
+
+        this.emit_hook(Hook::WorkModeEnter, Vec::new());
+
+        drop(this);
+
+        kb.enter_work_mode();
+
+        Ok(())
+    }
+
+    /// Restores the saved session and focuses the workspaces that were focused before
+    pub fn restore_session(&mut self) -> SystemResult {
+        let session = match self.session_store.load() {
+            Ok(None) => self.session_store.load_legacy().map(|legacy| {
+                legacy.map(|content| {
+                    info!("Migrating the workspaces of {} to the session", session::LEGACY_FILE);
+                    Session::from_legacy(&content, self)
+                })
+            }),
+            session => session,
+        };
+
+        match session {
+            Ok(Some(session)) => session.restore(self, system::api::get_windows()),
+            Ok(None) => {}
+            Err(e) => error!("Failed to load the session: {}", e),
+        }
+
         let mut focused_workspaces = Vec::<i32>::new();
-        let remove_title_bar = this.config.remove_title_bar;
-        let use_border = this.config.use_border;
-        let stored_grids: Vec<String> = Store::load();
-        let rules = this.config.rules.clone();
-        let additional_rules = this.additonal_rules.clone();
-        for display in this.displays.iter_mut() {
+        let remove_title_bar = self.config.remove_title_bar;
+        let use_border = self.config.use_border;
+        let rules = self.config.rules.clone();
+        let additional_rules = self.additonal_rules.clone();
+        for display in self.displays.iter_mut() {
             for grid in display.grids.iter_mut() {
-                if let Some(stored_grid) = stored_grids.get((grid.id - 1) as usize) {
-                    grid.from_string(stored_grid);
-                    Store::save(grid.id, grid.to_string());
-
-                    if let Err(e) = grid.modify_windows(|window| {
-                        let rules = rules.iter().chain(additional_rules.iter()).collect();
-                        window.set_matching_rule(rules);
-                        window.init(remove_title_bar, use_border)?;
-
-                        Ok(())
-                    }) {
-                        error!("Error while initializing window {:?}", e);
-                    }
+                if let Err(e) = grid.modify_windows(|window| {
+                    let rules = rules.iter().chain(additional_rules.iter()).collect();
+                    window.set_matching_rule(rules);
+                    window.init(remove_title_bar, use_border)?;
+
+                    Ok(())
+                }) {
+                    error!("Error while initializing window {:?}", e);
                 }
 
                 grid.hide(); // hides all the windows just loaded into the grid
@@ -529,23 +598,34 @@ impl AppState {
         if !focused_workspaces.is_empty() {
             // re-focus to show each display's focused workspace
             for id in focused_workspaces.iter().rev() {
-                this.change_workspace(*id, false)?;
+                self.change_workspace(*id, false)?;
             }
         } else {
             // otherwise just focus first workspace
-            this.change_workspace(1, false)?;
+            self.change_workspace(1, false)?;
         }
 
-        info!("Registering windows event handler");
-        this.window_event_listener.start(&this.event_channel);
-
-        let kb = this.keybindings_manager.as_ref().unwrap().clone();
+        Ok(())
+    }
 
-        drop(this);
+    /// Marks the session as changed if nog is in work mode. Outside of work mode the grids are
+    /// empty.
+    pub fn session_changed(&mut self) {
+        if self.work_mode {
+            self.session_store.changed();
+        }
+    }
 
-        kb.enter_work_mode();
+    /// Saves the session right away if it changed
+    pub fn save_session(&mut self) {
+        if self.session_store.time_until_save().is_none() {
+            return;
+        }
 
-        Ok(())
+        let session = Session::from_state(self);
+        if let Err(e) = self.session_store.save(&session) {
+            error!("Failed to save the session: {}", e);
+        }
     }
 
     pub fn leave_work_mode(state_arc: Arc<Mutex<AppState>>) -> SystemResult {
@@ -565,7 +645,9 @@ impl AppState {
             this.show_taskbars();
         }
 
+        this.save_session();
         this.cleanup()?;
+        this.emit_hook(Hook::WorkModeLeave, Vec::new());
         Ok(())
     }
 
@@ -640,6 +722,40 @@ impl AppState {
         Ok(())
     }
 
+    pub fn stack(&mut self, direction: Direction) -> SystemResult {
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+
+        if let Some(grid) = display.get_focused_grid_mut() {
+            if !config.ignore_fullscreen_actions || !grid.is_fullscreened() {
+                grid.stack_focused(direction);
+                display.refresh_grid(&config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn next_tab(&mut self) -> SystemResult {
+        self.cycle_tab(true)
+    }
+
+    pub fn prev_tab(&mut self) -> SystemResult {
+        self.cycle_tab(false)
+    }
+
+    fn cycle_tab(&mut self, forward: bool) -> SystemResult {
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+
+        if let Some(grid) = display.get_focused_grid_mut() {
+            grid.cycle_tab(forward)?;
+            display.refresh_grid(&config)?;
+        }
+
+        Ok(())
+    }
+
     pub fn focus(&mut self, direction: Direction) -> SystemResult {
         let config = self.config.clone();
         let display = self.get_current_display_mut();
@@ -677,6 +793,45 @@ impl AppState {
         Ok(())
     }
 
+    pub fn set_layout(&mut self, layout: Layout) -> SystemResult {
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+        if let Some(grid) = display.get_focused_grid_mut() {
+            grid.layout = layout;
+        }
+        display.refresh_grid(&config)?;
+        Ok(())
+    }
+
+    pub fn save_layout(&mut self, name: String) -> Result<(), SnapshotError> {
+        let snapshot = self
+            .get_current_display()
+            .get_focused_grid()
+            .and_then(LayoutSnapshot::of)
+            .ok_or(SnapshotError::EmptyWorkspace)?;
+
+        self.saved_layouts.insert(name, snapshot);
+
+        Ok(())
+    }
+
+    pub fn load_layout(&mut self, name: String, rules: Vec<SlotRule>) -> Result<(), SnapshotError> {
+        let snapshot = self
+            .saved_layouts
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| SnapshotError::Unknown(name.clone()))?;
+
+        let config = self.config.clone();
+        let display = self.get_current_display_mut();
+        if let Some(grid) = display.get_focused_grid_mut() {
+            snapshot.apply(&name, grid, &rules)?;
+        }
+        display.refresh_grid(&config)?;
+
+        Ok(())
+    }
+
     pub fn each_window(&mut self, cb: impl Fn(&mut NativeWindow) -> SystemResult + Copy) -> SystemResult {
         for d in &mut self.displays {
             for g in &mut d.grids {
@@ -693,16 +848,43 @@ impl AppState {
         let window =
             NativeWindow::get_foreground_window().expect("Failed to get foreground window");
         let current_workspace_id = self.workspace_id;
+
+        let floating = self
+            .get_current_grid_mut()
+            .and_then(|g| g.remove_floating(window.id));
+
+        if let Some(mut floating) = floating {
+            let window = &floating.window;
+            debug!("Managing floating window '{}' | {}", window.title, window.id);
+            floating
+                .window
+                .init(config.remove_title_bar, config.use_border)?;
+
+            let display = self.get_current_display_mut();
+            if let Some(grid) = display.get_focused_grid_mut() {
+                grid.unfloat_window(floating);
+            }
+            display.refresh_grid(&config)?;
+
+            return Ok(());
+        }
+
         let grid = self.find_grid_containing_window(window.id);
 
         if let Some(grid) = grid {
             // don't do anything if focused window isn't on current grid
             if grid.id == current_workspace_id {
-                if let Some(mut w) = grid.remove_by_window_id(window.id) {
-                    debug!("Unmanaging window '{}' | {}", w.title, w.id);
-                    w.cleanup();
+                if let Some(w) = grid.float_window(window.id) {
+                    debug!("Floating window '{}' | {}", w.title, w.id);
+                    w.cleanup()?;
+                    let w = w.clone();
+
                     if let Some(d) = self.find_grid_display(current_workspace_id) {
-                        d.refresh_grid(&config);
+                        d.refresh_grid(&config)?;
+
+                        let rect = w.get_rect().map_err(SystemError::MoveWindow)?;
+                        w.set_window_pos(rect.centered_in(&d.tiling_area(&config)), None, None)
+                            .map_err(SystemError::MoveWindow)?;
                     }
                 }
             }
@@ -720,6 +902,170 @@ impl AppState {
         Ok(())
     }
 
+    /// Explains why nog did or didn't manage the window, which defaults to the foreground window.
+    /// Windows that nog didn't see appear get evaluated now.
+    pub fn explain_window(&self, id: Option<WindowId>) -> SystemResult<Decision> {
+        let id = match id {
+            Some(id) => id,
+            None => NativeWindow::get_foreground_window()?.id,
+        };
+
+        if let Some(decision) = self.decisions.get(&id) {
+            return Ok(decision.clone());
+        }
+
+        let mut window = NativeWindow::from(id);
+        let rules = self
+            .config
+            .rules
+            .iter()
+            .chain(self.additonal_rules.iter())
+            .collect();
+        window.set_matching_rule(rules);
+
+        let mut decision = Decision::evaluate(self, &window, false);
+        for grid in self.get_grids() {
+            if grid.contains(id) {
+                decision.outcome = Outcome::Tiled(grid.id);
+            } else if grid.get_floating_window(id).is_some() {
+                decision.outcome = Outcome::Floating(grid.id);
+            }
+        }
+
+        Ok(decision)
+    }
+
+    /// Moves the focused window by `amount` pixels if it is floating
+    pub fn move_floating(&mut self, direction: Direction, amount: i32) -> SystemResult {
+        self.modify_floating_rect(|mut rect| {
+            let (x, y) = match direction {
+                Direction::Left => (-amount, 0),
+                Direction::Right => (amount, 0),
+                Direction::Up => (0, -amount),
+                Direction::Down => (0, amount),
+            };
+            rect.left += x;
+            rect.right += x;
+            rect.top += y;
+            rect.bottom += y;
+            rect
+        })
+    }
+
+    /// Moves the edge of the focused window in the given direction by `amount` pixels if it is
+    /// floating. A negative amount shrinks the window.
+    pub fn resize_floating(&mut self, direction: Direction, amount: i32) -> SystemResult {
+        let min_width = self.config.min_width;
+        let min_height = self.config.min_height;
+
+        self.modify_floating_rect(|mut rect| {
+            match direction {
+                Direction::Left => rect.left = (rect.left - amount).min(rect.right - min_width),
+                Direction::Right => rect.right = (rect.right + amount).max(rect.left + min_width),
+                Direction::Up => rect.top = (rect.top - amount).min(rect.bottom - min_height),
+                Direction::Down => {
+                    rect.bottom = (rect.bottom + amount).max(rect.top + min_height)
+                }
+            }
+            rect
+        })
+    }
+
+    fn modify_floating_rect(&mut self, f: impl FnOnce(Rectangle) -> Rectangle) -> SystemResult {
+        let id = NativeWindow::get_foreground_window()?.id;
+
+        if let Some(window) = self
+            .get_current_grid()
+            .and_then(|g| g.get_floating_window(id))
+        {
+            let rect = window.get_rect().map_err(SystemError::MoveWindow)?;
+            window
+                .set_window_pos(f(rect), None, None)
+                .map_err(SystemError::MoveWindow)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the window from the workspace it belongs to, no matter whether it is tiled or
+    /// floating. Tiled windows get cleaned up.
+    fn take_window(&mut self, id: WindowId) -> SystemResult<Option<NativeWindow>> {
+        for grid in self.get_grids_mut() {
+            if let Some(floating) = grid.remove_floating(id) {
+                return Ok(Some(floating.window));
+            }
+
+            if let Some(mut window) = grid.remove_by_window_id(id) {
+                window.cleanup()?;
+                return Ok(Some(window));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Hides the focused window and stores it in the scratchpad with the given name
+    pub fn move_to_scratchpad(&mut self, name: String) -> Result<(), ScratchpadError> {
+        let config = self.config.clone();
+        let id = NativeWindow::get_foreground_window()?.id;
+
+        self.scratchpad.ensure_free(&name, id)?;
+
+        let window = self.take_window(id)?.unwrap_or_else(|| NativeWindow::from(id));
+        debug!("Moving window {} to the scratchpad {}", id, name);
+        self.scratchpad.insert(name, window);
+
+        self.get_current_display().refresh_grid(&config)?;
+
+        Ok(())
+    }
+
+    /// Shows the window of the scratchpad centered and floating on the current workspace. Hides
+    /// it again if it is already shown there.
+    pub fn toggle_scratchpad(&mut self, name: String) -> Result<(), ScratchpadError> {
+        let config = self.config.clone();
+        let id = self.scratchpad.get_id(&name)?;
+
+        let window = match self.scratchpad.take_hidden(&name)? {
+            Some(window) => window,
+            None => {
+                let shown_here = self
+                    .get_current_grid()
+                    .map(|g| g.get_floating_window(id).is_some() || g.contains(id))
+                    .unwrap_or(false);
+
+                let window = match self.take_window(id)? {
+                    Some(window) => window,
+                    None => return Ok(()),
+                };
+
+                if shown_here {
+                    self.scratchpad.insert(name, window);
+                    self.get_current_display().refresh_grid(&config)?;
+                    return Ok(());
+                }
+
+                // it is shown on another workspace, so it moves to this one
+                window
+            }
+        };
+
+        let display = self.get_current_display_mut();
+        let rect = window.get_rect().map_err(SystemError::MoveWindow)?;
+        window
+            .set_window_pos(rect.centered_in(&display.tiling_area(&config)), None, None)
+            .map_err(SystemError::MoveWindow)?;
+        window.show();
+        window.focus()?;
+
+        if let Some(grid) = display.get_focused_grid_mut() {
+            grid.floating_windows.push(window.into());
+        }
+        display.refresh_grid(&config)?;
+
+        Ok(())
+    }
+
     pub fn reset_column(&mut self) -> SystemResult {
         let config = self.config.clone();
         let display = self.get_current_display_mut();
@@ -758,6 +1104,7 @@ impl AppState {
     pub fn change_workspace(&mut self, id: i32, _force: bool) -> SystemResult {
         let config = self.config.clone();
         let current = self.get_current_display().id;
+        let previous = self.workspace_id;
         if let Some(d) = self.find_grid_display_mut(id) {
             let new = d.id;
             d.focus_workspace(&config, id)?;
@@ -767,6 +1114,12 @@ impl AppState {
                 self.get_display_by_id(current)
                     .map(|d| d.refresh_grid(&config));
             }
+            if previous != id {
+                self.emit_hook(
+                    Hook::WorkspaceChange,
+                    vec![("workspace", id.into()), ("previous", previous.into())],
+                );
+            }
         }
 
         Ok(())
@@ -965,6 +1318,17 @@ impl AppState {
 fn on_quit(state: &mut AppState) -> SystemResult {
     os_specific_cleanup();
 
+    update::stop();
+    let installed = update::InstallDirs::current()
+        .map_err(update::UpdateError::from)
+        .and_then(|dirs| update::install_staged(&update::get_updates_path(), &dirs));
+    match installed {
+        Ok(Some(version)) => info!("Installed nog {}", version),
+        Ok(None) => {}
+        Err(e) => error!("Failed to install the update. {}", e),
+    }
+
+    state.save_session();
     state.cleanup()?;
 
     popup::cleanup();
@@ -974,14 +1338,20 @@ fn on_quit(state: &mut AppState) -> SystemResult {
     process::exit(0);
 }
 
-#[cfg(target_os = "windows")]
+#[cfg(feature = "headless")]
+fn os_specific_cleanup() {}
+
+#[cfg(feature = "headless")]
+fn os_specific_setup(_state: Arc<Mutex<AppState>>) {}
+
+#[cfg(all(target_os = "windows", not(feature = "headless")))]
 fn os_specific_cleanup() {
     if let Some(window) = tray::WINDOW.lock().as_ref() {
         tray::remove_icon(window.id.into());
     }
 }
 
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", not(feature = "headless")))]
 fn os_specific_setup(state: Arc<Mutex<AppState>>) {
     info!("Creating tray icon");
     tray::create(state);
@@ -989,13 +1359,15 @@ fn os_specific_setup(state: Arc<Mutex<AppState>>) {
 
 fn run(state_arc: Arc<Mutex<AppState>>) -> Result<(), Box<dyn std::error::Error>> {
     let receiver = state_arc.lock().event_channel.receiver.clone();
-    let sender = state_arc.lock().event_channel.sender.clone();
 
     if state_arc.lock().config.enable_hot_reloading {
         info!("Starting hot reloading of config");
         config::hot_reloading::start(state_arc.clone());
     }
 
+    info!("Starting IPC server");
+    ipc::start(state_arc.clone());
+
     startup::set_launch_on_startup(state_arc.lock().config.launch_on_startup);
 
     os_specific_setup(state_arc.clone());
@@ -1012,105 +1384,158 @@ fn run(state_arc: Arc<Mutex<AppState>>) -> Result<(), Box<dyn std::error::Error>
         AppState::enter_work_mode(state_arc.clone())?;
     }
 
+    state_arc
+        .lock()
+        .keybindings_manager
+        .as_ref()
+        .unwrap()
+        .report_conflicts();
+
     loop {
-        select! {
-            recv(receiver) -> maybe_msg => {
-                let msg = maybe_msg.unwrap();
-                let _ = match msg {
-                    Event::NewPopup(mut p) => {
-                        p.create(state_arc.clone())?;
-                        Ok(())
-                    },
-                    Event::ToggleAppbar(display_id) => {
-                        let window = state_arc
-                            .clone()
-                            .lock()
-                            .get_display_by_id(display_id)
-                            .and_then(|d| d.appbar.as_ref())
-                            .map(|bar| bar.window.get_native_window());
-
-                        if let Some(win) = window {
-                            if win.is_visible() {
-                                println!("before");
-                                win.hide();
-                                println!("after");
-                            } else {
-                                win.show();
-                            }
-                        }
-                        Ok(())
-                    },
-                    Event::Keybinding(kb) => {
-                        debug!("Received keybinding {:?}", kb);
-                        sender.send(Event::CallCallback { idx: kb.callback_id, is_mode_callback: false } ).unwrap();
-                        Ok(())
-                    },
-                    Event::LuaRuntimeError(err) => {
-                        error!("{}", lua::get_err_msg(&err));
-
-                        Ok(())
-                    }
-                    Event::CallCallback { idx, is_mode_callback } => {
-                        let rt = state_arc.lock().lua_rt.clone();
-                        let res = rt.with_lua(|lua| {
-                            LuaRuntime::get_callback(lua, idx)?.call::<_, ()>(())
-                        });
-
-                        if let Err(e) = res {
-                            sender.send(Event::LuaRuntimeError(e));
-                        } else if is_mode_callback {
-                            state_arc.lock().keybindings_manager.as_ref().map(|x| x.sender.clone()).unwrap().send(keybindings::ChanMessage::ModeCbExecuted);
-                        }
-
-                        Ok(())
-                    },
-                    Event::RedrawAppBar => {
-                        let windows = state_arc.lock().displays.iter().map(|d| d.appbar.as_ref()).flatten().map(|b| b.window.clone()).collect::<Vec<Window>>();
-
-                        for window in windows {
-                            window.redraw();
-                        }
-
-                        Ok(())
-                    },
-                    Event::WinEvent(ev) => event_handler::winevent::handle(&mut state_arc.lock(), ev),
-                    Event::Exit => {
-                        on_quit(&mut state_arc.lock())?;
+        // the session gets saved once no event arrived for a moment or once it waited long enough
+        let time_until_save = state_arc.lock().session_store.time_until_save();
+        match time_until_save {
+            Some(timeout) if timeout == Duration::from_secs(0) => state_arc.lock().save_session(),
+            Some(timeout) => select! {
+                recv(receiver) -> maybe_msg => handle_event(state_arc.clone(), maybe_msg.unwrap())?,
+                default(timeout) => state_arc.lock().save_session(),
+            },
+            None => select! {
+                recv(receiver) -> maybe_msg => handle_event(state_arc.clone(), maybe_msg.unwrap())?,
+            },
+        }
+    }
+}
+
+/// Handles a single event of the main loop.
+///
+/// Errors that happen while handling the event only get logged. An error is only returned if nog
+/// can't continue running.
+fn handle_event(state_arc: Arc<Mutex<AppState>>, msg: Event) -> SystemResult {
+    let sender = state_arc.lock().event_channel.sender.clone();
+    let previous_status = ipc::Status::capture(&state_arc);
+    let config_reloaded = matches!(msg, Event::ReloadConfig);
+
+    let result = match msg {
+        Event::NewPopup(mut p) => {
+            p.create(state_arc.clone())?;
+            Ok(())
+        },
+        Event::ToggleAppbar(display_id) => {
+            let window = state_arc
+                .clone()
+                .lock()
+                .get_display_by_id(display_id)
+                .and_then(|d| d.appbar.as_ref())
+                .map(|bar| bar.window.get_native_window());
+
+            if let Some(win) = window {
+                if win.is_visible() {
+                    win.hide();
+                } else {
+                    win.show();
+                }
+            }
+            Ok(())
+        },
+        Event::Keybinding(kb) => {
+            debug!("Received keybinding {:?}", kb);
+            sender.send(Event::CallCallback { idx: kb.callback_id, arg: None } ).unwrap();
+            state_arc.lock().keybinding_triggered(&kb);
+            Ok(())
+        },
+        Event::LuaRuntimeError(err) => {
+            error!("{}", lua::get_err_msg(&err));
+
+            if let Some(e) = lua::PluginError::find(&err) {
+                state_arc.lock().plugin_failed(&e.plugin, &e.message);
+            }
+
+            Ok(())
+        }
+        Event::CallCallback { idx, arg } => {
+            let (rt, name) = {
+                let state = state_arc.lock();
+                (state.lua_rt.clone(), state.callback_name(idx, arg.as_ref()))
+            };
+            let res = rt.watch(&name, |lua| {
+                let cb = LuaRuntime::get_callback(lua, idx)?;
+                match &arg {
+                    Some(arg) => cb.call::<_, ()>(lua::json_to_lua(lua, arg)?),
+                    None => cb.call::<_, ()>(()),
+                }
+                .map_err(|e| lua::attribute_error(lua, &cb, e))
+            });
+
+            if let Err(e) = res {
+                sender
+                    .send(Event::LuaRuntimeError(e))
+                    .expect("Failed to send lua-runtime-error event");
+            }
+
+            Ok(())
+        },
+        Event::RedrawAppBar => {
+            let windows = state_arc.lock().displays.iter().filter_map(|d| d.appbar.as_ref()).map(|b| b.window.clone()).collect::<Vec<Window>>();
+
+            for window in windows {
+                if let Err(e) = window.redraw() {
+                    error!("Failed to redraw the app bar {:?}", e);
+                }
+            }
+
+            Ok(())
+        },
+        Event::WinEvent(ev) => event_handler::winevent::handle(&mut state_arc.lock(), ev),
+        Event::Exit => on_quit(&mut state_arc.lock()),
+        Event::ReloadConfig => {
+            info!("Reloading Config");
+            let rt = state_arc.lock().lua_rt.clone();
+            state_arc.lock().leave_all_modes();
+            state_arc.lock().hooks.clear();
+            state_arc.lock().config.keybinding_conflicts.clear();
+            run_config(&rt);
+            lua::load_remaining_plugins(&state_arc);
+            state_arc.lock().emit_hook(Hook::ConfigReload, Vec::new());
+
+            Ok(())
+        },
+        Event::IpcRequest(request) => {
+            ipc::handle_request(state_arc.clone(), request);
+            Ok(())
+        },
+        Event::UpdateBarSections(display_id, left, center, right) => {
+            let mut state = state_arc.lock();
+            for d in state.displays.iter_mut() {
+                if d.id == display_id {
+                    if let Some(bar) = d.appbar.as_mut() {
+                        bar.left = left;
+                        bar.center = center;
+                        bar.right = right;
                         break;
-                    },
-                    Event::ReloadConfig => {
-                        info!("Reloading Config");
-                        let rt = state_arc.lock().lua_rt.clone();
-                        run_config(&rt);
-
-                        Ok(())
-                    },
-                    Event::UpdateBarSections(display_id, left, center, right) => {
-                        let mut state = state_arc.lock();
-                        for d in state.displays.iter_mut() {
-                            if d.id == display_id {
-                                if let Some(bar) = d.appbar.as_mut() {
-                                    bar.left = left;
-                                    bar.center = center;
-                                    bar.right = right;
-                                    break;
-                                }
-                            }
-                        }
-                        Ok(())
-                    },
-                    Event::ChangeWorkspace(id, force) => {
-                        state_arc.lock().change_workspace(id, force);
-                        Ok(())
                     }
-                }.map_err(|e| {
-                    error!("{:?}", e);
-                    crate::system::win::api::print_last_error();
-                });
+                }
             }
-        }
+            Ok(())
+        },
+        Event::ChangeWorkspace(id, force) => state_arc.lock().change_workspace(id, force),
+    };
+
+    if let Err(e) = result {
+        error!("{:?}", e);
+        system::api::print_last_error();
+    }
+
+    // Custom layouts can only be arranged after the event got handled, because they need to run lua
+    // code without holding the state
+    if let Err(e) = lua::arrange_custom_layouts(state_arc.clone()) {
+        error!("{:?}", e);
     }
 
+    ipc::notify_changes(&state_arc, previous_status, config_reloaded);
+
+    state_arc.lock().session_changed();
+
     Ok(())
 }
 
@@ -1123,11 +1548,7 @@ fn get_config_path() -> PathBuf {
 fn get_runtime_path() -> PathBuf {
     #[cfg(debug_assertions)] // dev
     {
-        let mut path: PathBuf = std::env::current_exe().unwrap();
-        path.pop();
-        path.pop();
-        path.pop();
-        path.push("twm");
+        let mut path: PathBuf = env!("CARGO_MANIFEST_DIR").into();
         path.push("runtime");
         path
     }
@@ -1146,7 +1567,7 @@ fn get_plugins_path() -> Result<PathBuf, String> {
 
     if !path.exists() {
         debug!("plugins folder doesn't exist yet. Creating the folder");
-        std::fs::create_dir(path.clone()).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(path.clone()).map_err(|e| e.to_string())?;
     }
 
     Ok(path)
@@ -1184,6 +1605,10 @@ fn main() {
     info!("Config: {:?}", get_config_path());
     info!("Runtime: {:?}", get_runtime_path());
 
+    if let Err(e) = update::InstallDirs::current().and_then(|dirs| update::remove_old_files(&dirs)) {
+        error!("Failed to remove the files of the previous version. {}", e);
+    }
+
     let state_arc = Arc::new(Mutex::new(AppState::default()));
 
     debug!("Setting up lua runtime");
@@ -1191,12 +1616,15 @@ fn main() {
 
     let rt = state_arc.lock().lua_rt.clone();
     run_config(&rt);
+    lua::load_remaining_plugins(&state_arc);
     rt.disable_setup().unwrap();
 
     info!("Initializing Application");
     state_arc.lock().init(state_arc.clone());
     info!("Initialized Application");
 
+    update::start(state_arc.clone());
+
     let arc = state_arc.clone();
 
     thread::spawn(move || loop {
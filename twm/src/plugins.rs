@@ -0,0 +1,311 @@
+//! Installs the plugins that got declared with `nog.plugins` and records the installed commits in
+//! `plugins.lock`.
+//!
+//! A plugin that is locked gets checked out at its locked commit, unless it gets updated. Plugins
+//! that are locked, but not declared anymore get removed. Folders in the plugins folder that the
+//! plugin manager didn't install stay untouched.
+use crate::{
+    get_config_path,
+    system::{SystemError, SystemResult},
+};
+use lock::{LockedPlugin, Lockfile};
+use log::{debug, error};
+use spec::PluginSpec;
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+};
+
+pub mod git;
+pub mod loader;
+pub mod lock;
+pub mod manifest;
+pub mod permission;
+pub mod spec;
+
+/// What happened to a plugin during a sync
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Installed(String),
+    Updated { from: String, to: String },
+    Unchanged(String),
+}
+
+impl Change {
+    /// The commit that is checked out now
+    pub fn commit(&self) -> &str {
+        match self {
+            Self::Installed(commit) | Self::Unchanged(commit) => commit,
+            Self::Updated { to, .. } => to,
+        }
+    }
+}
+
+fn short(commit: &str) -> &str {
+    commit.get(..7).unwrap_or(commit)
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Installed(commit) => write!(f, "installed {}", short(commit)),
+            Self::Updated { from, to } => write!(f, "updated {}..{}", short(from), short(to)),
+            Self::Unchanged(commit) => write!(f, "up to date at {}", short(commit)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    /// The declared plugins in the order they finished
+    pub results: Vec<(String, SystemResult<Change>)>,
+    /// The plugins that got removed, because they aren't declared anymore
+    pub removed: Vec<String>,
+}
+
+impl Report {
+    pub fn lines(&self) -> Vec<String> {
+        self.results
+            .iter()
+            .map(|(name, result)| match result {
+                Ok(change) => format!("{}: {}", name, change),
+                Err(e) => e.to_string(),
+            })
+            .chain(self.removed.iter().map(|name| format!("{}: removed", name)))
+            .collect()
+    }
+
+    pub fn errors(&self) -> Vec<&SystemError> {
+        self.results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().err())
+            .collect()
+    }
+}
+
+pub struct PluginManager {
+    pub plugins_path: PathBuf,
+    pub lock_path: PathBuf,
+}
+
+impl PluginManager {
+    pub fn new(plugins_path: PathBuf, lock_path: PathBuf) -> Self {
+        Self {
+            plugins_path,
+            lock_path,
+        }
+    }
+
+    /// The plugin manager of the plugins folder, whose lockfile lives next to the config
+    pub fn with_plugins_path(plugins_path: PathBuf) -> Self {
+        let mut lock_path = get_config_path();
+        lock_path.push("plugins.lock");
+        Self::new(plugins_path, lock_path)
+    }
+
+    /// Makes sure that no two plugins share a name or a folder
+    pub fn validate(specs: &[PluginSpec]) -> SystemResult {
+        let mut names = HashSet::new();
+        let mut dirs = HashSet::new();
+
+        for spec in specs {
+            if !names.insert(&spec.name) {
+                return Err(SystemError::InvalidPlugin(format!(
+                    "{} is declared twice",
+                    spec.name
+                )));
+            }
+            if !dirs.insert(spec.dir.to_lowercase()) {
+                return Err(SystemError::InvalidPlugin(format!(
+                    "more than one plugin uses the folder {}",
+                    spec.dir
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Installs the missing plugins and removes the plugins that aren't declared anymore. Plugins
+    /// get updated to the newest commit of their revision if `update` is set and checked out at
+    /// their locked commit otherwise.
+    ///
+    /// The plugins get synced in parallel and `on_progress` gets called whenever one of them is
+    /// done.
+    pub fn sync(
+        &self,
+        specs: &[PluginSpec],
+        update: bool,
+        mut on_progress: impl FnMut(&str, &SystemResult<Change>),
+    ) -> SystemResult<Report> {
+        Self::validate(specs)?;
+        fs::create_dir_all(&self.plugins_path).map_err(|e| SystemError::Plugin {
+            name: "plugins".into(),
+            reason: e.to_string(),
+        })?;
+
+        let old_lock = Lockfile::load(&self.lock_path)?;
+        let mut lock = Lockfile::default();
+        let mut report = Report::default();
+        let (sender, receiver) = mpsc::channel();
+
+        for spec in specs {
+            let sender = sender.clone();
+            let spec = spec.clone();
+            let path = self.plugins_path.join(&spec.dir);
+            let locked = old_lock.plugins.get(&spec.name).cloned();
+
+            thread::spawn(move || {
+                let result = sync_plugin(&path, &spec, locked.as_ref(), update);
+                let _ = sender.send((spec, result));
+            });
+        }
+        drop(sender);
+
+        for (spec, result) in receiver {
+            let result = result.map_err(|reason| SystemError::Plugin {
+                name: spec.name.clone(),
+                reason,
+            });
+
+            match &result {
+                Ok(change) => {
+                    debug!("{}: {}", spec.name, change);
+                    lock.plugins.insert(
+                        spec.name.clone(),
+                        LockedPlugin {
+                            source: spec.source.clone(),
+                            dir: spec.dir.clone(),
+                            revision: spec.revision.to_string(),
+                            commit: change.commit().into(),
+                        },
+                    );
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    // keeps the last working commit
+                    if let Some(locked) = old_lock.plugins.get(&spec.name) {
+                        lock.plugins.insert(spec.name.clone(), locked.clone());
+                    }
+                }
+            }
+
+            on_progress(&spec.name, &result);
+            report.results.push((spec.name, result));
+        }
+
+        let declared_dirs = specs
+            .iter()
+            .map(|spec| spec.dir.to_lowercase())
+            .collect::<HashSet<_>>();
+
+        for (name, locked) in old_lock.plugins {
+            if specs.iter().any(|spec| spec.name == name) {
+                continue;
+            }
+
+            let path = self.plugins_path.join(&locked.dir);
+            let is_own_folder = PluginSpec::new(&name)
+                .and_then(|spec| spec.with_dir(&locked.dir))
+                .is_ok();
+
+            if is_own_folder && !declared_dirs.contains(&locked.dir.to_lowercase()) && path.exists()
+            {
+                debug!("Removing {}", name);
+                if let Err(e) = remove_dir(&path) {
+                    let result = Err(SystemError::Plugin {
+                        name: name.clone(),
+                        reason: e.to_string(),
+                    });
+                    on_progress(&name, &result);
+                    report.results.push((name.clone(), result));
+                    lock.plugins.insert(name, locked);
+                    continue;
+                }
+            }
+
+            report.removed.push(name);
+        }
+
+        lock.save(&self.lock_path)?;
+
+        Ok(report)
+    }
+}
+
+fn sync_plugin(
+    path: &Path,
+    spec: &PluginSpec,
+    locked: Option<&LockedPlugin>,
+    update: bool,
+) -> Result<Change, String> {
+    let locked = locked
+        .filter(|locked| !update && locked.is_locked_for(spec))
+        .map(|locked| locked.commit.clone());
+
+    if !path.exists() {
+        git::clone(&spec.source, path)?;
+        let commit = match locked {
+            Some(commit) => commit,
+            None => git::resolve(path, &spec.revision)?,
+        };
+        git::checkout(path, &commit)?;
+        return Ok(Change::Installed(commit));
+    }
+
+    // the folder could be part of a git repository that contains the config
+    if !path.join(".git").exists() {
+        return Err(format!(
+            "the folder {} already exists and isn't a git repository",
+            spec.dir
+        ));
+    }
+
+    let head = git::head(path)?;
+    let target = match locked {
+        Some(commit) => commit,
+        None => {
+            git::fetch(path)?;
+            git::resolve(path, &spec.revision)?
+        }
+    };
+
+    if head == target {
+        return Ok(Change::Unchanged(head));
+    }
+
+    if git::checkout(path, &target).is_err() {
+        git::fetch(path)?;
+        git::checkout(path, &target)?;
+    }
+
+    Ok(Change::Updated {
+        from: head,
+        to: target,
+    })
+}
+
+/// Git marks the files of its objects as read-only, which stops `remove_dir_all` on Windows
+#[allow(clippy::permissions_set_readonly_false)]
+fn remove_dir(path: &Path) -> std::io::Result<()> {
+    if cfg!(windows) {
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let mut permissions = entry.metadata()?.permissions();
+            if entry.file_type()?.is_dir() {
+                remove_dir(&entry.path())?;
+            } else if permissions.readonly() {
+                permissions.set_readonly(false);
+                fs::set_permissions(entry.path(), permissions)?;
+            }
+        }
+    }
+
+    fs::remove_dir_all(path)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,60 @@
+//! The git commands that the plugin manager needs. Every function returns the error output of git
+//! when the command fails.
+use super::spec::Revision;
+use std::{path::Path, process::Command};
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("failed to run git: {}", e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr.trim().lines().last().unwrap_or("git failed").into())
+    }
+}
+
+pub fn clone(source: &str, dir: &Path) -> Result<(), String> {
+    let parent = dir.parent().unwrap_or(dir);
+    let dir = dir.to_string_lossy();
+    git(parent, &["clone", "--quiet", source, &dir]).map(|_| ())
+}
+
+pub fn fetch(dir: &Path) -> Result<(), String> {
+    git(dir, &["fetch", "--quiet", "--tags", "--force", "origin"])?;
+    // keeps `origin/HEAD` pointing at the default branch in case it changed
+    git(dir, &["remote", "set-head", "origin", "--auto"]).map(|_| ())
+}
+
+/// The commit that the revision currently points to
+pub fn resolve(dir: &Path, revision: &Revision) -> Result<String, String> {
+    let rev = match revision {
+        Revision::Default => "refs/remotes/origin/HEAD".to_string(),
+        Revision::Branch(branch) => format!("refs/remotes/origin/{}", branch),
+        Revision::Tag(tag) => format!("refs/tags/{}", tag),
+        Revision::Commit(commit) => commit.clone(),
+    };
+
+    git(
+        dir,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ],
+    )
+    .map_err(|_| format!("the {} doesn't exist", revision))
+}
+
+pub fn checkout(dir: &Path, commit: &str) -> Result<(), String> {
+    git(dir, &["checkout", "--quiet", "--detach", commit]).map(|_| ())
+}
+
+pub fn head(dir: &Path) -> Result<String, String> {
+    git(dir, &["rev-parse", "HEAD"])
+}
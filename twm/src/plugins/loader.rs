@@ -0,0 +1,257 @@
+//! Finds the plugins in the plugins folder and decides in which order they get loaded. A plugin
+//! only gets loaded after its dependencies, and a plugin whose manifest or dependencies are broken
+//! doesn't get loaded at all.
+use super::manifest::{Dependency, Manifest};
+use crate::config::update_channel::semantic_version::SemanticVersion;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Pending,
+    Loaded,
+    Failed(String),
+    /// Got disabled with `nog.plug_disable`
+    Disabled,
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Loaded => "loaded",
+            Self::Failed(_) => "failed",
+            Self::Disabled => "disabled",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Plugin {
+    /// The name in the manifest or the name of the folder if the plugin doesn't have one
+    pub name: String,
+    pub path: PathBuf,
+    pub manifest: Option<Manifest>,
+    pub status: Status,
+    /// How many times the callbacks of the plugin failed
+    pub errors: usize,
+    pub last_error: Option<String>,
+}
+
+impl Plugin {
+    pub fn new(path: PathBuf) -> Self {
+        let dir_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match Manifest::load(&path) {
+            Ok(manifest) => Self {
+                name: manifest
+                    .as_ref()
+                    .map(|m| m.name.clone())
+                    .unwrap_or(dir_name),
+                path,
+                manifest,
+                status: Status::Pending,
+                errors: 0,
+                last_error: None,
+            },
+            Err(e) => Self {
+                name: dir_name,
+                path,
+                manifest: None,
+                status: Status::Failed(e),
+                errors: 0,
+                last_error: None,
+            },
+        }
+    }
+
+    /// The folder that contains the modules of the plugin
+    pub fn lua_path(&self) -> PathBuf {
+        self.path.join("lua")
+    }
+
+    pub fn dependencies(&self) -> &[Dependency] {
+        self.manifest
+            .as_ref()
+            .map(|m| m.dependencies.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.status, Status::Failed(_))
+    }
+
+    /// The first dependency that failed to load
+    pub fn failed_dependency(&self, plugins: &[Plugin]) -> Option<String> {
+        self.dependencies()
+            .iter()
+            .find(|dep| {
+                let mut candidates = plugins.iter().filter(|p| p.name == dep.name).peekable();
+                candidates.peek().is_some() && candidates.all(Plugin::is_failed)
+            })
+            .map(|dep| dep.name.clone())
+    }
+}
+
+/// The plugins in the folder in the order they have to be loaded in
+pub fn discover(plugins_path: &Path, nog_version: &SemanticVersion) -> Vec<Plugin> {
+    let mut paths = fs::read_dir(plugins_path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    paths.sort();
+
+    order(paths.into_iter().map(Plugin::new).collect(), nog_version)
+}
+
+/// Checks the requirements of the plugins and orders them so that every plugin comes after its
+/// dependencies. The plugins that can't be loaded come last.
+pub fn order(mut plugins: Vec<Plugin>, nog_version: &SemanticVersion) -> Vec<Plugin> {
+    let mut by_name = HashMap::<String, usize>::new();
+
+    for i in 0..plugins.len() {
+        if plugins[i].is_failed() {
+            continue;
+        }
+        if let Some(&other) = by_name.get(&plugins[i].name) {
+            let reason = format!(
+                "the plugin in {} already has the name {}",
+                plugins[other].path.display(),
+                plugins[i].name
+            );
+            plugins[i].status = Status::Failed(reason);
+        } else {
+            by_name.insert(plugins[i].name.clone(), i);
+        }
+    }
+
+    for i in 0..plugins.len() {
+        if plugins[i].is_failed() {
+            continue;
+        }
+        if let Some(reason) = check_requirements(&plugins[i], &plugins, &by_name, nog_version) {
+            plugins[i].status = Status::Failed(reason);
+        }
+    }
+
+    // a plugin fails if one of its dependencies does
+    loop {
+        let failed = (0..plugins.len()).find_map(|i| {
+            if plugins[i].is_failed() {
+                return None;
+            }
+            plugins[i]
+                .failed_dependency(&plugins)
+                .map(|dep| (i, format!("depends on {}, which failed to load", dep)))
+        });
+
+        match failed {
+            Some((i, reason)) => plugins[i].status = Status::Failed(reason),
+            None => break,
+        }
+    }
+
+    let mut remaining = plugins
+        .iter()
+        .enumerate()
+        .filter(|(_, plugin)| !plugin.is_failed())
+        .map(|(i, plugin)| (i, plugin.dependencies().len()))
+        .collect::<HashMap<_, _>>();
+    let mut ready = remaining
+        .iter()
+        .filter(|(_, &count)| count == 0)
+        .map(|(&i, _)| (plugins[i].name.clone(), i))
+        .collect::<BTreeSet<_>>();
+    let mut order = Vec::new();
+
+    while let Some(next) = ready.iter().next().cloned() {
+        ready.remove(&next);
+        remaining.remove(&next.1);
+        order.push(next.1);
+
+        for (&i, count) in remaining.iter_mut() {
+            if plugins[i]
+                .dependencies()
+                .iter()
+                .any(|dep| dep.name == next.0)
+            {
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert((plugins[i].name.clone(), i));
+                }
+            }
+        }
+    }
+
+    for &i in remaining.keys() {
+        plugins[i].status = Status::Failed("its dependencies depend on each other".into());
+    }
+
+    let failed = (0..plugins.len()).filter(|i| plugins[*i].is_failed());
+    let order = order.into_iter().chain(failed).collect::<Vec<_>>();
+    let mut plugins = plugins.into_iter().map(Some).collect::<Vec<_>>();
+
+    order
+        .into_iter()
+        .filter_map(|i| plugins[i].take())
+        .collect()
+}
+
+fn check_requirements(
+    plugin: &Plugin,
+    plugins: &[Plugin],
+    by_name: &HashMap<String, usize>,
+    nog_version: &SemanticVersion,
+) -> Option<String> {
+    let manifest = plugin.manifest.as_ref()?;
+
+    if let Some(required) = &manifest.nog {
+        if required > nog_version {
+            return Some(format!(
+                "requires nog {} or newer, but this is {}",
+                required, nog_version
+            ));
+        }
+    }
+
+    for dep in &manifest.dependencies {
+        let installed = match by_name.get(&dep.name) {
+            Some(&i) => &plugins[i],
+            None => return Some(format!("depends on {}, which isn't installed", dep.name)),
+        };
+
+        if let Some(required) = &dep.version {
+            match installed.manifest.as_ref().map(|m| &m.version) {
+                Some(version) if version >= required => {}
+                Some(version) => {
+                    return Some(format!(
+                        "depends on {} {} or newer, but {} is installed",
+                        dep.name, required, version
+                    ))
+                }
+                None => {
+                    return Some(format!(
+                        "depends on {} {} or newer, but {} doesn't have a version",
+                        dep.name, required, dep.name
+                    ))
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,154 @@
+use super::{discover, order, Plugin, Status};
+use crate::{
+    config::update_channel::semantic_version::SemanticVersion,
+    plugins::manifest::{Manifest, FILE_NAME},
+    util::testing::TempDir,
+};
+use serde_json::json;
+use std::{fs, path::PathBuf, str::FromStr};
+
+fn version(version: &str) -> SemanticVersion {
+    SemanticVersion::from_str(version).unwrap()
+}
+
+fn plugin(manifest: serde_json::Value) -> Plugin {
+    let manifest = Manifest::from_json(&manifest).unwrap();
+    Plugin {
+        name: manifest.name.clone(),
+        path: PathBuf::from(&manifest.name),
+        manifest: Some(manifest),
+        status: Status::Pending,
+        errors: 0,
+        last_error: None,
+    }
+}
+
+fn statuses(plugins: &[Plugin]) -> Vec<(&str, &Status)> {
+    plugins
+        .iter()
+        .map(|plugin| (plugin.name.as_str(), &plugin.status))
+        .collect()
+}
+
+#[test]
+fn parses_manifests() {
+    let manifest = Manifest::from_json(&json!({
+        "name": "clock",
+        "version": "1.2.0",
+        "nog": "1.0.0",
+        "dependencies": { "base": "0.3.0", "utils": "*" },
+        "main": "clock.init",
+        "components": ["clock"],
+        "commands": ["toggle_seconds"]
+    }))
+    .unwrap();
+
+    assert_eq!(manifest.version, version("1.2.0"));
+    assert_eq!(manifest.nog, Some(version("1.0.0")));
+    assert_eq!(manifest.dependencies.len(), 2);
+    assert_eq!(manifest.dependencies[0].version, Some(version("0.3.0")));
+    assert_eq!(manifest.dependencies[1].version, None);
+    assert_eq!(manifest.main.as_deref(), Some("clock.init"));
+    assert_eq!(manifest.components, vec!["clock"]);
+
+    let invalid = [
+        json!({ "version": "1.0.0" }),
+        json!({ "name": "clock", "version": "one" }),
+        json!({ "name": "clock", "version": "1.0.0", "dependencies": ["base"] }),
+        json!({ "name": "clock", "version": "1.0.0", "dependencies": { "clock": "*" } }),
+        json!({ "name": "clock", "version": "1.0.0", "main": "clock/init" }),
+        json!({ "name": "clock", "version": "1.0.0", "components": ["clock"] }),
+        json!({ "name": "clock", "version": "1.0.0", "main": "clock", "commands": ["a-b"] }),
+    ];
+
+    for manifest in &invalid {
+        assert!(Manifest::from_json(manifest).is_err(), "{}", manifest);
+    }
+}
+
+#[test]
+fn dependencies_get_loaded_first() {
+    let plugins = vec![
+        plugin(json!({ "name": "c", "version": "1.0.0", "dependencies": { "b": "*" } })),
+        plugin(json!({ "name": "b", "version": "1.0.0", "dependencies": { "a": "1.0.0" } })),
+        plugin(json!({ "name": "d", "version": "1.0.0" })),
+        plugin(json!({ "name": "a", "version": "1.1.0" })),
+    ];
+
+    let plugins = order(plugins, &version("1.0.0"));
+    let names = plugins.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+
+    assert_eq!(names, vec!["a", "b", "c", "d"]);
+    assert!(plugins.iter().all(|p| p.status == Status::Pending));
+}
+
+#[test]
+fn broken_requirements_fail_the_plugin_and_its_dependents() {
+    let plugins = vec![
+        plugin(json!({ "name": "new", "version": "1.0.0", "nog": "2.0.0" })),
+        plugin(json!({ "name": "uses_new", "version": "1.0.0", "dependencies": { "new": "*" } })),
+        plugin(json!({ "name": "missing", "version": "1.0.0", "dependencies": { "gone": "*" } })),
+        plugin(json!({ "name": "old", "version": "0.1.0" })),
+        plugin(
+            json!({ "name": "uses_old", "version": "1.0.0", "dependencies": { "old": "0.2.0" } }),
+        ),
+        plugin(json!({ "name": "x", "version": "1.0.0", "dependencies": { "y": "*" } })),
+        plugin(json!({ "name": "y", "version": "1.0.0", "dependencies": { "x": "*" } })),
+        plugin(json!({ "name": "fine", "version": "1.0.0", "dependencies": { "old": "*" } })),
+    ];
+
+    let plugins = order(plugins, &version("1.5.0"));
+    let failed = |reason: &str| Status::Failed(reason.into());
+
+    assert_eq!(
+        statuses(&plugins),
+        vec![
+            ("old", &Status::Pending),
+            ("fine", &Status::Pending),
+            (
+                "new",
+                &failed("requires nog 2.0.0 or newer, but this is 1.5.0")
+            ),
+            ("uses_new", &failed("depends on new, which failed to load")),
+            ("missing", &failed("depends on gone, which isn't installed")),
+            (
+                "uses_old",
+                &failed("depends on old 0.2.0 or newer, but 0.1.0 is installed")
+            ),
+            ("x", &failed("its dependencies depend on each other")),
+            ("y", &failed("its dependencies depend on each other")),
+        ]
+    );
+}
+
+#[test]
+fn discovers_the_plugins_of_a_folder() {
+    let root = TempDir::new("plugin-loader");
+
+    let write = |dir: &str, manifest: &str| {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        fs::write(root.join(dir).join(FILE_NAME), manifest).unwrap();
+    };
+    write(
+        "clock",
+        r#"{ "name": "clock", "version": "1.0.0", "dependencies": { "base": "*" } }"#,
+    );
+    write("nog-base", r#"{ "name": "base", "version": "1.0.0" }"#);
+    write("copy", r#"{ "name": "base", "version": "2.0.0" }"#);
+    write("broken", "{ name: }");
+    fs::create_dir_all(root.join("legacy/lua")).unwrap();
+
+    let plugins = discover(&root, &version("1.0.0"));
+    let names = plugins.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+
+    // `copy` comes before `nog-base`, so it gets the name
+    assert_eq!(names, vec!["base", "clock", "legacy", "broken", "base"]);
+    assert_eq!(
+        plugins[0].manifest.as_ref().unwrap().version,
+        version("2.0.0")
+    );
+    assert!(plugins[2].manifest.is_none());
+    assert!(plugins[3].is_failed());
+    assert!(plugins[4].is_failed());
+    assert!(plugins[4].path.ends_with("nog-base"));
+}
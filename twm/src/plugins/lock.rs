@@ -0,0 +1,105 @@
+use super::spec::PluginSpec;
+use crate::system::{SystemError, SystemResult};
+use serde_json::{json, Map, Value};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Version of the format of the lockfile. Has to be increased whenever the format changes.
+pub const VERSION: u64 = 1;
+
+/// The commit that got installed for a plugin
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockedPlugin {
+    pub source: String,
+    pub dir: String,
+    /// The revision of the declaration that resolved to the commit
+    pub revision: String,
+    pub commit: String,
+}
+
+impl LockedPlugin {
+    /// Whether the commit got resolved for this declaration
+    pub fn is_locked_for(&self, spec: &PluginSpec) -> bool {
+        self.source == spec.source
+            && self.dir == spec.dir
+            && self.revision == spec.revision.to_string()
+    }
+}
+
+/// The contents of `plugins.lock`, which makes installing the plugins on another machine result in
+/// the same commits
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    /// The plugins by their name
+    pub plugins: BTreeMap<String, LockedPlugin>,
+}
+
+fn invalid(reason: impl Into<String>) -> SystemError {
+    SystemError::PluginLock(reason.into())
+}
+
+impl Lockfile {
+    /// An empty lockfile if the file doesn't exist
+    pub fn load(path: &Path) -> SystemResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+        let value = serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+        Self::from_json(&value)
+    }
+
+    pub fn save(&self, path: &Path) -> SystemResult {
+        let content = serde_json::to_string_pretty(&self.to_json()).unwrap();
+        fs::write(path, content).map_err(|e| invalid(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": VERSION,
+            "plugins": self
+                .plugins
+                .iter()
+                .map(|(name, plugin)| (name.clone(), json!({
+                    "source": plugin.source,
+                    "dir": plugin.dir,
+                    "revision": plugin.revision,
+                    "commit": plugin.commit,
+                })))
+                .collect::<Map<_, _>>(),
+        })
+    }
+
+    pub fn from_json(value: &Value) -> SystemResult<Self> {
+        let version = value["version"].as_u64().unwrap_or_default();
+        if version != VERSION {
+            return Err(invalid(format!("version {} isn't supported", version)));
+        }
+
+        let mut plugins = BTreeMap::new();
+        let entries = value["plugins"]
+            .as_object()
+            .ok_or_else(|| invalid("plugins has to be an object"))?;
+
+        for (name, plugin) in entries {
+            let field = |key: &str| {
+                plugin[key]
+                    .as_str()
+                    .map(String::from)
+                    .ok_or_else(|| invalid(format!("{} of {} has to be a string", key, name)))
+            };
+
+            plugins.insert(
+                name.clone(),
+                LockedPlugin {
+                    source: field("source")?,
+                    dir: field("dir")?,
+                    revision: field("revision")?,
+                    commit: field("commit")?,
+                },
+            );
+        }
+
+        Ok(Self { plugins })
+    }
+}
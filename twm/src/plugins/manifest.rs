@@ -0,0 +1,152 @@
+use crate::config::update_channel::semantic_version::SemanticVersion;
+use serde_json::Value;
+use std::{fs, path::Path, str::FromStr};
+
+/// The file at the root of a plugin that describes it
+pub const FILE_NAME: &str = "plugin.json";
+
+/// A plugin that has to be loaded before the one that depends on it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    /// The oldest version that works, where `None` accepts every version
+    pub version: Option<SemanticVersion>,
+}
+
+/// The contents of `plugin.json`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: String,
+    pub version: SemanticVersion,
+    /// The oldest version of nog that the plugin works with
+    pub nog: Option<SemanticVersion>,
+    pub dependencies: Vec<Dependency>,
+    /// The module that gets required when the plugin gets loaded
+    pub main: Option<String>,
+    /// The functions of `components` in the table returned by the entry module that get added to
+    /// `nog.components`
+    pub components: Vec<String>,
+    /// The functions of `commands` in the table returned by the entry module that get added to
+    /// `nog.commands`
+    pub commands: Vec<String>,
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_version(value: &Value, key: &str) -> Result<SemanticVersion, String> {
+    value
+        .as_str()
+        .ok_or_else(|| format!("{} has to be a string", key))
+        .and_then(|version| {
+            SemanticVersion::from_str(version).map_err(|e| format!("{} of {}", e, key))
+        })
+}
+
+fn parse_names(value: &Value, key: &str) -> Result<Vec<String>, String> {
+    if value.is_null() {
+        return Ok(Vec::new());
+    }
+
+    let values = value
+        .as_array()
+        .ok_or_else(|| format!("{} has to be a list", key))?;
+    let mut names = Vec::new();
+
+    for value in values {
+        match value.as_str() {
+            Some(name) if is_identifier(name) => names.push(name.to_string()),
+            _ => return Err(format!("{} has to contain lua identifiers", key)),
+        }
+    }
+
+    Ok(names)
+}
+
+impl Manifest {
+    /// `None` if the plugin doesn't have a manifest
+    pub fn load(plugin_path: &Path) -> Result<Option<Self>, String> {
+        let path = plugin_path.join(FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let value = serde_json::from_str(&content).map_err(|e| format!("{} {}", FILE_NAME, e))?;
+        Self::from_json(&value).map(Some)
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let name = value["name"]
+            .as_str()
+            .filter(|name| is_valid_name(name))
+            .ok_or("name has to be a string of letters, digits, -, _ or .")?
+            .to_string();
+
+        let version = parse_version(&value["version"], "version")?;
+
+        let nog = match &value["nog"] {
+            Value::Null => None,
+            nog => Some(parse_version(nog, "nog")?),
+        };
+
+        let mut dependencies = Vec::new();
+        if !value["dependencies"].is_null() {
+            let entries = value["dependencies"]
+                .as_object()
+                .ok_or("dependencies has to be an object")?;
+
+            for (dep_name, dep_version) in entries {
+                if dep_name == &name {
+                    return Err("a plugin can't depend on itself".into());
+                }
+
+                let version = match dep_version.as_str() {
+                    Some("*") => None,
+                    _ => Some(parse_version(dep_version, dep_name)?),
+                };
+
+                dependencies.push(Dependency {
+                    name: dep_name.clone(),
+                    version,
+                });
+            }
+        }
+
+        let main = match &value["main"] {
+            Value::Null => None,
+            main => Some(
+                main.as_str()
+                    .filter(|main| main.split('.').all(is_identifier))
+                    .ok_or("main has to be the name of a lua module")?
+                    .to_string(),
+            ),
+        };
+
+        let components = parse_names(&value["components"], "components")?;
+        let commands = parse_names(&value["commands"], "commands")?;
+
+        if main.is_none() && !(components.is_empty() && commands.is_empty()) {
+            return Err("components and commands need an entry module in main".into());
+        }
+
+        Ok(Self {
+            name,
+            version,
+            nog,
+            dependencies,
+            main,
+            components,
+            commands,
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use std::{fmt, str::FromStr};
+
+/// Access to a part of the standard library that plugins don't get by default
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Permission {
+    /// The whole `os` library instead of `clock`, `date`, `difftime` and `time`
+    Os,
+    Io,
+    /// `require("ffi")`
+    Ffi,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 3] = [Self::Os, Self::Io, Self::Ffi];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Os => "os",
+            Self::Io => "io",
+            Self::Ffi => "ffi",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|permission| permission.name() == s)
+            .ok_or_else(|| format!("{} isn't a permission, use os, io or ffi", s))
+    }
+}
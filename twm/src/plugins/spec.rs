@@ -0,0 +1,87 @@
+use std::{fmt, str::FromStr};
+
+/// Which commit of a plugin gets installed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Revision {
+    /// The newest commit of the default branch
+    #[default]
+    Default,
+    Branch(String),
+    Tag(String),
+    Commit(String),
+}
+
+impl fmt::Display for Revision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default branch"),
+            Self::Branch(branch) => write!(f, "branch {}", branch),
+            Self::Tag(tag) => write!(f, "tag {}", tag),
+            Self::Commit(commit) => write!(f, "commit {}", commit),
+        }
+    }
+}
+
+/// A plugin that got declared with `nog.plugins`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginSpec {
+    /// The GitHub repository in the form `owner/repo`
+    pub name: String,
+    /// Where git clones the plugin from, which defaults to the GitHub repository
+    pub source: String,
+    /// The folder inside the plugins folder, which defaults to the name of the repository
+    pub dir: String,
+    pub revision: Revision,
+}
+
+fn is_valid_part(part: &str) -> bool {
+    !part.is_empty()
+        && part != "."
+        && part != ".."
+        && part
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+impl PluginSpec {
+    pub fn new(name: &str) -> Result<Self, String> {
+        let parts = name.split('/').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [owner, repo] if is_valid_part(owner) && is_valid_part(repo) => Ok(Self {
+                name: name.into(),
+                source: format!("https://github.com/{}", name),
+                dir: repo.to_string(),
+                revision: Revision::Default,
+            }),
+            _ => Err(format!("{} isn't in the form owner/repo", name)),
+        }
+    }
+
+    /// Installs the plugin into another folder of the plugins folder
+    pub fn with_dir(mut self, dir: &str) -> Result<Self, String> {
+        if !is_valid_part(dir) {
+            return Err(format!("{} isn't a valid folder name", dir));
+        }
+        self.dir = dir.into();
+        Ok(self)
+    }
+}
+
+impl FromStr for PluginSpec {
+    type Err = String;
+
+    /// Parses `owner/repo` or `owner/repo@tag`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '@');
+        let mut spec = Self::new(parts.next().unwrap_or_default())?;
+
+        if let Some(tag) = parts.next() {
+            if tag.is_empty() {
+                return Err(format!("{} has no tag after the @", s));
+            }
+            spec.revision = Revision::Tag(tag.into());
+        }
+
+        Ok(spec)
+    }
+}
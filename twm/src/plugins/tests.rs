@@ -0,0 +1,228 @@
+use super::{
+    lock::Lockfile,
+    spec::{PluginSpec, Revision},
+    Change, PluginManager,
+};
+use crate::{system::SystemError, util::testing::TempDir};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(["-c", "user.name=nog", "-c", "user.email=nog@localhost"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// A bare repository that plugins get cloned from and a clone of it to push commits
+struct Upstream {
+    bare: PathBuf,
+    work: PathBuf,
+}
+
+impl Upstream {
+    fn new(root: &Path) -> Self {
+        let bare = root.join("upstream.git");
+        let work = root.join("work");
+        git(
+            root,
+            &["init", "--quiet", "--bare", "-b", "master", "upstream.git"],
+        );
+        git(root, &["clone", "--quiet", "upstream.git", "work"]);
+        Self { bare, work }
+    }
+
+    /// Pushes a commit to the branch and returns its hash
+    fn commit(&self, branch: &str, content: &str) -> String {
+        fs::create_dir_all(self.work.join("lua/plugin")).unwrap();
+        fs::write(self.work.join("lua/plugin/init.lua"), content).unwrap();
+        git(&self.work, &["checkout", "--quiet", "-B", branch]);
+        git(&self.work, &["add", "-A"]);
+        git(&self.work, &["commit", "--quiet", "-m", content]);
+        git(&self.work, &["push", "--quiet", "origin", branch]);
+        git(&self.work, &["rev-parse", "HEAD"])
+    }
+
+    fn tag(&self, tag: &str) {
+        git(&self.work, &["tag", tag]);
+        git(&self.work, &["push", "--quiet", "origin", tag]);
+    }
+
+    fn spec(&self, name: &str, revision: Revision) -> PluginSpec {
+        let mut spec = PluginSpec::new(name).unwrap();
+        spec.source = self.bare.to_string_lossy().into();
+        spec.revision = revision;
+        spec
+    }
+}
+
+fn manager(root: &Path) -> PluginManager {
+    PluginManager::new(root.join("plugins"), root.join("plugins.lock"))
+}
+
+fn head(root: &Path, dir: &str) -> String {
+    git(&root.join("plugins").join(dir), &["rev-parse", "HEAD"])
+}
+
+fn locked_commit(root: &Path, name: &str) -> Option<String> {
+    Lockfile::load(&root.join("plugins.lock"))
+        .unwrap()
+        .plugins
+        .get(name)
+        .map(|plugin| plugin.commit.clone())
+}
+
+#[test]
+fn parses_declarations() {
+    let spec = PluginSpec::from_str("user/repo@v1.2").unwrap();
+    assert_eq!(spec.name, "user/repo");
+    assert_eq!(spec.source, "https://github.com/user/repo");
+    assert_eq!(spec.dir, "repo");
+    assert_eq!(spec.revision, Revision::Tag("v1.2".into()));
+
+    assert!(PluginSpec::from_str("repo").is_err());
+    assert!(PluginSpec::from_str("user/repo/extra").is_err());
+    assert!(PluginSpec::from_str("user/..").is_err());
+    assert!(PluginSpec::from_str("user/repo@").is_err());
+    assert!(PluginSpec::new("user/repo")
+        .unwrap()
+        .with_dir("../x")
+        .is_err());
+}
+
+#[test]
+fn installs_plugins_at_their_revision() {
+    let root = TempDir::new("plugins-install");
+    let upstream = Upstream::new(&root);
+    let tagged = upstream.commit("master", "return 1");
+    upstream.tag("v1.0");
+    let newest = upstream.commit("master", "return 2");
+    let dev = upstream.commit("dev", "return 3");
+
+    let specs = vec![
+        upstream.spec("nog/one", Revision::Tag("v1.0".into())),
+        upstream.spec("nog/two", Revision::Default),
+        upstream.spec("nog/three", Revision::Branch("dev".into())),
+        upstream.spec("nog/four", Revision::Commit(tagged.clone())),
+    ];
+    let report = manager(&root).sync(&specs, false, |_, _| {}).unwrap();
+
+    assert!(report.errors().is_empty());
+    assert_eq!(report.results.len(), 4);
+    assert_eq!(head(&root, "one"), tagged);
+    assert_eq!(head(&root, "two"), newest);
+    assert_eq!(head(&root, "three"), dev);
+    assert_eq!(head(&root, "four"), tagged);
+    assert_eq!(locked_commit(&root, "nog/two"), Some(newest));
+    assert!(root.join("plugins/one/lua/plugin/init.lua").exists());
+}
+
+#[test]
+fn locked_commits_stay_until_an_update() {
+    let root = TempDir::new("plugins-lock");
+    let upstream = Upstream::new(&root);
+    let first = upstream.commit("master", "return 1");
+    let specs = vec![upstream.spec("nog/one", Revision::Default)];
+    manager(&root).sync(&specs, false, |_, _| {}).unwrap();
+
+    let second = upstream.commit("master", "return 2");
+    fs::remove_dir_all(root.join("plugins/one")).unwrap();
+    let report = manager(&root).sync(&specs, false, |_, _| {}).unwrap();
+    assert_eq!(
+        report.results[0].1.as_ref().unwrap(),
+        &Change::Installed(first.clone())
+    );
+    assert_eq!(head(&root, "one"), first);
+
+    let report = manager(&root).sync(&specs, true, |_, _| {}).unwrap();
+    assert_eq!(
+        report.results[0].1.as_ref().unwrap(),
+        &Change::Updated {
+            from: first,
+            to: second.clone()
+        }
+    );
+    assert_eq!(locked_commit(&root, "nog/one"), Some(second.clone()));
+
+    let report = manager(&root).sync(&specs, true, |_, _| {}).unwrap();
+    assert_eq!(
+        report.results[0].1.as_ref().unwrap(),
+        &Change::Unchanged(second)
+    );
+}
+
+#[test]
+fn undeclared_plugins_get_removed() {
+    let root = TempDir::new("plugins-remove");
+    let upstream = Upstream::new(&root);
+    upstream.commit("master", "return 1");
+    fs::create_dir_all(root.join("plugins/manual/lua")).unwrap();
+
+    let specs = vec![upstream.spec("nog/one", Revision::Default)];
+    manager(&root).sync(&specs, false, |_, _| {}).unwrap();
+    assert!(root.join("plugins/one").exists());
+
+    let report = manager(&root).sync(&[], false, |_, _| {}).unwrap();
+    assert_eq!(report.removed, vec!["nog/one"]);
+    assert!(!root.join("plugins/one").exists());
+    assert!(root.join("plugins/manual").exists());
+    assert_eq!(locked_commit(&root, "nog/one"), None);
+}
+
+#[test]
+fn failing_plugins_dont_stop_the_others() {
+    let root = TempDir::new("plugins-failure");
+    let upstream = Upstream::new(&root);
+    let commit = upstream.commit("master", "return 1");
+    fs::create_dir_all(root.join("plugins/taken")).unwrap();
+
+    let specs = vec![
+        upstream.spec("nog/one", Revision::Tag("missing".into())),
+        upstream.spec("nog/two", Revision::Default),
+        upstream.spec("nog/taken", Revision::Default),
+    ];
+    let mut progress = Vec::new();
+    let report = manager(&root)
+        .sync(&specs, false, |name, result| {
+            progress.push((name.to_string(), result.is_ok()))
+        })
+        .unwrap();
+
+    progress.sort();
+    assert_eq!(
+        progress,
+        vec![
+            ("nog/one".to_string(), false),
+            ("nog/taken".to_string(), false),
+            ("nog/two".to_string(), true)
+        ]
+    );
+    assert_eq!(report.errors().len(), 2);
+    assert!(report.errors().iter().any(|e| matches!(
+        e,
+        SystemError::Plugin { name, reason } if name == "nog/one" && reason.contains("tag missing")
+    )));
+    assert_eq!(head(&root, "two"), commit);
+    assert_eq!(locked_commit(&root, "nog/one"), None);
+}
+
+#[test]
+fn plugins_cant_share_a_folder() {
+    let specs = vec![
+        PluginSpec::new("a/plugin").unwrap(),
+        PluginSpec::new("b/plugin").unwrap(),
+    ];
+
+    assert!(matches!(
+        PluginManager::validate(&specs),
+        Err(SystemError::InvalidPlugin(_))
+    ));
+}
@@ -48,6 +48,11 @@ impl Popup {
     }
 
     pub fn error(msg: Vec<String>, state_arc: Arc<Mutex<AppState>>) {
+        Popup::show(msg, state_arc);
+    }
+
+    /// Shows the lines in a popup that closes with Alt+Q
+    pub fn show(msg: Vec<String>, state_arc: Arc<Mutex<AppState>>) {
         thread::spawn(move || Popup::new_error(msg).create(state_arc).unwrap());
     }
 
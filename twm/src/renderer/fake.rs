@@ -0,0 +1,35 @@
+use super::Renderer;
+use crate::{
+    config::Config, display::Display, system::NativeWindow, system::Rectangle,
+    system::SystemError, system::SystemResult, tile_grid::TileGrid,
+};
+
+/// Places every window exactly inside of its tile, because fake windows don't have any
+/// decorations that need to be compensated for.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct FakeRenderer;
+
+impl Renderer for FakeRenderer {
+    fn render<TRenderer: Renderer>(
+        &self,
+        _grid: &TileGrid<TRenderer>,
+        window: &NativeWindow,
+        _config: &Config,
+        _display: &Display,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> SystemResult {
+        let rect = Rectangle {
+            left: x,
+            right: x + width,
+            top: y,
+            bottom: y + height,
+        };
+
+        window
+            .set_window_pos(rect, None, None)
+            .map_err(SystemError::DrawTile)
+    }
+}
@@ -3,8 +3,14 @@ use crate::{
     tile_grid::TileGrid,
 };
 
+#[cfg(feature = "headless")]
+pub use fake::FakeRenderer as NativeRenderer;
+#[cfg(not(feature = "headless"))]
 pub use win::WinRenderer as NativeRenderer;
 
+#[cfg(feature = "headless")]
+pub mod fake;
+#[cfg(not(feature = "headless"))]
 pub mod win;
 
 pub trait Renderer {
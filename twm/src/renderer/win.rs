@@ -37,7 +37,7 @@ impl Renderer for WinRenderer {
             } else {
                 top -= border_height * 2;
 
-                if config.use_border {
+                if config.use_border && !rule.no_border {
                     left += 1;
                     right -= 1;
                     top += 1;
@@ -0,0 +1,78 @@
+use crate::system::{NativeWindow, SystemError, WindowId};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ScratchpadError {
+    #[error("There is no scratchpad called {0}")]
+    Unknown(String),
+    #[error("The scratchpad {0} already has a window")]
+    Occupied(String),
+    #[error(transparent)]
+    System(#[from] SystemError),
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    id: WindowId,
+    /// `None` while the window is shown as a floating window of a workspace
+    hidden: Option<NativeWindow>,
+}
+
+/// Windows that got moved to the scratchpad under a name. They stay hidden until they get
+/// toggled, which shows them as a floating window of the current workspace.
+#[derive(Default, Debug, Clone)]
+pub struct Scratchpad {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Scratchpad {
+    /// Fails if a different window already uses the name
+    pub fn ensure_free(&self, name: &str, id: WindowId) -> Result<(), ScratchpadError> {
+        match self.entries.get(name) {
+            Some(entry) if entry.id != id => Err(ScratchpadError::Occupied(name.into())),
+            _ => Ok(()),
+        }
+    }
+    /// Hides the window and stores it under the name. A window can only belong to a single name,
+    /// so it loses its previous one.
+    pub fn insert(&mut self, name: String, window: NativeWindow) {
+        self.remove_window(window.id);
+        window.hide();
+        self.entries.insert(
+            name,
+            Entry {
+                id: window.id,
+                hidden: Some(window),
+            },
+        );
+    }
+    pub fn get_id(&self, name: &str) -> Result<WindowId, ScratchpadError> {
+        self.entries
+            .get(name)
+            .map(|e| e.id)
+            .ok_or_else(|| ScratchpadError::Unknown(name.into()))
+    }
+    /// Takes the window out of the scratchpad to show it. The name keeps belonging to the
+    /// window. Returns `None` if the window is already shown.
+    pub fn take_hidden(&mut self, name: &str) -> Result<Option<NativeWindow>, ScratchpadError> {
+        self.entries
+            .get_mut(name)
+            .map(|e| e.hidden.take())
+            .ok_or_else(|| ScratchpadError::Unknown(name.into()))
+    }
+    pub fn contains(&self, id: WindowId) -> bool {
+        self.entries.values().any(|e| e.id == id)
+    }
+    /// Forgets the window, which happens when it gets destroyed
+    pub fn remove_window(&mut self, id: WindowId) {
+        self.entries.retain(|_, e| e.id != id);
+    }
+    /// Empties the scratchpad and returns every window that is still hidden
+    pub fn release_all(&mut self) -> Vec<NativeWindow> {
+        std::mem::take(&mut self.entries)
+            .into_values()
+            .filter_map(|e| e.hidden)
+            .collect()
+    }
+}
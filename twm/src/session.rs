@@ -0,0 +1,739 @@
+//! Persists the state of every workspace, so it can be restored after nog restarts.
+//!
+//! The session gets stored as versioned json. Window ids change whenever a program gets restarted,
+//! which is why every window is stored together with its process name and title. These get used
+//! to find the window again when the id doesn't exist anymore.
+use crate::{
+    config::rule::{Action as RuleAction, Rule},
+    split_direction::SplitDirection,
+    system::{NativeWindow, Rectangle},
+    tile_grid::{floating::FloatingWindow, parser, snapshot::LayoutSnapshot, tree::GridTree},
+    AppState,
+};
+use log::{debug, error, warn};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Version of the format of the session file. Has to be increased whenever the format changes.
+pub const VERSION: u64 = 1;
+/// The file that contained the grid of every workspace before nog saved sessions
+pub const LEGACY_FILE: &str = "workspaces.grid";
+/// How long the session waits after a change before it gets saved, so a burst of events only
+/// saves it once
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("Failed to access the session file: {0}")]
+    Io(#[from] io::Error),
+    #[error("The session file isn't valid json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Version {0} of the session file isn't supported")]
+    UnsupportedVersion(u64),
+    #[error("Invalid value at {path}: {reason}")]
+    Invalid { path: String, reason: String },
+}
+
+/// Describes a window well enough to find it again after a restart
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowInfo {
+    pub id: i32,
+    pub process_name: String,
+    pub title: String,
+}
+
+impl WindowInfo {
+    pub fn of(window: &NativeWindow) -> Self {
+        let title = if window.title.is_empty() {
+            window.get_title().unwrap_or_default()
+        } else {
+            window.title.clone()
+        };
+
+        Self {
+            id: window.id.0,
+            process_name: window.get_process_name(),
+            // titles that come from the system can be padded with null characters
+            title: title.trim_end_matches('\0').into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkspaceSession {
+    pub id: i32,
+    pub split_direction: SplitDirection,
+    pub tree: Option<GridTree<WindowInfo>>,
+    pub floating: Vec<WindowInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisplaySession {
+    pub rect: Rectangle,
+    pub focused_workspace: Option<i32>,
+    /// Only contains the workspaces that have windows
+    pub workspaces: Vec<WorkspaceSession>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Session {
+    pub displays: Vec<DisplaySession>,
+    /// Process name patterns of the rules that got added by ignoring a window
+    pub ignored: Vec<String>,
+    /// Layout snapshots that got saved by the user
+    pub layouts: BTreeMap<String, LayoutSnapshot>,
+}
+
+impl Session {
+    pub fn from_state(state: &AppState) -> Self {
+        Self {
+            displays: state
+                .displays
+                .iter()
+                .map(|d| DisplaySession {
+                    rect: d.rect,
+                    focused_workspace: d.focused_grid_id,
+                    workspaces: d
+                        .grids
+                        .iter()
+                        .filter(|g| !g.is_empty() || !g.floating_windows.is_empty())
+                        .map(|g| WorkspaceSession {
+                            id: g.id,
+                            split_direction: g.next_axis,
+                            tree: g.to_tree(WindowInfo::of),
+                            floating: g
+                                .floating_windows
+                                .iter()
+                                .map(|f| WindowInfo::of(&f.window))
+                                .collect(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            ignored: state
+                .additonal_rules
+                .iter()
+                .filter(|r| r.action == RuleAction::Ignore)
+                .filter_map(|r| r.matcher.process())
+                .map(|pattern| pattern.as_str().into())
+                .collect(),
+            layouts: state.saved_layouts.clone(),
+        }
+    }
+
+    /// Converts the content of the legacy `workspaces.grid` file, where the n-th line contains the
+    /// grid of the n-th workspace. The workspaces stay on the display they are currently on.
+    pub fn from_legacy(content: &str, state: &AppState) -> Self {
+        let mut displays = state
+            .displays
+            .iter()
+            .map(|d| DisplaySession {
+                rect: d.rect,
+                focused_workspace: None,
+                workspaces: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+
+        for (idx, line) in content.lines().enumerate() {
+            let id = idx as i32 + 1;
+            let tree = match parser::parse(line) {
+                Ok(Some(tree)) => tree,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Skipping workspace {} of {}: {}", id, LEGACY_FILE, e);
+                    continue;
+                }
+            };
+
+            let (display, grid) = match state
+                .displays
+                .iter()
+                .enumerate()
+                .find_map(|(idx, d)| d.get_grid_by_id(id).map(|g| (idx, g)))
+            {
+                Some(found) => found,
+                None => continue,
+            };
+
+            displays[display].workspaces.push(WorkspaceSession {
+                id,
+                split_direction: grid.next_axis,
+                tree: tree.filter_map(&mut |id| Some(WindowInfo::of(&NativeWindow::from(id)))),
+                floating: Vec::new(),
+            });
+        }
+
+        Self {
+            displays,
+            ..Default::default()
+        }
+    }
+
+    /// Puts the given windows back into the workspaces they were part of.
+    ///
+    /// A display of the session belongs to the display with the same rectangle. If there is none,
+    /// the display at the same position gets used instead. Workspaces get moved to the display
+    /// they belong to.
+    pub fn restore(self, state: &mut AppState, windows: Vec<NativeWindow>) {
+        for (name, layout) in self.layouts {
+            state.saved_layouts.entry(name).or_insert(layout);
+        }
+
+        for pattern in &self.ignored {
+            if state
+                .additonal_rules
+                .iter()
+                .any(|r| r.matcher.process().map(|p| p.as_str()) == Some(pattern.as_str()))
+            {
+                continue;
+            }
+
+            match Regex::new(pattern) {
+                Ok(regex) => state.additonal_rules.push(Rule::ignore_process(regex)),
+                Err(e) => error!("Failed to restore ignore rule {}: {}", pattern, e),
+            }
+        }
+
+        let managed = state
+            .get_grids()
+            .iter()
+            .flat_map(|g| {
+                g.get_windows()
+                    .into_iter()
+                    .chain(g.floating_windows.iter().map(|f| &f.window))
+            })
+            .map(|w| w.id)
+            .collect::<Vec<_>>();
+
+        let mut windows = windows
+            .into_iter()
+            .filter(|w| !managed.contains(&w.id))
+            .map(Some)
+            .collect::<Vec<_>>();
+
+        let available = windows
+            .iter()
+            .flatten()
+            .map(WindowInfo::of)
+            .collect::<Vec<_>>();
+
+        let saved = self
+            .displays
+            .iter()
+            .flat_map(|d| d.workspaces.iter())
+            .flat_map(|ws| {
+                ws.tree
+                    .iter()
+                    .flat_map(|t| t.items())
+                    .chain(ws.floating.iter())
+            })
+            .collect::<Vec<_>>();
+
+        // the windows get taken in the same order as they appear in `saved`
+        let mut matches = match_windows(&saved, &available).into_iter();
+        let mut take = || matches.next().flatten().and_then(|idx| windows[idx].take());
+
+        for (idx, display) in self.displays.into_iter().enumerate() {
+            let target = state
+                .displays
+                .iter()
+                .position(|d| d.rect == display.rect)
+                .or_else(|| Some(idx).filter(|idx| *idx < state.displays.len()));
+
+            for ws in display.workspaces {
+                let id = ws.id;
+                let tree = ws.tree.and_then(|t| t.filter_map(&mut |_| take()));
+                let floating = ws
+                    .floating
+                    .iter()
+                    .filter_map(|_| take())
+                    .map(FloatingWindow::from)
+                    .collect();
+
+                let current = match state
+                    .displays
+                    .iter()
+                    .position(|d| d.get_grid_by_id(id).is_some())
+                {
+                    Some(current) => current,
+                    None => {
+                        debug!("Workspace {} of the session doesn't exist", id);
+                        continue;
+                    }
+                };
+
+                let target = target.unwrap_or(current);
+
+                if current != target {
+                    let source = &mut state.displays[current];
+                    let grid = source.remove_grid_by_id(id).unwrap();
+                    if source.focused_grid_id == Some(id) {
+                        source.focused_grid_id = None;
+                    }
+
+                    let grids = &mut state.displays[target].grids;
+                    grids.push(grid);
+                    grids.sort_by_key(|g| g.id);
+                }
+
+                let grid = state.displays[target].get_grid_by_id_mut(id).unwrap();
+                grid.next_axis = ws.split_direction;
+                grid.floating_windows = floating;
+                if let Some(tree) = tree {
+                    grid.load_tree(tree);
+                }
+            }
+
+            if let Some(target) = target.map(|t| &mut state.displays[t]) {
+                if let Some(id) = display.focused_workspace {
+                    if target.get_grid_by_id(id).is_some() {
+                        target.focused_grid_id = Some(id);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": VERSION,
+            "displays": self.displays.iter().map(|d| json!({
+                "rect": {
+                    "left": d.rect.left,
+                    "top": d.rect.top,
+                    "right": d.rect.right,
+                    "bottom": d.rect.bottom,
+                },
+                "focused_workspace": d.focused_workspace,
+                "workspaces": d.workspaces.iter().map(|ws| json!({
+                    "id": ws.id,
+                    "split_direction": ws.split_direction.to_string(),
+                    "tree": ws.tree.as_ref().map(|t| tree_to_json(t, "window", &window_to_json)),
+                    "floating": ws.floating.iter().map(window_to_json).collect::<Vec<_>>(),
+                })).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+            "ignored": self.ignored,
+            "layouts": self
+                .layouts
+                .iter()
+                .map(|(name, l)| (name.clone(), tree_to_json(&l.tree, "slot", &|slot| json!(slot))))
+                .collect::<serde_json::Map<_, _>>(),
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, SessionError> {
+        let root = Field::root(value);
+        let version = root.get("version")?.uint()?;
+
+        if version != VERSION {
+            return Err(SessionError::UnsupportedVersion(version));
+        }
+
+        let displays = root
+            .get("displays")?
+            .items()?
+            .into_iter()
+            .map(|d| {
+                let rect = d.get("rect")?;
+                Ok(DisplaySession {
+                    rect: Rectangle {
+                        left: rect.get("left")?.int()?,
+                        top: rect.get("top")?.int()?,
+                        right: rect.get("right")?.int()?,
+                        bottom: rect.get("bottom")?.int()?,
+                    },
+                    focused_workspace: d.opt("focused_workspace").map(|f| f.int()).transpose()?,
+                    workspaces: d
+                        .get("workspaces")?
+                        .items()?
+                        .into_iter()
+                        .map(|ws| workspace_from_json(&ws))
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<_, SessionError>>()?;
+
+        let ignored = root
+            .get("ignored")?
+            .items()?
+            .into_iter()
+            .map(|p| p.string().map(String::from))
+            .collect::<Result<_, _>>()?;
+
+        // sessions that got saved before layouts existed don't contain them
+        let layouts = match root.opt("layouts") {
+            Some(layouts) => layouts
+                .entries()?
+                .into_iter()
+                .map(|(name, l)| {
+                    let tree = tree_from_json(&l, "slot", &|f| f.uint().map(|s| s as usize))?;
+                    Ok((name, LayoutSnapshot { tree }))
+                })
+                .collect::<Result<_, SessionError>>()?,
+            None => BTreeMap::new(),
+        };
+
+        Ok(Self {
+            displays,
+            ignored,
+            layouts,
+        })
+    }
+}
+
+impl FromStr for Session {
+    type Err = SessionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Session::from_json(&serde_json::from_str(s)?)
+    }
+}
+
+/// Returns the index of the available window that belongs to each saved window.
+///
+/// A window that still has the same id and process wins. After that the process name and title
+/// have to match and if that isn't possible either, the first window of the same process gets
+/// used. Every window can only be used once.
+pub fn match_windows(saved: &[&WindowInfo], available: &[WindowInfo]) -> Vec<Option<usize>> {
+    let passes: [fn(&WindowInfo, &WindowInfo) -> bool; 3] = [
+        |saved, window| saved.id == window.id && saved.process_name == window.process_name,
+        |saved, window| saved.process_name == window.process_name && saved.title == window.title,
+        |saved, window| saved.process_name == window.process_name,
+    ];
+
+    let mut taken = vec![false; available.len()];
+    let mut result = vec![None; saved.len()];
+
+    for is_match in passes.iter() {
+        for (idx, window) in saved.iter().enumerate() {
+            if result[idx].is_some() {
+                continue;
+            }
+
+            if let Some(found) =
+                (0..available.len()).find(|i| !taken[*i] && is_match(window, &available[*i]))
+            {
+                taken[found] = true;
+                result[idx] = Some(found);
+            }
+        }
+    }
+
+    result
+}
+
+fn window_to_json(window: &WindowInfo) -> Value {
+    json!({
+        "id": window.id,
+        "process_name": window.process_name,
+        "title": window.title,
+    })
+}
+
+/// Converts the tree into json. The item of each tile gets stored with the given key.
+fn tree_to_json<T>(tree: &GridTree<T>, key: &str, item_to_json: &dyn Fn(&T) -> Value) -> Value {
+    let children_to_json = |children: &Vec<GridTree<T>>| {
+        children
+            .iter()
+            .map(|c| tree_to_json(c, key, item_to_json))
+            .collect::<Vec<_>>()
+    };
+
+    match tree {
+        GridTree::Column { size, children } => json!({
+            "type": "column",
+            "size": size,
+            "children": children_to_json(children),
+        }),
+        GridTree::Row { size, children } => json!({
+            "type": "row",
+            "size": size,
+            "children": children_to_json(children),
+        }),
+        GridTree::Stack {
+            size,
+            active,
+            children,
+        } => json!({
+            "type": "stack",
+            "size": size,
+            "active": active,
+            "children": children_to_json(children),
+        }),
+        GridTree::Tile {
+            size,
+            focused,
+            fullscreen,
+            item,
+        } => {
+            let mut value = json!({
+                "type": "tile",
+                "size": size,
+                "focused": focused,
+                "fullscreen": fullscreen,
+            });
+            value[key] = item_to_json(item);
+            value
+        }
+    }
+}
+
+fn window_from_json(field: &Field) -> Result<WindowInfo, SessionError> {
+    Ok(WindowInfo {
+        id: field.get("id")?.int()?,
+        process_name: field.get("process_name")?.string()?.into(),
+        title: field.get("title")?.string()?.into(),
+    })
+}
+
+fn tree_from_json<T>(
+    field: &Field,
+    key: &str,
+    item_from_json: &dyn Fn(&Field) -> Result<T, SessionError>,
+) -> Result<GridTree<T>, SessionError> {
+    let size = field.get("size")?.uint()? as u32;
+    let children = || {
+        field
+            .get("children")?
+            .items()?
+            .iter()
+            .map(|c| tree_from_json(c, key, item_from_json))
+            .collect::<Result<_, _>>()
+    };
+
+    Ok(match field.get("type")?.string()? {
+        "column" => GridTree::Column {
+            size,
+            children: children()?,
+        },
+        "row" => GridTree::Row {
+            size,
+            children: children()?,
+        },
+        "stack" => GridTree::Stack {
+            size,
+            active: field.get("active")?.uint()? as usize,
+            children: children()?,
+        },
+        "tile" => GridTree::Tile {
+            size,
+            focused: field.get("focused")?.boolean()?,
+            fullscreen: field.get("fullscreen")?.boolean()?,
+            item: item_from_json(&field.get(key)?)?,
+        },
+        x => return Err(field.invalid(format!("unknown node type {}", x))),
+    })
+}
+
+fn workspace_from_json(field: &Field) -> Result<WorkspaceSession, SessionError> {
+    let split_direction = field.get("split_direction")?;
+
+    Ok(WorkspaceSession {
+        id: field.get("id")?.int()?,
+        split_direction: SplitDirection::from_str(split_direction.string()?)
+            .map_err(|e| split_direction.invalid(e.to_string()))?,
+        tree: field
+            .opt("tree")
+            .map(|t| tree_from_json(&t, "window", &window_from_json))
+            .transpose()?,
+        floating: field
+            .get("floating")?
+            .items()?
+            .iter()
+            .map(window_from_json)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// A json value that knows where it is inside of the session, which makes it possible to point to
+/// the invalid value in an error.
+struct Field<'a> {
+    value: &'a Value,
+    path: String,
+}
+
+impl<'a> Field<'a> {
+    fn root(value: &'a Value) -> Self {
+        Self {
+            value,
+            path: String::from("$"),
+        }
+    }
+
+    fn invalid(&self, reason: impl Into<String>) -> SessionError {
+        SessionError::Invalid {
+            path: self.path.clone(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns the value of the key if it exists and isn't null
+    fn opt(&self, key: &str) -> Option<Field<'a>> {
+        self.value
+            .get(key)
+            .filter(|v| !v.is_null())
+            .map(|value| Field {
+                value,
+                path: format!("{}.{}", self.path, key),
+            })
+    }
+
+    fn get(&self, key: &str) -> Result<Field<'a>, SessionError> {
+        self.opt(key)
+            .ok_or_else(|| self.invalid(format!("missing {}", key)))
+    }
+
+    fn items(&self) -> Result<Vec<Field<'a>>, SessionError> {
+        let items = self
+            .value
+            .as_array()
+            .ok_or_else(|| self.invalid("expected an array"))?;
+
+        Ok(items
+            .iter()
+            .enumerate()
+            .map(|(i, value)| Field {
+                value,
+                path: format!("{}[{}]", self.path, i),
+            })
+            .collect())
+    }
+
+    fn entries(&self) -> Result<Vec<(String, Field<'a>)>, SessionError> {
+        let entries = self
+            .value
+            .as_object()
+            .ok_or_else(|| self.invalid("expected an object"))?;
+
+        Ok(entries
+            .iter()
+            .map(|(key, value)| {
+                let field = Field {
+                    value,
+                    path: format!("{}.{}", self.path, key),
+                };
+                (key.clone(), field)
+            })
+            .collect())
+    }
+
+    fn int(&self) -> Result<i32, SessionError> {
+        self.value
+            .as_i64()
+            .map(|x| x as i32)
+            .ok_or_else(|| self.invalid("expected an integer"))
+    }
+
+    fn uint(&self) -> Result<u64, SessionError> {
+        self.value
+            .as_u64()
+            .ok_or_else(|| self.invalid("expected a positive integer"))
+    }
+
+    fn string(&self) -> Result<&'a str, SessionError> {
+        self.value
+            .as_str()
+            .ok_or_else(|| self.invalid("expected a string"))
+    }
+
+    fn boolean(&self) -> Result<bool, SessionError> {
+        self.value
+            .as_bool()
+            .ok_or_else(|| self.invalid("expected a boolean"))
+    }
+}
+
+/// Decides where the session gets saved to
+#[derive(Clone, Debug, Default)]
+pub struct SessionStore {
+    /// Without a path the session only gets kept in memory
+    path: Option<PathBuf>,
+    last_saved: Option<String>,
+    /// When the session changed for the first time since it got saved
+    changed_at: Option<Instant>,
+}
+
+impl SessionStore {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            last_saved: None,
+            changed_at: None,
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        crate::get_config_path().join("session.json")
+    }
+
+    /// Remembers that the session changed, so it gets saved once the delay is over
+    pub fn changed(&mut self) {
+        self.changed_at.get_or_insert_with(Instant::now);
+    }
+
+    /// Returns how long the changed session waits before it gets saved or `None` if it didn't
+    /// change
+    pub fn time_until_save(&self) -> Option<Duration> {
+        self.changed_at
+            .map(|at| SAVE_DELAY.checked_sub(at.elapsed()).unwrap_or_default())
+    }
+
+    /// Writes the session to the file, unless it didn't change since the last time. The file gets
+    /// replaced at once, so a crash while saving can't leave half of a session behind.
+    pub fn save(&mut self, session: &Session) -> Result<(), SessionError> {
+        self.changed_at = None;
+
+        let content = serde_json::to_string_pretty(&session.to_json())?;
+
+        if self.last_saved.as_ref() == Some(&content) {
+            return Ok(());
+        }
+
+        if let Some(path) = &self.path {
+            let tmp_path = path.with_extension("json.tmp");
+            fs::write(&tmp_path, &content)?;
+            fs::rename(&tmp_path, path)?;
+        }
+
+        self.last_saved = Some(content);
+
+        Ok(())
+    }
+
+    /// Returns `None` if there is no saved session
+    pub fn load(&self) -> Result<Option<Session>, SessionError> {
+        let content = match &self.path {
+            Some(path) => read_if_exists(path)?,
+            None => self.last_saved.clone(),
+        };
+
+        content.map(|c| Session::from_str(&c)).transpose()
+    }
+
+    /// Returns the content of the legacy `workspaces.grid` file next to the session file
+    pub fn load_legacy(&self) -> Result<Option<String>, SessionError> {
+        match &self.path {
+            Some(path) => Ok(read_if_exists(&path.with_file_name(LEGACY_FILE))?),
+            None => Ok(None),
+        }
+    }
+}
+
+fn read_if_exists(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests;
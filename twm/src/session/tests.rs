@@ -0,0 +1,233 @@
+use super::{
+    match_windows, DisplaySession, Session, SessionError, SessionStore, WindowInfo,
+    WorkspaceSession, LEGACY_FILE,
+};
+use crate::{
+    split_direction::SplitDirection,
+    system::{fake, fake::FakeWindow, Rectangle},
+    tile_grid::snapshot::LayoutSnapshot,
+    tile_grid::tree::GridTree,
+    util::testing::TempDir,
+    AppState,
+};
+use std::{collections::BTreeMap, fs, str::FromStr};
+
+fn window(id: i32, process_name: &str, title: &str) -> WindowInfo {
+    WindowInfo {
+        id,
+        process_name: process_name.into(),
+        title: title.into(),
+    }
+}
+
+fn create_session() -> Session {
+    Session {
+        displays: vec![DisplaySession {
+            rect: Rectangle {
+                left: 0,
+                top: 0,
+                right: 1920,
+                bottom: 1080,
+            },
+            focused_workspace: Some(2),
+            workspaces: vec![WorkspaceSession {
+                id: 2,
+                split_direction: SplitDirection::Horizontal,
+                tree: Some(GridTree::Column {
+                    size: 120,
+                    children: vec![
+                        GridTree::Tile {
+                            size: 60,
+                            focused: true,
+                            fullscreen: false,
+                            item: window(1, "code.exe", "main.rs"),
+                        },
+                        GridTree::Tile {
+                            size: 60,
+                            focused: false,
+                            fullscreen: false,
+                            item: window(2, "firefox.exe", "Nog"),
+                        },
+                    ],
+                }),
+                floating: vec![window(3, "calc.exe", "Calculator")],
+            }],
+        }],
+        ignored: vec!["^spotify.exe$".into()],
+        layouts: vec![(
+            String::from("dev"),
+            LayoutSnapshot {
+                tree: GridTree::Row {
+                    size: 120,
+                    children: vec![
+                        GridTree::Tile {
+                            size: 80,
+                            focused: false,
+                            fullscreen: false,
+                            item: 1,
+                        },
+                        GridTree::Tile {
+                            size: 40,
+                            focused: false,
+                            fullscreen: false,
+                            item: 2,
+                        },
+                    ],
+                },
+            },
+        )]
+        .into_iter()
+        .collect(),
+    }
+}
+
+#[test]
+fn json_round_trip() {
+    let session = create_session();
+    let json = serde_json::to_string(&session.to_json()).unwrap();
+
+    assert_eq!(session, Session::from_str(&json).unwrap());
+}
+
+#[test]
+fn rejects_other_versions() {
+    let mut json = create_session().to_json();
+    json["version"] = 2.into();
+
+    match Session::from_json(&json) {
+        Err(SessionError::UnsupportedVersion(2)) => {}
+        x => panic!("Expected unsupported version, got {:?}", x),
+    }
+}
+
+#[test]
+fn invalid_value_has_path() {
+    let mut json = create_session().to_json();
+    json["displays"][0]["workspaces"][0]["tree"]["children"][1]["size"] = "big".into();
+
+    assert_eq!(
+        "Invalid value at $.displays[0].workspaces[0].tree.children[1].size: expected a positive integer",
+        Session::from_json(&json).unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn layouts_are_optional() {
+    let mut json = create_session().to_json();
+    json.as_object_mut().unwrap().remove("layouts");
+
+    assert_eq!(BTreeMap::new(), Session::from_json(&json).unwrap().layouts);
+}
+
+#[test]
+fn match_prefers_same_id() {
+    let first = window(1, "code.exe", "a");
+    let second = window(2, "code.exe", "b");
+    let saved = vec![&first, &second];
+    let available = vec![window(2, "code.exe", "b"), window(1, "code.exe", "b")];
+
+    assert_eq!(vec![Some(1), Some(0)], match_windows(&saved, &available));
+}
+
+#[test]
+fn match_falls_back_to_title_and_process() {
+    let first = window(1, "code.exe", "a");
+    let second = window(2, "code.exe", "b");
+    let third = window(3, "code.exe", "c");
+    let saved = vec![&first, &second, &third];
+    let available = vec![
+        window(10, "code.exe", "c"),
+        window(11, "code.exe", "b"),
+        window(12, "firefox.exe", "a"),
+    ];
+
+    assert_eq!(
+        vec![None, Some(1), Some(0)],
+        match_windows(&saved, &available)
+    );
+}
+
+#[test]
+fn match_uses_process_for_changed_titles() {
+    let first = window(1, "code.exe", "a");
+    let second = window(2, "code.exe", "b");
+    let saved = vec![&first, &second];
+    let available = vec![window(10, "code.exe", "x"), window(11, "code.exe", "b")];
+
+    assert_eq!(vec![Some(0), Some(1)], match_windows(&saved, &available));
+}
+
+#[test]
+fn store_replaces_the_file() {
+    let dir = TempDir::new("session-store");
+    let path = dir.join("session.json");
+    let mut store = SessionStore::new(Some(path.clone()));
+    assert!(store.load().unwrap().is_none());
+    assert!(store.time_until_save().is_none());
+
+    store.changed();
+    assert!(store.time_until_save().is_some());
+    store.save(&create_session()).unwrap();
+    assert!(store.time_until_save().is_none());
+
+    assert_eq!(Some(create_session()), store.load().unwrap());
+    assert_eq!(
+        vec![path],
+        fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn legacy_grids_get_migrated() {
+    fake::reset();
+    fake::add_display(Rectangle {
+        left: 0,
+        top: 0,
+        right: 1920,
+        bottom: 1080,
+    });
+    let code = fake::add_window(FakeWindow::new("main.rs", "code.exe"));
+    let firefox = fake::add_window(FakeWindow::new("Nog", "firefox.exe"));
+    let calc = fake::add_window(FakeWindow::new("Calculator", "calc.exe"));
+    let state = AppState::default();
+
+    let dir = TempDir::new("session-legacy");
+    let content = format!(
+        "c0|120[t0|60|{},t1|60|{}]\n\nc0|120[t0|60|1\nt0|120|{}\n",
+        code.0, firefox.0, calc.0
+    );
+    fs::write(dir.join(LEGACY_FILE), content).unwrap();
+    let store = SessionStore::new(Some(dir.join("session.json")));
+    assert!(store.load().unwrap().is_none());
+    let session = Session::from_legacy(&store.load_legacy().unwrap().unwrap(), &state);
+
+    let workspaces = &session.displays[0].workspaces;
+    // the broken grid of the third workspace gets skipped
+    assert_eq!(
+        vec![1, 4],
+        workspaces.iter().map(|ws| ws.id).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some(GridTree::Column {
+            size: 120,
+            children: vec![
+                GridTree::Tile {
+                    size: 60,
+                    focused: false,
+                    fullscreen: false,
+                    item: window(code.0, "code.exe", "main.rs"),
+                },
+                GridTree::Tile {
+                    size: 60,
+                    focused: false,
+                    fullscreen: false,
+                    item: window(firefox.0, "firefox.exe", "Nog"),
+                },
+            ],
+        }),
+        workspaces[0].tree
+    );
+}
@@ -0,0 +1,448 @@
+//! Replays traces of events against nog running on the fake system.
+//!
+//! A trace is a text file with one command per line. Empty lines and lines starting with `#` are
+//! ignored.
+//!
+//! ```text
+//! # has to come before every other command. Defaults to a single 1920x1080 display
+//! display <left> <top> <right> <bottom>
+//! # runs the lua code like it was part of the config
+//! lua <code>
+//! # creates a new window and sends a `Show` event. The first window gets the id 1
+//! show <process name> <title>
+//! destroy <window id>
+//! focus <window id>
+//! minimize <window id>
+//! # presses the key combos one after another, which have to trigger a keybinding or continue a
+//! # chord. Sends the keybinding event once a keybinding gets triggered. Keybindings whose
+//! # condition doesn't match the foreground window pass their keys through, which gets recorded
+//! key <key combo>...
+//! workspace <workspace id>
+//! # sends the request like an IPC client and records the response. After a `subscribe` request
+//! # every notification gets recorded as well
+//! ipc <json>
+//! # records the layout of every display
+//! snapshot
+//! # records the position of every tiled and floating window in the focused workspaces and
+//! # whether they are hidden or translucent
+//! rects
+//! # saves the session, clears every workspace and restores the session like after a restart.
+//! # With `reopen` every window gets a new id first, like after restarting the programs.
+//! restart [reopen]
+//! ```
+//!
+//! The output of a trace contains every snapshot, IPC response, notification and lua error.
+//! Traces live in `src/simulation/traces` next to a `.golden` file, which contains the expected
+//! output.
+use crate::{
+    event::Event,
+    handle_event,
+    ipc::Request,
+    keybindings::chord::{ChordAction, ChordState},
+    keybindings::condition::{self, FocusedWindow},
+    keybindings::keybinding::Keybinding,
+    lua::get_err_msg,
+    lua::setup_lua_rt,
+    session::SessionStore,
+    system::api,
+    system::fake,
+    system::fake::FakeWindow,
+    system::NativeWindow,
+    system::Rectangle,
+    system::WindowId,
+    win_event_handler::win_event::WinEvent,
+    win_event_handler::win_event_type::WinEventType,
+    AppState,
+};
+use crossbeam_channel::{unbounded, Receiver};
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("line {line}: {reason}")]
+pub struct TraceError {
+    pub line: usize,
+    pub reason: String,
+}
+
+pub struct Simulation {
+    state_arc: Arc<Mutex<AppState>>,
+    output: Vec<String>,
+    subscriptions: Vec<Receiver<Value>>,
+    /// The simulation has no keybinding manager, which usually keeps track of chords
+    chord: ChordState,
+}
+
+impl Simulation {
+    /// Creates a new simulation with the given displays, which has the first workspace focused.
+    pub fn new(displays: Vec<Rectangle>) -> Self {
+        fake::reset();
+
+        for rect in displays {
+            fake::add_display(rect);
+        }
+
+        let state_arc = Arc::new(Mutex::new(AppState::default()));
+
+        // the session of a simulation only lives in memory
+        state_arc.lock().session_store = SessionStore::new(None);
+
+        // luv is a native module that only gets shipped for windows
+        state_arc
+            .lock()
+            .lua_rt
+            .run_str("luv stub", "package.preload.luv = function() return {} end");
+
+        setup_lua_rt(state_arc.clone());
+
+        state_arc
+            .lock()
+            .change_workspace(1, false)
+            .expect("Failed to focus the first workspace");
+
+        Self {
+            state_arc,
+            output: Vec::new(),
+            subscriptions: Vec::new(),
+            chord: ChordState::default(),
+        }
+    }
+
+    /// Runs every command of the trace and returns the output
+    pub fn run_trace(trace: &str) -> Result<String, TraceError> {
+        let mut displays = Vec::new();
+        let mut commands = Vec::new();
+
+        for (i, line) in trace.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with("display ") {
+                if !commands.is_empty() {
+                    return Err(TraceError {
+                        line: i + 1,
+                        reason: "displays have to be defined before any other command".into(),
+                    });
+                }
+
+                displays.push(parse_display(line).map_err(|reason| TraceError {
+                    line: i + 1,
+                    reason,
+                })?);
+            } else {
+                commands.push((i + 1, line));
+            }
+        }
+
+        let mut simulation = Simulation::new(displays);
+
+        for (line, command) in commands {
+            simulation
+                .run_command(command)
+                .map_err(|reason| TraceError { line, reason })?;
+        }
+
+        Ok(simulation.output.join("\n"))
+    }
+
+    pub fn run_command(&mut self, command: &str) -> Result<(), String> {
+        let (name, args) = split_command(command);
+
+        match name {
+            "lua" => {
+                let rt = self.state_arc.lock().lua_rt.clone();
+                if let Err(e) = rt.with_lua(|lua| lua.load(args).set_name("trace")?.exec()) {
+                    self.output.push(format!("error: {}", get_err_msg(&e)));
+                }
+            }
+            "show" => {
+                let (process_name, title) = split_command(args);
+                let id = fake::add_window(FakeWindow::new(title, process_name));
+                self.send_win_event(WinEventType::Show(false), id);
+            }
+            "destroy" => {
+                let id = parse_window_id(args)?;
+                fake::remove_window(id);
+                self.send_win_event(WinEventType::Destroy, id);
+            }
+            "focus" => {
+                let id = parse_window_id(args)?;
+                fake::set_foreground_window(id);
+                self.send_win_event(WinEventType::FocusChange, id);
+            }
+            "minimize" => {
+                let id = parse_window_id(args)?;
+                fake::modify_window(id, |w| w.minimized = true);
+                self.send_win_event(WinEventType::Minimize, id);
+            }
+            "key" => {
+                let target = Keybinding::from_str(args).map_err(|e| e.to_string())?;
+                let state = self.state_arc.lock();
+                let work_mode = state.work_mode;
+                let mode = state.modes.current().map(|mode| mode.name.as_str());
+                let timeout = Duration::from_millis(state.config.chord_timeout.max(0) as u64);
+                let kbs = state
+                    .config
+                    .keybindings
+                    .iter()
+                    .filter(|kb| kb.is_active(work_mode, mode))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let rt = state.lua_rt.clone();
+                drop(state);
+
+                let focused = NativeWindow::get_foreground_window()
+                    .map(|window| FocusedWindow::capture(&window))
+                    .unwrap_or_default();
+                let inactive = condition::find_inactive(&kbs, &focused, &rt);
+                let active = kbs
+                    .iter()
+                    .filter(|kb| !inactive.contains(&kb.callback_id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                for combo in target.key_combos() {
+                    match self.chord.press(combo, &active, Instant::now(), timeout) {
+                        ChordAction::Trigger(kb) => self.send(Event::Keybinding(kb)),
+                        ChordAction::Wait(_) => {}
+                        ChordAction::Ignore
+                            if kbs.iter().any(|kb| kb.first_key_combo() == combo) =>
+                        {
+                            self.output.push(format!("key: {} passed through", combo))
+                        }
+                        ChordAction::Ignore => return Err(format!("{} is not bound", combo)),
+                    }
+                }
+            }
+            "workspace" => {
+                let id = args
+                    .parse()
+                    .map_err(|_| format!("{} is not a valid workspace id", args))?;
+                self.send(Event::ChangeWorkspace(id, false));
+            }
+            "ipc" => {
+                let (reply_sender, reply_receiver) = unbounded();
+                let response = match Request::parse(args, reply_sender) {
+                    Ok(request) => {
+                        let is_subscription = request.is_subscription();
+                        self.send(Event::IpcRequest(request));
+                        let response = reply_receiver
+                            .try_recv()
+                            .map_err(|_| "the request didn't get answered".to_string())?;
+
+                        if is_subscription && response.get("error").is_none() {
+                            self.subscriptions.push(reply_receiver);
+                        }
+
+                        response
+                    }
+                    Err(response) => response,
+                };
+                self.output.push(format!("ipc: {}", response));
+            }
+            "snapshot" => {
+                let snapshot = self.snapshot();
+                self.output.push(snapshot);
+            }
+            "rects" => {
+                let rects = self.rects();
+                self.output.push(rects);
+            }
+            "restart" => self.restart(args == "reopen")?,
+            x => return Err(format!("unknown command {}", x)),
+        }
+
+        for subscription in &self.subscriptions {
+            for notification in subscription.try_iter() {
+                self.output.push(format!("notification: {}", notification));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn restart(&mut self, reopen: bool) -> Result<(), String> {
+        let mut state = self.state_arc.lock();
+        state.save_session();
+
+        for grid in state.get_grids_mut() {
+            grid.cleanup().map_err(|e| e.to_string())?;
+            grid.floating_windows.clear();
+        }
+
+        for display in state.displays.iter_mut() {
+            display.focused_grid_id = None;
+        }
+
+        state.additonal_rules.clear();
+
+        if reopen {
+            for window in api::get_windows() {
+                if let Some(window) = fake::remove_window(window.id) {
+                    fake::add_window(window);
+                }
+            }
+        }
+
+        state.restore_session().map_err(|e| e.to_string())
+    }
+
+    fn send_win_event(&mut self, typ: WinEventType, id: WindowId) {
+        self.send(Event::WinEvent(WinEvent {
+            typ,
+            window: id.into(),
+        }));
+    }
+
+    /// Handles the event and every event that got emitted while handling it.
+    pub fn send(&mut self, event: Event) {
+        let receiver = self.state_arc.lock().event_channel.receiver.clone();
+        let mut next = Some(event);
+
+        while let Some(event) = next {
+            match event {
+                Event::LuaRuntimeError(e) => {
+                    self.output.push(format!("error: {}", get_err_msg(&e)));
+                }
+                // Exiting would stop the whole test process
+                Event::Exit => {}
+                event => {
+                    handle_event(self.state_arc.clone(), event).expect("Failed to handle event")
+                }
+            }
+
+            next = receiver.try_recv().ok();
+        }
+    }
+
+    /// Returns the layout of every workspace that is either focused or not empty.
+    pub fn snapshot(&self) -> String {
+        let state = self.state_arc.lock();
+        let mut lines = vec![String::from("snapshot")];
+
+        for display in &state.displays {
+            lines.push(format!(
+                "  display {}: workspace {}",
+                display.id.0,
+                display
+                    .focused_grid_id
+                    .map(|id| id.to_string())
+                    .unwrap_or("-".into())
+            ));
+
+            for grid in display.get_active_grids() {
+                let mut line = format!(
+                    "    workspace {}: focused {} | {}",
+                    grid.id,
+                    grid.get_focused_window()
+                        .map(|w| w.id.to_string())
+                        .unwrap_or("-".into()),
+                    grid.to_string()
+                );
+
+                if !grid.floating_windows.is_empty() {
+                    let ids = grid
+                        .floating_windows
+                        .iter()
+                        .map(|f| f.window.id.to_string())
+                        .collect::<Vec<_>>();
+                    line.push_str(&format!(" | floating {}", ids.join(",")));
+                }
+
+                lines.push(line.trim_end().into());
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    /// Returns the position of every tiled and floating window in the focused workspaces sorted by
+    /// their id. Hidden and translucent windows are marked as such.
+    pub fn rects(&self) -> String {
+        let state = self.state_arc.lock();
+        let mut ids = state
+            .displays
+            .iter()
+            .filter_map(|d| d.get_focused_grid())
+            .flat_map(|g| {
+                g.get_windows()
+                    .into_iter()
+                    .chain(g.floating_windows.iter().map(|f| &f.window))
+            })
+            .map(|w| w.id)
+            .collect::<Vec<_>>();
+
+        ids.sort_by_key(|id| id.0);
+
+        let mut lines = vec![String::from("rects")];
+
+        for id in ids {
+            if let Some(window) = fake::get_window(id) {
+                let rect = window.rect;
+                let mut line = format!(
+                    "  window {}: {} {} {} {}{}",
+                    id,
+                    rect.left,
+                    rect.top,
+                    rect.right,
+                    rect.bottom,
+                    if window.visible { "" } else { " hidden" }
+                );
+
+                if window.opacity < 1.0 {
+                    line.push_str(&format!(" opacity {}", window.opacity));
+                }
+
+                lines.push(line);
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Splits the command into its name and the remaining arguments
+fn split_command(command: &str) -> (&str, &str) {
+    let mut parts = command.splitn(2, ' ');
+    let name = parts.next().unwrap_or_default();
+    let args = parts.next().unwrap_or_default().trim();
+
+    (name, args)
+}
+
+fn parse_window_id(args: &str) -> Result<WindowId, String> {
+    args.parse::<i32>()
+        .map(WindowId)
+        .map_err(|_| format!("{} is not a valid window id", args))
+}
+
+fn parse_display(line: &str) -> Result<Rectangle, String> {
+    let values = line
+        .split_whitespace()
+        .skip(1)
+        .map(|x| x.parse::<i32>())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    match values.as_slice() {
+        [left, top, right, bottom] => Ok(Rectangle {
+            left: *left,
+            top: *top,
+            right: *right,
+            bottom: *bottom,
+        }),
+        _ => Err("a display needs exactly four values: left top right bottom".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,64 @@
+use super::Simulation;
+use std::{fs, path::PathBuf};
+
+fn traces_path() -> PathBuf {
+    let mut path: PathBuf = env!("CARGO_MANIFEST_DIR").into();
+    path.push("src");
+    path.push("simulation");
+    path.push("traces");
+    path
+}
+
+/// Runs every trace and compares the output with its golden file.
+///
+/// Setting the `NOG_BLESS` environment variable overwrites the golden files instead.
+#[test]
+fn traces_match_golden_files() {
+    let bless = std::env::var("NOG_BLESS").is_ok();
+    let mut failed = Vec::new();
+
+    for entry in fs::read_dir(traces_path()).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.extension().and_then(|x| x.to_str()) != Some("trace") {
+            continue;
+        }
+
+        let trace = fs::read_to_string(&path).unwrap();
+        let output =
+            Simulation::run_trace(&trace).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let golden_path = path.with_extension("golden");
+
+        if bless {
+            fs::write(&golden_path, format!("{}\n", output)).unwrap();
+            continue;
+        }
+
+        let golden = fs::read_to_string(&golden_path).unwrap_or_default();
+
+        if golden.trim_end() != output {
+            failed.push(format!(
+                "{}\n--- expected\n{}\n--- actual\n{}",
+                path.display(),
+                golden.trim_end(),
+                output
+            ));
+        }
+    }
+
+    assert!(failed.is_empty(), "\n{}", failed.join("\n\n"));
+}
+
+#[test]
+fn displays_have_to_come_first() {
+    let err = Simulation::run_trace("snapshot\ndisplay 0 0 1920 1080").unwrap_err();
+
+    assert_eq!(err.line, 2);
+}
+
+#[test]
+fn unknown_commands_are_rejected() {
+    let err = Simulation::run_trace("\n# comment\njump 1").unwrap_err();
+
+    assert_eq!(err.line, 3);
+}
@@ -0,0 +1,47 @@
+//! The functions of the platform that nog got compiled for.
+use super::{DisplayId, NativePlatform, NativeWindow, Platform, Rectangle, SystemResult};
+use crate::{display::Display, keybindings::keybinding::Keybinding, task_bar::Taskbar};
+
+pub fn print_last_error() {
+    NativePlatform::print_last_error()
+}
+
+pub fn get_displays() -> Vec<Display> {
+    NativePlatform::get_displays()
+}
+
+pub fn get_display_dpi(id: DisplayId) -> u32 {
+    NativePlatform::get_display_dpi(id)
+}
+
+pub fn get_display_rect(id: DisplayId) -> Rectangle {
+    NativePlatform::get_display_rect(id)
+}
+
+pub fn get_taskbars() -> Vec<Taskbar> {
+    NativePlatform::get_taskbars()
+}
+
+pub fn get_windows() -> Vec<NativeWindow> {
+    NativePlatform::get_windows()
+}
+
+pub fn add_launch_on_startup() {
+    NativePlatform::add_launch_on_startup()
+}
+
+pub fn remove_launch_on_startup() {
+    NativePlatform::remove_launch_on_startup()
+}
+
+pub fn register_keybinding(kb: &Keybinding) -> SystemResult {
+    NativePlatform::register_keybinding(kb)
+}
+
+pub fn unregister_keybinding(kb: &Keybinding) -> SystemResult {
+    NativePlatform::unregister_keybinding(kb)
+}
+
+pub fn launch_program(cmd: String) -> SystemResult {
+    NativePlatform::launch_program(cmd)
+}
@@ -0,0 +1,477 @@
+//! An in-memory implementation of the system layer.
+//!
+//! Instead of talking to an operating system, every window and display lives inside a
+//! [`FakeSystem`](FakeSystem) that tests can populate through the functions of this module.
+//! Each thread owns its own fake system, which means tests can run in parallel without seeing
+//! each others windows.
+use super::{DisplayId, Rectangle, SystemError, SystemResult, WindowId};
+use crate::config::rule::{self, WindowProperties};
+use crate::{display::Display, window::gwl_ex_style::GwlExStyle, window::gwl_style::GwlStyle, Rule};
+use log::debug;
+use std::{cell::RefCell, collections::BTreeMap};
+use thiserror::Error;
+
+pub mod api;
+#[cfg(test)]
+pub mod testing;
+pub mod win_event_listener;
+
+pub const BIN_NAME: &str = "nog";
+
+/// The [`Platform`](super::Platform) of the fake system
+pub struct FakePlatform;
+
+thread_local! {
+    static SYSTEM: RefCell<FakeSystem> = RefCell::new(FakeSystem::default());
+}
+
+/// The state of a window that only exists inside the fake system.
+#[derive(Debug, Clone)]
+pub struct FakeWindow {
+    pub title: String,
+    pub class_name: String,
+    pub process_path: String,
+    pub rect: Rectangle,
+    pub style: GwlStyle,
+    pub exstyle: GwlExStyle,
+    pub parent: Option<WindowId>,
+    pub visible: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub topmost: bool,
+    /// Between 0 (invisible) and 1 (opaque)
+    pub opacity: f32,
+}
+
+impl Default for FakeWindow {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            class_name: String::from("FakeWindow"),
+            process_path: String::new(),
+            rect: Rectangle {
+                left: 0,
+                right: 800,
+                top: 0,
+                bottom: 600,
+            },
+            style: GwlStyle::OVERLAPPEDWINDOW | GwlStyle::VISIBLE,
+            exstyle: GwlExStyle::WINDOWEDGE,
+            parent: None,
+            visible: true,
+            minimized: false,
+            maximized: false,
+            topmost: false,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl FakeWindow {
+    pub fn new(title: &str, process_name: &str) -> Self {
+        Self {
+            title: title.into(),
+            process_path: format!("C:\\Program Files\\{}", process_name),
+            ..Default::default()
+        }
+    }
+    #[cfg(test)]
+    pub fn with_class_name(mut self, class_name: &str) -> Self {
+        self.class_name = class_name.into();
+        self
+    }
+    pub fn with_rect(mut self, rect: Rectangle) -> Self {
+        self.rect = rect;
+        self
+    }
+    #[cfg(test)]
+    pub fn with_style(mut self, style: GwlStyle) -> Self {
+        self.style = style;
+        self
+    }
+    #[cfg(test)]
+    pub fn with_ex_style(mut self, exstyle: GwlExStyle) -> Self {
+        self.exstyle = exstyle;
+        self
+    }
+    pub fn with_parent(mut self, parent: WindowId) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FakeDisplay {
+    pub id: DisplayId,
+    pub rect: Rectangle,
+    pub dpi: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct FakeSystem {
+    next_window_id: i32,
+    windows: BTreeMap<i32, FakeWindow>,
+    displays: Vec<FakeDisplay>,
+    foreground: Option<WindowId>,
+    keybindings: Vec<i32>,
+    launched_programs: Vec<String>,
+    launch_on_startup: bool,
+}
+
+impl FakeSystem {
+    /// Returns the configured displays or a single full hd display if none were added.
+    fn displays(&mut self) -> &Vec<FakeDisplay> {
+        if self.displays.is_empty() {
+            self.displays.push(FakeDisplay {
+                id: DisplayId(1),
+                rect: Rectangle {
+                    left: 0,
+                    right: 1920,
+                    top: 0,
+                    bottom: 1080,
+                },
+                dpi: 96,
+            });
+        }
+
+        &self.displays
+    }
+}
+
+fn with_system<T>(f: impl FnOnce(&mut FakeSystem) -> T) -> T {
+    SYSTEM.with(|system| f(&mut system.borrow_mut()))
+}
+
+fn with_window<T>(id: WindowId, f: impl FnOnce(&mut FakeWindow) -> T) -> FakeResult<T> {
+    with_system(|system| {
+        system
+            .windows
+            .get_mut(&id.0)
+            .map(f)
+            .ok_or(FakeError::WindowNotFound(id))
+    })
+}
+
+/// Removes every window and display of the fake system of the current thread.
+#[cfg(test)]
+pub fn reset() {
+    with_system(|system| *system = FakeSystem::default());
+}
+
+#[cfg(test)]
+pub fn add_display(rect: Rectangle) -> DisplayId {
+    with_system(|system| {
+        let id = DisplayId(system.displays.len() as i32 + 1);
+        system.displays.push(FakeDisplay { id, rect, dpi: 96 });
+        id
+    })
+}
+
+pub fn add_window(window: FakeWindow) -> WindowId {
+    with_system(|system| {
+        system.next_window_id += 1;
+        system.windows.insert(system.next_window_id, window);
+        WindowId(system.next_window_id)
+    })
+}
+
+pub fn remove_window(id: WindowId) -> Option<FakeWindow> {
+    with_system(|system| {
+        if system.foreground == Some(id) {
+            system.foreground = None;
+        }
+        system.windows.remove(&id.0)
+    })
+}
+
+#[cfg(test)]
+pub fn get_window(id: WindowId) -> Option<FakeWindow> {
+    with_system(|system| system.windows.get(&id.0).cloned())
+}
+
+#[cfg(test)]
+pub fn modify_window(id: WindowId, f: impl FnOnce(&mut FakeWindow)) -> bool {
+    with_window(id, f).is_ok()
+}
+
+pub fn set_foreground_window(id: WindowId) {
+    with_system(|system| system.foreground = Some(id));
+}
+
+#[cfg(test)]
+pub fn get_launched_programs() -> Vec<String> {
+    with_system(|system| system.launched_programs.clone())
+}
+
+#[cfg(test)]
+pub fn get_registered_keybindings() -> Vec<i32> {
+    with_system(|system| system.keybindings.clone())
+}
+
+#[derive(Error, Debug)]
+pub enum FakeError {
+    #[error("Window {0} doesn't exist")]
+    WindowNotFound(WindowId),
+    #[error("Fake return value is null")]
+    Null,
+}
+
+pub type FakeResult<T = ()> = Result<T, FakeError>;
+
+#[derive(Debug, Clone)]
+pub struct Window {
+    pub id: WindowId,
+    pub title: String,
+    pub maximized: bool,
+    pub rule: Option<Rule>,
+    pub style: GwlStyle,
+    pub exstyle: GwlExStyle,
+    pub original_style: GwlStyle,
+    pub original_rect: Rectangle,
+}
+
+impl PartialEq<i32> for Window {
+    fn eq(&self, other: &i32) -> bool {
+        self.id == *other
+    }
+}
+
+impl From<WindowId> for Window {
+    fn from(val: WindowId) -> Self {
+        let mut window = Window::new();
+        window.id = val;
+        window
+    }
+}
+
+impl Window {
+    pub fn is_hidden(&self) -> bool {
+        with_window(self.id, |w| !w.visible).unwrap_or(true)
+    }
+    pub fn is_visible(&self) -> bool {
+        !self.is_hidden()
+    }
+    pub fn should_manage(&self) -> bool {
+        match (self.get_style(), self.get_ex_style()) {
+            (Ok(style), Ok(ex_style)) => {
+                style.contains(GwlStyle::CAPTION) && !ex_style.contains(GwlExStyle::DLGMODALFRAME)
+            }
+            _ => false,
+        }
+    }
+    pub fn add_title_bar(&mut self) -> SystemResult {
+        let rule = self.rule.clone().unwrap_or_default();
+        if !rule.chromium && !rule.firefox {
+            self.style.insert(GwlStyle::CAPTION);
+            self.style.insert(GwlStyle::THICKFRAME);
+        }
+        self.update_style()
+            .map(|_| {})
+            .map_err(SystemError::Unknown)
+    }
+    pub fn add_border(&mut self) -> SystemResult {
+        self.style.insert(GwlStyle::BORDER);
+        self.update_style()
+            .map(|_| {})
+            .map_err(SystemError::Unknown)
+    }
+    pub fn remove_border(&mut self) -> SystemResult {
+        self.style.remove(GwlStyle::BORDER);
+        self.update_style()
+            .map(|_| {})
+            .map_err(SystemError::Unknown)
+    }
+    pub fn remove_title_bar(&mut self) -> SystemResult {
+        let rule = self.rule.clone().unwrap_or_default();
+        if !rule.chromium && !rule.firefox {
+            self.style.remove(GwlStyle::CAPTION);
+            self.style.remove(GwlStyle::THICKFRAME);
+        }
+        self.update_style()
+            .map(|_| {})
+            .map_err(SystemError::Unknown)
+    }
+    /// Returns the display that contains the center of the window
+    pub fn get_display(&self) -> FakeResult<Display> {
+        let rect = self.get_rect()?;
+        let x = rect.left + rect.width() / 2;
+        let y = rect.top + rect.height() / 2;
+
+        with_system(|system| {
+            system
+                .displays()
+                .iter()
+                .find(|d| {
+                    d.rect.left <= x && x < d.rect.right && d.rect.top <= y && y < d.rect.bottom
+                })
+                .map(|d| d.id)
+        })
+        .map(Display::new)
+        .ok_or(FakeError::Null)
+    }
+    pub fn get_foreground_window() -> SystemResult<Window> {
+        with_system(|system| system.foreground)
+            .map(Window::from)
+            .ok_or(SystemError::GetForegroundWindow(FakeError::Null))
+    }
+    pub fn get_class_name(&self) -> FakeResult<String> {
+        with_window(self.id, |w| w.class_name.clone())
+    }
+    pub fn get_parent_window(&self) -> FakeResult<WindowId> {
+        with_window(self.id, |w| w.parent)?.ok_or(FakeError::Null)
+    }
+    pub fn get_style(&self) -> FakeResult<GwlStyle> {
+        with_window(self.id, |w| w.style)
+    }
+    pub fn get_ex_style(&self) -> FakeResult<GwlExStyle> {
+        with_window(self.id, |w| w.exstyle)
+    }
+    pub fn get_title(&self) -> FakeResult<String> {
+        with_window(self.id, |w| w.title.clone())
+    }
+    pub fn get_rect(&self) -> FakeResult<Rectangle> {
+        with_window(self.id, |w| w.rect)
+    }
+    pub fn is_window(&self) -> bool {
+        with_window(self.id, |_| {}).is_ok()
+    }
+    pub fn reset_style(&mut self) {
+        self.style = self.original_style;
+    }
+    pub fn update_style(&self) -> FakeResult<i32> {
+        let style = self.style;
+        with_window(self.id, |w| std::mem::replace(&mut w.style, style).bits())
+    }
+    /// Makes the window translucent. An opacity of 1 makes it opaque again.
+    pub fn set_opacity(&mut self, opacity: f32) -> FakeResult {
+        with_window(self.id, |w| w.opacity = opacity.clamp(0.0, 1.0))
+    }
+    pub fn to_foreground(&self, topmost: bool) -> FakeResult {
+        with_window(self.id, |w| w.topmost |= topmost)
+    }
+    pub fn remove_topmost(&self) -> FakeResult {
+        with_window(self.id, |w| w.topmost = false)
+    }
+    /// The fake system has no z-order, so `order` and `flags` only exist to mirror the native
+    /// api.
+    pub fn set_window_pos(
+        &self,
+        rect: Rectangle,
+        _order: Option<WindowId>,
+        _flags: Option<u32>,
+    ) -> FakeResult {
+        with_window(self.id, |w| w.rect = rect)
+    }
+    fn reset_pos(&self) -> FakeResult {
+        self.set_window_pos(self.original_rect, None, None)
+    }
+    pub fn get_process_name(&self) -> String {
+        self.get_process_path()
+            .rsplit('\\')
+            .next()
+            .unwrap()
+            .to_string()
+    }
+    pub fn get_process_path(&self) -> String {
+        with_window(self.id, |w| w.process_path.clone()).unwrap_or_default()
+    }
+    pub fn new() -> Self {
+        Self {
+            id: 0.into(),
+            title: String::from(""),
+            maximized: false,
+            rule: None,
+            style: GwlStyle::default(),
+            exstyle: GwlExStyle::default(),
+            original_style: GwlStyle::default(),
+            original_rect: Rectangle::default(),
+        }
+    }
+    pub fn cleanup(&mut self) -> SystemResult {
+        self.reset_style();
+        self.update_style().map_err(SystemError::CleanupWindow)?;
+        self.reset_pos().map_err(SystemError::CleanupWindow)?;
+
+        let is_translucent = self.rule.as_ref().is_some_and(|r| r.opacity.is_some());
+        if is_translucent {
+            self.set_opacity(1.0).map_err(SystemError::CleanupWindow)?;
+        }
+
+        if self.maximized {
+            self.maximize()?;
+        }
+
+        Ok(())
+    }
+    pub fn show(&self) {
+        let _ = with_window(self.id, |w| w.visible = true);
+    }
+    pub fn hide(&self) {
+        let _ = with_window(self.id, |w| w.visible = false);
+    }
+    pub fn close(&self) -> SystemResult {
+        debug!("Closing fake window {}", self.id);
+        remove_window(self.id)
+            .map(|_| {})
+            .ok_or(SystemError::CloseWindow(FakeError::WindowNotFound(self.id)))
+    }
+    pub fn focus(&self) -> SystemResult {
+        with_window(self.id, |_| {}).map_err(SystemError::FocusWindow)?;
+        set_foreground_window(self.id);
+        Ok(())
+    }
+    pub fn redraw(&self) -> SystemResult {
+        Ok(())
+    }
+    pub fn init(&mut self, remove_title_bar: bool, use_border: bool) -> SystemResult {
+        self.original_style = self.get_style().map_err(SystemError::Init)?;
+        if self.original_style.contains(GwlStyle::MAXIMIZE) {
+            self.restore().map_err(SystemError::Init)?;
+            self.maximized = true;
+            self.original_style.remove(GwlStyle::MAXIMIZE);
+        }
+        self.style = self.original_style;
+        self.exstyle = self.get_ex_style().map_err(SystemError::Init)?;
+        self.original_rect = self.get_rect().map_err(SystemError::Init)?;
+
+        if remove_title_bar {
+            self.remove_title_bar()?;
+        }
+
+        if use_border && !self.rule.as_ref().is_some_and(|rule| rule.no_border) {
+            self.add_border()?;
+        }
+
+        Ok(())
+    }
+    pub fn set_matching_rule(&mut self, rules: Vec<&Rule>) {
+        if let Some(rule) = rule::find_match(rules, &WindowProperties::capture(self)) {
+            debug!("Rule({:?}) matched!", rule.matcher);
+            self.rule = Some(rule.clone());
+        }
+    }
+    fn restore(&self) -> FakeResult {
+        with_window(self.id, |w| {
+            w.style.remove(GwlStyle::MAXIMIZE);
+            w.maximized = false;
+            w.minimized = false;
+        })
+    }
+    pub fn minimize(&self) -> SystemResult {
+        with_window(self.id, |w| w.minimized = true).map_err(SystemError::MinimizeWindow)
+    }
+    pub fn maximize(&self) -> SystemResult {
+        with_window(self.id, |w| {
+            w.style.insert(GwlStyle::MAXIMIZE);
+            w.maximized = true;
+        })
+        .map_err(SystemError::MaximizeWindow)
+    }
+}
+
+#[cfg(test)]
+mod tests;
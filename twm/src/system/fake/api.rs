@@ -0,0 +1,119 @@
+use super::{
+    win_event_listener::WinEventListener, with_system, FakeError, FakePlatform, Window, BIN_NAME,
+};
+use crate::{
+    display::Display, keybindings::keybinding::Keybinding, system::DisplayId, system::Platform,
+    system::Rectangle, system::SystemError, system::SystemResult, system::WindowId,
+    task_bar::Taskbar,
+};
+use log::error;
+
+impl Platform for FakePlatform {
+    type Window = Window;
+    type WinEventListener = WinEventListener;
+    type Error = FakeError;
+
+    const BIN_NAME: &'static str = BIN_NAME;
+
+    fn get_last_error() -> String {
+        String::new()
+    }
+
+    fn print_last_error() {
+        error!("FAKE ERROR: {}", Self::get_last_error());
+    }
+
+    fn get_displays() -> Vec<Display> {
+        with_system(|system| {
+            system
+                .displays()
+                .iter()
+                .map(|d| d.id)
+                .collect::<Vec<DisplayId>>()
+        })
+        .into_iter()
+        .map(Display::new)
+        .collect()
+    }
+
+    fn get_display_dpi(id: DisplayId) -> u32 {
+        with_system(|system| {
+            system
+                .displays()
+                .iter()
+                .find(|d| d.id == id)
+                .map(|d| d.dpi)
+                .unwrap_or(96)
+        })
+    }
+
+    fn get_display_rect(id: DisplayId) -> Rectangle {
+        with_system(|system| {
+            system
+                .displays()
+                .iter()
+                .find(|d| d.id == id)
+                .map(|d| d.rect)
+                .unwrap_or_default()
+        })
+    }
+
+    /// The fake system doesn't have a taskbar
+    fn get_taskbars() -> Vec<Taskbar> {
+        Vec::new()
+    }
+
+    fn get_windows() -> Vec<Self::Window> {
+        with_system(|system| {
+            system
+                .windows
+                .iter()
+                .map(|(id, w)| {
+                    let mut window = Self::Window::from(WindowId(*id));
+                    window.title = w.title.clone();
+                    window
+                })
+                .collect()
+        })
+    }
+
+    fn add_launch_on_startup() {
+        with_system(|system| system.launch_on_startup = true);
+    }
+
+    fn remove_launch_on_startup() {
+        with_system(|system| system.launch_on_startup = false);
+    }
+
+    fn register_keybinding(kb: &Keybinding) -> SystemResult {
+        with_system(|system| {
+            if system.keybindings.contains(&kb.get_id()) {
+                Err(SystemError::RegisterKeybinding(format!("{:?}", kb)))
+            } else {
+                system.keybindings.push(kb.get_id());
+                Ok(())
+            }
+        })
+    }
+
+    fn unregister_keybinding(kb: &Keybinding) -> SystemResult {
+        with_system(|system| {
+            let len = system.keybindings.len();
+            system.keybindings.retain(|id| *id != kb.get_id());
+
+            if system.keybindings.len() == len {
+                Err(SystemError::UnregisterKeybinding {
+                    key: format!("{:?}", kb),
+                    os_error: String::from("Hot key is not registered"),
+                })
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    fn launch_program(cmd: String) -> SystemResult {
+        with_system(|system| system.launched_programs.push(cmd));
+        Ok(())
+    }
+}
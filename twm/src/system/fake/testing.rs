@@ -0,0 +1,32 @@
+//! Helpers for the tests that simulate windows in the fake system
+use super::{add_window, reset, FakeWindow};
+use crate::{
+    event_handler, system::WindowId, win_event_handler::win_event::WinEvent,
+    win_event_handler::win_event_type::WinEventType, AppState,
+};
+
+/// Resets the fake system and returns a state that has the first workspace focused
+pub fn create_state() -> AppState {
+    reset();
+    let mut state = AppState::default();
+    state.change_workspace(1, false).unwrap();
+    state
+}
+
+pub fn send_event(state: &mut AppState, typ: WinEventType, id: WindowId) {
+    event_handler::winevent::handle(
+        state,
+        WinEvent {
+            typ,
+            window: id.into(),
+        },
+    )
+    .unwrap();
+}
+
+/// Adds the window to the fake system and lets nog know that it appeared
+pub fn show_window(state: &mut AppState, window: FakeWindow) -> WindowId {
+    let id = add_window(window);
+    send_event(state, WinEventType::Show(false), id);
+    id
+}
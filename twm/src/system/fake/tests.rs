@@ -0,0 +1,101 @@
+use super::{
+    testing::{create_state, send_event, show_window},
+    FakeWindow,
+};
+use crate::{
+    system::api, system::fake, system::Rectangle, system::WindowId,
+    win_event_handler::win_event_type::WinEventType, window::gwl_style::GwlStyle, AppState,
+};
+
+fn is_managed(state: &AppState, id: WindowId) -> bool {
+    state.get_grids().iter().any(|g| g.contains(id))
+}
+
+#[test]
+fn show_manages_window_and_fills_working_area() {
+    let mut state = create_state();
+    let id = show_window(&mut state, FakeWindow::new("Terminal", "term.exe"));
+    let bar_height = state.config.bar.height;
+
+    assert!(is_managed(&state, id));
+
+    let window = fake::get_window(id).unwrap();
+    assert_eq!(window.rect.left, 0);
+    assert_eq!(window.rect.top, bar_height);
+    assert_eq!(window.rect.right, 1920);
+    assert_eq!(window.rect.bottom, 1080);
+    assert!(!window.style.contains(GwlStyle::CAPTION));
+}
+
+#[test]
+fn show_ignores_small_windows() {
+    let mut state = create_state();
+    let id = show_window(
+        &mut state,
+        FakeWindow::new("Tooltip", "term.exe").with_rect(Rectangle {
+            left: 0,
+            right: 100,
+            top: 0,
+            bottom: 100,
+        }),
+    );
+
+    assert!(!is_managed(&state, id));
+}
+
+#[test]
+fn show_ignores_windows_without_a_caption() {
+    let mut state = create_state();
+    let id = show_window(
+        &mut state,
+        FakeWindow::new("Splash", "term.exe").with_style(GwlStyle::POPUP | GwlStyle::VISIBLE),
+    );
+
+    assert!(!is_managed(&state, id));
+}
+
+#[test]
+fn second_window_splits_the_grid() {
+    let mut state = create_state();
+    let first = show_window(&mut state, FakeWindow::new("First", "term.exe"));
+    let second = show_window(&mut state, FakeWindow::new("Second", "term.exe"));
+
+    let first = fake::get_window(first).unwrap();
+    let second = fake::get_window(second).unwrap();
+
+    assert_eq!(first.rect.left, 0);
+    assert_eq!(first.rect.right, second.rect.left);
+    assert_eq!(second.rect.right, 1920);
+}
+
+#[test]
+fn change_workspace_hides_and_shows_windows() {
+    let mut state = create_state();
+    let id = show_window(&mut state, FakeWindow::new("Terminal", "term.exe"));
+
+    state.change_workspace(2, false).unwrap();
+    assert!(!fake::get_window(id).unwrap().visible);
+
+    state.change_workspace(1, false).unwrap();
+    assert!(fake::get_window(id).unwrap().visible);
+}
+
+#[test]
+fn destroy_unmanages_window() {
+    let mut state = create_state();
+    let id = show_window(&mut state, FakeWindow::new("Terminal", "term.exe"));
+
+    fake::remove_window(id);
+    send_event(&mut state, WinEventType::Destroy, id);
+
+    assert!(!is_managed(&state, id));
+}
+
+#[test]
+fn launch_program_records_the_command() {
+    fake::reset();
+
+    api::launch_program("notepad.exe notes.txt".into()).unwrap();
+
+    assert_eq!(fake::get_launched_programs(), vec!["notepad.exe notes.txt"]);
+}
@@ -0,0 +1,17 @@
+use crate::event::EventChannel;
+use log::debug;
+
+/// The fake system never emits events on its own. Tests send the `WinEvent`s they want to
+/// simulate directly.
+#[derive(Debug, Clone, Default)]
+pub struct WinEventListener;
+
+impl WinEventListener {
+    pub fn start(&self, _channel: &EventChannel) {
+        debug!("Registering fake win event hook");
+    }
+
+    pub fn stop(&self) {
+        debug!("Unregistering fake win event hook");
+    }
+}
@@ -1,15 +1,48 @@
+use crate::{display::Display, keybindings::keybinding::Keybinding, task_bar::Taskbar};
 use thiserror::Error;
 
+pub mod api;
+#[cfg(feature = "headless")]
+pub mod fake;
+#[cfg(not(feature = "headless"))]
 pub mod win;
 
-pub use win::api;
-pub use win::win_event_listener::WinEventListener;
-pub use win::Window as NativeWindow;
-pub use win::BIN_NAME;
+/// Everything nog needs from the operating system. Each backend implements this once and the
+/// rest of nog only talks to the backend it got compiled for through [`NativePlatform`].
+pub trait Platform {
+    type Window;
+    type WinEventListener;
+    type Error: std::error::Error + 'static;
 
-pub type SpecificError = win::WinError;
+    /// The file name of the nog executable
+    const BIN_NAME: &'static str;
 
-#[derive(Debug, Default, PartialEq, Clone, Copy)]
+    fn get_last_error() -> String;
+    fn print_last_error();
+    fn get_displays() -> Vec<Display>;
+    fn get_display_dpi(id: DisplayId) -> u32;
+    fn get_display_rect(id: DisplayId) -> Rectangle;
+    fn get_taskbars() -> Vec<Taskbar>;
+    /// Returns every window, including hidden ones
+    fn get_windows() -> Vec<Self::Window>;
+    fn add_launch_on_startup();
+    fn remove_launch_on_startup();
+    fn register_keybinding(kb: &Keybinding) -> SystemResult;
+    fn unregister_keybinding(kb: &Keybinding) -> SystemResult;
+    fn launch_program(cmd: String) -> SystemResult;
+}
+
+#[cfg(feature = "headless")]
+pub type NativePlatform = fake::FakePlatform;
+#[cfg(not(feature = "headless"))]
+pub type NativePlatform = win::WinPlatform;
+
+pub type NativeWindow = <NativePlatform as Platform>::Window;
+pub type WinEventListener = <NativePlatform as Platform>::WinEventListener;
+pub type SpecificError = <NativePlatform as Platform>::Error;
+pub const BIN_NAME: &str = NativePlatform::BIN_NAME;
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct WindowId(pub i32);
 
 impl std::fmt::Display for WindowId {
@@ -45,7 +78,7 @@ impl PartialEq<i32> for DisplayId {
     }
 }
 
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
 pub struct Rectangle {
     pub left: i32,
     pub right: i32,
@@ -60,6 +93,21 @@ impl Rectangle {
     pub fn height(&self) -> i32 {
         self.bottom - self.top
     }
+    /// Returns a rectangle of the same size that is centered in the area. The rectangle gets
+    /// shrunk if it doesn't fit into the area.
+    pub fn centered_in(&self, area: &Rectangle) -> Rectangle {
+        let width = self.width().min(area.width());
+        let height = self.height().min(area.height());
+        let left = area.left + (area.width() - width) / 2;
+        let top = area.top + (area.height() - height) / 2;
+
+        Rectangle {
+            left,
+            top,
+            right: left + width,
+            bottom: top + height,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -82,6 +130,10 @@ pub enum SystemError {
     MinimizeWindow(SpecificError),
     #[error("Failed to maximize window")]
     MaximizeWindow(SpecificError),
+    #[error("Failed to move window")]
+    MoveWindow(SpecificError),
+    #[error("Failed to change the opacity of window")]
+    SetOpacity(SpecificError),
     #[error("Failed to draw tile")]
     DrawTile(SpecificError),
     #[error("Failed to get foreground window")]
@@ -92,6 +144,12 @@ pub enum SystemError {
     RegisterKeybinding(String),
     #[error("Failed to unregister keybinding")]
     UnregisterKeybinding { key: String, os_error: String },
+    #[error("The plugin declaration is invalid: {0}")]
+    InvalidPlugin(String),
+    #[error("Failed to sync the plugin {name}: {reason}")]
+    Plugin { name: String, reason: String },
+    #[error("Failed to access plugins.lock: {0}")]
+    PluginLock(String),
     #[error("An error that is specific to the platform occured")]
     Native(#[from] SpecificError),
     #[error("An unknown error occured")]
@@ -1,4 +1,5 @@
 use super::{DisplayId, Rectangle, SystemError, SystemResult, WindowId};
+use crate::config::rule::{self, WindowProperties};
 use crate::{
     display::Display, util, window::gwl_ex_style::GwlExStyle, window::gwl_style::GwlStyle, Rule,
 };
@@ -13,7 +14,10 @@ pub mod api;
 pub mod menu;
 pub mod win_event_listener;
 
-pub const BIN_NAME: &'static str = "nog.exe";
+pub const BIN_NAME: &str = "nog.exe";
+
+/// The [`Platform`](super::Platform) of windows
+pub struct WinPlatform;
 
 impl From<HWND> for WindowId {
     fn from(val: HWND) -> Self {
@@ -51,12 +55,6 @@ impl From<HMONITOR> for Display {
     }
 }
 
-impl PartialEq<i32> for Display {
-    fn eq(&self, other: &i32) -> bool {
-        self.id == *other
-    }
-}
-
 #[derive(Error, Debug)]
 pub enum WinError {
     #[error("Winapi return value is null")]
@@ -257,6 +255,19 @@ impl Window {
             nullable_to_result::<i32>(SetWindowLongA(self.id.into(), GWL_STYLE, self.style.bits()))
         }
     }
+    /// Makes the window translucent. An opacity of 1 makes it opaque again.
+    pub fn set_opacity(&mut self, opacity: f32) -> WinResult {
+        self.exstyle.insert(GwlExStyle::LAYERED);
+        unsafe {
+            SetWindowLongA(self.id.into(), GWL_EXSTYLE, self.exstyle.bits());
+            bool_to_result(SetLayeredWindowAttributes(
+                self.id.into(),
+                0,
+                (opacity.max(0.0).min(1.0) * 255.0) as u8,
+                LWA_ALPHA,
+            ))
+        }
+    }
     /// This could error if the window is already in the foreground
     pub fn to_foreground(&self, topmost: bool) -> WinResult {
         self.set_window_pos(
@@ -346,6 +357,11 @@ impl Window {
         self.update_style().map_err(SystemError::CleanupWindow)?;
         self.reset_pos().map_err(SystemError::CleanupWindow)?;
 
+        let is_translucent = self.rule.as_ref().is_some_and(|r| r.opacity.is_some());
+        if is_translucent {
+            self.set_opacity(1.0).map_err(SystemError::CleanupWindow)?;
+        }
+
         if self.maximized {
             self.maximize()?;
         }
@@ -403,28 +419,16 @@ impl Window {
             self.remove_title_bar()?;
         }
 
-        if use_border {
+        if use_border && !self.rule.as_ref().is_some_and(|rule| rule.no_border) {
             self.add_border()?;
         }
 
         Ok(())
     }
     pub fn set_matching_rule(&mut self, rules: Vec<&Rule>) {
-        for rule in rules {
-            // checks for path
-            let process_name = if rule.pattern.to_string().contains('\\') {
-                self.get_process_path()
-            } else {
-                self.get_process_name()
-            };
-
-            let window_name = self.title.clone();
-
-            if rule.pattern.is_match(&process_name) || rule.pattern.is_match(&window_name) {
-                debug!("Rule({:?}) matched!", rule.pattern);
-                self.rule = Some(rule.clone());
-                break;
-            }
+        if let Some(rule) = rule::find_match(rules, &WindowProperties::capture(self)) {
+            debug!("Rule({:?}) matched!", rule.matcher);
+            self.rule = Some(rule.clone());
         }
     }
     fn restore(&self) -> WinResult {
@@ -9,21 +9,30 @@ use crate::{
     system::SystemResult,
     system::WindowId,
     tile_grid::{
-        graph_wrapper::GraphWrapper, node::Node, node::NodeInfo, text_renderer::TextRenderer,
-        tile_render_info::TileRenderInfo,
+        floating::FloatingWindow, graph_wrapper::GraphWrapper, layout::Area,
+        layout::CustomArrangement, layout::Layout, layout::LayoutTile, node::Node, node::NodeInfo,
+        parser::ParseError, text_renderer::TextRenderer,
+        tile_render_info::TileRenderInfo, tree::GridTree,
     },
 };
 use log::{debug, error, info};
 use std::cmp;
 
+pub mod floating;
 pub mod graph_wrapper;
+pub mod layout;
 pub mod node;
-pub mod store;
+pub mod parser;
+pub mod snapshot;
+pub mod stack;
 pub mod text_renderer;
 pub mod tile_render_info;
+pub mod tree;
 
 static FULL_SIZE: u32 = 120;
 static HALF_SIZE: u32 = FULL_SIZE / 2;
+/// Size of the area used for finding neighbors in a layout that isn't manual
+static LAYOUT_SIZE: u32 = 10_000;
 
 #[derive(Clone, Debug)]
 pub struct TileGrid<TRenderer: Renderer = NativeRenderer> {
@@ -37,6 +46,11 @@ pub struct TileGrid<TRenderer: Renderer = NativeRenderer> {
     //       pushing a tile "above" a focused tile in a column or "before" a focused tile in a row
     //       as opposed to the current way where it always adds below/after
     pub next_direction: Direction,
+    pub layout: Layout,
+    pub custom_arrangement: Option<CustomArrangement>,
+    /// Windows of this workspace that got toggled to floating. They aren't part of the tree, but
+    /// get hidden and shown together with the tiles.
+    pub floating_windows: Vec<FloatingWindow>,
     graph: GraphWrapper,
 }
 
@@ -52,26 +66,30 @@ impl TileGrid {
         let render_infos = self.get_render_info(64, 20);
         debug!("{}", TextRenderer::render(64, 20, render_infos));
 
-        let (padding, margin) = (
-            if config.inner_gap > 0 {
-                config.inner_gap / 2
-            } else {
-                0
-            },
-            if config.outer_gap > 0 {
-                config.outer_gap
-            } else {
-                0
-            },
-        );
+        let padding = if config.inner_gap > 0 {
+            config.inner_gap / 2
+        } else {
+            0
+        };
 
-        let display_width = display.working_area_width(config) - margin;
-        let display_height = display.working_area_height(config) - margin;
-        let display_left = display.working_area_left() + (margin / 2);
-        let display_top = display.working_area_top(config) + (margin / 2);
+        let tiling_area = display.tiling_area(config);
+        let display_width = tiling_area.width();
+        let display_height = tiling_area.height();
+        let display_left = tiling_area.left;
+        let display_top = tiling_area.top;
 
         let render_infos = self.get_render_info(display_width as u32, display_height as u32);
 
+        // the visible tile of a stack changes without the grid getting hidden and shown
+        for (node_id, visible) in self.get_stacked_tiles() {
+            let window = self.graph.node(node_id).get_window();
+            if visible {
+                window.show();
+            } else {
+                window.hide();
+            }
+        }
+
         info!("Beginning Rendering");
         for render_info in render_infos {
             let left_padding = if render_info.x != 0 { padding } else { 0 };
@@ -107,6 +125,25 @@ impl TileGrid {
 
         Ok(())
     }
+}
+
+impl<TRenderer: Renderer> TileGrid<TRenderer> {
+    pub fn new(id: i32, renderer: TRenderer) -> TileGrid<TRenderer> {
+        Self {
+            id,
+            // display: get_primary_display(),
+            renderer,
+            taskbar_window: 0,
+            graph: GraphWrapper::new(),
+            fullscreen_id: None,
+            focused_id: None,
+            next_axis: SplitDirection::Vertical,
+            next_direction: Direction::Right,
+            layout: Layout::default(),
+            custom_arrangement: None,
+            floating_windows: Vec::new(),
+        }
+    }
     /// Returns a list of render information for each tile in the graph
     /// inner/outer padding should be handled outside of the tile grid by reducing the
     /// width/height by the outer padding and trimming off between tiles with the inner padding.
@@ -129,12 +166,166 @@ impl TileGrid {
                 }
                 _ => (),
             }
+        } else if !self.layout.is_manual() {
+            render_infos = self.populate_layout_render_info(width, height);
         } else if let Some(root_id) = self.graph.get_root() {
             render_infos = self.populate_render_info(render_infos, root_id, 0, width, 0, height);
         }
 
         render_infos
     }
+    /// Returns the render information of each tile arranged by a layout that isn't manual.
+    /// The focused tile gets rendered last, so it ends up on top of overlapping tiles.
+    fn populate_layout_render_info(&self, width: u32, height: u32) -> Vec<TileRenderInfo> {
+        let mut render_infos = self
+            .get_layout_areas(width, height)
+            .into_iter()
+            .map(|(id, area)| {
+                let (order, size) = self.graph.node(id).get_info();
+                TileRenderInfo {
+                    window: self.graph.node(id).get_window().clone(),
+                    x: area.x,
+                    y: area.y,
+                    height: area.height,
+                    width: area.width,
+                    debug_id: id,
+                    debug_size: size,
+                    debug_order: order,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(idx) = render_infos
+            .iter()
+            .position(|info| Some(info.debug_id) == self.focused_id)
+        {
+            let focused = render_infos.remove(idx);
+            render_infos.push(focused);
+        }
+
+        render_infos
+    }
+    /// Returns the area of each tile that gets placed by the layout. Tiles that don't have an area
+    /// in the current arrangement of a custom layout are left out.
+    fn get_layout_areas(&self, width: u32, height: u32) -> Vec<(usize, Area)> {
+        let tiles = self.get_ordered_tiles();
+
+        match &self.layout {
+            Layout::Custom(name) => self
+                .custom_arrangement
+                .as_ref()
+                .filter(|arrangement| &arrangement.layout == name)
+                .map(|arrangement| {
+                    tiles
+                        .into_iter()
+                        .filter_map(|id| {
+                            let window_id = self.graph.node(id).get_window().id;
+                            arrangement
+                                .get_area(window_id, width, height)
+                                .map(|area| (id, area))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            layout => {
+                let areas = layout
+                    .arrange(tiles.len(), Area::new(0, 0, width, height))
+                    .unwrap_or_default();
+
+                tiles.into_iter().zip(areas).collect()
+            }
+        }
+    }
+    /// Returns the information a custom layout needs about each tile in order
+    pub fn get_layout_tiles(&self) -> Vec<LayoutTile> {
+        self.get_ordered_tiles()
+            .into_iter()
+            .map(|id| {
+                let window = self.graph.node(id).get_window();
+                LayoutTile {
+                    window_id: window.id,
+                    title: window.get_title().unwrap_or_default(),
+                    focused: self.focused_id == Some(id),
+                }
+            })
+            .collect()
+    }
+    /// Returns the ids of every tile in the order they appear in the tree (left to right, top to bottom)
+    fn get_ordered_tiles(&self) -> Vec<usize> {
+        let mut tiles = Vec::new();
+        let mut stack: Vec<usize> = self.graph.get_root().into_iter().collect();
+
+        while let Some(id) = stack.pop() {
+            match self.graph.node(id) {
+                Node::Tile(_) => tiles.push(id),
+                // the hidden tiles of a stack don't get arranged
+                Node::Stack(_) => stack.extend(self.graph.get_active_child(id)),
+                _ => stack.extend(self.graph.get_sorted_children(id).into_iter().rev()),
+            }
+        }
+
+        tiles
+    }
+    /// Returns the closest tile in the given direction based on the areas of the current layout.
+    /// Tiles of the monocle layout all overlap, so the previous/next tile gets returned instead.
+    fn get_neighbor_in_layout(&self, node_id: usize, direction: Direction) -> Option<usize> {
+        let tiles = self.get_ordered_tiles();
+        let idx = tiles.iter().position(|id| *id == node_id)?;
+
+        if self.layout == Layout::Monocle {
+            return match direction {
+                Direction::Left | Direction::Up => idx.checked_sub(1),
+                Direction::Right | Direction::Down => Some(idx + 1),
+            }
+            .and_then(|idx| tiles.get(idx).copied());
+        }
+
+        let areas = self.get_layout_areas(LAYOUT_SIZE, LAYOUT_SIZE);
+        let current = areas.iter().find(|(id, _)| *id == node_id)?.1;
+        let overlaps_horizontally =
+            |a: &Area| a.x < current.x + current.width && current.x < a.x + a.width;
+        let overlaps_vertically =
+            |a: &Area| a.y < current.y + current.height && current.y < a.y + a.height;
+
+        areas
+            .iter()
+            .filter(|(id, _)| *id != node_id)
+            .filter_map(|(id, area)| {
+                // (distance in the direction, offset on the other axis)
+                let distance = match direction {
+                    Direction::Left if overlaps_vertically(area) => {
+                        current.x.checked_sub(area.x + area.width)
+                    }
+                    Direction::Right if overlaps_vertically(area) => {
+                        area.x.checked_sub(current.x + current.width)
+                    }
+                    Direction::Up if overlaps_horizontally(area) => {
+                        current.y.checked_sub(area.y + area.height)
+                    }
+                    Direction::Down if overlaps_horizontally(area) => {
+                        area.y.checked_sub(current.y + current.height)
+                    }
+                    _ => None,
+                }?;
+                let offset = match direction {
+                    Direction::Left | Direction::Right => (area.y as i64 - current.y as i64).abs(),
+                    Direction::Up | Direction::Down => (area.x as i64 - current.x as i64).abs(),
+                };
+
+                Some(((distance, offset), *id))
+            })
+            .min_by_key(|(key, _)| *key)
+            .map(|(_, id)| id)
+    }
+    /// Exchanges the windows of the two tiles
+    fn swap_windows(&mut self, first: usize, second: usize) {
+        let first_window = self.graph.node(first).get_window().clone();
+        let second_window = std::mem::replace(
+            self.graph.node_mut(second).get_window_mut(),
+            first_window,
+        );
+        *self.graph.node_mut(first).get_window_mut() = second_window;
+    }
     /// A recursive function that walks the graph and populates the supplied vec with rendering information
     /// for each node based on the given resolution.
     fn populate_render_info(
@@ -245,26 +436,22 @@ impl TileGrid {
                     count += 1;
                 }
             }
+            Node::Stack(_) => {
+                if let Some(child) = self.graph.get_active_child(current_node_id) {
+                    render_infos = self.populate_render_info(
+                        render_infos,
+                        child,
+                        min_x,
+                        max_x,
+                        min_y,
+                        max_y,
+                    );
+                }
+            }
         }
 
         render_infos
     }
-}
-
-impl<TRenderer: Renderer> TileGrid<TRenderer> {
-    pub fn new(id: i32, renderer: TRenderer) -> TileGrid<TRenderer> {
-        Self {
-            id,
-            // display: get_primary_display(),
-            renderer,
-            taskbar_window: 0,
-            graph: GraphWrapper::new(),
-            fullscreen_id: None,
-            focused_id: None,
-            next_axis: SplitDirection::Vertical,
-            next_direction: Direction::Right,
-        }
-    }
     /// Returns whether the tile grid is populated or not
     pub fn is_empty(&self) -> bool {
         self.graph.is_empty()
@@ -273,13 +460,18 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
     pub fn is_fullscreened(&self) -> bool {
         self.fullscreen_id.is_some()
     }
-    /// Iterates and hides every window managed by the current tile grid
+    /// Iterates and hides every window managed by the current tile grid, including the floating
+    /// ones
     pub fn hide(&self) {
         for node_id in self.graph.nodes() {
             if self.graph.node(node_id).is_tile() {
                 self.graph.node(node_id).get_window().hide();
             }
         }
+
+        for floating in &self.floating_windows {
+            floating.window.hide();
+        }
     }
     /// Removes the focused node, if it exists, and returns the window on that node.
     /// Leaves the tile_grid in an unfocused state and un-fullscreens if currently fullscreened.
@@ -290,8 +482,15 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
 
         removed_node.map(|x| x.take_window())
     }
-    /// Calls cleanup on all managed windows and clears the tile_grid
+    /// Calls cleanup on all managed windows and clears the tile_grid. The hidden tiles of stacks
+    /// get shown, because nothing would show them anymore.
     pub fn cleanup(&mut self) -> SystemResult {
+        for (node_id, visible) in self.get_stacked_tiles() {
+            if !visible {
+                self.graph.node(node_id).get_window().show();
+            }
+        }
+
         self.modify_windows(|window| window.cleanup())?;
         self.graph.clear();
         self.focused_id = None;
@@ -350,8 +549,15 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
                 .set_size(size_per_child + get_remainder_slice());
         }
     }
-    /// Iterates and shows every window managed by the current tile_grid
+    /// Iterates and shows every window managed by the current tile_grid, including the floating
+    /// ones. The hidden tiles of stacks stay hidden.
     pub fn show(&self) -> SystemResult {
+        let hidden = self
+            .get_stacked_tiles()
+            .into_iter()
+            .filter(|(_, visible)| !visible)
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
         let mut nodes = self.graph.nodes().collect::<Vec<usize>>();
         if self.fullscreen_id.is_some() {
             nodes.sort_by_key(|n| {
@@ -363,7 +569,7 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             });
         }
         for node_id in nodes {
-            if self.graph.node(node_id).is_tile() {
+            if self.graph.node(node_id).is_tile() && !hidden.contains(&node_id) {
                 let window = self.graph.node(node_id).get_window();
                 window.show();
                 window
@@ -375,6 +581,18 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             }
         }
 
+        // the floating windows get shown last to keep them above the tiles
+        for floating in &self.floating_windows {
+            floating.window.show();
+            floating
+                .window
+                .to_foreground(true)
+                .map_err(SystemError::ShowWindow)?;
+            if let Err(e) = floating.window.remove_topmost() {
+                error!("{}", e);
+            }
+        }
+
         if let Some(focused_id) = self.focused_id {
             match self.graph.node(focused_id).get_window().focus() {
                 Err(_) => info!("Failed focusing window in node {}", focused_id),
@@ -431,9 +649,16 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             .collect()
     }
     /// Swaps position of the focused tile with the tile in the supplied direction. See swap for more details on behavior.
+    /// In a layout that isn't manual the window of the focused tile gets swapped with the window of the
+    /// neighboring tile instead and the focus follows the window.
     pub fn swap_focused(&mut self, direction: Direction) {
         if let Some(focused_id) = self.focused_id {
-            self.swap(focused_id, direction);
+            if self.layout.is_manual() {
+                self.swap(focused_id, direction);
+            } else if let Some(neighbor_id) = self.get_neighbor_in_layout(focused_id, direction) {
+                self.swap_windows(focused_id, neighbor_id);
+                self.focused_id = Some(neighbor_id);
+            }
         }
     }
     /// Swaps position of the given tile with the tile in the supplied direction. No-op if grid has no focused tile.
@@ -484,36 +709,21 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             return Ok(());
         }
 
-        let parent_id = self.graph.map_to_parent(self.focused_id);
-        if let Some(mut parent_id) = parent_id {
-            let mut target_focus: Option<usize> = None;
-            let mut current_focus = self.focused_id.unwrap();
-            while !target_focus.is_some() {
-                let children = self.graph.get_children(parent_id).len();
-                let focused_order = self.graph.node(current_focus).get_order();
-
-                let should_focus_sibling = match (&direction, self.graph.node(parent_id)) {
-                    (Direction::Left, Node::Column(_)) | (Direction::Up, Node::Row(_)) => {
-                        focused_order > 0 && children > 1
-                    }
-                    (Direction::Right, Node::Column(_)) | (Direction::Down, Node::Row(_)) => {
-                        focused_order < (children - 1) as u32
-                    }
-                    _ => false,
-                };
-
-                if should_focus_sibling {
-                    target_focus = self.graph.get_neighbor(current_focus, direction);
-                } else if let Some(p_id) = self.graph.map_to_parent(Some(parent_id)) {
-                    // focus on parent and iterate again to find a tile in chosen direction
-                    current_focus = parent_id;
-                    parent_id = p_id;
-                } else {
-                    // no parent, can't move in direction
-                    target_focus = self.focused_id;
-                }
+        if !self.layout.is_manual() {
+            let focused_id = self.focused_id.unwrap();
+            if let Some(neighbor_id) = self.get_neighbor_in_layout(focused_id, direction) {
+                self.focused_id = Some(neighbor_id);
+                self.graph.node(neighbor_id).get_window().focus()?;
             }
 
+            return Ok(());
+        }
+
+        let focused_id = self.focused_id.unwrap();
+        if self.graph.map_to_parent(Some(focused_id)).is_some() {
+            // without a neighbor the focus can't move in the direction
+            let target_focus = self.find_neighbor(focused_id, direction).or(Some(focused_id));
+
             self.focused_id = self.graph.to_closest_tile(target_focus, Some(direction));
             self.graph
                 .node(self.focused_id.unwrap())
@@ -527,15 +737,61 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
 
         Ok(())
     }
+    /// Travels up the graph from the given node until it finds a parent that has a sibling of the
+    /// node (or of one of its parents) in the given direction and returns the sibling.
+    fn find_neighbor(&self, node_id: usize, direction: Direction) -> Option<usize> {
+        let mut current_id = node_id;
+
+        while let Some(parent_id) = self.graph.map_to_parent(Some(current_id)) {
+            let children = self.graph.get_children(parent_id).len();
+            let order = self.graph.node(current_id).get_order();
+
+            let has_sibling = match (&direction, self.graph.node(parent_id)) {
+                (Direction::Left, Node::Column(_)) | (Direction::Up, Node::Row(_)) => {
+                    order > 0 && children > 1
+                }
+                (Direction::Right, Node::Column(_)) | (Direction::Down, Node::Row(_)) => {
+                    order < (children - 1) as u32
+                }
+                _ => false,
+            };
+
+            if has_sibling {
+                return self.graph.get_neighbor(current_id, direction);
+            }
+
+            // iterate again with the parent to find a node in the chosen direction
+            current_id = parent_id;
+        }
+
+        None
+    }
     /// Resets the order of all child nodes by sorting them and then "re-indexing" their order starting at 0
+    ///
+    /// A stack keeps showing the same tile or the one after it, if the visible tile got removed.
     fn reset_order(&mut self, parent_id: usize) {
         let nodes = self.graph.get_sorted_children(parent_id);
+        let new_active = match self.graph.node(parent_id) {
+            Node::Stack((_, active)) => Some(
+                nodes
+                    .iter()
+                    .position(|id| self.graph.node(*id).get_order() >= *active)
+                    .unwrap_or(nodes.len().saturating_sub(1)) as u32,
+            ),
+            _ => None,
+        };
 
         let mut order = 0;
         for node in nodes {
             self.graph.node_mut(node).set_order(order);
             order += 1;
         }
+
+        if let (Some(new_active), Node::Stack((_, active))) =
+            (new_active, self.graph.node_mut(parent_id))
+        {
+            *active = new_active;
+        }
     }
     /// Removes and returns the node of the given node_id. The behavior of the removal falls into one of three cases:
     /// Case One: If the graph only has one node and it's the given node, then the graph is emptied.
@@ -667,6 +923,7 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
     /// See get_last_tile for more information.
     pub fn focus_last_tile(self: &mut Self) {
         self.focused_id = self.get_last_tile();
+        self.activate_tab(self.focused_id);
     }
     /// Returns the an Option NodeID (usize) of the last Tile in the tile grid.
     /// This is somewhat arbitrary as it won't necessarily be the last node added to
@@ -686,6 +943,7 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
         });
         if maybe_window_tile.is_some() {
             self.focused_id = maybe_window_tile;
+            self.activate_tab(maybe_window_tile);
         }
     }
     /// Creates a node from the given window and adds it to the graph if the grid doesn't already contain the window.
@@ -741,9 +999,8 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
                         // this determines how the node should be added
                         let operation = match (self.graph.node(parent_id), self.next_axis) {
                             (Node::Column(_), SplitDirection::Vertical)
-                            | (Node::Row(_), SplitDirection::Horizontal) => {
-                                PushOperation::AppendToParent
-                            }
+                            | (Node::Row(_), SplitDirection::Horizontal)
+                            | (Node::Stack(_), _) => PushOperation::AppendToParent,
                             (Node::Column(_), _) => PushOperation::SwapAndAppend(Node::row),
                             (Node::Row(_), _) => PushOperation::SwapAndAppend(Node::column),
                             _ => {
@@ -763,6 +1020,8 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
                                     .set_info(new_node_order, self.make_space_for_node(parent_id));
                                 self.shift_order(parent_id, new_node_order);
                                 self.focused_id = Some(self.graph.add_child(parent_id, new_node));
+                                // a new tile of a stack is the visible one
+                                self.activate_tab(self.focused_id);
                             }
                             PushOperation::SwapAndAppend(create_node) => {
                                 // parent is opposite type of what we want to add
@@ -817,6 +1076,79 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             self.graph.node_mut(*node).set_order(shift_point);
         }
     }
+    /// Connects the node, which isn't part of the tree yet, next to the sibling along the axis. The
+    /// node becomes a child of the sibling if the sibling itself is a container of that axis. A new
+    /// container takes the place of the sibling if neither the sibling nor its parent fit the axis.
+    /// Tiles of a stack can't have a neighbor, so the node gets placed next to the stack instead.
+    fn insert_beside(
+        &mut self,
+        sibling_id: usize,
+        node_id: usize,
+        axis: SplitDirection,
+        after: bool,
+    ) {
+        let matches_axis = |node: &Node| match axis {
+            SplitDirection::Vertical => node.is_column(),
+            SplitDirection::Horizontal => node.is_row(),
+        };
+
+        let sibling_id = self
+            .graph
+            .map_to_parent(Some(sibling_id))
+            .filter(|id| self.graph.node(*id).is_stack())
+            .unwrap_or(sibling_id);
+        let parent_id = self.graph.map_to_parent(Some(sibling_id));
+        let (sibling_order, sibling_size) = self.graph.node(sibling_id).get_info();
+
+        let target = if matches_axis(self.graph.node(sibling_id)) {
+            let order = if after {
+                self.graph.get_children(sibling_id).len() as u32
+            } else {
+                0
+            };
+            Some((sibling_id, order))
+        } else {
+            parent_id
+                .filter(|id| matches_axis(self.graph.node(*id)))
+                .map(|id| (id, sibling_order + if after { 1 } else { 0 }))
+        };
+
+        match target {
+            Some((parent_id, order)) => {
+                let size = self.make_space_for_node(parent_id);
+                self.graph.node_mut(node_id).set_info(order, size);
+                self.shift_order(parent_id, order);
+                self.graph.connect(parent_id, node_id);
+            }
+            None => {
+                // the parent is gone, so a new one takes the place of the sibling
+                let (order, size) = match parent_id {
+                    Some(_) => (sibling_order, sibling_size),
+                    None => (0, FULL_SIZE),
+                };
+                let container = match axis {
+                    SplitDirection::Vertical => Node::column(order, size),
+                    SplitDirection::Horizontal => Node::row(order, size),
+                };
+                let container_id = self.graph.add_node(container);
+
+                if let Some(parent_id) = parent_id {
+                    self.graph.disconnect(parent_id, sibling_id);
+                    self.graph.connect(parent_id, container_id);
+                }
+                self.graph.connect(container_id, sibling_id);
+
+                let (sibling_order, new_order) = if after { (0, 1) } else { (1, 0) };
+                self.graph
+                    .node_mut(sibling_id)
+                    .set_info(sibling_order, HALF_SIZE);
+                self.graph
+                    .node_mut(node_id)
+                    .set_info(new_order, HALF_SIZE);
+                self.graph.connect(container_id, node_id);
+            }
+        }
+    }
     fn make_space_for_node(&mut self, parent_id: usize) -> u32 {
         let mut children = self.graph.get_children(parent_id);
         let target_size_of_new_item = (FULL_SIZE as f32 / (children.len() as f32 + 1.0)).floor();
@@ -914,6 +1246,11 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             let focused_id = self.focused_id.unwrap();
             let children = self.graph.get_sorted_children(parent_id);
 
+            if self.graph.node(parent_id).is_stack() {
+                self.move_out_of_stack(parent_id, direction);
+                return;
+            }
+
             // This block handles when the focused tile is directly under the root node
             if !self.graph.map_to_parent(Some(parent_id)).is_some() {
                 let new_root = match self.graph.node(parent_id) {
@@ -1043,6 +1380,11 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
                             .set_info(new_order, new_size);
                         self.graph.connect(sibling_id, focused_id);
                     }
+                    Node::Stack(_) => {
+                        // move focused into the stack as its visible tile
+                        self.disconnect_child(parent_id, focused_id);
+                        self.add_to_stack(sibling_id, focused_id);
+                    }
                     Node::Tile(_) => {
                         if number_of_children == 2 {
                             // don't do anything if there are only two nodes and they're both tiles
@@ -1178,6 +1520,7 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
     /// tiles:    t#|#|#   (t)ile (#1)order (#2)size (#3) window ID   Example: t0|60|1 (a tile with order 0, size 60 and windowID 1)
     /// columns:  c#|#[]   (c)olumn (#1)order (#2)size  [..] any children Example: c0|120[t0|60|1] (a column with order 0, size 120 and one child tile)
     /// rows:     r#|#[]   (r)olumn (#1)order (#2)size  [..] any children Example: r0|120[t0|60|1] (a row with order 0, size 120 and one child tile)
+    /// stacks:   s#|#|#[] (s)tack (#1)order (#2)size (#3)order of the visible child [..] child tiles Example: s0|120|1[t0|60|1,t1|60|2] (a stack showing window 2)
     ///     Grid          Tree                         String
     ///                     c          
     ///    11112222        / \
@@ -1193,7 +1536,7 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
     }
     fn inner_to_string(&self, id: usize) -> String {
         match self.graph.node(id) {
-            Node::Column(_) | Node::Row(_) => format!(
+            Node::Column(_) | Node::Row(_) | Node::Stack(_) => format!(
                 "{}[{}]",
                 self.graph.node(id).to_string(),
                 self.stringify_children(id)
@@ -1209,113 +1552,131 @@ impl<TRenderer: Renderer> TileGrid<TRenderer> {
             .collect::<Vec<String>>()
             .join(",")
     }
-    /// Takes string formatted from the to_string function, parses it and populates the tile grid with the nodes and the right relationships
-    /// Currently this will panic if the string isn't formatted correctly, although the strings passed into this function should be generated
-    /// by the to_string function. An incorrectly formatted string would indicate a bug in the to_string function.
-    pub fn from_string(&mut self, target: &String) {
-        if target.len() == 0 {
-            return;
-        }
-
-        self.inner_from_string(&target[..], None);
+    /// Returns the tree of the grid, which uses `f` to describe the window of each tile
+    pub fn to_tree<T>(&self, mut f: impl FnMut(&NativeWindow) -> T) -> Option<GridTree<T>> {
+        self.graph
+            .get_root()
+            .map(|root| self.inner_to_tree(root, FULL_SIZE, &mut f))
+    }
+    fn inner_to_tree<T>(
+        &self,
+        id: usize,
+        size: u32,
+        f: &mut impl FnMut(&NativeWindow) -> T,
+    ) -> GridTree<T> {
+        let mut children = || {
+            self.graph
+                .get_sorted_children(id)
+                .into_iter()
+                .map(|child| self.inner_to_tree(child, self.graph.node(child).get_size(), f))
+                .collect()
+        };
 
-        #[cfg(not(test))] // TODO: Need to refactor Window to be able to fake calls in unit tests
-        {
-            self.remove_empty_tiles();
+        match self.graph.node(id) {
+            Node::Column(_) => GridTree::Column {
+                size,
+                children: children(),
+            },
+            Node::Row(_) => GridTree::Row {
+                size,
+                children: children(),
+            },
+            Node::Tile((_, window)) => GridTree::Tile {
+                size,
+                focused: self.focused_id == Some(id),
+                fullscreen: self.fullscreen_id == Some(id),
+                item: f(window),
+            },
+            Node::Stack(_) => GridTree::Stack {
+                size,
+                active: self
+                    .graph
+                    .get_active_child(id)
+                    .map(|child| self.graph.node(child).get_order() as usize)
+                    .unwrap_or(0),
+                children: children(),
+            },
         }
     }
-    fn inner_from_string(&mut self, target: &str, parent_id: Option<usize>) -> usize {
-        // intended to get the matching brace when nested children occur [ [ [ ] ] ]
-        //                                                               ^         ^
-        let get_closing_brace_index = |s: &str| {
-            let mut bracket_count: usize = 0;
-            let mut index: usize = 0;
-
-            for c in s.chars() {
-                index += 1;
-                match c {
-                    '[' => bracket_count += 1,
-                    ']' => {
-                        bracket_count -= 1;
-                        if bracket_count == 0 {
-                            return index;
-                        }
-                    }
-                    _ => continue,
+    /// Replaces the tiles of the grid with the given tree. The tree is expected to be valid, which
+    /// is the case for trees returned by `GridTree::filter_map`.
+    pub fn load_tree(&mut self, tree: GridTree<NativeWindow>) {
+        self.graph.clear();
+        self.focused_id = None;
+        self.fullscreen_id = None;
+        self.custom_arrangement = None;
+        self.inner_load_tree(tree, None, 0, FULL_SIZE);
+    }
+    fn inner_load_tree(
+        &mut self,
+        tree: GridTree<NativeWindow>,
+        parent_id: Option<usize>,
+        order: u32,
+        size: u32,
+    ) {
+        let info = NodeInfo { order, size };
+        let (node, children) = match tree {
+            GridTree::Column { children, .. } => (Node::Column(info), children),
+            GridTree::Row { children, .. } => (Node::Row(info), children),
+            GridTree::Stack {
+                active, children, ..
+            } => (Node::Stack((info, active as u32)), children),
+            GridTree::Tile {
+                focused,
+                fullscreen,
+                item,
+                ..
+            } => {
+                let id = self.graph.add_node(Node::Tile((info, item)));
+                if let Some(parent_id) = parent_id {
+                    self.graph.connect(parent_id, id);
+                }
+                if focused {
+                    self.focused_id = Some(id);
                 }
+                if fullscreen {
+                    self.fullscreen_id = Some(id);
+                }
+                return;
             }
-
-            index
         };
 
-        match target.chars().nth(0).unwrap() {
-            't' => {
-                // create tile node
-                let end_info_index = cmp::min(
-                    target.find(']').unwrap_or(target.len()),
-                    target.find(',').unwrap_or(target.len()),
-                );
-                let tile = &target[1..end_info_index];
-                let tile_information = tile.split("|").collect::<Vec<&str>>();
-                let window =
-                    NativeWindow::from(WindowId::from(tile_information[2].parse::<i32>().unwrap()));
-
-                match parent_id {
-                    Some(id) => {
-                        let order = tile_information[0].parse::<u32>().unwrap();
-                        let size = tile_information[1].parse::<u32>().unwrap();
-                        let tile_node = Node::Tile((
-                            NodeInfo {
-                                order: order,
-                                size: size,
-                            },
-                            window,
-                        ));
-                        let tile_node_id = self.graph.add_node(tile_node);
-                        self.graph.connect(id, tile_node_id);
-                    }
-                    None => self.push(window), // simple case of just one tile in graph, so just push it in
-                }
+        let id = self.graph.add_node(node);
+        if let Some(parent_id) = parent_id {
+            self.graph.connect(parent_id, id);
+        }
 
-                end_info_index
+        for (order, child) in children.into_iter().enumerate() {
+            let size = child.size();
+            self.inner_load_tree(child, Some(id), order as u32, size);
+        }
+    }
+    /// Takes a string formatted by the to_string function, parses it and replaces the tiles of the
+    /// grid with the parsed nodes. Every tile gets a window with the id from the string.
+    ///
+    /// Syntax errors get returned, while a string that breaks the invariants of a grid (sizes that
+    /// don't add up, orders with gaps, empty columns, ...) gets repaired. The grid stays untouched
+    /// if an error gets returned.
+    pub fn load_string(&mut self, target: &str) -> Result<(), ParseError> {
+        match parser::parse(target)? {
+            Some(tree) => {
+                let tree = tree.filter_map(&mut |id| Some(NativeWindow::from(id)));
+                self.load_tree(tree.expect("Keeping every tile can't remove the root"));
             }
-            character @ 'c' | character @ 'r' => {
-                // create column or row node
-                let end_info_index = target.find('[').unwrap();
-                let node_information = &target[1..end_info_index].split("|").collect::<Vec<&str>>();
-                let order = node_information[0].parse::<u32>().unwrap();
-                let size = node_information[1].parse::<u32>().unwrap();
-                let node_info = NodeInfo {
-                    order: order,
-                    size: size,
-                };
-                let node = if character == 'c' {
-                    Node::Column(node_info)
-                } else {
-                    Node::Row(node_info)
-                };
-                let node_id = self.graph.add_node(node);
-
-                if let Some(id) = parent_id {
-                    self.graph.connect(id, node_id);
-                }
-
-                let open_bracket_index = end_info_index;
-                let close_bracket_index =
-                    open_bracket_index + get_closing_brace_index(&target[open_bracket_index..]);
-                let mut current_index = open_bracket_index + 1;
-
-                while current_index < close_bracket_index {
-                    current_index += self.inner_from_string(
-                        &target[current_index..close_bracket_index],
-                        Some(node_id),
-                    );
-                }
-
-                close_bracket_index
+            None => {
+                self.graph.clear();
+                self.focused_id = None;
+                self.fullscreen_id = None;
+                self.custom_arrangement = None;
             }
-            _ => 1, // some other character like a comma that can be skipped
         }
+
+        #[cfg(not(test))] // TODO: Need to refactor Window to be able to fake calls in unit tests
+        {
+            self.remove_empty_tiles();
+        }
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,138 @@
+use super::{
+    node::{Node, NodeInfo},
+    TileGrid,
+};
+use crate::{
+    renderer::Renderer,
+    split_direction::SplitDirection,
+    system::{NativeWindow, WindowId},
+};
+
+/// Where the tile of a floating window was before the window got floated.
+///
+/// The position is stored relative to a window of the neighboring sibling, which keeps it
+/// meaningful while the rest of the tree changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TilePosition {
+    /// The window of the sibling's tile that was closest to the removed tile
+    anchor: WindowId,
+    /// How many levels above the tile of the anchor the sibling was
+    depth: usize,
+    /// Vertical if the parent was a column and horizontal if it was a row
+    axis: SplitDirection,
+    /// Whether the removed tile came after the sibling
+    after: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct FloatingWindow {
+    pub window: NativeWindow,
+    /// `None` if the window wasn't part of the tree or if it was the only tile
+    pub previous: Option<TilePosition>,
+}
+
+impl From<NativeWindow> for FloatingWindow {
+    fn from(window: NativeWindow) -> Self {
+        Self {
+            window,
+            previous: None,
+        }
+    }
+}
+
+impl<TRenderer: Renderer> TileGrid<TRenderer> {
+    /// Removes the tile of the window and moves the window into the floating layer, while
+    /// remembering where the tile was.
+    pub fn float_window(&mut self, id: WindowId) -> Option<&mut NativeWindow> {
+        let node_id = self.find_tile(id)?;
+        let previous = self.position_of(node_id);
+        let window = self.remove_by_window_id(id)?;
+
+        self.floating_windows
+            .push(FloatingWindow { window, previous });
+        self.floating_windows.last_mut().map(|f| &mut f.window)
+    }
+    /// Moves a floating window back into the tree. The tile gets placed where it was before the
+    /// window got floated, as long as the neighboring window is still part of the grid. Otherwise
+    /// the window gets pushed like a new one.
+    pub fn unfloat_window(&mut self, floating: FloatingWindow) {
+        let FloatingWindow { window, previous } = floating;
+        let anchor = previous
+            .as_ref()
+            .and_then(|p| self.find_tile(p.anchor).map(|id| (id, p)));
+
+        match anchor {
+            Some((anchor_id, position)) => self.insert_next_to(anchor_id, window, position),
+            None => self.push(window),
+        }
+    }
+    /// Removes the window from the floating windows of this grid
+    pub fn remove_floating(&mut self, id: WindowId) -> Option<FloatingWindow> {
+        self.floating_windows
+            .iter()
+            .position(|f| f.window.id == id)
+            .map(|idx| self.floating_windows.remove(idx))
+    }
+    pub fn get_floating_window(&self, id: WindowId) -> Option<&NativeWindow> {
+        self.floating_windows
+            .iter()
+            .map(|f| &f.window)
+            .find(|w| w.id == id)
+    }
+    fn find_tile(&self, id: WindowId) -> Option<usize> {
+        self.graph.find(|n| n.is_tile() && n.get_window().id == id)
+    }
+    fn position_of(&self, node_id: usize) -> Option<TilePosition> {
+        let parent_id = self.graph.map_to_parent(Some(node_id))?;
+        let siblings = self.graph.get_sorted_children(parent_id);
+        let idx = siblings.iter().position(|s| *s == node_id)?;
+
+        // prefer the sibling before the tile, because the tile gets inserted after it by default
+        let (mut anchor, after) = if idx > 0 {
+            (siblings[idx - 1], true)
+        } else {
+            (*siblings.get(1)?, false)
+        };
+
+        let mut depth = 0;
+        while !self.graph.node(anchor).is_tile() {
+            let children = self.graph.get_sorted_children(anchor);
+            anchor = if after {
+                *children.last()?
+            } else {
+                *children.first()?
+            };
+            depth += 1;
+        }
+
+        Some(TilePosition {
+            anchor: self.graph.node(anchor).get_window().id,
+            depth,
+            axis: if self.graph.node(parent_id).is_column() {
+                SplitDirection::Vertical
+            } else {
+                SplitDirection::Horizontal
+            },
+            after,
+        })
+    }
+    fn insert_next_to(&mut self, anchor_id: usize, window: NativeWindow, position: &TilePosition) {
+        // the tree could have gotten flatter in the meantime, so the climb can stop at the root
+        let mut sibling_id = anchor_id;
+        for _ in 0..position.depth {
+            match self.graph.map_to_parent(Some(sibling_id)) {
+                Some(parent_id) => sibling_id = parent_id,
+                None => break,
+            }
+        }
+
+        let node_id = self
+            .graph
+            .add_node(Node::Tile((NodeInfo { order: 0, size: 0 }, window)));
+        self.insert_beside(sibling_id, node_id, position.axis, position.after);
+        self.focused_id = Some(node_id);
+    }
+}
+
+#[cfg(test)]
+mod tests;
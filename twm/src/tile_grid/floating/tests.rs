@@ -0,0 +1,95 @@
+use crate::{
+    renderer::NativeRenderer,
+    system::{fake, fake::FakeWindow, NativeWindow, WindowId},
+    tile_grid::TileGrid,
+};
+
+fn create_grid(layout: &str) -> TileGrid {
+    let mut grid = TileGrid::new(1, NativeRenderer);
+    grid.load_string(layout).unwrap();
+    grid
+}
+
+/// Floats the window and checks the resulting layout, before it puts the window back again
+fn float_and_restore(layout: &str, id: i32, floated: &str) {
+    let mut grid = create_grid(layout);
+
+    assert!(grid.float_window(WindowId(id)).is_some());
+    assert_eq!(floated, grid.to_string());
+    assert_eq!(1, grid.floating_windows.len());
+
+    let floating = grid.remove_floating(WindowId(id)).unwrap();
+    grid.unfloat_window(floating);
+
+    assert_eq!(layout, grid.to_string());
+    assert_eq!(Some(WindowId(id)), grid.get_focused_window().map(|w| w.id));
+}
+
+#[test]
+fn restores_position_between_siblings() {
+    float_and_restore(
+        "c0|120[t0|40|1,t1|40|2,t2|40|3]",
+        2,
+        "c0|120[t0|60|1,t1|60|3]",
+    );
+}
+
+#[test]
+fn restores_first_child() {
+    float_and_restore(
+        "c0|120[t0|40|1,t1|40|2,t2|40|3]",
+        1,
+        "c0|120[t0|60|2,t1|60|3]",
+    );
+}
+
+#[test]
+fn restores_collapsed_parent() {
+    float_and_restore(
+        "c0|120[t0|60|1,r1|60[t0|60|2,t1|60|3]]",
+        3,
+        "c0|120[t0|60|1,t1|60|2]",
+    );
+}
+
+#[test]
+fn restores_next_to_container() {
+    float_and_restore(
+        "r0|120[c0|60[t0|60|1,t1|60|2],t1|60|3]",
+        3,
+        "c0|60[t0|60|1,t1|60|2]",
+    );
+}
+
+#[test]
+fn pushes_window_without_neighbor() {
+    let mut grid = create_grid("c0|120[t0|60|1,t1|60|2]");
+    grid.float_window(WindowId(2));
+    grid.remove_by_window_id(WindowId(1));
+    grid.push(NativeWindow::from(WindowId(3)));
+
+    let floating = grid.remove_floating(WindowId(2)).unwrap();
+    grid.unfloat_window(floating);
+
+    assert_eq!("c0|120[t0|60|3,t1|60|2]", grid.to_string());
+}
+
+#[test]
+fn floating_windows_follow_the_workspace() {
+    fake::reset();
+    let tiled = fake::add_window(FakeWindow::new("tiled", "code.exe"));
+    let floating = fake::add_window(FakeWindow::new("floating", "calc.exe"));
+
+    let mut grid = TileGrid::new(1, NativeRenderer);
+    grid.push(NativeWindow::from(tiled));
+    grid.push(NativeWindow::from(floating));
+    grid.float_window(floating);
+
+    grid.hide();
+    assert!(!fake::get_window(tiled).unwrap().visible);
+    assert!(!fake::get_window(floating).unwrap().visible);
+
+    grid.show().unwrap();
+    assert!(fake::get_window(tiled).unwrap().visible);
+    assert!(fake::get_window(floating).unwrap().visible);
+}
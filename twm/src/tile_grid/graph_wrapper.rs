@@ -222,10 +222,26 @@ impl GraphWrapper {
                         moving_direction,
                     )
                 }
+                Node::Stack(_) => self.to_closest_tile(self.get_active_child(id), moving_direction),
                 _ => Some(id),
             }
         } else {
             None
         }
     }
+
+    /// Returns the visible child of a stack. Falls back to the last child if the stack lost the
+    /// children after the active one.
+    pub fn get_active_child(&self, stack_id: usize) -> Option<usize> {
+        match self.node(stack_id) {
+            Node::Stack((_, active)) => {
+                let children = self.get_sorted_children(stack_id);
+                children
+                    .get(*active as usize)
+                    .or_else(|| children.last())
+                    .copied()
+            }
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,294 @@
+use crate::system::WindowId;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// A part of the area a grid is allowed to use
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Area {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Area {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Splits the area into `count` columns. Pixels that can't be distributed evenly are given to
+    /// the first columns.
+    pub fn split_columns(&self, count: u32) -> Vec<Area> {
+        split_evenly(self.width, count)
+            .into_iter()
+            .map(|(offset, width)| Area::new(self.x + offset, self.y, width, self.height))
+            .collect()
+    }
+
+    /// Splits the area into `count` rows. Pixels that can't be distributed evenly are given to
+    /// the first rows.
+    pub fn split_rows(&self, count: u32) -> Vec<Area> {
+        split_evenly(self.height, count)
+            .into_iter()
+            .map(|(offset, height)| Area::new(self.x, self.y + offset, self.width, height))
+            .collect()
+    }
+
+    /// Returns the left part with the given width and the remaining right part
+    fn cut_left(&self, width: u32) -> (Area, Area) {
+        let width = width.min(self.width);
+        (
+            Area::new(self.x, self.y, width, self.height),
+            Area::new(self.x + width, self.y, self.width - width, self.height),
+        )
+    }
+
+    /// Returns the top part with the given height and the remaining bottom part
+    fn cut_top(&self, height: u32) -> (Area, Area) {
+        let height = height.min(self.height);
+        (
+            Area::new(self.x, self.y, self.width, height),
+            Area::new(self.x, self.y + height, self.width, self.height - height),
+        )
+    }
+}
+
+/// Returns the offset and length of each part
+fn split_evenly(length: u32, count: u32) -> Vec<(u32, u32)> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let size = length / count;
+    let mut remainder = length % count;
+    let mut offset = 0;
+
+    (0..count)
+        .map(|_| {
+            let mut part = size;
+            if remainder > 0 {
+                remainder -= 1;
+                part += 1;
+            }
+            let res = (offset, part);
+            offset += part;
+            res
+        })
+        .collect()
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum LayoutError {
+    #[error("Unknown layout {0}")]
+    Unknown(String),
+    #[error("The ratio of a layout has to be between 0 and 1 (found {0})")]
+    InvalidRatio(f32),
+}
+
+/// Decides where the tiles of a grid get placed.
+///
+/// `Manual` uses the column/row tree, which gets built while pushing windows and can be changed by
+/// the user. Every other layout only uses the order of the tiles in the tree and arranges them on
+/// its own.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Layout {
+    #[default]
+    Manual,
+    /// The first `count` tiles are placed in the master column on the left, which takes up `ratio`
+    /// of the width. The remaining tiles get stacked on the right.
+    MasterStack { count: u32, ratio: f32 },
+    /// Every tile takes half of the remaining space, going clockwise
+    Spiral,
+    /// Every tile takes up the whole space
+    Monocle,
+    /// The tiles are distributed into evenly sized rows and columns
+    Grid,
+    /// A layout that got registered in lua with `nog.register_layout`
+    Custom(String),
+}
+
+impl Layout {
+    pub const NAMES: [&'static str; 5] = ["manual", "master_stack", "spiral", "monocle", "grid"];
+
+    pub fn master_stack(count: u32, ratio: f32) -> Result<Self, LayoutError> {
+        if ratio <= 0.0 || ratio >= 1.0 {
+            return Err(LayoutError::InvalidRatio(ratio));
+        }
+
+        Ok(Layout::MasterStack { count, ratio })
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Layout::Manual => "manual",
+            Layout::MasterStack { .. } => "master_stack",
+            Layout::Spiral => "spiral",
+            Layout::Monocle => "monocle",
+            Layout::Grid => "grid",
+            Layout::Custom(name) => name,
+        }
+    }
+
+    pub fn is_manual(&self) -> bool {
+        *self == Layout::Manual
+    }
+
+    /// Returns the area of each tile in the same order as the tiles. Returns `None` for the manual
+    /// layout, because it depends on the tree instead of the order, and for custom layouts, because
+    /// they get arranged by lua.
+    pub fn arrange(&self, tiles: usize, area: Area) -> Option<Vec<Area>> {
+        let tiles = tiles as u32;
+
+        if tiles == 0 {
+            return Some(Vec::new());
+        }
+
+        Some(match self {
+            Layout::Manual | Layout::Custom(_) => return None,
+            Layout::MasterStack { count, ratio } => {
+                let count = *count;
+                if count == 0 || tiles <= count {
+                    area.split_rows(tiles)
+                } else {
+                    let master_width = (area.width as f32 * ratio).round() as u32;
+                    let (master, stack) = area.cut_left(master_width);
+                    let mut areas = master.split_rows(count);
+                    areas.extend(stack.split_rows(tiles - count));
+                    areas
+                }
+            }
+            Layout::Spiral => {
+                let mut areas = Vec::new();
+                let mut remaining = area;
+
+                for i in 0..tiles {
+                    if i == tiles - 1 {
+                        areas.push(remaining);
+                        break;
+                    }
+
+                    // the first two tiles of every turn take the left/top half and the other two the right/bottom half
+                    let (first, second) = if i % 2 == 0 {
+                        remaining.cut_left(remaining.width / 2)
+                    } else {
+                        remaining.cut_top(remaining.height / 2)
+                    };
+
+                    if i % 4 < 2 {
+                        areas.push(first);
+                        remaining = second;
+                    } else {
+                        areas.push(second);
+                        remaining = first;
+                    }
+                }
+
+                areas
+            }
+            Layout::Monocle => vec![area; tiles as usize],
+            Layout::Grid => {
+                let columns = (tiles as f32).sqrt().ceil() as u32;
+                let rows = tiles.div_ceil(columns);
+
+                area.split_rows(rows)
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(i, row)| {
+                        let placed = i as u32 * columns;
+                        row.split_columns(columns.min(tiles - placed))
+                    })
+                    .collect()
+            }
+        })
+    }
+}
+
+/// Information about a tile that gets passed to a custom layout
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutTile {
+    pub window_id: WindowId,
+    pub title: String,
+    pub focused: bool,
+}
+
+/// The result of the last time a custom layout arranged the tiles of a grid.
+///
+/// Custom layouts can't be called while rendering, because the lua runtime might be busy, so the
+/// result gets stored until the tiles or the available space change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomArrangement {
+    pub layout: String,
+    pub tiles: Vec<LayoutTile>,
+    /// Size of the space the tiles got arranged in
+    pub width: u32,
+    pub height: u32,
+    /// Area of each tile in the same order as the tiles. Tiles without an area don't get rendered.
+    pub areas: Vec<Option<Area>>,
+}
+
+impl CustomArrangement {
+    /// Whether the arrangement is still valid for the given state of a grid
+    pub fn is_arrangement_of(
+        &self,
+        layout: &str,
+        tiles: &[LayoutTile],
+        width: u32,
+        height: u32,
+    ) -> bool {
+        self.layout == layout && self.tiles == tiles && self.width == width && self.height == height
+    }
+
+    /// Returns the area of the window scaled to the given size
+    pub fn get_area(&self, window_id: WindowId, width: u32, height: u32) -> Option<Area> {
+        let idx = self.tiles.iter().position(|t| t.window_id == window_id)?;
+        let area = self.areas.get(idx).copied().flatten()?;
+        let scale = |value: u32, from: u32, to: u32| {
+            if from == 0 {
+                0
+            } else {
+                (value as u64 * to as u64 / from as u64) as u32
+            }
+        };
+
+        Some(Area::new(
+            scale(area.x, self.width, width),
+            scale(area.y, self.height, height),
+            scale(area.width, self.width, width),
+            scale(area.height, self.height, height),
+        ))
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Layout {
+    type Err = LayoutError;
+
+    /// Parses the name of a built-in layout. The master stack layout starts with a single master,
+    /// which takes up 60% of the width.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "manual" => Layout::Manual,
+            "master_stack" => Layout::MasterStack {
+                count: 1,
+                ratio: 0.6,
+            },
+            "spiral" => Layout::Spiral,
+            "monocle" => Layout::Monocle,
+            "grid" => Layout::Grid,
+            _ => return Err(LayoutError::Unknown(s.into())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,148 @@
+use super::{Area, CustomArrangement, Layout, LayoutError, LayoutTile};
+use crate::system::WindowId;
+use std::str::FromStr;
+
+fn arrange(layout: Layout, tiles: usize) -> Vec<(u32, u32, u32, u32)> {
+    layout
+        .arrange(tiles, Area::new(0, 0, 100, 50))
+        .unwrap()
+        .into_iter()
+        .map(|a| (a.x, a.y, a.width, a.height))
+        .collect()
+}
+
+#[test]
+fn manual_layout_depends_on_the_tree() {
+    assert_eq!(None, Layout::Manual.arrange(2, Area::new(0, 0, 100, 50)));
+}
+
+#[test]
+fn empty_layout_has_no_areas() {
+    assert!(arrange(Layout::Grid, 0).is_empty());
+}
+
+#[test]
+fn master_stack_places_masters_left() {
+    let layout = Layout::master_stack(1, 0.6).unwrap();
+
+    assert_eq!(vec![(0, 0, 100, 50)], arrange(layout.clone(), 1));
+    assert_eq!(
+        vec![(0, 0, 60, 50), (60, 0, 40, 25), (60, 25, 40, 25)],
+        arrange(layout, 3)
+    );
+}
+
+#[test]
+fn master_stack_with_multiple_masters() {
+    let layout = Layout::master_stack(2, 0.5).unwrap();
+
+    assert_eq!(
+        vec![(0, 0, 100, 25), (0, 25, 100, 25)],
+        arrange(layout.clone(), 2)
+    );
+    assert_eq!(
+        vec![(0, 0, 50, 25), (0, 25, 50, 25), (50, 0, 50, 50)],
+        arrange(layout, 3)
+    );
+}
+
+#[test]
+fn master_stack_rejects_invalid_ratio() {
+    assert_eq!(
+        Err(LayoutError::InvalidRatio(1.5)),
+        Layout::master_stack(1, 1.5)
+    );
+}
+
+#[test]
+fn spiral_halves_remaining_space_clockwise() {
+    assert_eq!(
+        vec![
+            (0, 0, 50, 50),
+            (50, 0, 50, 25),
+            (75, 25, 25, 25),
+            (50, 37, 25, 13),
+            (50, 25, 25, 12)
+        ],
+        arrange(Layout::Spiral, 5)
+    );
+}
+
+#[test]
+fn monocle_uses_whole_space() {
+    assert_eq!(vec![(0, 0, 100, 50); 3], arrange(Layout::Monocle, 3));
+}
+
+#[test]
+fn grid_stretches_last_row() {
+    assert_eq!(
+        vec![(0, 0, 50, 25), (50, 0, 50, 25), (0, 25, 100, 25)],
+        arrange(Layout::Grid, 3)
+    );
+}
+
+#[test]
+fn grid_distributes_remainder() {
+    assert_eq!(
+        vec![(0, 0, 34, 17), (34, 0, 33, 17), (67, 0, 33, 17)],
+        arrange(Layout::Grid, 9)[0..3].to_vec()
+    );
+}
+
+#[test]
+fn parse_layout_names() {
+    for name in &Layout::NAMES {
+        assert_eq!(*name, Layout::from_str(name).unwrap().to_string());
+    }
+
+    assert_eq!(
+        Err(LayoutError::Unknown("tabbed".into())),
+        Layout::from_str("tabbed")
+    );
+}
+
+fn create_arrangement() -> CustomArrangement {
+    CustomArrangement {
+        layout: "custom".into(),
+        tiles: vec![
+            LayoutTile {
+                window_id: WindowId(1),
+                title: "first".into(),
+                focused: true,
+            },
+            LayoutTile {
+                window_id: WindowId(2),
+                title: "second".into(),
+                focused: false,
+            },
+        ],
+        width: 200,
+        height: 100,
+        areas: vec![Some(Area::new(0, 0, 100, 100)), None],
+    }
+}
+
+#[test]
+fn custom_arrangement_scales_areas() {
+    let arrangement = create_arrangement();
+
+    assert_eq!(
+        Some(Area::new(0, 0, 50, 20)),
+        arrangement.get_area(WindowId(1), 100, 20)
+    );
+    assert_eq!(None, arrangement.get_area(WindowId(2), 100, 20));
+    assert_eq!(None, arrangement.get_area(WindowId(3), 100, 20));
+}
+
+#[test]
+fn custom_arrangement_gets_outdated() {
+    let arrangement = create_arrangement();
+    let mut tiles = arrangement.tiles.clone();
+
+    assert!(arrangement.is_arrangement_of("custom", &tiles, 200, 100));
+    assert!(!arrangement.is_arrangement_of("other", &tiles, 200, 100));
+    assert!(!arrangement.is_arrangement_of("custom", &tiles, 200, 50));
+
+    tiles[1].focused = true;
+    assert!(!arrangement.is_arrangement_of("custom", &tiles, 200, 100));
+}
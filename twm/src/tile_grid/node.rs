@@ -12,6 +12,8 @@ pub enum Node {
     Column(NodeInfo),
     Row(NodeInfo),
     Tile((NodeInfo, NativeWindow)),
+    /// Tiles that occupy the same area, of which only the one with the order `active` is visible
+    Stack((NodeInfo, u32)),
 }
 
 impl Node {
@@ -23,6 +25,10 @@ impl Node {
         Node::Column(NodeInfo { order, size })
     }
 
+    pub fn stack(order: u32, size: u32, active: u32) -> Node {
+        Node::Stack((NodeInfo { order, size }, active))
+    }
+
     pub fn is_tile(&self) -> bool {
         match self {
             Node::Tile(_) => true,
@@ -46,9 +52,13 @@ impl Node {
         }
     }
 
+    pub fn is_stack(&self) -> bool {
+        matches!(self, Node::Stack(_))
+    }
+
     pub fn set_info(&mut self, order: u32, size: u32) {
         match self {
-            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) => {
+            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) | Node::Stack((n, _)) => {
                 n.order = order;
                 n.size = size;
             }
@@ -57,31 +67,37 @@ impl Node {
 
     pub fn get_info(&self) -> (u32, u32) {
         match self {
-            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) => (n.order, n.size),
+            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) | Node::Stack((n, _)) => {
+                (n.order, n.size)
+            }
         }
     }
 
     pub fn set_size(&mut self, size: u32) {
         match self {
-            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) => n.size = size,
+            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) | Node::Stack((n, _)) => {
+                n.size = size
+            }
         }
     }
 
     pub fn set_order(&mut self, order: u32) {
         match self {
-            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) => n.order = order,
+            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) | Node::Stack((n, _)) => {
+                n.order = order
+            }
         }
     }
 
     pub fn get_size(&self) -> u32 {
         match self {
-            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) => n.size,
+            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) | Node::Stack((n, _)) => n.size,
         }
     }
 
     pub fn get_order(&self) -> u32 {
         match self {
-            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) => n.order,
+            Node::Column(n) | Node::Row(n) | Node::Tile((n, _)) | Node::Stack((n, _)) => n.order,
         }
     }
 
@@ -124,6 +140,7 @@ impl Node {
             Node::Column(info) => format!("c{}|{}", info.order, info.size),
             Node::Row(info) => format!("r{}|{}", info.order, info.size),
             Node::Tile((info, window)) => format!("t{}|{}|{}", info.order, info.size, window.id),
+            Node::Stack((info, active)) => format!("s{}|{}|{}", info.order, info.size, active),
         }
     }
 }
@@ -0,0 +1,351 @@
+//! Parses the string representation of a grid, which gets created by `TileGrid::to_string`.
+//!
+//! Syntax errors get rejected. A string that is syntactically valid can still break the
+//! invariants of a grid, like the sizes of the children of a column not adding up to the full
+//! size. These violations get logged and repaired.
+use super::{tree::GridTree, FULL_SIZE};
+use crate::system::WindowId;
+use log::warn;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Violation {
+    #[error("the sizes of the children add up to {0} instead of {}", FULL_SIZE)]
+    Sizes(u32),
+    #[error("the orders of the children aren't contiguous starting at 0")]
+    Orders,
+    #[error("the {0} doesn't have any children")]
+    EmptyContainer(&'static str),
+    #[error("the {0} only has a single child")]
+    SingleChild(&'static str),
+    #[error("the {0} is nested directly inside of another {0}")]
+    NestedContainer(&'static str),
+    #[error("the {0} is part of a stack, which can only contain tiles")]
+    StackedContainer(&'static str),
+    #[error("the stack doesn't have a child with the order {0} to show")]
+    ActiveTab(u32),
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+    #[error("unexpected character `{0}`")]
+    UnexpectedChar(char),
+    #[error("expected a number")]
+    InvalidNumber,
+    #[error("window {0} appears more than once")]
+    DuplicateWindow(i32),
+}
+
+/// `position` is the byte offset into the parsed string
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("Invalid grid at position {position}: {kind}")]
+pub struct ParseError {
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+/// Returns `None` if the string is empty.
+///
+/// Every violation gets repaired: the sizes get scaled to add up to the full size, the children get
+/// renumbered using their order, empty containers get removed, containers with a single child get
+/// replaced by the child and nested containers of the same type get merged. Containers inside of a
+/// stack get replaced by their tiles and a stack that has an unknown active child shows its first
+/// child.
+pub fn parse(input: &str) -> Result<Option<GridTree<WindowId>>, ParseError> {
+    let root = match parse_raw(input)? {
+        Some(root) => root,
+        None => return Ok(None),
+    };
+
+    let mut violations = Vec::new();
+    root.validate(&mut violations);
+
+    for (position, violation) in violations {
+        warn!("Repairing grid at position {}: {}", position, violation);
+    }
+
+    Ok(root.into_tree().filter_map(&mut Some))
+}
+
+/// Parses the string without checking the invariants
+fn parse_raw(input: &str) -> Result<Option<RawNode>, ParseError> {
+    let trimmed = input.trim_start();
+    let offset = input.len() - trimmed.len();
+    let trimmed = trimmed.trim_end();
+
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+
+    let mut parser = Parser {
+        input: trimmed,
+        offset,
+        position: 0,
+        windows: Vec::new(),
+    };
+
+    let root = parser.node()?;
+
+    if parser.position < trimmed.len() {
+        return Err(parser.unexpected());
+    }
+
+    Ok(Some(root))
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    /// Amount of whitespace that got trimmed from the start of the input
+    offset: usize,
+    position: usize,
+    windows: Vec<i32>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, position: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            position: self.offset + position,
+            kind,
+        }
+    }
+
+    /// Returns the error for the character at the current position
+    fn unexpected(&self) -> ParseError {
+        let kind = match self.input[self.position..].chars().next() {
+            Some(c) => ParseErrorKind::UnexpectedChar(c),
+            None => ParseErrorKind::UnexpectedEnd,
+        };
+
+        self.error(self.position, kind)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.position).copied()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), ParseError> {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, ParseError> {
+        let start = self.position;
+
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+
+        while self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
+            self.position += 1;
+        }
+
+        if start == self.position {
+            return Err(match self.peek() {
+                Some(_) => self.error(start, ParseErrorKind::InvalidNumber),
+                None => self.unexpected(),
+            });
+        }
+
+        self.input[start..self.position]
+            .parse()
+            .map_err(|_| self.error(start, ParseErrorKind::InvalidNumber))
+    }
+
+    fn node(&mut self) -> Result<RawNode, ParseError> {
+        let position = self.position;
+        let typ = self.peek();
+
+        if !matches!(typ, Some(b't') | Some(b'c') | Some(b'r') | Some(b's')) {
+            return Err(self.unexpected());
+        }
+
+        self.position += 1;
+
+        let order = self.number()?;
+        self.expect(b'|')?;
+        let size = self.number()?;
+
+        let kind = if typ == Some(b't') {
+            self.expect(b'|')?;
+            let window_position = self.position;
+            let id = self.number()?;
+
+            if self.windows.contains(&id) {
+                return Err(self.error(window_position, ParseErrorKind::DuplicateWindow(id)));
+            }
+
+            self.windows.push(id);
+            RawKind::Tile(WindowId(id))
+        } else if typ == Some(b's') {
+            self.expect(b'|')?;
+            let active = self.number()?;
+            RawKind::Stack(active, self.children()?)
+        } else {
+            let children = self.children()?;
+            if typ == Some(b'c') {
+                RawKind::Column(children)
+            } else {
+                RawKind::Row(children)
+            }
+        };
+
+        Ok(RawNode {
+            position: self.offset + position,
+            order,
+            size,
+            kind,
+        })
+    }
+
+    fn children(&mut self) -> Result<Vec<RawNode>, ParseError> {
+        let mut children = Vec::new();
+
+        self.expect(b'[')?;
+
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(children);
+        }
+
+        loop {
+            children.push(self.node()?);
+
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(children);
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+}
+
+/// A node exactly like it was written in the string
+struct RawNode {
+    /// The byte offset into the parsed string
+    position: usize,
+    order: u32,
+    size: u32,
+    kind: RawKind,
+}
+
+enum RawKind {
+    Column(Vec<RawNode>),
+    Row(Vec<RawNode>),
+    Tile(WindowId),
+    /// The order of the visible child and the children
+    Stack(u32, Vec<RawNode>),
+}
+
+impl RawNode {
+    fn name(&self) -> &'static str {
+        match self.kind {
+            RawKind::Column(_) => "column",
+            RawKind::Row(_) => "row",
+            RawKind::Tile(_) => "tile",
+            RawKind::Stack(..) => "stack",
+        }
+    }
+
+    fn validate(&self, violations: &mut Vec<(usize, Violation)>) {
+        let children = match &self.kind {
+            RawKind::Column(children) | RawKind::Row(children) | RawKind::Stack(_, children) => {
+                children
+            }
+            RawKind::Tile(_) => return,
+        };
+
+        match children.len() {
+            0 => violations.push((self.position, Violation::EmptyContainer(self.name()))),
+            1 => violations.push((self.position, Violation::SingleChild(self.name()))),
+            _ => {}
+        }
+
+        if !children.is_empty() {
+            let sum = children
+                .iter()
+                .fold(0u32, |sum, c| sum.saturating_add(c.size));
+            if sum != FULL_SIZE {
+                violations.push((self.position, Violation::Sizes(sum)));
+            }
+
+            let mut orders = children.iter().map(|c| c.order).collect::<Vec<_>>();
+            orders.sort();
+            if orders
+                .into_iter()
+                .enumerate()
+                .any(|(i, order)| i as u32 != order)
+            {
+                violations.push((self.position, Violation::Orders));
+            }
+        }
+
+        if let RawKind::Stack(active, _) = self.kind {
+            if !children.is_empty() && children.iter().all(|c| c.order != active) {
+                violations.push((self.position, Violation::ActiveTab(active)));
+            }
+        }
+
+        for child in children {
+            if let RawKind::Stack(..) = self.kind {
+                if !matches!(child.kind, RawKind::Tile(_)) {
+                    violations.push((child.position, Violation::StackedContainer(child.name())));
+                }
+            } else if child.name() == self.name() {
+                violations.push((child.position, Violation::NestedContainer(self.name())));
+            }
+
+            child.validate(violations);
+        }
+    }
+
+    /// Converts the node into a tree, which has the children sorted by their order
+    fn into_tree(self) -> GridTree<WindowId> {
+        // anything bigger can't be valid anyway and would overflow while normalizing the sizes
+        let size = self.size.min(FULL_SIZE);
+        let sort = |children: &mut Vec<RawNode>| {
+            // the sort is stable, which keeps children with the same order in the written order
+            children.sort_by_key(|c| c.order);
+        };
+        let sorted = |mut children: Vec<RawNode>| {
+            sort(&mut children);
+            children.into_iter().map(RawNode::into_tree).collect()
+        };
+
+        match self.kind {
+            RawKind::Column(children) => GridTree::Column {
+                size,
+                children: sorted(children),
+            },
+            RawKind::Row(children) => GridTree::Row {
+                size,
+                children: sorted(children),
+            },
+            RawKind::Tile(id) => GridTree::Tile {
+                size,
+                focused: false,
+                fullscreen: false,
+                item: id,
+            },
+            RawKind::Stack(active, mut children) => {
+                sort(&mut children);
+                GridTree::Stack {
+                    size,
+                    active: children.iter().position(|c| c.order == active).unwrap_or(0),
+                    children: children.into_iter().map(RawNode::into_tree).collect(),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
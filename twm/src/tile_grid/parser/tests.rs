@@ -0,0 +1,151 @@
+use super::{parse, parse_raw, ParseError, ParseErrorKind, Violation};
+use crate::{system::WindowId, tile_grid::tree::GridTree};
+
+fn tile(size: u32, id: i32) -> GridTree<WindowId> {
+    GridTree::Tile {
+        size,
+        focused: false,
+        fullscreen: false,
+        item: WindowId(id),
+    }
+}
+
+fn violations(input: &str) -> Vec<(usize, Violation)> {
+    let mut violations = Vec::new();
+    parse_raw(input).unwrap().unwrap().validate(&mut violations);
+    violations
+}
+
+fn repaired(input: &str) -> GridTree<WindowId> {
+    parse(input).unwrap().unwrap()
+}
+
+#[test]
+fn parses_valid_grid() {
+    let expected = GridTree::Column {
+        size: 120,
+        children: vec![
+            tile(60, 1),
+            GridTree::Row {
+                size: 60,
+                children: vec![tile(40, 2), tile(80, -3)],
+            },
+        ],
+    };
+
+    assert_eq!(
+        Some(expected),
+        parse("c0|120[t0|60|1,r1|60[t0|40|2,t1|80|-3]]").unwrap()
+    );
+    assert!(violations("c0|120[t0|60|1,r1|60[t0|40|2,t1|80|-3]]").is_empty());
+    assert_eq!(None, parse("  ").unwrap());
+}
+
+#[test]
+fn syntax_errors_have_position() {
+    let cases = vec![
+        ("x0|120|1", 0, ParseErrorKind::UnexpectedChar('x')),
+        ("c0|120[t0|60|1,t1|60|2", 22, ParseErrorKind::UnexpectedEnd),
+        (
+            "c0|120[t0|60|1;t1|60|2]",
+            14,
+            ParseErrorKind::UnexpectedChar(';'),
+        ),
+        ("c0|120[t0|60|a]", 13, ParseErrorKind::InvalidNumber),
+        ("t0|99999999999|1", 3, ParseErrorKind::InvalidNumber),
+        (
+            "c0|120[t0|60|1,t1|60|1]",
+            21,
+            ParseErrorKind::DuplicateWindow(1),
+        ),
+        (" t0|120|1]", 9, ParseErrorKind::UnexpectedChar(']')),
+    ];
+
+    for (input, position, kind) in cases {
+        assert_eq!(
+            ParseError { position, kind },
+            parse(input).unwrap_err(),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn finds_violations() {
+    let cases = vec![
+        ("c0|120[t0|60|1,t1|50|2]", 0, Violation::Sizes(110)),
+        ("c0|120[t0|60|1,t2|60|2]", 0, Violation::Orders),
+        (
+            "c0|120[t0|60|1,r1|60[]]",
+            15,
+            Violation::EmptyContainer("row"),
+        ),
+        ("c0|120[t0|120|1]", 0, Violation::SingleChild("column")),
+        (
+            "c0|120[t0|60|1,c1|60[t0|60|2,t1|60|3]]",
+            15,
+            Violation::NestedContainer("column"),
+        ),
+        (
+            "s0|120|0[t0|60|1,r1|60[t0|60|2,t1|60|3]]",
+            17,
+            Violation::StackedContainer("row"),
+        ),
+        ("s0|120|2[t0|60|1,t1|60|2]", 0, Violation::ActiveTab(2)),
+    ];
+
+    for (input, position, violation) in cases {
+        assert_eq!(vec![(position, violation)], violations(input), "{}", input);
+    }
+}
+
+#[test]
+fn repairs_sizes_and_orders() {
+    assert_eq!(
+        GridTree::Row {
+            size: 120,
+            children: vec![tile(24, 2), tile(96, 1)],
+        },
+        repaired("r0|120[t3|1000000|1,t1|30|2]")
+    );
+}
+
+#[test]
+fn repairs_structure() {
+    assert_eq!(tile(120, 1), repaired("c0|120[r0|120[t0|120|1],c1|0[]]"));
+    assert_eq!(tile(120, 1), repaired("  c0|120[t0|120|1]"));
+    assert_eq!(
+        GridTree::Column {
+            size: 120,
+            children: vec![tile(60, 1), tile(30, 2), tile(30, 3)],
+        },
+        repaired("c0|120[t0|60|1,c1|60[t0|60|2,t1|60|3]]")
+    );
+}
+
+#[test]
+fn parses_stack() {
+    assert_eq!(
+        GridTree::Column {
+            size: 120,
+            children: vec![
+                tile(60, 1),
+                GridTree::Stack {
+                    size: 60,
+                    active: 1,
+                    children: vec![tile(60, 2), tile(60, 3)],
+                },
+            ],
+        },
+        repaired("c0|120[t0|60|1,s1|60|1[t0|60|2,t1|60|3]]")
+    );
+    assert_eq!(
+        GridTree::Stack {
+            size: 120,
+            active: 0,
+            children: vec![tile(40, 1), tile(40, 2), tile(40, 3)],
+        },
+        repaired("s0|120|5[t0|60|1,c1|60[t0|60|2,t1|60|3]]")
+    );
+}
@@ -0,0 +1,170 @@
+use super::{tree::GridTree, TileGrid};
+use crate::{
+    renderer::Renderer,
+    system::{NativeWindow, SystemError},
+};
+use regex::Regex;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("There is no saved layout called {0}")]
+    Unknown(String),
+    #[error("The layout {layout} doesn't have a slot {slot}")]
+    InvalidSlot { layout: String, slot: usize },
+    #[error("The workspace doesn't have any windows")]
+    EmptyWorkspace,
+    #[error(transparent)]
+    System(#[from] SystemError),
+}
+
+/// Decides which window gets placed into a slot of a layout snapshot
+#[derive(Clone, Debug)]
+pub struct SlotRule {
+    pub slot: usize,
+    /// Gets matched against the process name and the title of the window
+    pub pattern: Regex,
+}
+
+impl SlotRule {
+    fn matches(&self, window: &NativeWindow) -> bool {
+        self.pattern.is_match(&window.get_process_name()) || self.pattern.is_match(&window.title)
+    }
+}
+
+/// The structure and sizes of a grid without its windows.
+///
+/// Every tile is a placeholder, called slot. The slots are numbered in the order they appear in the
+/// tree starting at 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutSnapshot {
+    pub tree: GridTree<usize>,
+}
+
+impl LayoutSnapshot {
+    /// Returns `None` if the grid is empty
+    pub fn of<TRenderer: Renderer>(grid: &TileGrid<TRenderer>) -> Option<Self> {
+        let mut slot = 0;
+        grid.to_tree(|_| {
+            slot += 1;
+            slot
+        })
+        .map(|tree| Self {
+            tree: without_focus(tree),
+        })
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.tree.items().len()
+    }
+
+    /// Rebuilds the tiles of the grid using this snapshot.
+    ///
+    /// Every rule places the first matching window into its slot. The remaining slots get filled
+    /// with the remaining windows in the order they appeared in the grid. Slots without a window get
+    /// removed and windows without a slot get pushed afterwards.
+    pub fn apply<TRenderer: Renderer>(
+        &self,
+        name: &str,
+        grid: &mut TileGrid<TRenderer>,
+        rules: &[SlotRule],
+    ) -> Result<(), SnapshotError> {
+        let slot_count = self.slot_count();
+
+        if let Some(rule) = rules.iter().find(|r| r.slot == 0 || r.slot > slot_count) {
+            return Err(SnapshotError::InvalidSlot {
+                layout: name.into(),
+                slot: rule.slot,
+            });
+        }
+
+        let focused = grid.get_focused_window().map(|w| w.id);
+        let windows = grid
+            .to_tree(|w| w.clone())
+            .map(|tree| tree.items().into_iter().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let slots = assign_slots(slot_count, &windows, rules);
+        let mut windows = windows.into_iter().map(Some).collect::<Vec<_>>();
+
+        let tree = self
+            .tree
+            .clone()
+            .filter_map(&mut |slot| slots[slot - 1].and_then(|idx| windows[idx].take()));
+
+        if let Some(tree) = tree {
+            grid.load_tree(tree);
+        }
+
+        for window in windows.into_iter().flatten() {
+            grid.push(window);
+        }
+
+        match focused {
+            Some(id) => grid.focus_tile_by_window_id(id),
+            None => grid.focus_last_tile(),
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the index of the window that belongs into each slot
+fn assign_slots(
+    slot_count: usize,
+    windows: &[NativeWindow],
+    rules: &[SlotRule],
+) -> Vec<Option<usize>> {
+    let mut slots = vec![None; slot_count];
+    let mut taken = vec![false; windows.len()];
+
+    for rule in rules {
+        if slots[rule.slot - 1].is_some() {
+            continue;
+        }
+
+        if let Some(idx) = (0..windows.len()).find(|i| !taken[*i] && rule.matches(&windows[*i])) {
+            taken[idx] = true;
+            slots[rule.slot - 1] = Some(idx);
+        }
+    }
+
+    let mut remaining = (0..windows.len()).filter(|i| !taken[*i]);
+
+    for slot in slots.iter_mut().filter(|s| s.is_none()) {
+        *slot = remaining.next();
+    }
+
+    slots
+}
+
+fn without_focus<T>(tree: GridTree<T>) -> GridTree<T> {
+    match tree {
+        GridTree::Column { size, children } => GridTree::Column {
+            size,
+            children: children.into_iter().map(without_focus).collect(),
+        },
+        GridTree::Row { size, children } => GridTree::Row {
+            size,
+            children: children.into_iter().map(without_focus).collect(),
+        },
+        GridTree::Stack {
+            size,
+            active,
+            children,
+        } => GridTree::Stack {
+            size,
+            active,
+            children: children.into_iter().map(without_focus).collect(),
+        },
+        GridTree::Tile { size, item, .. } => GridTree::Tile {
+            size,
+            focused: false,
+            fullscreen: false,
+            item,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests;
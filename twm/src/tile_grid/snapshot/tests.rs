@@ -0,0 +1,110 @@
+use super::{LayoutSnapshot, SlotRule, SnapshotError};
+use crate::{
+    renderer::NativeRenderer, system::fake, system::fake::FakeWindow, system::NativeWindow,
+    tile_grid::TileGrid,
+};
+use regex::Regex;
+
+fn create_grid(layout: &str, processes: &[&str]) -> TileGrid {
+    fake::reset();
+
+    for process in processes {
+        fake::add_window(FakeWindow::new(process, process));
+    }
+
+    let mut grid = TileGrid::new(1, NativeRenderer);
+    grid.load_string(layout).unwrap();
+    grid
+}
+
+fn rule(slot: usize, pattern: &str) -> SlotRule {
+    SlotRule {
+        slot,
+        pattern: Regex::new(pattern).unwrap(),
+    }
+}
+
+#[test]
+fn snapshot_has_no_windows() {
+    let grid = create_grid("c0|120[t0|80|1,r1|40[t0|60|2,t1|60|3]]", &["a", "b", "c"]);
+    let snapshot = LayoutSnapshot::of(&grid).unwrap();
+
+    assert_eq!(3, snapshot.slot_count());
+    assert_eq!(vec![&1, &2, &3], snapshot.tree.items());
+}
+
+#[test]
+fn empty_grid_has_no_snapshot() {
+    let grid = create_grid("", &[]);
+
+    assert_eq!(None, LayoutSnapshot::of(&grid));
+}
+
+#[test]
+fn apply_fills_slots_in_order() {
+    let snapshot = LayoutSnapshot::of(&create_grid(
+        "c0|120[t0|80|1,r1|40[t0|60|2,t1|60|3]]",
+        &["a", "b", "c"],
+    ))
+    .unwrap();
+    let mut grid = create_grid("r0|120[t0|40|1,t1|40|2,t2|40|3]", &["a", "b", "c"]);
+
+    snapshot.apply("test", &mut grid, &[]).unwrap();
+
+    assert_eq!("c0|120[t0|80|1,r1|40[t0|60|2,t1|60|3]]", grid.to_string());
+}
+
+#[test]
+fn apply_uses_rules_first() {
+    let snapshot =
+        LayoutSnapshot::of(&create_grid("c0|120[t0|80|1,t1|40|2]", &["a", "b"])).unwrap();
+    let mut grid = create_grid("r0|120[t0|60|1,t1|60|2]", &["firefox.exe", "code.exe"]);
+
+    snapshot
+        .apply("test", &mut grid, &[rule(1, "^code.exe$")])
+        .unwrap();
+
+    assert_eq!("c0|120[t0|80|2,t1|40|1]", grid.to_string());
+}
+
+#[test]
+fn apply_with_fewer_windows_drops_slots() {
+    let snapshot = LayoutSnapshot::of(&create_grid(
+        "c0|120[t0|80|1,r1|40[t0|60|2,t1|60|3]]",
+        &["a", "b", "c"],
+    ))
+    .unwrap();
+    let mut grid = create_grid("r0|120[t0|60|1,t1|60|2]", &["a", "b"]);
+
+    snapshot.apply("test", &mut grid, &[]).unwrap();
+
+    assert_eq!("c0|120[t0|80|1,t1|40|2]", grid.to_string());
+}
+
+#[test]
+fn apply_with_more_windows_pushes_them() {
+    let snapshot = LayoutSnapshot::of(&create_grid("t0|120|1", &["a"])).unwrap();
+    let mut grid = create_grid("r0|120[t0|60|1,t1|60|2]", &["a", "b"]);
+    grid.focused_id = None;
+
+    snapshot.apply("test", &mut grid, &[]).unwrap();
+
+    assert_eq!(
+        vec![1, 2],
+        grid.get_windows()
+            .iter()
+            .map(|w: &&NativeWindow| w.id.0)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn apply_rejects_unknown_slots() {
+    let snapshot = LayoutSnapshot::of(&create_grid("t0|120|1", &["a"])).unwrap();
+    let mut grid = create_grid("t0|120|1", &["a"]);
+
+    match snapshot.apply("test", &mut grid, &[rule(2, "a")]) {
+        Err(SnapshotError::InvalidSlot { slot: 2, .. }) => {}
+        x => panic!("Expected invalid slot, got {:?}", x),
+    }
+}
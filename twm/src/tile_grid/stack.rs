@@ -0,0 +1,171 @@
+use super::{
+    node::{Node, NodeInfo},
+    TileGrid, FULL_SIZE,
+};
+use crate::{
+    direction::Direction,
+    renderer::Renderer,
+    split_direction::SplitDirection,
+    system::{NativeWindow, SystemResult},
+};
+
+impl<TRenderer: Renderer> TileGrid<TRenderer> {
+    /// Moves the focused tile into the stack of the closest tile in the given direction. A tile that
+    /// isn't part of a stack yet becomes one. The moved tile is the visible tile of the stack
+    /// afterwards.
+    pub fn stack_focused(&mut self, direction: Direction) {
+        let focused_id = match self.focused_id {
+            Some(id) => id,
+            None => return,
+        };
+
+        let target_id = if self.layout.is_manual() {
+            self.find_neighbor(focused_id, direction)
+                .and_then(|id| self.graph.to_closest_tile(Some(id), Some(direction)))
+        } else {
+            self.get_neighbor_in_layout(focused_id, direction)
+        };
+
+        let target_id = match target_id {
+            Some(id) => id,
+            None => return,
+        };
+
+        // removing a tile never removes a different tile, so the target stays valid
+        let window_id = self.graph.node(focused_id).get_window().id;
+        let window = match self.remove_by_window_id(window_id) {
+            Some(window) => window,
+            None => return,
+        };
+
+        let stack_id = match self.graph.map_to_parent(Some(target_id)) {
+            Some(parent_id) if self.graph.node(parent_id).is_stack() => parent_id,
+            parent_id => {
+                let (order, size) = match parent_id {
+                    Some(_) => self.graph.node(target_id).get_info(),
+                    None => (0, FULL_SIZE),
+                };
+                let stack_id = self.graph.add_node(Node::stack(order, size, 0));
+
+                if let Some(parent_id) = parent_id {
+                    self.graph.disconnect(parent_id, target_id);
+                    self.graph.connect(parent_id, stack_id);
+                }
+
+                self.graph.node_mut(target_id).set_info(0, FULL_SIZE);
+                self.graph.connect(stack_id, target_id);
+                stack_id
+            }
+        };
+
+        let node_id = self
+            .graph
+            .add_node(Node::Tile((NodeInfo { order: 0, size: 0 }, window)));
+        self.add_to_stack(stack_id, node_id);
+        self.focused_id = Some(node_id);
+    }
+    /// Shows and focuses the next (or previous) tile of the stack that contains the focused tile.
+    /// Cycling past the last tile continues at the first one.
+    pub fn cycle_tab(&mut self, forward: bool) -> SystemResult {
+        let stack_id = match self
+            .graph
+            .map_to_parent(self.focused_id)
+            .filter(|id| self.graph.node(*id).is_stack())
+        {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let children = self.graph.get_sorted_children(stack_id);
+        let len = children.len();
+        let order = self.graph.node(self.focused_id.unwrap()).get_order() as usize;
+        let next_id = if forward {
+            children[(order + 1) % len]
+        } else {
+            children[(order + len - 1) % len]
+        };
+
+        self.focused_id = Some(next_id);
+        self.activate_tab(self.focused_id);
+
+        if self.is_fullscreened() {
+            self.fullscreen_id = self.focused_id;
+        }
+
+        // hidden windows can't get focused
+        let window = self.graph.node(next_id).get_window();
+        window.show();
+        window.focus()
+    }
+    /// Returns the windows of the stack that contains the focused tile in their order. The list is
+    /// empty if the focused tile isn't part of a stack.
+    pub fn get_tabs(&self) -> Vec<&NativeWindow> {
+        self.graph
+            .map_to_parent(self.focused_id)
+            .filter(|id| self.graph.node(*id).is_stack())
+            .map(|id| {
+                self.graph
+                    .get_sorted_children(id)
+                    .into_iter()
+                    .map(|child| self.graph.node(child).get_window())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    /// Returns the tiles of every stack and whether they are the visible tile of their stack
+    pub(super) fn get_stacked_tiles(&self) -> Vec<(usize, bool)> {
+        self.graph
+            .nodes()
+            .filter(|id| self.graph.node(*id).is_stack())
+            .flat_map(|stack_id| {
+                let active = self.graph.get_active_child(stack_id);
+                self.graph
+                    .get_children(stack_id)
+                    .into_iter()
+                    .map(move |id| (id, Some(id) == active))
+            })
+            .collect()
+    }
+    /// Makes the tile the visible one, if it's part of a stack
+    pub(super) fn activate_tab(&mut self, node_id: Option<usize>) {
+        if let Some(node_id) = node_id {
+            let order = self.graph.node(node_id).get_order();
+
+            if let Some(parent_id) = self.graph.map_to_parent(Some(node_id)) {
+                if let Node::Stack((_, active)) = self.graph.node_mut(parent_id) {
+                    *active = order;
+                }
+            }
+        }
+    }
+    /// Appends the node, which isn't part of the tree yet, to the stack and makes it the visible tile
+    pub(super) fn add_to_stack(&mut self, stack_id: usize, node_id: usize) {
+        let order = self.graph.get_children(stack_id).len() as u32;
+        let size = self.make_space_for_node(stack_id);
+
+        self.graph.node_mut(node_id).set_info(order, size);
+        self.graph.connect(stack_id, node_id);
+        self.activate_tab(Some(node_id));
+    }
+    /// Moves the focused tile out of its stack, so it ends up next to the stack in the given
+    /// direction. A stack that only has a single tile left gets replaced by the tile.
+    pub(super) fn move_out_of_stack(&mut self, stack_id: usize, direction: Direction) {
+        let focused_id = match self.focused_id {
+            Some(id) => id,
+            None => return,
+        };
+
+        let axis = match direction {
+            Direction::Left | Direction::Right => SplitDirection::Vertical,
+            Direction::Up | Direction::Down => SplitDirection::Horizontal,
+        };
+        let after = direction == Direction::Right || direction == Direction::Down;
+
+        self.disconnect_child(stack_id, focused_id);
+        self.insert_beside(stack_id, focused_id, axis, after);
+        self.bubble_siblingless_child(stack_id);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,83 @@
+use crate::{
+    direction::Direction,
+    renderer::NativeRenderer,
+    system::{NativeWindow, WindowId},
+    tile_grid::TileGrid,
+};
+
+fn create_grid(layout: &str, focused: i32) -> TileGrid {
+    let mut grid = TileGrid::new(1, NativeRenderer);
+    grid.load_string(layout).unwrap();
+    grid.focus_tile_by_window_id(WindowId(focused));
+    grid
+}
+
+#[test]
+fn stacks_onto_neighbor() {
+    let mut grid = create_grid("c0|120[t0|30|1,t1|30|2,t2|30|3,t3|30|4]", 3);
+
+    grid.stack_focused(Direction::Left);
+    assert_eq!(
+        "c0|120[t0|40|1,s1|40|1[t0|60|2,t1|60|3],t2|40|4]",
+        grid.to_string()
+    );
+    assert_eq!(Some(WindowId(3)), grid.get_focused_window().map(|w| w.id));
+    assert_eq!(
+        vec![WindowId(2), WindowId(3)],
+        grid.get_tabs().iter().map(|w| w.id).collect::<Vec<_>>()
+    );
+
+    grid.focus_tile_by_window_id(WindowId(4));
+    grid.stack_focused(Direction::Left);
+    assert_eq!(
+        "c0|120[t0|60|1,s1|60|2[t0|40|2,t1|40|3,t2|40|4]]",
+        grid.to_string()
+    );
+}
+
+#[test]
+fn renders_visible_tile_only() {
+    let grid = create_grid("c0|120[t0|60|1,s1|60|0[t0|60|2,t1|60|3]]", 1);
+    let rendered = grid
+        .get_render_info(100, 100)
+        .into_iter()
+        .map(|info| (info.window.id, info.x, info.width))
+        .collect::<Vec<_>>();
+
+    assert_eq!(vec![(WindowId(1), 0, 50), (WindowId(2), 50, 50)], rendered);
+}
+
+#[test]
+fn moves_in_and_out_of_stack() {
+    let mut grid = create_grid("c0|120[t0|60|1,s1|60|1[t0|60|2,t1|60|3]]", 3);
+
+    grid.move_focused_out(Direction::Right);
+    assert_eq!("c0|120[t0|40|1,t1|40|2,t2|40|3]", grid.to_string());
+
+    let mut grid = create_grid("c0|120[t0|60|1,s1|60|0[t0|60|2,t1|60|3]]", 1);
+
+    grid.move_focused_in(Direction::Right);
+    assert_eq!("s0|120|2[t0|40|2,t1|40|3,t2|40|1]", grid.to_string());
+}
+
+#[test]
+fn pushes_into_stack() {
+    let mut grid = create_grid("c0|120[t0|60|1,s1|60|1[t0|60|2,t1|60|3]]", 2);
+
+    grid.push(NativeWindow::from(WindowId(4)));
+    assert_eq!(
+        "c0|120[t0|60|1,s1|60|1[t0|40|2,t1|40|4,t2|40|3]]",
+        grid.to_string()
+    );
+}
+
+#[test]
+fn keeps_visible_tile_while_removing() {
+    let mut grid = create_grid("c0|120[t0|60|1,s1|60|1[t0|40|2,t1|40|3,t2|40|4]]", 1);
+
+    grid.remove_by_window_id(WindowId(2));
+    assert_eq!("c0|120[t0|60|1,s1|60|0[t0|60|3,t1|60|4]]", grid.to_string());
+
+    grid.remove_by_window_id(WindowId(3));
+    assert_eq!("c0|120[t0|60|1,t1|60|4]", grid.to_string());
+}
@@ -1,3 +1,4 @@
+use super::layout::Layout;
 use super::node::{Node, NodeInfo};
 use super::text_renderer::TextRenderer;
 use super::TileGrid;
@@ -10,7 +11,6 @@ use crate::{
 use crate::{direction::Direction, split_direction::SplitDirection};
 use lazy_static::lazy_static;
 use std::sync::Mutex;
-use winapi::shared::windef::{HMONITOR, HWND, RECT};
 
 fn create_window(id: i32) -> NativeWindow {
     let mut window = NativeWindow::new();
@@ -1425,21 +1425,25 @@ fn to_string_large_layout() {
 #[test]
 fn from_string_columns() {
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&"t0|120|1".into());
+    tile_grid.load_string("t0|120|1").unwrap();
     assert_eq!("t0|120|1", tile_grid.to_string());
 
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&"c0|120[t0|60|1,t1|60|2]".into());
+    tile_grid.load_string("c0|120[t0|60|1,t1|60|2]").unwrap();
     assert_eq!("c0|120[t0|60|1,t1|60|2]", tile_grid.to_string());
 
     // testing three tiles pushed in
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&"c0|120[t0|40|1,t1|40|2,t2|40|3]".into());
+    tile_grid
+        .load_string("c0|120[t0|40|1,t1|40|2,t2|40|3]")
+        .unwrap();
     assert_eq!("c0|120[t0|40|1,t1|40|2,t2|40|3]", tile_grid.to_string());
 
     // testing four tiles pushed in
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&"c0|120[t0|30|1,t1|30|2,t2|30|3,t3|30|4]".into());
+    tile_grid
+        .load_string("c0|120[t0|30|1,t1|30|2,t2|30|3,t3|30|4]")
+        .unwrap();
     assert_eq!(
         "c0|120[t0|30|1,t1|30|2,t2|30|3,t3|30|4]",
         tile_grid.to_string()
@@ -1450,22 +1454,26 @@ fn from_string_columns() {
 fn from_string_rows() {
     // testing just one tile
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&"t0|120|1".into());
+    tile_grid.load_string("t0|120|1").unwrap();
     assert_eq!("t0|120|1", tile_grid.to_string());
 
     // testing two tiles pushed in
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&"r0|120[t0|60|1,t1|60|2]".into());
+    tile_grid.load_string("r0|120[t0|60|1,t1|60|2]").unwrap();
     assert_eq!("r0|120[t0|60|1,t1|60|2]", tile_grid.to_string());
 
     // testing three tiles pushed in
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&"r0|120[t0|40|1,t1|40|2,t2|40|3]".into());
+    tile_grid
+        .load_string("r0|120[t0|40|1,t1|40|2,t2|40|3]")
+        .unwrap();
     assert_eq!("r0|120[t0|40|1,t1|40|2,t2|40|3]", tile_grid.to_string());
 
     // testing four tiles pushed in
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&"r0|120[t0|30|1,t1|30|2,t2|30|3,t3|30|4]".into());
+    tile_grid
+        .load_string("r0|120[t0|30|1,t1|30|2,t2|30|3,t3|30|4]")
+        .unwrap();
     assert_eq!(
         "r0|120[t0|30|1,t1|30|2,t2|30|3,t3|30|4]",
         tile_grid.to_string()
@@ -1482,7 +1490,9 @@ fn from_string_children() {
           t1 t2 t3
     */
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&"c0|120[t0|60|1,r1|60[t0|40|2,t1|40|3,t2|40|4]]".into());
+    tile_grid
+        .load_string("c0|120[t0|60|1,r1|60[t0|40|2,t1|40|3,t2|40|4]]")
+        .unwrap();
     assert_eq!(
         "c0|120[t0|60|1,r1|60[t0|40|2,t1|40|3,t2|40|4]]",
         tile_grid.to_string()
@@ -1493,7 +1503,7 @@ fn from_string_children() {
 fn from_string_large_layout() {
     let large_layout_string = "c0|120[t0|60|1,r1|60[t0|24|2,t1|24|3,c2|24[t0|24|6,t1|24|7,r2|24[t0|40|10,t1|40|12,t2|40|11],t3|24|9,t4|24|8],t3|24|5,t4|24|4]]";
     let mut tile_grid = TileGrid::new(0, TestRenderer {});
-    tile_grid.from_string(&large_layout_string.into());
+    tile_grid.load_string(large_layout_string).unwrap();
     assert_eq!(large_layout_string, tile_grid.to_string());
 }
 
@@ -1569,6 +1579,150 @@ fn remove_merges_rows() {
     assert_eq!(3, node_3);
 }
 
+fn get_window_areas(tile_grid: &TileGrid<TestRenderer>) -> Vec<(i32, u32, u32, u32, u32)> {
+    let mut areas = tile_grid
+        .get_render_info(100, 50)
+        .into_iter()
+        .map(|info| {
+            (
+                info.window.id.into(),
+                info.x,
+                info.y,
+                info.width,
+                info.height,
+            )
+        })
+        .collect::<Vec<_>>();
+    areas.sort();
+    areas
+}
+
+fn get_focused_window_id(tile_grid: &TileGrid<TestRenderer>) -> i32 {
+    get_window_id(tile_grid, tile_grid.focused_id.unwrap())
+}
+
+#[test]
+fn layout_uses_order_of_tree() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.layout = Layout::master_stack(1, 0.6).unwrap();
+    perform_actions(&mut tile_grid, "p,p,axh,p,fl,p");
+    /*
+        Tree: c(1, r(2, 3)) with 4 pushed after 1 -> order 1 4 2 3
+    */
+
+    assert_eq!(
+        vec![
+            (1, 0, 0, 60, 50),
+            (2, 60, 17, 40, 17),
+            (3, 60, 34, 40, 16),
+            (4, 60, 0, 40, 17)
+        ],
+        get_window_areas(&tile_grid)
+    );
+}
+
+#[test]
+fn layout_renders_focused_tile_last() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.layout = Layout::Monocle;
+    perform_actions(&mut tile_grid, "p,p,p,fl");
+
+    let render_infos = tile_grid.get_render_info(100, 50);
+    let last: i32 = render_infos.last().unwrap().window.id.into();
+
+    assert_eq!(2, last);
+}
+
+#[test]
+fn fullscreen_overrides_layout() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.layout = Layout::Grid;
+    perform_actions(&mut tile_grid, "p,p,p,full");
+
+    assert_eq!(vec![(3, 0, 0, 100, 50)], get_window_areas(&tile_grid));
+}
+
+#[test]
+fn layout_focus_uses_areas() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.layout = Layout::master_stack(1, 0.6).unwrap();
+    perform_actions(&mut tile_grid, "p,p,p");
+
+    perform_actions(&mut tile_grid, "fu");
+    assert_eq!(2, get_focused_window_id(&tile_grid));
+
+    perform_actions(&mut tile_grid, "fl");
+    assert_eq!(1, get_focused_window_id(&tile_grid));
+
+    perform_actions(&mut tile_grid, "fl");
+    assert_eq!(1, get_focused_window_id(&tile_grid));
+
+    perform_actions(&mut tile_grid, "fr");
+    assert_eq!(2, get_focused_window_id(&tile_grid));
+}
+
+#[test]
+fn monocle_focus_cycles_through_order() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.layout = Layout::Monocle;
+    perform_actions(&mut tile_grid, "p,p,p");
+
+    perform_actions(&mut tile_grid, "fl,fl");
+    assert_eq!(1, get_focused_window_id(&tile_grid));
+
+    perform_actions(&mut tile_grid, "fu");
+    assert_eq!(1, get_focused_window_id(&tile_grid));
+
+    perform_actions(&mut tile_grid, "fd");
+    assert_eq!(2, get_focused_window_id(&tile_grid));
+}
+
+#[test]
+fn layout_swap_exchanges_windows() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.layout = Layout::master_stack(1, 0.5).unwrap();
+    perform_actions(&mut tile_grid, "p,p,p,sl");
+
+    assert_eq!(3, get_focused_window_id(&tile_grid));
+    assert_eq!(
+        vec![(1, 50, 25, 50, 25), (2, 50, 0, 50, 25), (3, 0, 0, 50, 50)],
+        get_window_areas(&tile_grid)
+    );
+}
+
+#[test]
+fn tree_round_trip() {
+    let layout = "c0|120[t0|60|1,r1|60[t0|24|2,t1|48|3,c2|48[t0|60|4,t1|60|5]]]";
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid.load_string(layout).unwrap();
+    tile_grid.focus_tile_by_window_id(WindowId(3));
+    tile_grid.toggle_fullscreen();
+
+    let tree = tile_grid.to_tree(|w| w.id.0).unwrap();
+    let mut restored = TileGrid::new(0, TestRenderer {});
+    restored.load_tree(tree.filter_map(&mut |id| Some(create_window(id))).unwrap());
+
+    assert_eq!(layout, restored.to_string());
+    assert_eq!(3, get_focused_window_id(&restored));
+    assert!(restored.is_fullscreened());
+}
+
+#[test]
+fn tree_without_windows_keeps_grid_valid() {
+    let mut tile_grid = TileGrid::new(0, TestRenderer {});
+    tile_grid
+        .load_string("c0|120[t0|60|1,r1|60[t0|60|2,t1|60|3]]")
+        .unwrap();
+
+    let tree = tile_grid.to_tree(|w| w.id.0).unwrap();
+    let tree = tree
+        .filter_map(&mut |id| Some(create_window(id)).filter(|_| id != 2))
+        .unwrap();
+    tile_grid.load_tree(tree);
+
+    assert_eq!("c0|120[t0|60|1,t1|60|3]", tile_grid.to_string());
+}
+
 fn print(tile_grid: &TileGrid) {
     let render_infos = tile_grid.get_render_info(127, 90);
     println!("{}", TextRenderer::render(127, 90, render_infos));
@@ -0,0 +1,223 @@
+use super::FULL_SIZE;
+
+/// The column/row tree of a grid without being tied to the graph or to actual windows.
+///
+/// Every tile holds an `item`, which is whatever describes the window of that tile. This makes it
+/// possible to store a grid and to build it again later with different windows.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GridTree<T> {
+    Column {
+        size: u32,
+        children: Vec<GridTree<T>>,
+    },
+    Row {
+        size: u32,
+        children: Vec<GridTree<T>>,
+    },
+    Tile {
+        size: u32,
+        focused: bool,
+        fullscreen: bool,
+        item: T,
+    },
+    /// Only contains tiles. `active` is the index of the visible one.
+    Stack {
+        size: u32,
+        active: usize,
+        children: Vec<GridTree<T>>,
+    },
+}
+
+impl<T> GridTree<T> {
+    pub fn size(&self) -> u32 {
+        match self {
+            GridTree::Column { size, .. }
+            | GridTree::Row { size, .. }
+            | GridTree::Tile { size, .. }
+            | GridTree::Stack { size, .. } => *size,
+        }
+    }
+
+    fn set_size(&mut self, new_size: u32) {
+        match self {
+            GridTree::Column { size, .. }
+            | GridTree::Row { size, .. }
+            | GridTree::Tile { size, .. }
+            | GridTree::Stack { size, .. } => *size = new_size,
+        }
+    }
+
+    fn with_size(mut self, size: u32) -> Self {
+        self.set_size(size);
+        self
+    }
+
+    /// Returns the items of every tile in the order they appear in the tree
+    pub fn items(&self) -> Vec<&T> {
+        match self {
+            GridTree::Column { children, .. }
+            | GridTree::Row { children, .. }
+            | GridTree::Stack { children, .. } => children.iter().flat_map(|c| c.items()).collect(),
+            GridTree::Tile { item, .. } => vec![item],
+        }
+    }
+
+    /// Maps the item of every tile and drops the tiles where `f` returns `None`.
+    ///
+    /// The resulting tree is kept valid: empty columns and rows get removed, a column or row with
+    /// a single child gets replaced by that child, nested columns (or rows) get merged into their
+    /// parent and the sizes of every list of children add up to the full size again. The same goes
+    /// for stacks, which also get their nested containers replaced by the tiles of them.
+    pub fn filter_map<U>(self, f: &mut impl FnMut(T) -> Option<U>) -> Option<GridTree<U>> {
+        match self {
+            GridTree::Tile {
+                size,
+                focused,
+                fullscreen,
+                item,
+            } => f(item).map(|item| GridTree::Tile {
+                size,
+                focused,
+                fullscreen,
+                item,
+            }),
+            GridTree::Column { size, children } => {
+                let children = children
+                    .into_iter()
+                    .filter_map(|c| c.filter_map(f))
+                    .collect();
+                GridTree::container(true, size, children)
+            }
+            GridTree::Row { size, children } => {
+                let children = children
+                    .into_iter()
+                    .filter_map(|c| c.filter_map(f))
+                    .collect();
+                GridTree::container(false, size, children)
+            }
+            GridTree::Stack {
+                size,
+                active,
+                children,
+            } => {
+                let mut tiles = Vec::new();
+                let mut new_active = 0;
+
+                for (i, child) in children.into_iter().enumerate() {
+                    if i == active {
+                        new_active = tiles.len();
+                    }
+                    if let Some(child) = child.filter_map(f) {
+                        tiles.extend(child.into_tiles());
+                    }
+                }
+
+                match tiles.len() {
+                    0 => None,
+                    1 => tiles.pop().map(|tile| tile.with_size(size)),
+                    len => {
+                        normalize_sizes(&mut tiles);
+                        Some(GridTree::Stack {
+                            size,
+                            // the next tile becomes visible if the visible one got dropped
+                            active: new_active.min(len - 1),
+                            children: tiles,
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    fn into_tiles(self) -> Vec<GridTree<T>> {
+        match self {
+            GridTree::Column { children, .. }
+            | GridTree::Row { children, .. }
+            | GridTree::Stack { children, .. } => {
+                children.into_iter().flat_map(|c| c.into_tiles()).collect()
+            }
+            tile => vec![tile],
+        }
+    }
+
+    /// Builds a column or row out of children, which are already valid on their own
+    fn container(is_column: bool, size: u32, children: Vec<GridTree<T>>) -> Option<GridTree<T>> {
+        let mut flattened = Vec::new();
+
+        for child in children {
+            match child {
+                GridTree::Column {
+                    size: child_size,
+                    children,
+                } if is_column => {
+                    flattened.extend(children.into_iter().map(|c| c.scaled(child_size)))
+                }
+                GridTree::Row {
+                    size: child_size,
+                    children,
+                } if !is_column => {
+                    flattened.extend(children.into_iter().map(|c| c.scaled(child_size)))
+                }
+                child => flattened.push(child),
+            }
+        }
+
+        match flattened.len() {
+            0 => None,
+            1 => flattened.pop().map(|child| child.with_size(size)),
+            _ => {
+                normalize_sizes(&mut flattened);
+                Some(if is_column {
+                    GridTree::Column {
+                        size,
+                        children: flattened,
+                    }
+                } else {
+                    GridTree::Row {
+                        size,
+                        children: flattened,
+                    }
+                })
+            }
+        }
+    }
+
+    /// Scales the size of a child of a column/row, which itself has the given size
+    fn scaled(self, parent_size: u32) -> Self {
+        let size = self.size() * parent_size / FULL_SIZE;
+        self.with_size(size)
+    }
+}
+
+/// Changes the sizes of the nodes, so they add up to the full size while keeping their ratio.
+/// If none of the nodes has a size, they all get the same share.
+fn normalize_sizes<T>(nodes: &mut [GridTree<T>]) {
+    let count = nodes.len() as u32;
+    let total: u32 = nodes.iter().map(|n| n.size()).sum();
+    let mut sizes = nodes
+        .iter()
+        .map(|n| {
+            (n.size() * FULL_SIZE)
+                .checked_div(total)
+                .unwrap_or(FULL_SIZE / count)
+        })
+        .collect::<Vec<u32>>();
+
+    // every size lost less than one while rounding down, so the remainder is smaller than the
+    // amount of nodes
+    let mut remainder = FULL_SIZE - sizes.iter().sum::<u32>();
+    for size in sizes.iter_mut() {
+        if remainder == 0 {
+            break;
+        }
+        *size += 1;
+        remainder -= 1;
+    }
+
+    for (node, size) in nodes.iter_mut().zip(sizes) {
+        node.set_size(size);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,194 @@
+use super::GridTree;
+
+fn tile(size: u32, item: i32) -> GridTree<i32> {
+    GridTree::Tile {
+        size,
+        focused: false,
+        fullscreen: false,
+        item,
+    }
+}
+
+fn keep_all_but(removed: i32) -> impl FnMut(i32) -> Option<i32> {
+    move |item| Some(item).filter(|item| *item != removed)
+}
+
+#[test]
+fn filter_map_keeps_valid_tree() {
+    let tree = GridTree::Column {
+        size: 120,
+        children: vec![tile(60, 1), tile(60, 2)],
+    };
+
+    assert_eq!(Some(tree.clone()), tree.filter_map(&mut Some));
+}
+
+#[test]
+fn filter_map_renormalizes_sizes() {
+    let tree = GridTree::Row {
+        size: 120,
+        children: vec![tile(40, 1), tile(20, 2), tile(60, 3)],
+    };
+
+    assert_eq!(
+        Some(GridTree::Row {
+            size: 120,
+            children: vec![tile(48, 1), tile(72, 3)],
+        }),
+        tree.filter_map(&mut keep_all_but(2))
+    );
+}
+
+#[test]
+fn filter_map_collapses_single_child() {
+    let tree = GridTree::Column {
+        size: 120,
+        children: vec![
+            tile(60, 1),
+            GridTree::Row {
+                size: 60,
+                children: vec![tile(60, 2), tile(60, 3)],
+            },
+        ],
+    };
+
+    assert_eq!(
+        Some(GridTree::Column {
+            size: 120,
+            children: vec![tile(60, 1), tile(60, 3)],
+        }),
+        tree.filter_map(&mut keep_all_but(2))
+    );
+}
+
+#[test]
+fn filter_map_merges_nested_lists_of_same_type() {
+    // removing 2 leaves a column inside of a column
+    let tree = GridTree::Column {
+        size: 120,
+        children: vec![
+            tile(60, 1),
+            GridTree::Row {
+                size: 60,
+                children: vec![
+                    tile(60, 2),
+                    GridTree::Column {
+                        size: 60,
+                        children: vec![tile(60, 3), tile(60, 4)],
+                    },
+                ],
+            },
+        ],
+    };
+
+    assert_eq!(
+        Some(GridTree::Column {
+            size: 120,
+            children: vec![tile(60, 1), tile(30, 3), tile(30, 4)],
+        }),
+        tree.filter_map(&mut keep_all_but(2))
+    );
+}
+
+#[test]
+fn filter_map_removes_empty_tree() {
+    let tree = GridTree::Column {
+        size: 120,
+        children: vec![tile(60, 1), tile(60, 1)],
+    };
+
+    assert_eq!(None, tree.filter_map(&mut keep_all_but(1)));
+}
+
+#[test]
+fn filter_map_shares_missing_sizes() {
+    let tree = GridTree::Row {
+        size: 120,
+        children: vec![
+            tile(0, 1),
+            tile(0, 2),
+            tile(0, 3),
+            tile(0, 4),
+            tile(0, 5),
+            tile(0, 6),
+            tile(0, 7),
+        ],
+    };
+
+    let sizes = match tree.filter_map(&mut Some) {
+        Some(GridTree::Row { children, .. }) => children.iter().map(|c| c.size()).collect(),
+        _ => Vec::new(),
+    };
+
+    assert_eq!(vec![18, 17, 17, 17, 17, 17, 17], sizes);
+}
+
+#[test]
+fn items_are_in_order() {
+    let tree = GridTree::Column {
+        size: 120,
+        children: vec![
+            tile(60, 1),
+            GridTree::Row {
+                size: 60,
+                children: vec![tile(60, 2), tile(60, 3)],
+            },
+        ],
+    };
+
+    assert_eq!(vec![&1, &2, &3], tree.items());
+}
+
+#[test]
+fn filter_map_shows_next_tile_of_stack() {
+    let tree = GridTree::Stack {
+        size: 60,
+        active: 1,
+        children: vec![tile(40, 1), tile(40, 2), tile(40, 3)],
+    };
+
+    assert_eq!(
+        Some(GridTree::Stack {
+            size: 60,
+            active: 1,
+            children: vec![tile(60, 1), tile(60, 3)],
+        }),
+        tree.filter_map(&mut keep_all_but(2))
+    );
+}
+
+#[test]
+fn filter_map_flattens_stack() {
+    let tree = GridTree::Column {
+        size: 120,
+        children: vec![
+            tile(60, 1),
+            GridTree::Stack {
+                size: 60,
+                active: 0,
+                children: vec![
+                    GridTree::Row {
+                        size: 60,
+                        children: vec![tile(60, 2), tile(60, 3)],
+                    },
+                    tile(60, 4),
+                ],
+            },
+        ],
+    };
+
+    assert_eq!(
+        Some(GridTree::Column {
+            size: 120,
+            children: vec![
+                tile(60, 1),
+                GridTree::Stack {
+                    size: 60,
+                    active: 0,
+                    children: vec![tile(40, 2), tile(40, 3), tile(40, 4)],
+                },
+            ],
+        }),
+        tree.filter_map(&mut Some)
+    );
+}
@@ -68,7 +68,7 @@ pub fn create(state: Arc<Mutex<AppState>>) {
                     if let Some(id) = PopupId::from_u16(LOWORD(msg.params.0 as u32)) {
                         match id {
                             PopupId::Exit => unsafe {
-                                PostMessageW(msg.hwnd, WM_CLOSE, 0, 0);
+                                PostMessageW(msg.hwnd.into(), WM_CLOSE, 0, 0);
                                 sender.send(Event::Exit).expect("Failed to send event");
                             },
                             PopupId::Reload => {
@@ -80,9 +80,9 @@ pub fn create(state: Arc<Mutex<AppState>>) {
                     }
                 } else if msg.code == WM_APP && msg.params.1 as u32 == WM_RBUTTONUP {
                     unsafe {
-                        SetForegroundWindow(msg.hwnd);
-                        show_popup_menu(msg.hwnd);
-                        PostMessageW(msg.hwnd, WM_APP + 1, 0, 0);
+                        SetForegroundWindow(msg.hwnd.into());
+                        show_popup_menu(msg.hwnd.into());
+                        PostMessageW(msg.hwnd.into(), WM_APP + 1, 0, 0);
                     }
                 }
             }
@@ -1,31 +1,426 @@
-#![allow(unreachable_code)]
-#![allow(dead_code)]
-
-use crate::AppState;
+//! Keeps nog up to date with the releases of its update channel.
+//!
+//! Applying an update only stages the new files, because Windows doesn't allow overwriting the
+//! executable of a running process. The staged files replace the installed ones when nog quits,
+//! which works by renaming the running executable out of the way.
+use crate::{
+    config::update_channel::{semantic_version::SemanticVersion, UpdateChannel},
+    config::Config,
+    event::Event,
+    get_config_path, get_runtime_path,
+    popup::Popup,
+    AppState,
+};
+use log::{debug, error, info};
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
     sync::atomic::{AtomicBool, Ordering},
+    sync::Arc,
     thread,
 };
+use thiserror::Error;
+
+pub mod directory;
+#[cfg(not(feature = "headless"))]
+pub mod github;
+
+static STOP: AtomicBool = AtomicBool::new(false);
+
+/// The name of the archive that every release contains
+pub const ARCHIVE_NAME: &str = "Nog.zip";
+
+#[derive(Error, Debug)]
+pub enum UpdateError {
+    #[error("No update channel is configured")]
+    NoChannel,
+    #[error("Failed to access the update files: {0}")]
+    Io(#[from] io::Error),
+    #[error("The archive of the release is invalid: {0}")]
+    Archive(#[from] zip::result::ZipError),
+    #[error("Failed to fetch the releases: {0}")]
+    Source(String),
+    #[error("The archive has the checksum {actual}, but the release says {expected}")]
+    Checksum { expected: String, actual: String },
+}
+
+pub type UpdateResult<T = ()> = Result<T, UpdateError>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Release {
+    pub version: SemanticVersion,
+    /// Where the source finds the archive, like a url or a path
+    pub archive: String,
+    /// Where the source finds the file that contains the SHA-256 hash of the archive
+    pub checksum: String,
+}
+
+/// Lists the releases of an update channel and downloads their archives
+pub trait ReleaseSource: Send {
+    /// The releases in any order
+    fn releases(&self) -> UpdateResult<Vec<Release>>;
+    /// The SHA-256 hash of the archive of the release as a hex string
+    fn checksum(&self, release: &Release) -> UpdateResult<String>;
+    /// Writes the archive of the release into `writer`
+    fn download(&self, release: &Release, writer: &mut dyn Write) -> UpdateResult;
+}
+
+/// The release source of the channel. Headless builds can only update from a directory.
+pub fn source(channel: &UpdateChannel) -> UpdateResult<Box<dyn ReleaseSource>> {
+    if let Some(path) = &channel.path {
+        return Ok(Box::new(directory::DirectorySource::new(path.clone())));
+    }
+
+    #[cfg(not(feature = "headless"))]
+    return Ok(Box::new(github::GithubSource::new(
+        channel.repo.clone(),
+        channel.branch.clone(),
+    )));
+
+    #[cfg(feature = "headless")]
+    Err(UpdateError::Source(format!(
+        "the update channel {} has no path",
+        channel.name
+    )))
+}
+
+/// The version of the running nog. Builds of a branch don't have one, so they use the version of
+/// the crate.
+pub fn current_version() -> SemanticVersion {
+    option_env!("NOG_VERSION")
+        .and_then(|version| SemanticVersion::from_str(version).ok())
+        .unwrap_or_else(|| SemanticVersion::from(env!("CARGO_PKG_VERSION").to_string()))
+}
+
+/// The directory that holds the downloaded archives and the staged files
+pub fn get_updates_path() -> PathBuf {
+    let mut path = get_config_path();
+    path.push("updates");
+    path
+}
+
+/// Where the files of a release get installed
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstallDirs {
+    pub bin: PathBuf,
+    pub runtime: PathBuf,
+}
 
-const STOP: AtomicBool = AtomicBool::new(false);
+impl InstallDirs {
+    /// The `bin` folder is the folder of the executable and the `runtime` folder is the one nog
+    /// loads its runtime from
+    pub fn from_exe(exe: &Path, runtime: PathBuf) -> Self {
+        Self {
+            bin: exe.parent().map(Path::to_path_buf).unwrap_or_default(),
+            runtime,
+        }
+    }
+
+    /// The folders of the running nog
+    pub fn current() -> io::Result<Self> {
+        Ok(Self::from_exe(
+            &std::env::current_exe()?,
+            get_runtime_path(),
+        ))
+    }
+
+    /// Where a file of the archive gets installed, where `path` is relative to the `Nog` folder
+    fn target(&self, path: &Path) -> Option<PathBuf> {
+        if let Ok(path) = path.strip_prefix("bin") {
+            Some(self.bin.join(path))
+        } else if let Ok(path) = path.strip_prefix("runtime") {
+            Some(self.runtime.join(path))
+        } else {
+            None
+        }
+    }
+}
 
-pub fn start(state: &AppState) -> Result<(), ()> {
-    if let Some(_update_channel) = state.config.get_update_channel() {
-        let update_interval = state.config.update_interval;
+pub fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+pub struct Updater {
+    pub source: Box<dyn ReleaseSource>,
+    pub current: SemanticVersion,
+    pub pre_releases: bool,
+    /// Holds the downloaded archives and the staged files
+    pub dir: PathBuf,
+}
+
+impl Updater {
+    /// The updater of the default update channel
+    pub fn from_config(config: &Config) -> UpdateResult<Self> {
+        let channel = config.get_update_channel().ok_or(UpdateError::NoChannel)?;
+
+        Ok(Self {
+            source: source(channel)?,
+            current: current_version(),
+            pre_releases: channel.pre_releases,
+            dir: get_updates_path(),
+        })
+    }
 
-        thread::spawn(move || {
-            while !STOP.load(Ordering::SeqCst) {
-                todo!();
-                thread::sleep(update_interval);
+    /// The newest release if it is newer than the running version
+    pub fn check(&self) -> UpdateResult<Option<Release>> {
+        Ok(self
+            .source
+            .releases()?
+            .into_iter()
+            .filter(|release| self.pre_releases || !release.version.is_pre_release())
+            .filter(|release| release.version > self.current)
+            .max_by(|a, b| a.version.cmp(&b.version)))
+    }
+
+    /// Downloads the archive of the release, verifies its checksum and extracts it into the
+    /// staging directory. The config folder of the archive gets skipped to keep the config of the
+    /// user.
+    pub fn stage(&self, release: &Release) -> UpdateResult {
+        fs::create_dir_all(&self.dir)?;
+        let expected = self.source.checksum(release)?;
+
+        let archive_path = self.dir.join(format!("Nog-{}.zip", release.version));
+        debug!("Downloading {} to {:?}", release.archive, archive_path);
+        self.source
+            .download(release, &mut File::create(&archive_path)?)?;
+
+        let mut bytes = Vec::new();
+        File::open(&archive_path)?.read_to_end(&mut bytes)?;
+        let actual = sha256(&bytes);
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            fs::remove_file(&archive_path)?;
+            return Err(UpdateError::Checksum { expected, actual });
+        }
+
+        let staged_path = staged_path(&self.dir);
+        if staged_path.exists() {
+            fs::remove_dir_all(&staged_path)?;
+        }
+
+        let mut archive = zip::ZipArchive::new(io::Cursor::new(bytes))?;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let path = match file.enclosed_name().and_then(installed_path) {
+                Some(path) if !file.is_dir() => path,
+                _ => continue,
+            };
+
+            let path = staged_path.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
             }
-        });
+            io::copy(&mut file, &mut File::create(path)?)?;
+        }
+
+        fs::write(staged_version_path(&self.dir), release.version.to_string())?;
+        fs::remove_file(&archive_path)?;
 
-        STOP.store(false, Ordering::SeqCst);
+        info!("Staged nog {}", release.version);
+
+        Ok(())
+    }
+}
+
+fn staged_path(dir: &Path) -> PathBuf {
+    dir.join("staged")
+}
+
+fn staged_version_path(dir: &Path) -> PathBuf {
+    dir.join("staged_version")
+}
+
+/// The version whose files get installed when nog quits
+pub fn staged_version(dir: &Path) -> Option<SemanticVersion> {
+    let version = fs::read_to_string(staged_version_path(dir)).ok()?;
+    SemanticVersion::from_str(&version).ok()
+}
+
+/// Replaces the installed files with the files that got staged in `dir`. Files that get replaced
+/// keep existing with an `.old` suffix until [`remove_old_files`] runs, because the running
+/// executable can't be removed.
+pub fn install_staged(
+    dir: &Path,
+    install_dirs: &InstallDirs,
+) -> UpdateResult<Option<SemanticVersion>> {
+    let version = match staged_version(dir) {
+        Some(version) => version,
+        None => return Ok(None),
+    };
+
+    let staged_path = staged_path(dir);
+    for path in files(&staged_path)? {
+        let target = match install_dirs.target(path.strip_prefix(&staged_path).unwrap()) {
+            Some(target) => target,
+            None => continue,
+        };
+        if target.exists() {
+            let old = old_path(&target);
+            if old.exists() {
+                fs::remove_file(&old)?;
+            }
+            fs::rename(&target, &old)?;
+        } else if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&path, &target)?;
+    }
+
+    fs::remove_dir_all(&staged_path)?;
+    fs::remove_file(staged_version_path(dir))?;
+
+    Ok(Some(version))
+}
+
+/// The path of an entry of the archive relative to the install directory. Releases contain a
+/// `Nog` folder with the `bin`, `config` and `runtime` folders.
+fn installed_path(path: &Path) -> Option<PathBuf> {
+    let path = path.strip_prefix("Nog").unwrap_or(path);
+    match path.components().next() {
+        Some(Component::Normal(dir)) if dir == "bin" || dir == "runtime" => Some(path.into()),
+        _ => None,
+    }
+}
+
+fn old_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".old");
+    path.with_file_name(name)
+}
+
+/// Every file in the directory and its subdirectories
+fn files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+
+    if dir.is_dir() {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                paths.extend(files(&path)?);
+            } else {
+                paths.push(path);
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Removes the files that got replaced by the last update
+pub fn remove_old_files(install_dirs: &InstallDirs) -> io::Result<()> {
+    for dir in &[&install_dirs.bin, &install_dirs.runtime] {
+        for path in files(dir)? {
+            if path.extension().is_some_and(|ext| ext == "old") {
+                fs::remove_file(path)?;
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Checks for a new release and stages it if `apply` is set, which happens on its own thread,
+/// because downloading the release can take a while. The outcome gets passed to the callback as a
+/// table with the `current` version, the `version` of the new release and an `error`, or gets
+/// shown in a popup without a callback.
+pub fn run_in_background(state_arc: Arc<Mutex<AppState>>, apply: bool, callback: Option<usize>) {
+    thread::spawn(move || {
+        let updater = Updater::from_config(&state_arc.lock().config);
+        let res = updater.and_then(|updater| {
+            let release = updater.check()?;
+            if let Some(release) = release.as_ref().filter(|_| apply) {
+                if staged_version(&updater.dir).as_ref() != Some(&release.version) {
+                    updater.stage(release)?;
+                }
+            }
+            Ok(release)
+        });
+
+        let callback = match callback {
+            Some(callback) => callback,
+            None => {
+                let text = match &res {
+                    Ok(Some(release)) if apply => {
+                        format!("Nog {} gets installed when nog quits.", release.version)
+                    }
+                    Ok(Some(release)) => format!("Nog {} is available.", release.version),
+                    Ok(None) => "Nog is up to date.".into(),
+                    Err(e) => e.to_string(),
+                };
+                Popup::show(vec![text], state_arc);
+                return;
+            }
+        };
+
+        let mut arg = Map::new();
+        arg.insert("current".into(), current_version().to_string().into());
+        match res {
+            Ok(Some(release)) => {
+                arg.insert("version".into(), release.version.to_string().into());
+            }
+            Ok(None) => {}
+            Err(e) => {
+                arg.insert("error".into(), e.to_string().into());
+            }
+        }
+
+        state_arc
+            .lock()
+            .event_channel
+            .sender
+            .send(Event::CallCallback {
+                idx: callback,
+                arg: Some(Value::Object(arg)),
+            })
+            .unwrap();
+    });
+}
+
+/// Checks the default update channel for new releases every `update_interval` and tells the
+/// user about each new version once.
+pub fn start(state_arc: Arc<Mutex<AppState>>) {
+    STOP.store(false, Ordering::SeqCst);
+
+    thread::spawn(move || {
+        let mut notified: Option<SemanticVersion> = None;
+
+        while !STOP.load(Ordering::SeqCst) {
+            let (updater, update_interval) = {
+                let state = state_arc.lock();
+                (
+                    Updater::from_config(&state.config),
+                    state.config.update_interval,
+                )
+            };
+
+            match updater.and_then(|updater| updater.check()) {
+                Ok(Some(release)) if notified.as_ref() != Some(&release.version) => {
+                    info!("Nog {} is available", release.version);
+                    Popup::show(
+                        vec![
+                            format!("Nog {} is available.", release.version),
+                            "Call nog.update_apply() to install it when nog quits.".into(),
+                        ],
+                        state_arc.clone(),
+                    );
+                    notified = Some(release.version);
+                }
+                Ok(_) | Err(UpdateError::NoChannel) => {}
+                Err(e) => error!("Failed to check for updates: {}", e),
+            }
+
+            thread::sleep(update_interval);
+        }
+    });
+}
+
 pub fn stop() {
     STOP.store(true, Ordering::SeqCst);
 }
+
+#[cfg(test)]
+mod tests;
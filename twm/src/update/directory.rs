@@ -0,0 +1,70 @@
+use super::{Release, ReleaseSource, UpdateResult, ARCHIVE_NAME};
+use crate::config::update_channel::semantic_version::SemanticVersion;
+use log::warn;
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// Releases that live in a directory, like a network share. Every release is a folder named after
+/// its version, which contains the archive and a file with its checksum:
+///
+/// ```text
+/// releases/
+///   v0.14.0/
+///     Nog.zip
+///     Nog.zip.sha256
+/// ```
+pub struct DirectorySource {
+    path: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl ReleaseSource for DirectorySource {
+    fn releases(&self) -> UpdateResult<Vec<Release>> {
+        let mut releases = Vec::new();
+
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            let version = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| SemanticVersion::from_str(name).ok());
+            let version = match version {
+                Some(version) if path.is_dir() => version,
+                _ => continue,
+            };
+
+            let checksum = path.join(format!("{}.sha256", ARCHIVE_NAME));
+            if !checksum.is_file() {
+                warn!("Skipping {:?}, because it has no checksum", path);
+                continue;
+            }
+
+            releases.push(Release {
+                version,
+                archive: path.join(ARCHIVE_NAME).to_string_lossy().into(),
+                checksum: checksum.to_string_lossy().into(),
+            });
+        }
+
+        Ok(releases)
+    }
+
+    fn checksum(&self, release: &Release) -> UpdateResult<String> {
+        let content = fs::read_to_string(&release.checksum)?;
+        Ok(content.split_whitespace().next().unwrap_or_default().into())
+    }
+
+    fn download(&self, release: &Release, writer: &mut dyn Write) -> UpdateResult {
+        io::copy(&mut File::open(&release.archive)?, writer)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use super::{Release, ReleaseSource, UpdateError, UpdateResult, ARCHIVE_NAME};
+use crate::config::update_channel::{repository::Repository, semantic_version::SemanticVersion};
+use log::warn;
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::{io::Write, str::FromStr};
+
+fn source_error(e: reqwest::Error) -> UpdateError {
+    UpdateError::Source(e.to_string())
+}
+
+/// The releases of a GitHub repository that got built from the branch. Releases need a tag that is
+/// a semantic version and the archive together with a file containing its checksum as assets.
+pub struct GithubSource {
+    repo: Repository,
+    branch: String,
+    client: Client,
+}
+
+impl GithubSource {
+    pub fn new(repo: Repository, branch: String) -> Self {
+        Self {
+            repo,
+            branch,
+            client: Client::builder()
+                .user_agent("nog")
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    fn get(&self, url: &str) -> UpdateResult<reqwest::blocking::Response> {
+        self.client
+            .get(url)
+            .send()
+            .and_then(|res| res.error_for_status())
+            .map_err(source_error)
+    }
+
+    fn asset_url<'a>(release: &'a Value, name: &str) -> Option<&'a str> {
+        release["assets"]
+            .as_array()?
+            .iter()
+            .find(|asset| asset["name"] == name)?["browser_download_url"]
+            .as_str()
+    }
+}
+
+impl ReleaseSource for GithubSource {
+    fn releases(&self) -> UpdateResult<Vec<Release>> {
+        let url = format!("https://api.github.com/repos/{}/releases", self.repo);
+        let json: Value = self.get(&url)?.json().map_err(source_error)?;
+        let mut releases = Vec::new();
+
+        for release in json.as_array().into_iter().flatten() {
+            if release["target_commitish"] != self.branch.as_str() {
+                continue;
+            }
+
+            let tag = release["tag_name"].as_str().unwrap_or_default();
+            let version = match SemanticVersion::from_str(tag) {
+                Ok(version) => version,
+                Err(_) => continue,
+            };
+
+            let checksum_name = format!("{}.sha256", ARCHIVE_NAME);
+            let (archive, checksum) = match (
+                Self::asset_url(release, ARCHIVE_NAME),
+                Self::asset_url(release, &checksum_name),
+            ) {
+                (Some(archive), Some(checksum)) => (archive, checksum),
+                _ => {
+                    warn!("Skipping the release {}, because it has no checksum", tag);
+                    continue;
+                }
+            };
+
+            releases.push(Release {
+                version,
+                archive: archive.into(),
+                checksum: checksum.into(),
+            });
+        }
+
+        Ok(releases)
+    }
+
+    fn checksum(&self, release: &Release) -> UpdateResult<String> {
+        let content = self.get(&release.checksum)?.text().map_err(source_error)?;
+        Ok(content.split_whitespace().next().unwrap_or_default().into())
+    }
+
+    fn download(&self, release: &Release, writer: &mut dyn Write) -> UpdateResult {
+        let mut res = self.get(&release.archive)?;
+        std::io::copy(&mut res, writer)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use super::{
+    current_version, directory::DirectorySource, install_staged, remove_old_files,
+    run_in_background, sha256, staged_version, InstallDirs, UpdateError, Updater, ARCHIVE_NAME,
+};
+use crate::{
+    config::update_channel::{semantic_version::SemanticVersion, UpdateChannel},
+    event::Event,
+    util::testing::TempDir,
+    AppState,
+};
+use parking_lot::Mutex;
+use serde_json::json;
+use std::{fs, io::Write, path::Path, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+use zip::{write::FileOptions, ZipWriter};
+
+fn version(s: &str) -> SemanticVersion {
+    SemanticVersion::from_str(s).unwrap()
+}
+
+/// Puts a release that contains the files into the directory
+fn publish(releases: &Path, tag: &str, files: &[(&str, &str)]) {
+    let mut archive = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, content) in files {
+        archive.start_file(*name, FileOptions::default()).unwrap();
+        archive.write_all(content.as_bytes()).unwrap();
+    }
+    let bytes = archive.finish().unwrap().into_inner();
+
+    let dir = releases.join(tag);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(ARCHIVE_NAME), &bytes).unwrap();
+    fs::write(
+        dir.join(format!("{}.sha256", ARCHIVE_NAME)),
+        format!("{}  {}\n", sha256(&bytes), ARCHIVE_NAME),
+    )
+    .unwrap();
+}
+
+fn updater(releases: &Path, current: &str, pre_releases: bool) -> Updater {
+    Updater {
+        source: Box::new(DirectorySource::new(releases.into())),
+        current: version(current),
+        pre_releases,
+        dir: releases.join("updates"),
+    }
+}
+
+#[test]
+fn check_finds_the_newest_release() {
+    let releases = TempDir::new("update-check");
+    publish(&releases, "v0.13.0", &[]);
+    publish(&releases, "v0.14.0", &[]);
+    publish(&releases, "v0.15.0-beta.1", &[]);
+    publish(&releases, "development-release", &[]);
+
+    let release = updater(&releases, "0.13.0", false).check().unwrap();
+    assert_eq!(release.unwrap().version, version("0.14.0"));
+
+    let release = updater(&releases, "0.13.0", true).check().unwrap();
+    assert_eq!(release.unwrap().version, version("0.15.0-beta.1"));
+
+    assert_eq!(updater(&releases, "0.14.0", false).check().unwrap(), None);
+}
+
+#[test]
+fn staged_files_replace_the_installed_ones() {
+    let releases = TempDir::new("update-install");
+    // the executable lives in the install folder, while the runtime and the config live in the
+    // data and config folders of the user
+    let install_dir = releases.join("Program Files/nog");
+    let runtime_dir = releases.join("AppData/Local/nog/runtime");
+    let config_dir = releases.join("AppData/Roaming/nog");
+    fs::create_dir_all(install_dir.join("bin")).unwrap();
+    fs::create_dir_all(config_dir.join("config")).unwrap();
+    fs::write(install_dir.join("bin/nog.exe"), "0.13.0").unwrap();
+    fs::write(config_dir.join("config/init.lua"), "-- mine").unwrap();
+
+    let dirs = InstallDirs::from_exe(&install_dir.join("bin/nog.exe"), runtime_dir.clone());
+    assert_eq!(dirs.bin, install_dir.join("bin"));
+
+    publish(
+        &releases,
+        "v0.14.0",
+        &[
+            ("Nog/bin/nog.exe", "0.14.0"),
+            ("Nog/config/init.lua", "-- default"),
+            ("Nog/runtime/lua/runtime.lua", "-- runtime"),
+        ],
+    );
+
+    let updater = updater(&releases, "0.13.0", false);
+    let release = updater.check().unwrap().unwrap();
+    updater.stage(&release).unwrap();
+    assert_eq!(staged_version(&updater.dir), Some(version("0.14.0")));
+    assert_eq!(
+        fs::read_to_string(install_dir.join("bin/nog.exe")).unwrap(),
+        "0.13.0"
+    );
+
+    let installed = install_staged(&updater.dir, &dirs).unwrap();
+    assert_eq!(installed, Some(version("0.14.0")));
+    assert_eq!(staged_version(&updater.dir), None);
+
+    let read = |path: PathBuf| fs::read_to_string(path).unwrap();
+    assert_eq!(read(install_dir.join("bin/nog.exe")), "0.14.0");
+    assert_eq!(read(install_dir.join("bin/nog.exe.old")), "0.13.0");
+    assert_eq!(read(config_dir.join("config/init.lua")), "-- mine");
+    assert_eq!(read(runtime_dir.join("lua/runtime.lua")), "-- runtime");
+    assert!(!install_dir.join("runtime").exists());
+    assert!(!config_dir.join("bin").exists());
+
+    remove_old_files(&dirs).unwrap();
+    assert!(!install_dir.join("bin/nog.exe.old").exists());
+    assert!(install_dir.join("bin/nog.exe").exists());
+
+    assert_eq!(install_staged(&updater.dir, &dirs).unwrap(), None);
+}
+
+#[test]
+fn archives_with_a_wrong_checksum_get_rejected() {
+    let releases = TempDir::new("update-checksum");
+    publish(&releases, "v0.14.0", &[("Nog/bin/nog.exe", "0.14.0")]);
+    fs::write(releases.join("v0.14.0").join(ARCHIVE_NAME), "tampered").unwrap();
+
+    let updater = updater(&releases, "0.13.0", false);
+    let release = updater.check().unwrap().unwrap();
+
+    match updater.stage(&release) {
+        Err(UpdateError::Checksum { expected, actual }) => {
+            assert_eq!(expected, updater.source.checksum(&release).unwrap());
+            assert_eq!(actual, sha256(b"tampered"));
+        }
+        res => panic!("expected a checksum error, got {:?}", res),
+    }
+    assert_eq!(staged_version(&updater.dir), None);
+}
+
+#[test]
+fn background_checks_report_to_the_callback() {
+    let releases = TempDir::new("update-background");
+    publish(&releases, "v99.0.0", &[]);
+
+    let state_arc = Arc::new(Mutex::new(AppState::default()));
+    let receiver = {
+        let mut state = state_arc.lock();
+        state.config.update_channels = vec![UpdateChannel {
+            name: "local".into(),
+            path: Some(releases.to_path_buf()),
+            ..Default::default()
+        }];
+        state.config.default_update_channel = Some("local".into());
+        state.event_channel.receiver.clone()
+    };
+
+    run_in_background(state_arc, false, Some(7));
+
+    match receiver.recv_timeout(Duration::from_secs(10)).unwrap() {
+        Event::CallCallback { idx, arg } => {
+            assert_eq!(idx, 7);
+            assert_eq!(
+                arg,
+                Some(json!({ "current": current_version().to_string(), "version": "99.0.0" }))
+            );
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(test)]
+pub mod testing;
+
 pub fn bytes_to_string(buffer: &[i8]) -> String {
     buffer
         .iter()
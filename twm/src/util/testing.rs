@@ -0,0 +1,41 @@
+//! Helpers that are shared by the tests of several modules
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// An empty directory in the temp folder that gets removed once it goes out of scope
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// The name has to be unique across all tests, because they run in parallel
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("nog-{}-{}", name, std::process::id()));
+        if path.exists() {
+            fs::remove_dir_all(&path).unwrap();
+        }
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,104 @@
+use super::{WindowEvent, WindowInner, WindowPlatform};
+use crate::{
+    system::fake, system::fake::FakePlatform, system::fake::FakeWindow, system::NativeWindow,
+    system::Rectangle, system::WindowId, system::BIN_NAME, AppState,
+};
+use log::error;
+use mlua::Result as RuntimeResult;
+use parking_lot::Mutex;
+use std::{sync::mpsc::Sender, sync::Arc, thread, thread::JoinHandle};
+
+/// Approximated size of a single character, because the fake system can't measure text.
+const CHAR_WIDTH: i32 = 8;
+const CHAR_HEIGHT: i32 = 16;
+
+/// Drawing does nothing in the fake system, the api only exists so the event handlers of nog's
+/// own windows can be shared between the backends.
+#[derive(Debug, Clone)]
+pub struct Api {
+    pub hdc: i32,
+    pub background_color: i32,
+    pub window: NativeWindow,
+}
+
+impl Api {
+    pub fn set_clickable_cursor(&self) {}
+    pub fn set_default_cursor(&self) {}
+    pub fn set_text_color(&self, _color: i32) {}
+    pub fn with_font<T>(
+        &self,
+        _name: &str,
+        _size: i32,
+        cb: impl Fn() -> RuntimeResult<T>,
+    ) -> RuntimeResult<T> {
+        cb()
+    }
+    pub fn set_background_color(&self, _color: i32) {}
+    pub fn reset_background_color(&self) {
+        self.set_background_color(self.background_color)
+    }
+    pub fn fill_rect(&self, _x: i32, _y: i32, _width: i32, _height: i32, _color: i32) {}
+    pub fn calculate_text_rect(&self, text: &str) -> Rectangle {
+        let lines = text.split('\n');
+
+        Rectangle {
+            left: 0,
+            right: lines.clone().map(|l| l.chars().count()).max().unwrap_or(0) as i32 * CHAR_WIDTH,
+            top: 0,
+            bottom: lines.count() as i32 * CHAR_HEIGHT,
+        }
+    }
+    pub fn write_text(&self, _text: &str, _x: i32, _y: i32, _vcenter: bool, _hcenter: bool) {}
+}
+
+impl WindowPlatform for FakePlatform {
+    type Api = Api;
+
+    /// Creates the window inside the fake system of the calling thread and emits the `Create`
+    /// event.
+    ///
+    /// There is no message loop in the fake system, so the returned thread finishes immediately.
+    fn create<TEventHandler: Fn(&WindowEvent) -> RuntimeResult<()> + Sync + Send + 'static>(
+        inner_arc: Arc<Mutex<WindowInner>>,
+        parent: WindowId,
+        state_arc: Arc<Mutex<AppState>>,
+        show: bool,
+        sender: Sender<WindowId>,
+        event_handler: TEventHandler,
+    ) -> JoinHandle<()> {
+        let mut inner = inner_arc.lock();
+
+        let mut fake_window = FakeWindow::new(&inner.title, BIN_NAME)
+            .with_rect(Rectangle {
+                left: inner.x,
+                right: inner.x + inner.width,
+                top: inner.y,
+                bottom: inner.y + inner.height,
+            })
+            .with_visible(show);
+
+        if parent != 0 {
+            fake_window = fake_window.with_parent(parent);
+        }
+
+        let id = fake::add_window(fake_window);
+        let window: NativeWindow = id.into();
+
+        sender.send(id).unwrap();
+        inner.native_window = Some(window.clone());
+
+        drop(inner);
+
+        let display_id = window.get_display().map(|d| d.id).unwrap_or_default();
+
+        if let Err(e) = event_handler(&WindowEvent::Create {
+            display_id,
+            window_id: id,
+            state_arc,
+        }) {
+            error!("{}", e);
+        }
+
+        thread::spawn(|| {})
+    }
+}
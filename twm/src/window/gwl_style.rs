@@ -1,61 +1,34 @@
 use bitflags::bitflags;
-use winapi::um::winuser::WS_BORDER;
-use winapi::um::winuser::WS_CAPTION;
-use winapi::um::winuser::WS_CHILD;
-use winapi::um::winuser::WS_CHILDWINDOW;
-use winapi::um::winuser::WS_CLIPCHILDREN;
-use winapi::um::winuser::WS_CLIPSIBLINGS;
-use winapi::um::winuser::WS_DISABLED;
-use winapi::um::winuser::WS_DLGFRAME;
-use winapi::um::winuser::WS_GROUP;
-use winapi::um::winuser::WS_HSCROLL;
-use winapi::um::winuser::WS_ICONIC;
-use winapi::um::winuser::WS_MAXIMIZE;
-use winapi::um::winuser::WS_MAXIMIZEBOX;
-use winapi::um::winuser::WS_MINIMIZE;
-use winapi::um::winuser::WS_MINIMIZEBOX;
-use winapi::um::winuser::WS_OVERLAPPED;
-use winapi::um::winuser::WS_OVERLAPPEDWINDOW;
-use winapi::um::winuser::WS_POPUP;
-use winapi::um::winuser::WS_POPUPWINDOW;
-use winapi::um::winuser::WS_SIZEBOX;
-use winapi::um::winuser::WS_SYSMENU;
-use winapi::um::winuser::WS_TABSTOP;
-use winapi::um::winuser::WS_THICKFRAME;
-use winapi::um::winuser::WS_TILED;
-use winapi::um::winuser::WS_TILEDWINDOW;
-use winapi::um::winuser::WS_VISIBLE;
-use winapi::um::winuser::WS_VSCROLL;
 
 bitflags! {
     #[derive(Default)]
     pub struct GwlStyle: i32 {
-        const BORDER = WS_BORDER as i32;
-        const CAPTION = WS_CAPTION as i32;
-        const CHILD = WS_CHILD as i32;
-        const CHILDWINDOW = WS_CHILDWINDOW as i32;
-        const CLIPCHILDREN = WS_CLIPCHILDREN as i32;
-        const CLIPSIBLINGS = WS_CLIPSIBLINGS as i32;
-        const DISABLED = WS_DISABLED as i32;
-        const DLGFRAME = WS_DLGFRAME as i32;
-        const GROUP = WS_GROUP as i32;
-        const HSCROLL = WS_HSCROLL as i32;
-        const ICONIC = WS_ICONIC as i32;
-        const MAXIMIZE = WS_MAXIMIZE as i32;
-        const MAXIMIZEBOX = WS_MAXIMIZEBOX as i32;
-        const MINIMIZE = WS_MINIMIZE as i32;
-        const MINIMIZEBOX = WS_MINIMIZEBOX as i32;
-        const OVERLAPPED = WS_OVERLAPPED as i32;
-        const OVERLAPPEDWINDOW = WS_OVERLAPPEDWINDOW as i32;
-        const POPUP = WS_POPUP as i32;
-        const POPUPWINDOW = WS_POPUPWINDOW as i32;
-        const SIZEBOX = WS_SIZEBOX as i32;
-        const SYSMENU = WS_SYSMENU as i32;
-        const TABSTOP = WS_TABSTOP as i32;
-        const THICKFRAME = WS_THICKFRAME as i32;
-        const TILED = WS_TILED as i32;
-        const TILEDWINDOW = WS_TILEDWINDOW as i32;
-        const VISIBLE = WS_VISIBLE as i32;
-        const VSCROLL = WS_VSCROLL as i32;
+        const BORDER = 0x00800000;
+        const CAPTION = 0x00C00000;
+        const CHILD = 0x40000000;
+        const CHILDWINDOW = 0x40000000;
+        const CLIPCHILDREN = 0x02000000;
+        const CLIPSIBLINGS = 0x04000000;
+        const DISABLED = 0x08000000;
+        const DLGFRAME = 0x00400000;
+        const GROUP = 0x00020000;
+        const HSCROLL = 0x00100000;
+        const ICONIC = 0x20000000;
+        const MAXIMIZE = 0x01000000;
+        const MAXIMIZEBOX = 0x00010000;
+        const MINIMIZE = 0x20000000;
+        const MINIMIZEBOX = 0x00020000;
+        const OVERLAPPED = 0x00000000;
+        const OVERLAPPEDWINDOW = 0x00CF0000;
+        const POPUP = 0x80000000_u32 as i32;
+        const POPUPWINDOW = 0x80880000_u32 as i32;
+        const SIZEBOX = 0x00040000;
+        const SYSMENU = 0x00080000;
+        const TABSTOP = 0x00010000;
+        const THICKFRAME = 0x00040000;
+        const TILED = 0x00000000;
+        const TILEDWINDOW = 0x00CF0000;
+        const VISIBLE = 0x10000000;
+        const VSCROLL = 0x00200000;
     }
 }
@@ -0,0 +1,359 @@
+use super::{WindowEvent, WindowInner, WindowMsg, WindowPlatform};
+use crate::{
+    event::Event, message_loop, system::win::WinPlatform, system::NativeWindow, system::Rectangle,
+    system::WindowId, util, AppState,
+};
+use log::error;
+use mlua::Result as RuntimeResult;
+use parking_lot::Mutex;
+use std::{ffi::c_void, ffi::CString, sync::mpsc::Sender, sync::Arc, thread, thread::JoinHandle};
+use winapi::um::wingdi::SelectObject;
+use winapi::um::wingdi::LOGFONTA;
+use winapi::um::wingdi::{GetBValue, GetGValue, GetRValue, RGB};
+use winapi::um::{wingdi::CreateFontIndirectA, winuser::IDC_HAND, winuser::WM_MOUSEMOVE};
+use winapi::um::{wingdi::DeleteObject, winuser::DT_SINGLELINE, winuser::DT_VCENTER};
+use winapi::{
+    shared::minwindef::LPARAM, shared::minwindef::LRESULT, shared::minwindef::UINT,
+    shared::minwindef::WPARAM, shared::windef::HDC, shared::windef::HWND, shared::windef::POINT,
+    shared::windef::RECT, um::wingdi::CreateSolidBrush, um::wingdi::SetBkColor,
+    um::wingdi::SetTextColor, um::winuser::BeginPaint, um::winuser::CreateWindowExA,
+    um::winuser::DefWindowProcA, um::winuser::DrawTextW, um::winuser::EndPaint,
+    um::winuser::FillRect, um::winuser::GetCursorPos, um::winuser::GetDC, um::winuser::LoadCursorA,
+    um::winuser::PostMessageA, um::winuser::RegisterClassA, um::winuser::ReleaseDC,
+    um::winuser::SetCursor, um::winuser::UnregisterClassA, um::winuser::DT_CALCRECT,
+    um::winuser::IDC_ARROW, um::winuser::PAINTSTRUCT, um::winuser::WM_APP, um::winuser::WM_CLOSE,
+    um::winuser::WM_CREATE, um::winuser::WM_KILLFOCUS, um::winuser::WM_LBUTTONDOWN,
+    um::winuser::WM_PAINT, um::winuser::WM_SETCURSOR, um::winuser::WNDCLASSA,
+    um::winuser::WS_BORDER, um::winuser::WS_EX_NOACTIVATE, um::winuser::WS_EX_TOPMOST,
+    um::winuser::WS_OVERLAPPEDWINDOW, um::winuser::WS_POPUPWINDOW,
+};
+
+const WM_IDENT: u32 = WM_APP + 80;
+
+unsafe extern "system" fn window_cb(
+    hwnd: HWND,
+    msg: UINT,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    if msg == WM_SETCURSOR {
+        return 1;
+    } else if msg != WM_IDENT {
+        let payload = WindowMsg {
+            code: msg,
+            hwnd: hwnd.into(),
+            params: (w_param, l_param),
+        };
+
+        let ptr = Box::into_raw(Box::new(payload));
+
+        //TODO: Does nothing when WM_CLOSE is sent
+        PostMessageA(hwnd, WM_IDENT, ptr as usize, 0);
+    }
+
+    DefWindowProcA(hwnd, msg, w_param, l_param)
+}
+
+pub fn convert_color_to_winapi(color: u32) -> u32 {
+    RGB(GetRValue(color), GetGValue(color), GetBValue(color))
+}
+
+#[derive(Debug, Clone)]
+pub struct Api {
+    pub hdc: i32,
+    pub background_color: i32,
+    pub window: NativeWindow,
+}
+
+impl Api {
+    pub fn set_clickable_cursor(&self) {
+        unsafe {
+            SetCursor(LoadCursorA(std::ptr::null_mut(), IDC_HAND as *const i8));
+        }
+    }
+    pub fn set_default_cursor(&self) {
+        unsafe {
+            SetCursor(LoadCursorA(std::ptr::null_mut(), IDC_ARROW as *const i8));
+        }
+    }
+    pub fn set_text_color(&self, color: i32) {
+        unsafe {
+            SetTextColor(self.hdc as HDC, convert_color_to_winapi(color as u32));
+        }
+    }
+    pub fn with_font<T>(
+        &self,
+        name: &str,
+        size: i32,
+        cb: impl Fn() -> RuntimeResult<T>,
+    ) -> RuntimeResult<T> {
+        unsafe {
+            let mut logfont = LOGFONTA::default();
+            let mut font_name: [i8; 32] = [0; 32];
+
+            for (i, byte) in CString::new(name).unwrap().as_bytes().iter().enumerate() {
+                font_name[i] = *byte as i8;
+            }
+
+            logfont.lfHeight = size;
+            logfont.lfFaceName = font_name;
+
+            let font = CreateFontIndirectA(&logfont);
+            SelectObject(self.hdc as HDC, font as *mut c_void);
+            let res = cb();
+            DeleteObject(font as *mut c_void);
+            return res;
+        }
+    }
+    pub fn set_background_color(&self, color: i32) {
+        unsafe {
+            SetBkColor(self.hdc as HDC, convert_color_to_winapi(color as u32));
+        }
+    }
+    pub fn reset_background_color(&self) {
+        self.set_background_color(self.background_color)
+    }
+    pub fn fill_rect(&self, x: i32, y: i32, width: i32, height: i32, color: i32) {
+        unsafe {
+            let brush = CreateSolidBrush(convert_color_to_winapi(color as u32));
+            let mut rect = RECT {
+                left: x,
+                right: x + width,
+                top: y,
+                bottom: y + height,
+            };
+
+            FillRect(self.hdc as HDC, &mut rect, brush);
+
+            DeleteObject(brush as *mut c_void);
+        }
+    }
+    pub fn calculate_text_rect(&self, text: &str) -> Rectangle {
+        let c_text = util::to_widestring(&text);
+        let mut rect = RECT::default();
+        unsafe {
+            DrawTextW(self.hdc as HDC, c_text.as_ptr(), -1, &mut rect, DT_CALCRECT);
+        }
+        rect.into()
+    }
+    pub fn write_text(&self, text: &str, x: i32, y: i32, vcenter: bool, _hcenter: bool) {
+        let c_text = util::to_widestring(&text);
+        let mut rect = self.calculate_text_rect(text);
+
+        rect.left += x;
+        rect.right += x;
+        rect.top += y;
+        rect.bottom += y;
+
+        let mut rect = rect.into();
+        let mut flags = 0;
+
+        if vcenter {
+            flags = DT_VCENTER | DT_SINGLELINE;
+        }
+
+        unsafe {
+            DrawTextW(self.hdc as HDC, c_text.as_ptr(), -1, &mut rect, flags);
+        }
+    }
+}
+
+impl WindowPlatform for WinPlatform {
+    type Api = Api;
+
+    /// Creates the native window on a new thread and runs its message loop.
+    ///
+    /// The id of the created window gets sent through `sender` as soon as it exists.
+    fn create<TEventHandler: Fn(&WindowEvent) -> RuntimeResult<()> + Sync + Send + 'static>(
+        inner_arc: Arc<Mutex<WindowInner>>,
+        parent: WindowId,
+        state_arc: Arc<Mutex<AppState>>,
+        show: bool,
+        sender: Sender<WindowId>,
+        event_handler: TEventHandler,
+    ) -> JoinHandle<()> {
+        let state = state_arc.clone();
+
+        thread::spawn(move || unsafe {
+            let mut inner = inner_arc.lock();
+            let instance = winapi::um::libloaderapi::GetModuleHandleA(std::ptr::null_mut());
+            let c_name = CString::new(inner.title.clone().as_str()).unwrap();
+
+            let class = WNDCLASSA {
+                hInstance: instance,
+                lpszClassName: c_name.as_ptr(),
+                lpfnWndProc: Some(window_cb),
+                hbrBackground: CreateSolidBrush(inner.background_color as u32),
+                ..WNDCLASSA::default()
+            };
+
+            if RegisterClassA(&class) == 0 {
+                UnregisterClassA(c_name.as_ptr(), instance);
+                RegisterClassA(&class);
+            }
+
+            let mut exstyle = 0;
+            let mut style = WS_OVERLAPPEDWINDOW;
+
+            if inner.is_popup {
+                exstyle = WS_EX_NOACTIVATE | WS_EX_TOPMOST;
+                style = WS_POPUPWINDOW;
+            }
+
+            if !inner.border {
+                style &= !WS_BORDER
+            }
+
+            let hwnd = CreateWindowExA(
+                exstyle,
+                c_name.as_ptr(),
+                c_name.as_ptr(),
+                style,
+                inner.x,
+                inner.y,
+                inner.width,
+                inner.height,
+                parent.into(),
+                std::ptr::null_mut(),
+                instance,
+                std::ptr::null_mut(),
+            );
+
+            sender.send(hwnd.into()).unwrap();
+
+            let win: NativeWindow = hwnd.into();
+
+            if show {
+                win.show();
+            }
+
+            inner.native_window = Some(win);
+
+            let font = inner.font.clone();
+            let font_size = inner.font_size;
+            let background_color = inner.background_color;
+
+            drop(inner);
+
+            //TODO: make this cleaner
+            #[cfg(target_os = "windows")]
+            unsafe {
+                use winapi::um::shellapi::SHAppBarMessage;
+                use winapi::um::shellapi::ABE_TOP;
+                use winapi::um::shellapi::ABM_NEW;
+                use winapi::um::shellapi::APPBARDATA;
+                use winapi::um::winuser::WM_APP;
+
+                let mut appbar_data = APPBARDATA {
+                    cbSize: 4 + 4 + 4 + 4 + 16 + 4,
+                    hWnd: hwnd,
+                    uCallbackMessage: WM_APP + 1,
+                    uEdge: ABE_TOP,
+                    ..Default::default()
+                };
+
+                SHAppBarMessage(ABM_NEW, &mut appbar_data as *mut APPBARDATA);
+            }
+
+            message_loop::start(move |msg| {
+                if let Some(msg) = msg {
+                    if msg.message == WM_IDENT {
+                        let window: NativeWindow = hwnd.into();
+                        let display_id = fail_with!(window.get_display(), true).id;
+                        let hdc = GetDC(hwnd);
+                        let msg = *(msg.wParam as *const WindowMsg);
+                        let call_handler = |event| {
+                            if let Err(e) = event_handler(event) {
+                                state
+                                    .lock()
+                                    .event_channel
+                                    .sender
+                                    .send(Event::LuaRuntimeError(e))
+                                    .unwrap();
+                            }
+                        };
+
+                        if msg.code == WM_PAINT {
+                            let mut paint = PAINTSTRUCT::default();
+
+                            BeginPaint(hwnd, &mut paint);
+                            SetBkColor(hdc, background_color as u32);
+
+                            let api = Api {
+                                hdc: hdc as i32,
+                                window: window.clone(),
+                                background_color,
+                            };
+
+                            call_handler(&WindowEvent::Draw {
+                                display_id,
+                                window_id: window.id,
+                                state_arc: state_arc.clone(),
+                                api,
+                            });
+                            EndPaint(hwnd, &paint);
+                        } else if msg.code == WM_LBUTTONDOWN {
+                            let mut point = POINT::default();
+                            GetCursorPos(&mut point);
+                            let win_rect = window.get_rect().unwrap();
+
+                            call_handler(&WindowEvent::Click {
+                                display_id,
+                                window_id: window.id,
+                                state_arc: state_arc.clone(),
+                                x: point.x - win_rect.left,
+                                y: point.y - win_rect.top,
+                            });
+                        } else if msg.code == WM_CLOSE {
+                            call_handler(&WindowEvent::Close {
+                                display_id,
+                                window_id: window.id,
+                                state_arc: state_arc.clone(),
+                            });
+                        } else if msg.code == WM_CREATE {
+                            call_handler(&WindowEvent::Create {
+                                display_id,
+                                window_id: window.id,
+                                state_arc: state_arc.clone(),
+                            });
+                        } else if msg.code == WM_KILLFOCUS {
+                            call_handler(&WindowEvent::LostFocus {
+                                new_window: (msg.params.0 as i32).into(),
+                            });
+                        } else if msg.code == WM_MOUSEMOVE {
+                            let mut point = POINT::default();
+                            GetCursorPos(&mut point);
+                            let win_rect = window.get_rect().unwrap();
+
+                            let api = Api {
+                                hdc: hdc as i32,
+                                window: window.clone(),
+                                background_color,
+                            };
+
+                            call_handler(&WindowEvent::MouseMove {
+                                display_id,
+                                window_id: window.id,
+                                state_arc: state_arc.clone(),
+                                api,
+                                x: point.x - win_rect.left,
+                                y: point.y - win_rect.top,
+                            });
+                        } else {
+                            call_handler(&WindowEvent::Native {
+                                display_id,
+                                window_id: window.id,
+                                state_arc: state_arc.clone(),
+                                msg,
+                            });
+                        }
+
+                        ReleaseDC(hwnd, hdc);
+                    }
+                }
+
+                true
+            })
+        })
+    }
+}